@@ -2,16 +2,20 @@
 //! # local-guard-analysis-contract
 //!
 //! ## Purpose
-//! Defines the server analysis response schema and client-side mapping helpers.
+//! Defines the server analysis response schema, the ingest acknowledgement,
+//! and client-side mapping helpers.
 //!
 //! ## Responsibilities
 //! - Parse versioned analysis response payloads.
+//! - Parse ingest acknowledgements ([`IngestAck`]) returned by uploads.
 //! - Map risk categories to UI-safe risk levels.
 //! - Preserve unknown categories for forward compatibility.
 //!
 //! ## Data flow
 //! Raw JSON response -> [`parse_analysis_response`] -> [`map_risk_signals`] ->
 //! runtime UI status projection.
+//! Upload response body -> [`parse_ingest_ack`] -> batch/request linkage and,
+//! when present, the same mapping for the embedded analysis.
 //!
 //! ## Ownership and lifetimes
 //! Parsed values are owned structs to avoid borrowing from transient network
@@ -37,6 +41,9 @@ pub struct AnalysisResponse {
     pub schema_version: String,
     /// Request identifier for traceability.
     pub request_id: String,
+    /// Originating `BatchMetadata::batch_id`, when the server echoes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
     /// Individual model outputs.
     #[serde(default)]
     pub model_results: Vec<ModelResult>,
@@ -89,6 +96,38 @@ pub struct UiRiskSignal {
     pub level: RiskLevel,
 }
 
+/// Server disposition of an uploaded batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestStatus {
+    /// Batch stored for the first time.
+    Accepted,
+    /// Batch already stored under the same idempotency key; the original
+    /// `request_id` is returned.
+    Duplicate,
+}
+
+/// Acknowledgement body returned by a successful upload, as defined by
+/// `contracts/ingest-response.schema.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestAck {
+    /// Acknowledgement schema version.
+    pub schema_version: String,
+    /// Request identifier that later analysis responses carry.
+    pub request_id: String,
+    /// Disposition of the batch.
+    pub status: IngestStatus,
+    /// Originating `BatchMetadata::batch_id`, when the server echoes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Idempotency key of the upload, when the server echoes it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Analysis produced synchronously with the upload, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analysis: Option<AnalysisResponse>,
+}
+
 /// Parses raw JSON into validated analysis response.
 ///
 /// # Errors
@@ -114,6 +153,48 @@ pub fn parse_analysis_response(raw: &str) -> Result<AnalysisResponse, AnalysisCo
     Ok(parsed)
 }
 
+/// Parses a raw upload response body into a validated ingest
+/// acknowledgement.
+///
+/// # Errors
+/// Returns [`AnalysisContractError::Decode`] for invalid JSON.
+/// Returns [`AnalysisContractError::InvalidContract`] when mandatory fields
+/// are blank, or when the embedded analysis names a different request or
+/// batch than the acknowledgement.
+pub fn parse_ingest_ack(raw: &str) -> Result<IngestAck, AnalysisContractError> {
+    let parsed: IngestAck = serde_json::from_str(raw).map_err(AnalysisContractError::Decode)?;
+
+    if parsed.schema_version.trim().is_empty() {
+        return Err(AnalysisContractError::InvalidContract(
+            "schema_version is empty".to_string(),
+        ));
+    }
+
+    if parsed.request_id.trim().is_empty() {
+        return Err(AnalysisContractError::InvalidContract(
+            "request_id is empty".to_string(),
+        ));
+    }
+
+    if let Some(analysis) = &parsed.analysis {
+        if analysis.request_id != parsed.request_id {
+            return Err(AnalysisContractError::InvalidContract(format!(
+                "analysis request_id {} does not match acknowledgement {}",
+                analysis.request_id, parsed.request_id
+            )));
+        }
+        if let (Some(ack_batch), Some(analysis_batch)) = (&parsed.batch_id, &analysis.batch_id)
+            && ack_batch != analysis_batch
+        {
+            return Err(AnalysisContractError::InvalidContract(format!(
+                "analysis batch_id {analysis_batch} does not match acknowledgement {ack_batch}"
+            )));
+        }
+    }
+
+    Ok(parsed)
+}
+
 /// Maps category severities into UI-safe risk signals.
 ///
 /// Unknown category names are preserved with severity-based risk levels, so
@@ -161,6 +242,7 @@ mod tests {
         let response = AnalysisResponse {
             schema_version: ANALYSIS_SCHEMA_VERSION_V1.to_string(),
             request_id: "req-1".to_string(),
+            batch_id: None,
             model_results: vec![],
             categories: vec![CategoryAssessment {
                 category: "new_future_category".to_string(),
@@ -172,4 +254,46 @@ mod tests {
         assert_eq!(signals[0].category, "new_future_category");
         assert_eq!(signals[0].level, RiskLevel::Medium);
     }
+
+    #[test]
+    fn parses_ingest_ack_fixtures() {
+        let ack = parse_ingest_ack(include_str!(
+            "../../../contracts/fixtures/ingest-response.valid.json"
+        ))
+        .expect("accepted fixture should parse");
+        assert_eq!(ack.status, IngestStatus::Accepted);
+        assert_eq!(
+            ack.analysis
+                .as_ref()
+                .map(|analysis| analysis.request_id.as_str()),
+            Some("req-123")
+        );
+
+        let duplicate = parse_ingest_ack(include_str!(
+            "../../../contracts/fixtures/ingest-response.duplicate.valid.json"
+        ))
+        .expect("duplicate fixture should parse");
+        assert_eq!(duplicate.status, IngestStatus::Duplicate);
+        assert_eq!(duplicate.analysis, None);
+    }
+
+    #[test]
+    fn rejects_ack_with_foreign_analysis() {
+        let mut ack: serde_json::Value = serde_json::from_str(include_str!(
+            "../../../contracts/fixtures/ingest-response.valid.json"
+        ))
+        .expect("fixture json");
+        ack["analysis"]["request_id"] = serde_json::json!("req-999");
+        assert!(matches!(
+            parse_ingest_ack(&ack.to_string()),
+            Err(AnalysisContractError::InvalidContract(_))
+        ));
+
+        ack["analysis"]["request_id"] = serde_json::json!("req-123");
+        ack["analysis"]["batch_id"] = serde_json::json!("other-batch");
+        assert!(parse_ingest_ack(&ack.to_string()).is_err());
+
+        ack["request_id"] = serde_json::json!(" ");
+        assert!(parse_ingest_ack(&ack.to_string()).is_err());
+    }
 }
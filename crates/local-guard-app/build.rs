@@ -1,14 +1,16 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
 fn main() {
     let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("manifest dir"));
-    let version_path = manifest_dir
+    let workspace_root = manifest_dir
         .parent()
         .expect("crates dir")
         .parent()
         .expect("workspace root")
-        .join("VERSION");
+        .to_path_buf();
+    let version_path = workspace_root.join("VERSION");
 
     println!("cargo:rerun-if-changed={}", version_path.display());
 
@@ -20,4 +22,48 @@ fn main() {
     );
 
     println!("cargo:rustc-env=LOCAL_GUARD_VERSION={version}");
+
+    let target = std::env::var("TARGET").unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=LOCAL_GUARD_BUILD_TARGET={target}");
+
+    println!("cargo:rerun-if-env-changed=LOCAL_GUARD_GIT_HASH");
+    watch_git_head(&workspace_root);
+    let git_hash = std::env::var("LOCAL_GUARD_GIT_HASH")
+        .ok()
+        .filter(|hash| !hash.trim().is_empty())
+        .or_else(|| git_short_hash(&workspace_root))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=LOCAL_GUARD_GIT_HASH={}", git_hash.trim());
+}
+
+/// Re-runs the build script when the checked-out commit changes.
+fn watch_git_head(workspace_root: &Path) {
+    let git_dir = workspace_root.join(".git");
+    let head = git_dir.join("HEAD");
+    if !head.exists() {
+        return;
+    }
+    println!("cargo:rerun-if-changed={}", head.display());
+    if let Ok(contents) = fs::read_to_string(&head)
+        && let Some(reference) = contents.trim().strip_prefix("ref: ")
+    {
+        let reference = git_dir.join(reference);
+        if reference.exists() {
+            println!("cargo:rerun-if-changed={}", reference.display());
+        }
+    }
+}
+
+fn git_short_hash(workspace_root: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .current_dir(workspace_root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let hash = String::from_utf8(output.stdout).ok()?;
+    let hash = hash.trim();
+    (!hash.is_empty()).then(|| hash.to_string())
 }
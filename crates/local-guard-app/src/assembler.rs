@@ -0,0 +1,274 @@
+//! # Module: assembler
+//!
+//! ## Purpose
+//! Turns completed frame batches into upload payloads with every enrichment
+//! step (mosaic options, host context) applied in one place.
+//!
+//! ## Responsibilities
+//! - Compose the mosaic and batch metadata.
+//! - Attach policy-filtered host context from a [`ContextCollector`].
+//! - Stamp client provenance ([`ClientProvenance`]) and the enrolled
+//!   device id on every payload.
+//! - Assign a unique `batch_id` and a per-session `sequence_number`.
+//! - Link every payload to its predecessor (`prev_payload_hash`) and report
+//!   the new chain head for persistence.
+//! - Report non-fatal enrichment failures next to the payload.
+//!
+//! ## Invariants
+//! - Enrichment failures never drop a batch; the payload is built without
+//!   the failed fields.
+//! - Sequence numbers start at 1 for every new session id and increase by
+//!   one per successfully assembled payload; failed assemblies consume no
+//!   number, so a gap on the server always means a lost batch.
+//! - The chain head advances together with the sequence number; the first
+//!   payload of a session links to [`chain_root_hash`].
+//! - Chains are per session, as the ingest contract verifies them: a
+//!   resumed [`ChainState`] of another session (e.g. persisted before a
+//!   restart that needed a new login) is never continued, since the server
+//!   expects the new session to start at its own root.
+//!
+//! ## Error model
+//! Mosaic and metadata failures return [`AppError`]; context failures are
+//! returned in [`AssembledPayload::context_errors`].
+//!
+//! ## Security and privacy notes
+//! Context is attached only when a collector is configured and its policy
+//! enables at least one field.
+
+use std::sync::Mutex;
+
+use local_guard_context::{ContextCollector, ContextError};
+use local_guard_core::{
+    ChainState, ClientProvenance, Frame, MosaicPayload, QualityThresholds, chain_root_hash,
+    payload_chain_hash,
+};
+use local_guard_mosaic::MosaicOptions;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use crate::{AppError, batch_to_payload_with};
+
+/// Payload plus non-fatal enrichment diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembledPayload {
+    /// Upload-ready payload.
+    pub payload: MosaicPayload,
+    /// Context providers that failed for this batch.
+    pub context_errors: Vec<ContextError>,
+    /// Chain head after this payload; persist it to resume the chain.
+    pub chain: ChainState,
+}
+
+#[derive(Debug)]
+struct SequenceState {
+    rng: StdRng,
+    /// Chain head of the session that was assembled last.
+    chain: Option<ChainState>,
+}
+
+/// Builds payloads from frame batches with configured enrichments.
+///
+/// # Concurrency
+/// `assemble` takes `&self`; id and sequence assignment happen under one
+/// internal lock, so concurrent callers still get unique, gap-free numbers.
+#[derive(Debug)]
+pub struct PayloadAssembler {
+    mosaic_options: MosaicOptions,
+    quality_thresholds: QualityThresholds,
+    context: Option<ContextCollector>,
+    provenance: Option<ClientProvenance>,
+    device_id: Option<String>,
+    sequence: Mutex<SequenceState>,
+}
+
+impl Default for PayloadAssembler {
+    fn default() -> Self {
+        Self {
+            mosaic_options: MosaicOptions::default(),
+            quality_thresholds: QualityThresholds::default(),
+            context: None,
+            provenance: None,
+            device_id: None,
+            sequence: Mutex::new(SequenceState {
+                rng: StdRng::from_os_rng(),
+                chain: None,
+            }),
+        }
+    }
+}
+
+impl PayloadAssembler {
+    /// Creates an assembler with default mosaic options and no context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derives batch ids from `seed` instead of OS randomness (tests and
+    /// replays only; ids are then predictable).
+    pub fn with_batch_id_seed(self, seed: u64) -> Self {
+        if let Ok(mut state) = self.sequence.lock() {
+            state.rng = StdRng::seed_from_u64(seed);
+        }
+        self
+    }
+
+    /// Continues numbering `session_id` after `last_sequence_number`, e.g.
+    /// after a restart that resumed the session.
+    ///
+    /// The previous payload hash is unknown here, so the next payload links
+    /// to the session root and a verifier will flag the restart. Prefer
+    /// [`Self::with_chain_state`] when the chain head was persisted.
+    pub fn with_resumed_sequence(self, session_id: &str, last_sequence_number: u64) -> Self {
+        self.with_chain_state(ChainState {
+            session_id: session_id.to_string(),
+            sequence_number: last_sequence_number,
+            head_hash: chain_root_hash(session_id),
+        })
+    }
+
+    /// Continues the payload chain at `chain` (typically loaded from a
+    /// [`crate::ChainStore`]); ignored once a different session assembles.
+    pub fn with_chain_state(self, chain: ChainState) -> Self {
+        if let Ok(mut state) = self.sequence.lock() {
+            state.chain = Some(chain);
+        }
+        self
+    }
+
+    /// Returns the current chain head, if any payload was chained or a
+    /// state was resumed.
+    pub fn chain_state(&self) -> Option<ChainState> {
+        self.sequence
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .chain
+            .clone()
+    }
+
+    /// Uses `options` for mosaic composition (pool, cursor overlay).
+    pub fn with_mosaic_options(mut self, options: MosaicOptions) -> Self {
+        self.mosaic_options = options;
+        self
+    }
+
+    /// Flags blank tiles with `thresholds`; pass the capture tick's
+    /// thresholds so both agree on what is blank.
+    pub fn with_quality_thresholds(mut self, thresholds: QualityThresholds) -> Self {
+        self.quality_thresholds = thresholds;
+        self
+    }
+
+    /// Attaches host context collected by `collector` to every payload.
+    pub fn with_context(mut self, collector: ContextCollector) -> Self {
+        self.context = Some(collector);
+        self
+    }
+
+    /// Stamps `provenance` on every payload.
+    pub fn with_provenance(mut self, provenance: ClientProvenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    /// Stamps the enrolled `device_id` on every payload.
+    pub fn with_device_id(mut self, device_id: impl Into<String>) -> Self {
+        self.device_id = Some(device_id.into());
+        self
+    }
+
+    /// Returns the mosaic options in use.
+    pub fn mosaic_options(&self) -> &MosaicOptions {
+        &self.mosaic_options
+    }
+
+    /// Returns the context collector, when configured.
+    pub fn context_collector(&self) -> Option<&ContextCollector> {
+        self.context.as_ref()
+    }
+
+    /// Assembles one payload from a chronological frame batch.
+    ///
+    /// # Errors
+    /// Same as [`batch_to_payload_with`]; [`AppError::Core`] when the
+    /// payload cannot be hashed for the chain.
+    pub fn assemble(
+        &self,
+        frames: &[Frame],
+        session_id: &str,
+    ) -> Result<AssembledPayload, AppError> {
+        let mut payload = batch_to_payload_with(
+            frames,
+            session_id,
+            &self.mosaic_options,
+            &self.quality_thresholds,
+        )?;
+
+        payload.metadata.provenance = self.provenance.clone();
+        payload.metadata.device_id = self.device_id.clone();
+
+        let mut context_errors = Vec::new();
+        if let Some(collector) = &self.context {
+            let collection = collector.collect();
+            payload.metadata.context = collection.context;
+            context_errors = collection.errors;
+        }
+
+        // Invariant:
+        // - Identity and chain link are assigned last, once the payload
+        //   content is final, because the chain hash covers all metadata.
+        let chain = self.chain_payload(&mut payload, session_id)?;
+
+        Ok(AssembledPayload {
+            payload,
+            context_errors,
+            chain,
+        })
+    }
+
+    fn chain_payload(
+        &self,
+        payload: &mut MosaicPayload,
+        session_id: &str,
+    ) -> Result<ChainState, AppError> {
+        // Failure mode:
+        // - A poisoned lock only means another assembly panicked mid-update;
+        //   the state is still a valid (rng, last) pair, so keep using it.
+        let mut state = self
+            .sequence
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let head = match &state.chain {
+            Some(chain) if chain.session_id == session_id => chain.clone(),
+            _ => ChainState::root(session_id),
+        };
+
+        let mut bytes = [0_u8; 16];
+        state.rng.fill_bytes(&mut bytes);
+        payload.metadata.batch_id = Some(format_uuid_v4(bytes));
+        payload.metadata.sequence_number = Some(head.sequence_number.saturating_add(1));
+        payload.metadata.prev_payload_hash = Some(head.head_hash);
+
+        let next = ChainState {
+            session_id: session_id.to_string(),
+            sequence_number: head.sequence_number.saturating_add(1),
+            head_hash: payload_chain_hash(payload).map_err(AppError::Core)?,
+        };
+        state.chain = Some(next.clone());
+        Ok(next)
+    }
+}
+
+/// Formats 16 random bytes as an RFC 4122 version 4 UUID string.
+fn format_uuid_v4(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
@@ -0,0 +1,72 @@
+//! # Module: budget_store
+//!
+//! ## Purpose
+//! Persists upload data budget counters so a restart does not reset the
+//! bytes already sent today or this month.
+//!
+//! ## Responsibilities
+//! - Load the last saved [`BudgetUsage`] (absent file means nothing sent).
+//! - Save counters atomically after uploads.
+//!
+//! ## Invariants
+//! - Saves use the same temp-file-and-rename scheme as
+//!   [`crate::ChainStore`], so a crash never leaves a torn file.
+//!
+//! ## Error model
+//! I/O and decode failures return [`AppError::Persistence`]; a corrupt file
+//! is reported rather than treated as zero usage, which would silently
+//! reopen an exhausted budget.
+//!
+//! ## Security and privacy notes
+//! The file holds byte counts and period labels only.
+
+use std::path::{Path, PathBuf};
+
+use local_guard_upload::BudgetUsage;
+
+use crate::AppError;
+use crate::chain_store::{load_json_file, save_json_file};
+
+/// File name used for budget counters inside the runtime directory.
+pub const BUDGET_USAGE_FILE_NAME: &str = "upload-budget.json";
+
+/// File-backed store for upload data budget counters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetStore {
+    path: PathBuf,
+}
+
+impl BudgetStore {
+    /// Creates a store backed by the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a store for [`BUDGET_USAGE_FILE_NAME`] inside `dir`.
+    pub fn in_dir(dir: impl AsRef<Path>) -> Self {
+        Self::new(dir.as_ref().join(BUDGET_USAGE_FILE_NAME))
+    }
+
+    /// Returns the backing file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads saved counters; default (empty) usage when nothing was saved.
+    ///
+    /// # Errors
+    /// Returns [`AppError::Persistence`] when the file cannot be read or
+    /// decoded.
+    pub fn load(&self) -> Result<BudgetUsage, AppError> {
+        load_json_file(&self.path).map(Option::unwrap_or_default)
+    }
+
+    /// Atomically replaces the saved counters with `usage`.
+    ///
+    /// # Errors
+    /// Returns [`AppError::Persistence`] when the directory, temp file, or
+    /// rename fails.
+    pub fn save(&self, usage: &BudgetUsage) -> Result<(), AppError> {
+        save_json_file(&self.path, usage, "budget usage")
+    }
+}
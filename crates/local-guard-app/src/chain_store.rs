@@ -0,0 +1,126 @@
+//! # Module: chain_store
+//!
+//! ## Purpose
+//! Persists the payload hash-chain head so a restarted client continues its
+//! session chain instead of silently starting a new one.
+//!
+//! The ingest contract chains payloads per session, rooted at login. A
+//! restart that resumes the session (or restarts the capture worker within
+//! it) continues the saved chain; a new login is a new session and starts a
+//! new chain at its own root, replacing the saved head on the first save.
+//!
+//! ## Responsibilities
+//! - Load the last saved [`ChainState`] (absent file means no chain yet).
+//! - Save the chain head atomically after every assembled payload.
+//!
+//! ## Invariants
+//! - Saves write a sibling temp file and rename it over the target, so a
+//!   crash leaves either the old or the new state, never a torn file.
+//!
+//! ## Error model
+//! I/O and decode failures return [`AppError::Persistence`]; a corrupt file
+//! is reported rather than treated as "no chain", because resetting would
+//! look like a dropped batch to the server.
+//!
+//! ## Security and privacy notes
+//! The file holds a session id, a counter, and a hash; no pixels or tokens.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use local_guard_core::ChainState;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::AppError;
+
+/// File name used for the chain state inside the runtime directory.
+pub const CHAIN_STATE_FILE_NAME: &str = "chain-state.json";
+
+/// File-backed store for the payload chain head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainStore {
+    path: PathBuf,
+}
+
+impl ChainStore {
+    /// Creates a store backed by the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a store for [`CHAIN_STATE_FILE_NAME`] inside `dir`.
+    pub fn in_dir(dir: impl AsRef<Path>) -> Self {
+        Self::new(dir.as_ref().join(CHAIN_STATE_FILE_NAME))
+    }
+
+    /// Returns the backing file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the saved chain head; `Ok(None)` when nothing was saved yet.
+    ///
+    /// # Errors
+    /// Returns [`AppError::Persistence`] when the file cannot be read or
+    /// decoded.
+    pub fn load(&self) -> Result<Option<ChainState>, AppError> {
+        load_json_file(&self.path)
+    }
+
+    /// Atomically replaces the saved chain head with `state`.
+    ///
+    /// # Errors
+    /// Returns [`AppError::Persistence`] when the directory, temp file, or
+    /// rename fails.
+    pub fn save(&self, state: &ChainState) -> Result<(), AppError> {
+        save_json_file(&self.path, state, "chain state")
+    }
+}
+
+/// Reads and decodes the JSON file at `path`; `Ok(None)` when it is absent.
+pub(crate) fn load_json_file<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, AppError> {
+    let raw = match std::fs::read(path) {
+        Ok(raw) => raw,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(AppError::Persistence(format!(
+                "failed to read {}: {error}",
+                path.display()
+            )));
+        }
+    };
+    serde_json::from_slice(&raw).map(Some).map_err(|error| {
+        AppError::Persistence(format!("failed to decode {}: {error}", path.display()))
+    })
+}
+
+/// Atomically replaces the file at `path` with `value` as pretty JSON.
+pub(crate) fn save_json_file<T: Serialize>(
+    path: &Path,
+    value: &T,
+    what: &str,
+) -> Result<(), AppError> {
+    let persistence = |action: &str, error: std::io::Error| {
+        AppError::Persistence(format!("failed to {action} {}: {error}", path.display()))
+    };
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent).map_err(|error| persistence("create dir for", error))?;
+    }
+    let encoded = serde_json::to_vec_pretty(value)
+        .map_err(|error| AppError::Persistence(format!("failed to encode {what}: {error}")))?;
+
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    let mut file =
+        std::fs::File::create(&temp_path).map_err(|error| persistence("write", error))?;
+    file.write_all(&encoded)
+        .and_then(|()| file.sync_all())
+        .map_err(|error| persistence("write", error))?;
+    drop(file);
+    std::fs::rename(&temp_path, path).map_err(|error| persistence("replace", error))
+}
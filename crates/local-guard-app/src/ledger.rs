@@ -0,0 +1,236 @@
+//! # Module: ledger
+//!
+//! ## Purpose
+//! Remembers recently assembled batches so analysis responses (identified
+//! by `request_id`) can be traced back to the batch that produced them.
+//!
+//! ## Responsibilities
+//! - Record batch id, sequence number, session, and idempotency key.
+//! - Link a server `request_id` to its originating batch, from the ingest
+//!   acknowledgement or the analysis response.
+//! - Bound memory by evicting the oldest records.
+//!
+//! ## Invariants
+//! - At most `capacity` records are kept; eviction is FIFO.
+//! - Only payloads with both `batch_id` and `sequence_number` are recorded.
+//!
+//! ## Error model
+//! Lookups return `None` for unknown or evicted batches;
+//! [`link_analysis_response`] and [`link_ingest_ack`] report contract
+//! mismatches as [`AppError`].
+//!
+//! ## Security and privacy notes
+//! Records hold identifiers only, never pixels or context values.
+
+use std::collections::VecDeque;
+
+use local_guard_analysis_contract::{
+    AnalysisContractError, IngestAck, UiRiskSignal, map_risk_signals, parse_analysis_response,
+};
+use local_guard_core::MosaicPayload;
+use local_guard_upload::idempotency_key_for_payload;
+
+use crate::AppError;
+
+/// Default number of batches a [`BatchLedger`] remembers.
+pub const DEFAULT_LEDGER_CAPACITY: usize = 256;
+
+/// Correlation record for one assembled batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchRecord {
+    /// Unique batch identifier.
+    pub batch_id: String,
+    /// Per-session sequence number.
+    pub sequence_number: u64,
+    /// Session the batch belongs to.
+    pub session_id: String,
+    /// Display the batch was captured from.
+    pub screen_id: String,
+    /// Upload idempotency key of the payload.
+    pub idempotency_key: String,
+    /// Server request id, once an analysis response was linked.
+    pub request_id: Option<String>,
+}
+
+/// Bounded in-memory index of recent batches.
+#[derive(Debug, Clone)]
+pub struct BatchLedger {
+    capacity: usize,
+    records: VecDeque<BatchRecord>,
+}
+
+impl BatchLedger {
+    /// Creates a ledger keeping at most `capacity` records (minimum 1).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records `payload`, evicting the oldest record when full.
+    ///
+    /// Returns `None` when the payload carries no batch identity.
+    pub fn record(&mut self, payload: &MosaicPayload) -> Option<&BatchRecord> {
+        let metadata = &payload.metadata;
+        let batch_id = metadata.batch_id.clone()?;
+        let sequence_number = metadata.sequence_number?;
+
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(BatchRecord {
+            batch_id,
+            sequence_number,
+            session_id: metadata.session_id.clone(),
+            screen_id: metadata.screen_id.clone(),
+            idempotency_key: idempotency_key_for_payload(payload),
+            request_id: None,
+        });
+        self.records.back()
+    }
+
+    /// Returns the record for `batch_id`.
+    pub fn get(&self, batch_id: &str) -> Option<&BatchRecord> {
+        self.records
+            .iter()
+            .find(|record| record.batch_id == batch_id)
+    }
+
+    /// Returns the record already linked to `request_id`.
+    pub fn find_by_request_id(&self, request_id: &str) -> Option<&BatchRecord> {
+        self.records
+            .iter()
+            .find(|record| record.request_id.as_deref() == Some(request_id))
+    }
+
+    /// Returns the record whose payload had `idempotency_key`.
+    pub fn find_by_idempotency_key(&self, idempotency_key: &str) -> Option<&BatchRecord> {
+        self.records
+            .iter()
+            .find(|record| record.idempotency_key == idempotency_key)
+    }
+
+    /// Links `request_id` to `batch_id` and returns the updated record.
+    pub fn link_request(&mut self, batch_id: &str, request_id: &str) -> Option<&BatchRecord> {
+        let record = self
+            .records
+            .iter_mut()
+            .find(|record| record.batch_id == batch_id)?;
+        record.request_id = Some(request_id.to_string());
+        Some(record)
+    }
+
+    /// Returns the number of remembered batches.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns `true` when no batch is remembered.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl Default for BatchLedger {
+    fn default() -> Self {
+        Self::new(DEFAULT_LEDGER_CAPACITY)
+    }
+}
+
+/// Analysis response linked back to its originating batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedAnalysis {
+    /// Server request id.
+    pub request_id: String,
+    /// Originating batch, when known to the ledger.
+    pub batch: Option<BatchRecord>,
+    /// UI-safe risk signals.
+    pub signals: Vec<UiRiskSignal>,
+}
+
+/// Parses an analysis response and links its `request_id` to a batch.
+///
+/// # Parameters
+/// - `uploaded_batch_id`: batch id of the upload that returned `raw`; used
+///   when the server does not echo `batch_id`.
+///
+/// Without either id, the batch already linked to `request_id` (by
+/// [`link_ingest_ack`]) is used.
+///
+/// # Errors
+/// Returns [`AppError::Analysis`] when parsing fails or when the echoed
+/// `batch_id` contradicts `uploaded_batch_id`.
+pub fn link_analysis_response(
+    ledger: &mut BatchLedger,
+    raw: &str,
+    uploaded_batch_id: Option<&str>,
+) -> Result<LinkedAnalysis, AppError> {
+    let response = parse_analysis_response(raw).map_err(AppError::Analysis)?;
+    let batch = match resolve_batch_id(response.batch_id.as_deref(), uploaded_batch_id)? {
+        Some(batch_id) => ledger.link_request(batch_id, &response.request_id),
+        // Why: an ingest acknowledgement may already have linked the
+        // request id; see [`link_ingest_ack`].
+        None => ledger.find_by_request_id(&response.request_id),
+    }
+    .cloned();
+
+    Ok(LinkedAnalysis {
+        request_id: response.request_id.clone(),
+        batch,
+        signals: map_risk_signals(&response),
+    })
+}
+
+/// Links an ingest acknowledgement's `request_id` to its batch, so the
+/// analysis that follows resolves through
+/// [`BatchLedger::find_by_request_id`].
+///
+/// The batch is the one the acknowledgement echoes, else
+/// `uploaded_batch_id`, else the record with the echoed idempotency key.
+///
+/// Returns the linked analysis when the acknowledgement carries one; `None`
+/// when analysis arrives later.
+///
+/// # Errors
+/// Returns [`AppError::Analysis`] when the echoed `batch_id` contradicts
+/// `uploaded_batch_id`.
+pub fn link_ingest_ack(
+    ledger: &mut BatchLedger,
+    ack: &IngestAck,
+    uploaded_batch_id: Option<&str>,
+) -> Result<Option<LinkedAnalysis>, AppError> {
+    let batch_id = match resolve_batch_id(ack.batch_id.as_deref(), uploaded_batch_id)? {
+        Some(batch_id) => Some(batch_id.to_string()),
+        None => ack
+            .idempotency_key
+            .as_deref()
+            .and_then(|key| ledger.find_by_idempotency_key(key))
+            .map(|record| record.batch_id.clone()),
+    };
+    let batch = batch_id
+        .and_then(|batch_id| ledger.link_request(&batch_id, &ack.request_id))
+        .cloned();
+
+    Ok(ack.analysis.as_ref().map(|analysis| LinkedAnalysis {
+        request_id: ack.request_id.clone(),
+        batch,
+        signals: map_risk_signals(analysis),
+    }))
+}
+
+fn resolve_batch_id<'a>(
+    echoed: Option<&'a str>,
+    uploaded: Option<&'a str>,
+) -> Result<Option<&'a str>, AppError> {
+    match (echoed, uploaded) {
+        (Some(echoed), Some(uploaded)) if echoed != uploaded => {
+            Err(AppError::Analysis(AnalysisContractError::InvalidContract(
+                format!("response batch_id {echoed} does not match uploaded batch {uploaded}"),
+            )))
+        }
+        (Some(echoed), _) => Ok(Some(echoed)),
+        (None, uploaded) => Ok(uploaded),
+    }
+}
@@ -6,8 +6,21 @@
 //!
 //! ## Responsibilities
 //! - Enforce auth and consent gates before capture.
-//! - Convert chronological frame batches into upload payloads.
-//! - Provide transport security checks and kill-switch behavior.
+//! - Run capture ticks into per-display batches and recover from resolution
+//!   changes.
+//! - Convert chronological frame batches into upload payloads, enriched with
+//!   optional host context ([`PayloadAssembler`]).
+//! - Correlate analysis responses with originating batches ([`BatchLedger`]).
+//! - Persist the payload hash-chain head across restarts ([`ChainStore`]).
+//! - Configure upload pacing and data budgets, persist budget counters
+//!   ([`BudgetStore`]), and derive capture adjustments once a budget is
+//!   exhausted ([`budget_adjustment`]).
+//! - Hold assembled payloads until the upload dispatcher accepts them
+//!   ([`UploadBacklog`]).
+//! - Seal staged artifacts to the analysis backend key when configured
+//!   ([`body_sealer_from_env`], [`seal_artifact`]).
+//! - Provide transport security checks ([`transport_security_from_env`]),
+//!   proxy settings ([`proxy_from_env`]), and kill-switch behavior.
 //! - Project analysis responses into UI-safe status signals.
 //!
 //! ## Data flow
@@ -27,26 +40,99 @@
 //! - Kill-switch env var can stop capture safely at runtime.
 //! - Log redaction helpers strip token/credential strings.
 
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
 use local_guard_analysis_contract::{
     AnalysisContractError, UiRiskSignal, map_risk_signals, parse_analysis_response,
 };
-use local_guard_auth::{AuthError, AuthStateMachine};
-use local_guard_capture::{CaptureConfig, DisplayInfo, scheduled_capture_times};
-use local_guard_core::{Frame, MosaicPayload, SCHEMA_VERSION_V1, build_metadata};
-use local_guard_mosaic::{MosaicError, compose_temporal_mosaic};
-use local_guard_ui::UiState;
-use local_guard_upload::{UploadClient, UploadError, UploadReport};
+use local_guard_auth::{
+    AuthError, AuthStateMachine, DeviceIdentity, DeviceIdentityStore, DeviceStatus,
+    EnrollmentClient, EnrollmentState,
+};
+use local_guard_capture::{
+    CaptureBackend, CaptureConfig, CaptureMode, DEFAULT_BLANK_RETRY_DELAY, DisplayInfo,
+    MissedTickPolicy, capture_frame_with_blank_retry, scheduled_capture_times,
+};
+use local_guard_context::ContextPolicy;
+use local_guard_core::{
+    BatchMetadata, ClientProvenance, Frame, MosaicPayload, MultiDisplayFrameBatch, PixelFormat,
+    QualityThresholds, SCHEMA_VERSION_V1, build_metadata_with_thresholds,
+};
+use local_guard_crypto::{BodySealer, CryptoError, DeviceKey, ServerPublicKey};
+use local_guard_mosaic::{CursorOverlay, MosaicError, MosaicOptions, compose_temporal_mosaic_with};
+use local_guard_transport::{
+    ClientCertificateStatus, ClientIdentity, ProxyConfig, SpkiPin, TlsVersion, TransportError,
+    TransportSecurity, TransportSecurityError,
+};
+use local_guard_ui::{
+    StageStatus, UiBudgetAction, UiBudgetPeriod, UiCaptureMode, UiClientCertificateState,
+    UiDataBudgetState, UiDeviceState, UiState,
+};
+use local_guard_upload::{
+    BudgetExhaustionPolicy, BudgetMeter, BudgetPeriod, BudgetStatus, ChunkingPolicy,
+    CircuitBreaker, CircuitBreakerConfig, CircuitState, CompressionPolicy, ContentEncoding,
+    DataBudget, DispatcherConfig, RateLimiter, UploadClient, UploadClock, UploadDispatcher,
+    UploadError, UploadOrdering, UploadReport,
+};
 use thiserror::Error;
 use url::Url;
 
+mod assembler;
+mod budget_store;
+mod chain_store;
+mod ledger;
+mod upload_backlog;
+
+pub use assembler::{AssembledPayload, PayloadAssembler};
+pub use budget_store::{BUDGET_USAGE_FILE_NAME, BudgetStore};
+pub use chain_store::{CHAIN_STATE_FILE_NAME, ChainStore};
+pub use ledger::{
+    BatchLedger, BatchRecord, DEFAULT_LEDGER_CAPACITY, LinkedAnalysis, link_analysis_response,
+    link_ingest_ack,
+};
+pub use upload_backlog::{DEFAULT_UPLOAD_BACKLOG_BYTES, UploadBacklog};
+
 /// Build-time application version loaded from root `VERSION` file.
 pub const APP_VERSION: &str = env!("LOCAL_GUARD_VERSION");
 
+/// Git commit embedded by `build.rs` (`unknown` outside a git checkout).
+pub const BUILD_GIT_HASH: &str = env!("LOCAL_GUARD_GIT_HASH");
+
+/// Rust target triple embedded by `build.rs`.
+pub const BUILD_TARGET: &str = env!("LOCAL_GUARD_BUILD_TARGET");
+
+/// Capture rate used when `LOCAL_GUARD_CAPTURE_FPS` is unset or invalid.
+pub const DEFAULT_CAPTURE_FPS: f64 = 1.0;
+
+/// Default replay bound for `catch-up` without an explicit burst size.
+pub const DEFAULT_CATCH_UP_BURST: u32 = 3;
+
+/// Capture interval multiplier while an exhausted budget uses
+/// [`BudgetExhaustionPolicy::ReduceFps`].
+pub const BUDGET_REDUCED_FPS_DIVISOR: u32 = 4;
+
 /// Consolidated runtime status snapshot for simple UI projection.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RuntimeStatus {
     /// Whether auth/consent/display gates currently allow capture.
     pub capture_allowed: bool,
+    /// Capture mode (`SelectedDisplay` or `AllDisplays`).
+    pub capture_mode: String,
+    /// Device enrollment state (`Unenrolled`, `Enrolled`, `Revoked`).
+    pub device: String,
+    /// Mutual TLS client certificate state (`NotConfigured`, `Valid`, ...).
+    pub client_certificate: String,
+    /// Client certificate warning text, when the certificate needs
+    /// attention.
+    pub client_certificate_warning: Option<String>,
+    /// Upload data budget state (`Unlimited`, `Within`, `Exhausted`).
+    pub data_budget: String,
+    /// Data budget warning text, when usage is high or exhausted.
+    pub data_budget_warning: Option<String>,
+    /// Upload rate limit in bytes per second, when pacing is configured.
+    pub upload_rate_limit_bytes_per_sec: Option<u64>,
     /// Capture subsystem state as human-readable string.
     pub capture: String,
     /// Network subsystem state.
@@ -62,11 +148,159 @@ pub fn app_version() -> &'static str {
     APP_VERSION
 }
 
+/// Returns the running OS version, or `unknown` when it cannot be read.
+///
+/// # Notes
+/// Windows uses `RtlGetVersion`, which (unlike `GetVersionExW`) is not
+/// capped by the application manifest; Linux reads the kernel release.
+pub fn os_version() -> String {
+    #[cfg(windows)]
+    {
+        use windows_sys::Wdk::System::SystemServices::RtlGetVersion;
+        use windows_sys::Win32::System::SystemInformation::OSVERSIONINFOW;
+
+        // Safety:
+        // - `OSVERSIONINFOW` is plain data with its size field set before the
+        //   call; the pointer is valid for the duration of the call.
+        let mut info: OSVERSIONINFOW = unsafe { std::mem::zeroed() };
+        info.dwOSVersionInfoSize = std::mem::size_of::<OSVERSIONINFOW>() as u32;
+        if unsafe { RtlGetVersion(&mut info) } == 0 {
+            return format!(
+                "{}.{}.{}",
+                info.dwMajorVersion, info.dwMinorVersion, info.dwBuildNumber
+            );
+        }
+        "unknown".to_string()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .map(|release| release.trim().to_string())
+            .ok()
+            .filter(|release| !release.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        "unknown".to_string()
+    }
+}
+
+/// Builds the provenance block attached to every payload.
+///
+/// # Parameters
+/// - `capture_backend`: [`CaptureBackend::backend_name`] of the producer.
+/// - `encoder_settings`: encoder parameters in effect (format, quality).
+pub fn client_provenance(
+    capture_backend: &str,
+    encoder_settings: BTreeMap<String, String>,
+) -> ClientProvenance {
+    ClientProvenance {
+        app_version: APP_VERSION.to_string(),
+        git_hash: BUILD_GIT_HASH.to_string(),
+        target_triple: BUILD_TARGET.to_string(),
+        os_name: std::env::consts::OS.to_string(),
+        os_version: os_version(),
+        capture_backend: capture_backend.to_string(),
+        encoder_settings,
+    }
+}
+
 /// Returns `true` when auth state machine allows capture.
 pub fn auth_allows_capture(machine: &AuthStateMachine, now_ms: u64) -> bool {
     machine.can_capture(now_ms)
 }
 
+/// Returns `true` when the device enrollment state allows capture.
+pub fn device_allows_capture(enrollment: &EnrollmentState) -> bool {
+    enrollment.allows_capture()
+}
+
+/// Projects enrollment state into the UI device state.
+pub fn device_state_for_ui(enrollment: &EnrollmentState) -> UiDeviceState {
+    match enrollment {
+        EnrollmentState::Unenrolled => UiDeviceState::Unenrolled,
+        EnrollmentState::Enrolled(_) => UiDeviceState::Enrolled,
+        EnrollmentState::Revoked(_) => UiDeviceState::Revoked,
+    }
+}
+
+/// Reads the one-time enrollment code from `LOCAL_GUARD_ENROLLMENT_CODE`.
+pub fn enrollment_code_from_env() -> Option<String> {
+    std::env::var("LOCAL_GUARD_ENROLLMENT_CODE")
+        .ok()
+        .map(|code| code.trim().to_string())
+        .filter(|code| !code.is_empty())
+}
+
+/// Reads the protected ingest endpoint from `LOCAL_GUARD_INGEST_URL`.
+///
+/// Unset or blank leaves uploads off; staged batches are then only written
+/// to disk.
+pub fn ingest_url_from_env() -> Option<String> {
+    std::env::var("LOCAL_GUARD_INGEST_URL")
+        .ok()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+/// Restores the stored device identity or enrolls with `enrollment_code`,
+/// then refreshes the server status.
+///
+/// # Semantics
+/// - A stored identity for a different key counts as unenrolled.
+/// - A stored revoked identity restores as [`EnrollmentState::Revoked`]
+///   without asking the server; only a new enrollment code replaces it.
+/// - A refresh that reports revocation is saved before returning.
+/// - Status refresh failures keep the last known state (offline start).
+///
+/// # Errors
+/// Returns [`AppError::Auth`] when identity storage fails or enrollment is
+/// rejected.
+pub fn restore_enrollment(
+    store: &DeviceIdentityStore,
+    client: &EnrollmentClient,
+    key: &DeviceKey,
+    enrollment_code: Option<&str>,
+    now_ms: u64,
+) -> Result<EnrollmentState, AppError> {
+    let key_id = key.public_key().key_id();
+    let identity = store.load()?.filter(|identity| identity.key_id == key_id);
+
+    let identity = match (identity, enrollment_code) {
+        (Some(identity), Some(code)) if identity.status == DeviceStatus::Revoked => {
+            enroll_and_save(store, client, key, code, now_ms)?
+        }
+        (Some(identity), _) => identity,
+        (None, Some(code)) => enroll_and_save(store, client, key, code, now_ms)?,
+        (None, None) => return Ok(EnrollmentState::Unenrolled),
+    };
+
+    let mut state = EnrollmentState::from_identity(Some(identity));
+    if let EnrollmentState::Enrolled(identity) = &state
+        && let Ok(status) = client.refresh_status(identity)
+        && state.on_status(status)
+        && let Some(revoked) = state.identity()
+    {
+        store.save(revoked)?;
+    }
+    Ok(state)
+}
+
+fn enroll_and_save(
+    store: &DeviceIdentityStore,
+    client: &EnrollmentClient,
+    key: &DeviceKey,
+    enrollment_code: &str,
+    now_ms: u64,
+) -> Result<DeviceIdentity, AppError> {
+    let identity = client.enroll(enrollment_code, key, now_ms)?;
+    store.save(&identity)?;
+    Ok(identity)
+}
+
 /// Schedules deterministic capture times at configured FPS.
 ///
 /// # Errors
@@ -76,6 +310,82 @@ pub fn schedule_capture(fps: u32, start_ms: u64, frame_count: usize) -> Result<V
     Ok(scheduled_capture_times(config, start_ms, frame_count))
 }
 
+/// Parses a capture rate such as `1`, `2.5`, or `0.2` (one frame every 5 s).
+///
+/// # Errors
+/// Returns [`AppError::Capture`] when the value is not a number or is outside
+/// the supported FPS range.
+pub fn parse_capture_fps(raw: &str) -> Result<CaptureConfig, AppError> {
+    let fps = raw
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| AppError::Capture(local_guard_capture::CaptureError::InvalidFps))?;
+    CaptureConfig::from_fps(fps).map_err(AppError::Capture)
+}
+
+/// Reads capture cadence from `LOCAL_GUARD_CAPTURE_FPS`.
+///
+/// Unset or invalid values fall back to [`DEFAULT_CAPTURE_FPS`]; use
+/// [`try_capture_config_from_env`] to report an invalid value.
+pub fn capture_config_from_env() -> CaptureConfig {
+    try_capture_config_from_env()
+        .ok()
+        .flatten()
+        .unwrap_or_else(default_capture_config)
+}
+
+/// Reads capture cadence from `LOCAL_GUARD_CAPTURE_FPS`.
+///
+/// # Returns
+/// `Ok(None)` when unset.
+///
+/// # Errors
+/// Returns [`AppError::Capture`] when the value is set but invalid.
+pub fn try_capture_config_from_env() -> Result<Option<CaptureConfig>, AppError> {
+    match std::env::var("LOCAL_GUARD_CAPTURE_FPS") {
+        Ok(raw) => parse_capture_fps(&raw).map(Some),
+        Err(_) => Ok(None),
+    }
+}
+
+fn default_capture_config() -> CaptureConfig {
+    CaptureConfig::from_fps(DEFAULT_CAPTURE_FPS).unwrap_or_default()
+}
+
+/// Parses a missed-tick policy name.
+///
+/// Accepted forms (case-insensitive):
+/// - `skip`
+/// - `catch-up` (uses [`DEFAULT_CATCH_UP_BURST`])
+/// - `catch-up:<max_burst>`
+///
+/// Returns `None` for anything else.
+pub fn parse_missed_tick_policy(raw: &str) -> Option<MissedTickPolicy> {
+    let normalized = raw.trim().to_ascii_lowercase();
+    match normalized.split_once(':') {
+        None if normalized == "skip" => Some(MissedTickPolicy::Skip),
+        None if normalized == "catch-up" => Some(MissedTickPolicy::CatchUp {
+            max_burst: DEFAULT_CATCH_UP_BURST,
+        }),
+        Some(("catch-up", burst)) => burst
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .map(|max_burst| MissedTickPolicy::CatchUp { max_burst }),
+        _ => None,
+    }
+}
+
+/// Reads missed-tick policy from `LOCAL_GUARD_CAPTURE_MISSED_TICKS`.
+///
+/// Unset or invalid values fall back to [`MissedTickPolicy::Skip`].
+pub fn missed_tick_policy_from_env() -> MissedTickPolicy {
+    std::env::var("LOCAL_GUARD_CAPTURE_MISSED_TICKS")
+        .ok()
+        .and_then(|raw| parse_missed_tick_policy(&raw))
+        .unwrap_or_default()
+}
+
 /// Selects a display by id from enumerated display list.
 pub fn select_display(displays: &[DisplayInfo], display_id: &str) -> Option<DisplayInfo> {
     displays
@@ -84,21 +394,795 @@ pub fn select_display(displays: &[DisplayInfo], display_id: &str) -> Option<Disp
         .cloned()
 }
 
+/// Maps the UI capture-mode projection to the capture-layer mode.
+pub fn capture_mode_for_ui(mode: UiCaptureMode) -> CaptureMode {
+    match mode {
+        UiCaptureMode::SelectedDisplay => CaptureMode::SelectedDisplay,
+        UiCaptureMode::AllDisplays => CaptureMode::AllDisplays,
+    }
+}
+
+/// Resolves the display ids one capture tick must acquire.
+///
+/// # Returns
+/// - `SelectedDisplay`: the selected id when it is still enumerated, else empty.
+/// - `AllDisplays`: every enumerated display id in enumeration order.
+///
+/// An empty result means the tick has nothing to capture and callers should
+/// treat it as a configuration error rather than silently idling.
+pub fn resolve_capture_targets(
+    mode: CaptureMode,
+    displays: &[DisplayInfo],
+    selected_display: Option<&str>,
+) -> Vec<String> {
+    match mode {
+        CaptureMode::SelectedDisplay => selected_display
+            .and_then(|display_id| select_display(displays, display_id))
+            .map(|display| vec![display.id])
+            .unwrap_or_default(),
+        CaptureMode::AllDisplays => displays.iter().map(|display| display.id.clone()).collect(),
+    }
+}
+
+/// Result of one successful capture tick.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CaptureTickOutcome {
+    /// Frames captured in this tick (one per target display).
+    pub frames_captured: usize,
+    /// Batches that reached capacity during this tick.
+    pub completed_batches: Vec<Vec<Frame>>,
+    /// Width of the first captured frame.
+    pub frame_width: u32,
+    /// Height of the first captured frame.
+    pub frame_height: u32,
+    /// Display ids whose partial batch was discarded after a geometry change.
+    pub geometry_resets: Vec<String>,
+    /// Buffered frames discarded by geometry resets.
+    pub frames_discarded: usize,
+    /// Extra capture attempts made because frames came back blank.
+    pub blank_retries: u32,
+}
+
+/// Per-tick capture options.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureTickOptions {
+    /// Re-capture attempts per display while the frame is blank; `0` disables
+    /// retries and skips per-tick classification.
+    pub blank_retries: u32,
+    /// Pause before each blank-frame re-capture.
+    pub blank_retry_delay: Duration,
+    /// Thresholds used to classify blank frames.
+    pub thresholds: QualityThresholds,
+}
+
+impl Default for CaptureTickOptions {
+    fn default() -> Self {
+        Self {
+            blank_retries: 0,
+            blank_retry_delay: DEFAULT_BLANK_RETRY_DELAY,
+            thresholds: QualityThresholds::default(),
+        }
+    }
+}
+
+/// Reads blank-frame retry count from `LOCAL_GUARD_BLANK_FRAME_RETRIES`.
+///
+/// Unset or invalid values disable retries (`0`).
+pub fn blank_frame_retries_from_env() -> u32 {
+    std::env::var("LOCAL_GUARD_BLANK_FRAME_RETRIES")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u32>().ok())
+        .unwrap_or(0)
+}
+
+/// Reads the blank-frame re-capture pause from
+/// `LOCAL_GUARD_BLANK_FRAME_RETRY_DELAY_MS`.
+///
+/// Unset or invalid values use [`DEFAULT_BLANK_RETRY_DELAY`].
+pub fn blank_frame_retry_delay_from_env() -> Duration {
+    std::env::var("LOCAL_GUARD_BLANK_FRAME_RETRY_DELAY_MS")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .map_or(DEFAULT_BLANK_RETRY_DELAY, Duration::from_millis)
+}
+
+/// Parses a cursor overlay flag value.
+///
+/// Semantics:
+/// - `1`, `true`, `on` (case-insensitive) => default [`CursorOverlay`].
+/// - Any other value => no overlay.
+pub fn parse_cursor_overlay(raw: &str) -> Option<CursorOverlay> {
+    let normalized = raw.trim().to_ascii_lowercase();
+    matches!(normalized.as_str(), "1" | "true" | "on").then(CursorOverlay::default)
+}
+
+/// Reads the mosaic cursor overlay flag from `LOCAL_GUARD_CURSOR_OVERLAY`.
+///
+/// Unset disables the overlay; cursor state is still sent in metadata.
+pub fn cursor_overlay_from_env() -> Option<CursorOverlay> {
+    std::env::var("LOCAL_GUARD_CURSOR_OVERLAY")
+        .ok()
+        .and_then(|raw| parse_cursor_overlay(&raw))
+}
+
+/// Reads the context field policy from `LOCAL_GUARD_CONTEXT_FIELDS`.
+///
+/// Unset or invalid values disable context collection, so a typo can never
+/// widen what leaves the machine.
+pub fn context_policy_from_env() -> ContextPolicy {
+    std::env::var("LOCAL_GUARD_CONTEXT_FIELDS")
+        .ok()
+        .and_then(|raw| ContextPolicy::parse(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Reads upload body compression from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_UPLOAD_COMPRESSION`: `gzip` or `zstd`; unset, `off`, or
+///   any other value disables compression.
+/// - `LOCAL_GUARD_UPLOAD_COMPRESSION_MIN_BYTES`: bodies below this size are
+///   sent uncompressed; unset or invalid keeps
+///   [`local_guard_upload::DEFAULT_COMPRESSION_THRESHOLD_BYTES`].
+///
+/// Invalid values fall back to uncompressed uploads, which every ingest
+/// server accepts.
+pub fn upload_compression_from_env() -> Option<CompressionPolicy> {
+    let encoding = std::env::var("LOCAL_GUARD_UPLOAD_COMPRESSION")
+        .ok()
+        .and_then(|raw| ContentEncoding::parse(&raw))?;
+    let policy = CompressionPolicy::new(encoding);
+    Some(
+        match std::env::var("LOCAL_GUARD_UPLOAD_COMPRESSION_MIN_BYTES")
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+        {
+            Some(min_size_bytes) => policy.with_min_size_bytes(min_size_bytes),
+            None => policy,
+        },
+    )
+}
+
+/// Reads chunked upload settings from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_UPLOAD_CHUNKING`: `off` disables chunked uploads; any
+///   other value, or unset, keeps them on (servers without support fall
+///   back to single-shot automatically).
+/// - `LOCAL_GUARD_UPLOAD_CHUNK_BYTES`: requested chunk size; unset, zero,
+///   or invalid keeps [`local_guard_upload::DEFAULT_CHUNK_SIZE_BYTES`].
+/// - `LOCAL_GUARD_UPLOAD_CHUNKING_MIN_BYTES`: bodies below this size are
+///   sent single-shot; unset or invalid keeps
+///   [`local_guard_upload::DEFAULT_CHUNKING_THRESHOLD_BYTES`].
+pub fn upload_chunking_from_env() -> Option<ChunkingPolicy> {
+    let disabled = std::env::var("LOCAL_GUARD_UPLOAD_CHUNKING")
+        .is_ok_and(|raw| raw.trim().eq_ignore_ascii_case("off"));
+    if disabled {
+        return None;
+    }
+    let read_size = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+    };
+    let mut policy = ChunkingPolicy::new();
+    if let Some(chunk_size_bytes) =
+        read_size("LOCAL_GUARD_UPLOAD_CHUNK_BYTES").filter(|size| *size > 0)
+    {
+        policy = policy.with_chunk_size_bytes(chunk_size_bytes);
+    }
+    if let Some(min_body_bytes) = read_size("LOCAL_GUARD_UPLOAD_CHUNKING_MIN_BYTES") {
+        policy = policy.with_min_body_bytes(min_body_bytes);
+    }
+    Some(policy)
+}
+
+/// Builds the upload pacer from `LOCAL_GUARD_UPLOAD_RATE_LIMIT_BPS`
+/// (bytes per second).
+///
+/// Unset, zero, or invalid values disable pacing.
+pub fn upload_rate_limiter_from_env(clock: Arc<dyn UploadClock>) -> Option<RateLimiter> {
+    std::env::var("LOCAL_GUARD_UPLOAD_RATE_LIMIT_BPS")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .filter(|bytes_per_second| *bytes_per_second > 0)
+        .map(|bytes_per_second| RateLimiter::new(bytes_per_second, clock))
+}
+
+/// Builds the shared upload circuit breaker from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_UPLOAD_BREAKER_FAILURES`: consecutive retriable failures
+///   that open the circuit.
+/// - `LOCAL_GUARD_UPLOAD_BREAKER_COOL_DOWN_SECS`: seconds the circuit stays
+///   open before a probe.
+///
+/// Unset, zero, or invalid values keep the defaults.
+pub fn circuit_breaker_from_env(clock: Arc<dyn UploadClock>) -> CircuitBreaker {
+    let read = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+            .filter(|value| *value > 0)
+    };
+    let mut config = CircuitBreakerConfig::new();
+    if let Some(failures) = read("LOCAL_GUARD_UPLOAD_BREAKER_FAILURES") {
+        config = config.with_failure_threshold(u32::try_from(failures).unwrap_or(u32::MAX));
+    }
+    if let Some(seconds) = read("LOCAL_GUARD_UPLOAD_BREAKER_COOL_DOWN_SECS") {
+        config = config.with_cool_down(std::time::Duration::from_secs(seconds));
+    }
+    CircuitBreaker::new(config, clock)
+}
+
+/// Reads the upload dispatcher configuration from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_UPLOAD_WORKERS`: concurrent upload workers.
+/// - `LOCAL_GUARD_UPLOAD_MAX_IN_FLIGHT`: queued plus running payloads.
+/// - `LOCAL_GUARD_UPLOAD_ORDERING`: `session` (default) or `none`.
+///
+/// Unset, zero, or invalid values keep the defaults; an unknown ordering
+/// keeps per-session ordering.
+pub fn upload_dispatcher_config_from_env() -> DispatcherConfig {
+    let read = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<usize>().ok())
+            .filter(|value| *value > 0)
+    };
+    let mut config = DispatcherConfig::new();
+    if let Some(workers) = read("LOCAL_GUARD_UPLOAD_WORKERS") {
+        config = config.with_workers(workers);
+    }
+    if let Some(max_in_flight) = read("LOCAL_GUARD_UPLOAD_MAX_IN_FLIGHT") {
+        config = config.with_max_in_flight(max_in_flight);
+    }
+    let unordered = std::env::var("LOCAL_GUARD_UPLOAD_ORDERING")
+        .is_ok_and(|raw| raw.trim().eq_ignore_ascii_case("none"));
+    if unordered {
+        config = config.with_ordering(UploadOrdering::Unordered);
+    }
+    config
+}
+
+/// Cancels all dispatched uploads and empties the upload backlog once the
+/// session is gone (logout or expiry) or the kill switch disables capture.
+/// Returns how many payloads were outstanding; `0` when uploads may
+/// continue.
+pub fn cancel_uploads_unless_permitted(
+    dispatcher: &UploadDispatcher,
+    backlog: &UploadBacklog,
+    auth: &AuthStateMachine,
+    now_ms: u64,
+) -> usize {
+    // Invariant: uploads run under the same gate as capture, so nothing
+    // leaves the device after the user signs out or the switch is thrown.
+    if auth.can_capture(now_ms) && capture_enabled_from_env() {
+        return 0;
+    }
+    backlog.clear() + dispatcher.cancel_all()
+}
+
+/// Builds the upload backlog from `LOCAL_GUARD_UPLOAD_BACKLOG_BYTES`.
+///
+/// Unset, zero, or invalid values keep [`DEFAULT_UPLOAD_BACKLOG_BYTES`].
+pub fn upload_backlog_from_env() -> UploadBacklog {
+    let max_bytes = std::env::var("LOCAL_GUARD_UPLOAD_BACKLOG_BYTES")
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .filter(|bytes| *bytes > 0)
+        .unwrap_or(DEFAULT_UPLOAD_BACKLOG_BYTES);
+    UploadBacklog::new(max_bytes)
+}
+
+/// Reads the upload data budget from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_UPLOAD_DAILY_BUDGET_BYTES`: bytes per UTC day.
+/// - `LOCAL_GUARD_UPLOAD_MONTHLY_BUDGET_BYTES`: bytes per UTC month.
+/// - `LOCAL_GUARD_UPLOAD_BUDGET_POLICY`: `lower-quality`, `reduce-fps`, or
+///   `queue-only` (default, also used for invalid values).
+///
+/// Returns `None` when neither limit is set to a valid number.
+pub fn data_budget_from_env() -> Option<DataBudget> {
+    let read_limit = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|raw| raw.trim().parse::<u64>().ok())
+    };
+    let daily = read_limit("LOCAL_GUARD_UPLOAD_DAILY_BUDGET_BYTES");
+    let monthly = read_limit("LOCAL_GUARD_UPLOAD_MONTHLY_BUDGET_BYTES");
+    if daily.is_none() && monthly.is_none() {
+        return None;
+    }
+    let policy = std::env::var("LOCAL_GUARD_UPLOAD_BUDGET_POLICY")
+        .ok()
+        .and_then(|raw| BudgetExhaustionPolicy::parse(&raw))
+        .unwrap_or_default();
+    let mut budget = DataBudget::new(policy);
+    if let Some(limit_bytes) = daily {
+        budget = budget.with_daily_limit_bytes(limit_bytes);
+    }
+    if let Some(limit_bytes) = monthly {
+        budget = budget.with_monthly_limit_bytes(limit_bytes);
+    }
+    Some(budget)
+}
+
+/// Capture and staging changes in effect while a data budget is exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BudgetAdjustment {
+    /// Uploads are refused; staged batches wait for the period to reset.
+    pub uploads_paused: bool,
+    /// Capture runs [`BUDGET_REDUCED_FPS_DIVISOR`] times slower.
+    pub reduce_fps: bool,
+    /// Mosaics are encoded at reduced quality.
+    pub lower_quality: bool,
+}
+
+impl BudgetAdjustment {
+    /// Returns `config`, slowed down when [`Self::reduce_fps`] is set.
+    pub fn capture_config(&self, config: CaptureConfig) -> CaptureConfig {
+        if !self.reduce_fps {
+            return config;
+        }
+        config
+            .interval()
+            .checked_mul(BUDGET_REDUCED_FPS_DIVISOR)
+            .and_then(|interval| CaptureConfig::from_interval(interval).ok())
+            .unwrap_or(config)
+    }
+
+    /// Returns `base` JPEG quality, halved (minimum 1) when
+    /// [`Self::lower_quality`] is set.
+    pub fn jpeg_quality(&self, base: u8) -> u8 {
+        if self.lower_quality {
+            (base / 2).max(1)
+        } else {
+            base
+        }
+    }
+}
+
+/// Returns the adjustment for `meter`'s current status; no adjustment when
+/// no budget is configured or it has room left.
+pub fn budget_adjustment(meter: Option<&BudgetMeter>) -> BudgetAdjustment {
+    let Some(meter) = meter else {
+        return BudgetAdjustment::default();
+    };
+    if !matches!(meter.status(), BudgetStatus::Exhausted(_)) {
+        return BudgetAdjustment::default();
+    }
+    let policy = meter.budget().policy();
+    BudgetAdjustment {
+        uploads_paused: policy == BudgetExhaustionPolicy::QueueOnly,
+        reduce_fps: policy == BudgetExhaustionPolicy::ReduceFps,
+        lower_quality: policy == BudgetExhaustionPolicy::LowerQuality,
+    }
+}
+
+/// Projects `meter`'s current status into the UI state.
+pub fn data_budget_state_for_ui(meter: Option<&BudgetMeter>) -> UiDataBudgetState {
+    let Some(meter) = meter else {
+        return UiDataBudgetState::Unlimited;
+    };
+    let budget = meter.budget();
+    if budget.daily_limit_bytes().is_none() && budget.monthly_limit_bytes().is_none() {
+        return UiDataBudgetState::Unlimited;
+    }
+    match meter.status() {
+        BudgetStatus::Within { used_percent } => UiDataBudgetState::Within { used_percent },
+        BudgetStatus::Exhausted(period) => UiDataBudgetState::Exhausted {
+            period: match period {
+                BudgetPeriod::Daily => UiBudgetPeriod::Daily,
+                BudgetPeriod::Monthly => UiBudgetPeriod::Monthly,
+            },
+            action: match budget.policy() {
+                BudgetExhaustionPolicy::LowerQuality => UiBudgetAction::LowerQuality,
+                BudgetExhaustionPolicy::ReduceFps => UiBudgetAction::ReduceFps,
+                BudgetExhaustionPolicy::QueueOnly => UiBudgetAction::QueueOnly,
+            },
+        },
+    }
+}
+
+/// Projects the upload circuit breaker onto the network stage status.
+///
+/// `Idle` until the first send outcome, `Healthy` while closed without
+/// recent failures, and `Degraded` while failures accumulate, the circuit
+/// is open, or a half-open probe has not yet closed it.
+pub fn network_stage_status(breaker: Option<&CircuitBreaker>) -> StageStatus {
+    let Some(breaker) = breaker else {
+        return StageStatus::Idle;
+    };
+    let snapshot = breaker.snapshot();
+    match snapshot.state {
+        CircuitState::Closed if !snapshot.observed => StageStatus::Idle,
+        CircuitState::Closed if snapshot.consecutive_failures == 0 => StageStatus::Healthy,
+        CircuitState::Closed | CircuitState::Open | CircuitState::HalfOpen => StageStatus::Degraded,
+    }
+}
+
+/// Parses a hex-encoded X25519 analysis backend public key.
+///
+/// # Errors
+/// Returns [`AppError::Crypto`] for invalid hex or key length.
+pub fn parse_server_public_key(raw: &str) -> Result<ServerPublicKey, AppError> {
+    ServerPublicKey::from_hex(raw.trim()).map_err(AppError::Crypto)
+}
+
+/// Builds the payload sealer from `LOCAL_GUARD_SERVER_PUBLIC_KEY`.
+///
+/// Unset or blank disables sealing (`Ok(None)`).
+///
+/// # Errors
+/// Returns [`AppError::Crypto`] for a malformed key. Unlike other env
+/// settings this is not silently ignored: a typo must not downgrade staged
+/// artifacts to plaintext.
+pub fn body_sealer_from_env() -> Result<Option<BodySealer>, AppError> {
+    match std::env::var("LOCAL_GUARD_SERVER_PUBLIC_KEY") {
+        Ok(raw) if !raw.trim().is_empty() => {
+            parse_server_public_key(&raw).map(|key| Some(BodySealer::new(key)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Seals one staged artifact and returns the envelope JSON bytes.
+///
+/// `content_type` describes `plaintext` (for example `image/jpeg`) and is
+/// authenticated with the ciphertext.
+///
+/// # Errors
+/// Returns [`AppError::Crypto`] when sealing fails.
+pub fn seal_artifact(
+    sealer: &BodySealer,
+    plaintext: &[u8],
+    content_type: &str,
+) -> Result<Vec<u8>, AppError> {
+    sealer
+        .seal(plaintext, content_type)
+        .and_then(|envelope| envelope.to_json_bytes())
+        .map_err(AppError::Crypto)
+}
+
+/// Parses a comma-separated list of `sha256/<base64>` SPKI pins.
+///
+/// # Errors
+/// Returns [`AppError::TransportSecurity`] for the first malformed pin.
+pub fn parse_pin_list(raw: &str) -> Result<Vec<SpkiPin>, AppError> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|pin| !pin.is_empty())
+        .map(|pin| SpkiPin::parse(pin).map_err(AppError::TransportSecurity))
+        .collect()
+}
+
+/// Client certificates expiring within this window raise a UI warning.
+pub const CLIENT_CERT_EXPIRY_WARNING_MS: u64 = 14 * 24 * 60 * 60 * 1000;
+
+/// Loads the mutual TLS client identity from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_TLS_CLIENT_CERT`: PEM certificate chain, or a PKCS#12
+///   archive when the file ends in `.p12` or `.pfx`.
+/// - `LOCAL_GUARD_TLS_CLIENT_KEY`: PEM private key; defaults to the
+///   certificate file.
+/// - `LOCAL_GUARD_TLS_CLIENT_CERT_PASSWORD`: PKCS#12 password (default
+///   empty).
+///
+/// Returns `Ok(None)` when no certificate is configured.
+///
+/// # Errors
+/// Returns [`AppError::TransportSecurity`] when the files cannot be read,
+/// the key does not match, or a key is given for a PKCS#12 archive.
+pub fn client_identity_from_env() -> Result<Option<ClientIdentity>, AppError> {
+    let read = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let Some(cert_path) = read("LOCAL_GUARD_TLS_CLIENT_CERT").map(std::path::PathBuf::from) else {
+        return Ok(None);
+    };
+    let key_path = read("LOCAL_GUARD_TLS_CLIENT_KEY").map(std::path::PathBuf::from);
+    let is_pkcs12 = cert_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("p12") || extension.eq_ignore_ascii_case("pfx")
+        });
+
+    let identity = if is_pkcs12 {
+        if key_path.is_some() {
+            return Err(AppError::TransportSecurity(
+                TransportSecurityError::ClientIdentity(
+                    "LOCAL_GUARD_TLS_CLIENT_KEY cannot be combined with a PKCS#12 archive"
+                        .to_string(),
+                ),
+            ));
+        }
+        // Why: the password is read raw; surrounding spaces may be part of it.
+        let password = std::env::var("LOCAL_GUARD_TLS_CLIENT_CERT_PASSWORD").unwrap_or_default();
+        ClientIdentity::from_pkcs12_file(&cert_path, &password)
+    } else {
+        ClientIdentity::from_pem_files(&cert_path, key_path.as_deref())
+    };
+    identity.map(Some).map_err(AppError::TransportSecurity)
+}
+
+/// Projects the client identity's expiry status at `now_ms` into the UI
+/// state, warning within [`CLIENT_CERT_EXPIRY_WARNING_MS`].
+pub fn client_certificate_state_for_ui(
+    identity: Option<&ClientIdentity>,
+    now_ms: u64,
+) -> UiClientCertificateState {
+    let Some(identity) = identity else {
+        return UiClientCertificateState::NotConfigured;
+    };
+    match identity.status(now_ms, CLIENT_CERT_EXPIRY_WARNING_MS) {
+        ClientCertificateStatus::Valid => UiClientCertificateState::Valid,
+        ClientCertificateStatus::ExpiringSoon { remaining_ms } => {
+            UiClientCertificateState::ExpiringSoon {
+                days_left: remaining_ms / (24 * 60 * 60 * 1000),
+            }
+        }
+        ClientCertificateStatus::Expired => UiClientCertificateState::Expired,
+        ClientCertificateStatus::NotYetValid => UiClientCertificateState::NotYetValid,
+    }
+}
+
+/// Builds the auth and ingest transport security policy from the
+/// environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_TLS_PINS` / `LOCAL_GUARD_TLS_BACKUP_PINS`: pin lists
+///   ([`parse_pin_list`]); a primary set needs a distinct backup pin.
+/// - `LOCAL_GUARD_TLS_CA_BUNDLE`: PEM file replacing the public roots.
+/// - `LOCAL_GUARD_TLS_MIN_VERSION`: `1.2` (default) or `1.3`.
+/// - Client certificate variables read by [`client_identity_from_env`].
+///
+/// # Errors
+/// Returns [`AppError::TransportSecurity`] for any malformed value. Unlike
+/// most env settings these fail closed: a typo must never weaken trust.
+pub fn transport_security_from_env() -> Result<TransportSecurity, AppError> {
+    let read = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let pins = parse_pin_list(&read("LOCAL_GUARD_TLS_PINS").unwrap_or_default())?;
+    let backup_pins = parse_pin_list(&read("LOCAL_GUARD_TLS_BACKUP_PINS").unwrap_or_default())?;
+    let mut policy = TransportSecurity::new()
+        .with_pins(pins, backup_pins)
+        .map_err(AppError::TransportSecurity)?;
+    if let Some(path) = read("LOCAL_GUARD_TLS_CA_BUNDLE") {
+        policy = policy
+            .with_ca_bundle_file(path)
+            .map_err(AppError::TransportSecurity)?;
+    }
+    if let Some(raw) = read("LOCAL_GUARD_TLS_MIN_VERSION") {
+        let version = TlsVersion::parse(&raw).ok_or_else(|| {
+            AppError::TransportSecurity(TransportSecurityError::InvalidPolicy(format!(
+                "unsupported minimum tls version {raw}"
+            )))
+        })?;
+        policy = policy.with_min_tls_version(version);
+    }
+    if let Some(identity) = client_identity_from_env()? {
+        policy = policy.with_client_identity(identity);
+    }
+    Ok(policy)
+}
+
+/// Reads the HTTP proxy for auth and ingest traffic from the environment.
+///
+/// Variables:
+/// - `LOCAL_GUARD_PROXY_URL`: explicit proxy URL; takes precedence over the
+///   conventional variables. `direct` disables proxying even when
+///   `HTTPS_PROXY` is set.
+/// - `HTTPS_PROXY` / `https_proxy`: conventional proxy URL, used when no
+///   explicit one is set.
+/// - `LOCAL_GUARD_PROXY_USERNAME` / `LOCAL_GUARD_PROXY_PASSWORD`: basic auth
+///   credentials kept out of the URL; they replace URL userinfo.
+/// - `NO_PROXY` / `no_proxy`: comma-separated bypass list.
+///
+/// Returns `Ok(None)` when no proxy is configured.
+///
+/// # Errors
+/// Returns [`AppError::Proxy`] for a malformed URL or bypass entry, or a
+/// password without a username. These fail closed so traffic never leaves
+/// by a route the operator did not intend.
+pub fn proxy_from_env() -> Result<Option<ProxyConfig>, AppError> {
+    let read = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let raw = match read("LOCAL_GUARD_PROXY_URL") {
+        Some(explicit) if explicit.eq_ignore_ascii_case("direct") => return Ok(None),
+        Some(explicit) => explicit,
+        None => match read("HTTPS_PROXY").or_else(|| read("https_proxy")) {
+            Some(conventional) => conventional,
+            None => return Ok(None),
+        },
+    };
+
+    let mut proxy = ProxyConfig::parse(&raw).map_err(AppError::Proxy)?;
+    // Why: the password is read raw; surrounding spaces may be part of it.
+    let password = std::env::var("LOCAL_GUARD_PROXY_PASSWORD").ok();
+    match (read("LOCAL_GUARD_PROXY_USERNAME"), password) {
+        (Some(username), password) => {
+            proxy = proxy.with_credentials(username, password.unwrap_or_default());
+        }
+        (None, Some(_)) => {
+            return Err(AppError::Proxy(TransportError::InvalidProxy(
+                "LOCAL_GUARD_PROXY_PASSWORD requires LOCAL_GUARD_PROXY_USERNAME".to_string(),
+            )));
+        }
+        (None, None) => {}
+    }
+    if let Some(no_proxy) = read("NO_PROXY").or_else(|| read("no_proxy")) {
+        proxy = proxy.with_no_proxy(&no_proxy).map_err(AppError::Proxy)?;
+    }
+    Ok(Some(proxy))
+}
+
+/// Captures one frame per target display and routes them into `batches`.
+///
+/// # Behavior
+/// - Every target is captured with the same `captured_at_ms`.
+/// - When a display changes resolution mid-batch, its partial batch is
+///   discarded and the new frame starts a fresh window (reported in
+///   [`CaptureTickOutcome::geometry_resets`]).
+///
+/// # Errors
+/// Returns [`AppError::Capture`] on the first capture failure. No frame from a
+/// failed tick is buffered, so per-display batches stay in step.
+/// Returns [`AppError::Core`] when a frame cannot be buffered.
+pub fn run_capture_tick(
+    backend: &dyn CaptureBackend,
+    batches: &mut MultiDisplayFrameBatch,
+    display_ids: &[String],
+    captured_at_ms: u64,
+) -> Result<CaptureTickOutcome, AppError> {
+    run_capture_tick_with(
+        backend,
+        batches,
+        display_ids,
+        captured_at_ms,
+        &CaptureTickOptions::default(),
+    )
+}
+
+/// Same as [`run_capture_tick`], re-capturing blank frames per `options`.
+///
+/// # Errors
+/// Same as [`run_capture_tick`].
+pub fn run_capture_tick_with(
+    backend: &dyn CaptureBackend,
+    batches: &mut MultiDisplayFrameBatch,
+    display_ids: &[String],
+    captured_at_ms: u64,
+    options: &CaptureTickOptions,
+) -> Result<CaptureTickOutcome, AppError> {
+    let mut blank_retries = 0;
+    let mut frames = Vec::with_capacity(display_ids.len());
+    for display_id in display_ids {
+        let frame = if options.blank_retries == 0 {
+            backend.capture_frame(display_id, captured_at_ms)
+        } else {
+            capture_frame_with_blank_retry(
+                backend,
+                display_id,
+                captured_at_ms,
+                &options.thresholds,
+                options.blank_retries,
+                options.blank_retry_delay,
+            )
+            .map(|checked| {
+                blank_retries += checked.retries;
+                checked.frame
+            })
+        };
+        frames.push(frame.map_err(AppError::Capture)?);
+    }
+
+    let mut outcome = CaptureTickOutcome {
+        frames_captured: frames.len(),
+        frame_width: frames.first().map_or(0, |frame| frame.width),
+        frame_height: frames.first().map_or(0, |frame| frame.height),
+        blank_retries,
+        ..CaptureTickOutcome::default()
+    };
+    for frame in frames {
+        if batches
+            .buffered_geometry(&frame.screen_id)
+            .is_some_and(|geometry| geometry != (frame.width, frame.height))
+        {
+            outcome.frames_discarded += batches.reset_display(&frame.screen_id);
+            outcome.geometry_resets.push(frame.screen_id.clone());
+        }
+        if let Some(batch) = batches.push_frame(frame).map_err(AppError::Core)? {
+            outcome.completed_batches.push(batch);
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Projects a capture tick result onto the capture stage status.
+///
+/// # Returns
+/// - `Running` for a successful tick, including one that recovered from a
+///   geometry change.
+/// - `Degraded` for any capture or batching failure.
+pub fn capture_stage_status(result: &Result<CaptureTickOutcome, AppError>) -> StageStatus {
+    match result {
+        Ok(_) => StageStatus::Running,
+        Err(_) => StageStatus::Degraded,
+    }
+}
+
+/// Projects batch metadata onto the capture stage status.
+///
+/// # Returns
+/// `Degraded` when every tile of the batch is blank (locked workstation,
+/// screen off, or protected content), otherwise `Running`.
+pub fn capture_status_for_metadata(metadata: &BatchMetadata) -> StageStatus {
+    if metadata.frame_count > 0 && metadata.blank_tiles.len() >= metadata.frame_count {
+        StageStatus::Degraded
+    } else {
+        StageStatus::Running
+    }
+}
+
 /// Builds upload payload from one complete frame batch.
 ///
 /// # Errors
 /// Returns [`AppError::Mosaic`] when frame batch is invalid for 3x3 compose.
 /// Returns [`AppError::Core`] when metadata construction fails.
 pub fn batch_to_payload(frames: &[Frame], session_id: &str) -> Result<MosaicPayload, AppError> {
-    let mosaic = compose_temporal_mosaic(frames).map_err(AppError::Mosaic)?;
-    let metadata = build_metadata(frames, session_id).map_err(AppError::Core)?;
+    batch_to_payload_with(
+        frames,
+        session_id,
+        &MosaicOptions::default(),
+        &QualityThresholds::default(),
+    )
+}
+
+/// Builds upload payload with explicit mosaic options (e.g. a buffer pool)
+/// and the blank-frame `thresholds` the capture tick used.
+///
+/// `options.output_format` is ignored: the v1 contract carries RGBA8.
+///
+/// # Errors
+/// Same as [`batch_to_payload`].
+pub fn batch_to_payload_with(
+    frames: &[Frame],
+    session_id: &str,
+    options: &MosaicOptions,
+    thresholds: &QualityThresholds,
+) -> Result<MosaicPayload, AppError> {
+    let options = MosaicOptions {
+        output_format: PixelFormat::Rgba8,
+        ..options.clone()
+    };
+    let mosaic = compose_temporal_mosaic_with(frames, &options).map_err(AppError::Mosaic)?;
+    let metadata =
+        build_metadata_with_thresholds(frames, session_id, thresholds).map_err(AppError::Core)?;
 
     Ok(MosaicPayload {
         schema_version: SCHEMA_VERSION_V1.to_string(),
         metadata,
         mosaic_width: mosaic.width,
         mosaic_height: mosaic.height,
-        mosaic_rgba: mosaic.rgba,
+        mosaic_rgba: mosaic.pixels,
     })
 }
 
@@ -166,6 +1250,13 @@ pub fn capture_enabled_from_env() -> bool {
 pub fn project_runtime_status(state: &UiState) -> RuntimeStatus {
     RuntimeStatus {
         capture_allowed: state.can_start_capture() && capture_enabled_from_env(),
+        capture_mode: format!("{:?}", state.capture_mode),
+        device: format!("{:?}", state.device),
+        client_certificate: format!("{:?}", state.client_certificate),
+        client_certificate_warning: state.client_certificate_warning(),
+        data_budget: format!("{:?}", state.data_budget),
+        data_budget_warning: state.data_budget_warning(),
+        upload_rate_limit_bytes_per_sec: state.upload_rate_limit_bytes_per_sec,
         capture: format!("{:?}", state.capture),
         network: format!("{:?}", state.network),
         upload: format!("{:?}", state.upload),
@@ -191,4 +1282,16 @@ pub enum AppError {
     /// Analysis parse/mapping error.
     #[error("analysis error: {0}")]
     Analysis(AnalysisContractError),
+    /// Local state file could not be read or written.
+    #[error("persistence error: {0}")]
+    Persistence(String),
+    /// Key parsing or payload sealing error.
+    #[error("crypto error: {0}")]
+    Crypto(CryptoError),
+    /// Transport security policy is malformed.
+    #[error("transport security error: {0}")]
+    TransportSecurity(TransportSecurityError),
+    /// Proxy settings are malformed.
+    #[error("proxy configuration error: {0}")]
+    Proxy(TransportError),
 }
@@ -29,10 +29,11 @@ mod win32_ui {
     //! controls, runtime status projection, and per-run file logging.
 
     use std::cell::RefCell;
+    use std::collections::BTreeMap;
     use std::ffi::c_void;
     use std::fs::{File, OpenOptions};
     use std::io::Write;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::ptr::{null, null_mut};
     use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
@@ -42,15 +43,43 @@ mod win32_ui {
 
     use base64::Engine as _;
     use local_guard_app::{
-        app_version, batch_to_payload, capture_enabled_from_env, project_runtime_status,
+        BUILD_GIT_HASH, BUILD_TARGET, BudgetAdjustment, BudgetStore, CaptureTickOptions,
+        ChainStore, PayloadAssembler, UploadBacklog, app_version, blank_frame_retries_from_env,
+        blank_frame_retry_delay_from_env, body_sealer_from_env, budget_adjustment,
+        cancel_uploads_unless_permitted, capture_config_from_env, capture_enabled_from_env,
+        capture_mode_for_ui, capture_status_for_metadata, circuit_breaker_from_env,
+        client_certificate_state_for_ui, client_provenance, context_policy_from_env,
+        cursor_overlay_from_env, data_budget_from_env, data_budget_state_for_ui,
+        device_state_for_ui, enrollment_code_from_env, ingest_url_from_env,
+        missed_tick_policy_from_env, network_stage_status, os_version, project_runtime_status,
+        proxy_from_env, resolve_capture_targets, restore_enrollment, run_capture_tick_with,
+        seal_artifact, transport_security_from_env, try_capture_config_from_env,
+        upload_backlog_from_env, upload_chunking_from_env, upload_compression_from_env,
+        upload_dispatcher_config_from_env, upload_rate_limiter_from_env,
     };
     use local_guard_auth::{
         AuthClient, AuthError, AuthState, AuthStateMachine, AuthTransport, Credentials,
+        DeviceIdentityStore, EnrollmentClient, EnrollmentState, LocalEnrollmentService,
         LoginRequest, LoginResponse, SessionToken,
     };
-    use local_guard_capture::{CaptureBackend, DisplayInfo, RealCaptureBackend};
-    use local_guard_core::{Frame, FrameBatch, MosaicPayload};
-    use local_guard_ui::{StageStatus, UiAuthState, UiState};
+    use local_guard_capture::{
+        CaptureBackend, CaptureScheduler, DisplayInfo, RealCaptureBackend, SystemMonotonicClock,
+    };
+    use local_guard_context::ContextCollector;
+    use local_guard_core::{
+        Frame, FramePool, MosaicPayload, MultiDisplayFrameBatch, PixelFormat, convert_pixels,
+    };
+    use local_guard_crypto::{BodySealer, DeviceKey, DeviceKeyStore};
+    use local_guard_mosaic::MosaicOptions;
+    use local_guard_transport::{
+        ClientIdentity, EndpointAllowlist, HttpsConnector, TransportSecurity,
+    };
+    use local_guard_ui::{StageStatus, UiAuthState, UiCaptureMode, UiState};
+    use local_guard_upload::{
+        BudgetMeter, DispatchEvent, DispatchOutcome, FailureClass, HttpsUploadTransport,
+        RetryPolicy, SystemUploadClock, UploadClient, UploadClock, UploadDispatcher, UploadError,
+        classify_upload_error,
+    };
     use time::OffsetDateTime;
     use windows_sys::Win32::Foundation::{FILETIME, HWND, LPARAM, LRESULT, WPARAM};
     use windows_sys::Win32::Graphics::Gdi::{
@@ -83,9 +112,13 @@ mod win32_ui {
     const CONTROL_ID_START_BUTTON: i32 = 1006;
     const CONTROL_ID_STOP_BUTTON: i32 = 1007;
     const CONTROL_ID_FRAME_STATUS: i32 = 1008;
+    const CONTROL_ID_ALL_DISPLAYS_CHECKBOX: i32 = 1009;
 
     const TIMER_CAPTURE_ID: usize = 1;
-    const DEFAULT_CAPTURE_FPS: u32 = 1;
+    // Why:
+    // - WM_TIMER only wakes the scheduler; deadlines come from the monotonic
+    //   clock, so the poll period just bounds dispatch latency.
+    const SCHEDULER_POLL_MS: u64 = 15;
     const MOSAIC_JPEG_QUALITY: u8 = 9;
     const PREVIEW_MAX_WIDTH: u32 = 220;
     const PREVIEW_MAX_HEIGHT: u32 = 124;
@@ -96,6 +129,7 @@ mod win32_ui {
     const WM_CAPTURE_WORKER_EVENT: u32 = WM_APP + 1;
 
     const AUTH_ENDPOINT: &str = "https://auth.local-guard.test/r1/cstore-auth";
+    const ENROLLMENT_ENDPOINT: &str = "https://auth.local-guard.test/r1/device-enroll";
     static RUN_LOGGER: OnceLock<RunLogger> = OnceLock::new();
     static FIRST_PAINT_LOGGED: AtomicBool = AtomicBool::new(false);
 
@@ -169,6 +203,7 @@ mod win32_ui {
         password_edit: HWND,
         login_button: HWND,
         consent_checkbox: HWND,
+        all_displays_checkbox: HWND,
         display_combo: HWND,
         start_button: HWND,
         stop_button: HWND,
@@ -193,6 +228,7 @@ mod win32_ui {
     struct StagedPayloadArtifacts {
         jpeg_path: PathBuf,
         json_path: PathBuf,
+        jpeg_quality: u8,
         jpeg_size_bytes: usize,
         json_size_bytes: usize,
         base64_size_chars: usize,
@@ -215,7 +251,7 @@ mod win32_ui {
     enum WorkerCommand {
         CaptureTick {
             timer_tick_seq: u64,
-            display_id: String,
+            display_ids: Vec<String>,
             session_id: String,
             captured_at_ms: u64,
             queued_at: Instant,
@@ -241,6 +277,7 @@ mod win32_ui {
             timer_tick_seq: u64,
             frame_number: u64,
             buffered_frames: usize,
+            display_count: usize,
             queue_wait_ms: u128,
             capture_duration_ms: u128,
             capture_lag_ms: u128,
@@ -253,16 +290,34 @@ mod win32_ui {
             timer_tick_seq: u64,
             frame_number: u64,
             prepared_batches: u64,
+            batch_id: String,
+            sequence_number: u64,
             mosaic_width: u32,
             mosaic_height: u32,
             batch_prepare_ms: u128,
             stage_queue_wait_ms: u128,
             pending_stage_queue: usize,
-            artifacts: StagedPayloadArtifacts,
+            blank_tiles: usize,
+            capture_status: StageStatus,
+            context_errors: Vec<String>,
+            // Why:
+            // - Boxed so the event channel does not size every variant for
+            //   the preview bitmap and timing breakdown.
+            artifacts: Box<StagedPayloadArtifacts>,
         },
         WorkerError(String),
     }
 
+    /// Upload client and dispatcher for staged batches; absent when no
+    /// ingest endpoint is configured.
+    struct UploadRuntime {
+        client: UploadClient,
+        dispatcher: UploadDispatcher,
+        events: Receiver<DispatchEvent>,
+        budget_store: Option<BudgetStore>,
+        backlog: UploadBacklog,
+    }
+
     struct CaptureWorkerRuntime {
         command_tx: Sender<WorkerCommand>,
         event_rx: Receiver<WorkerEvent>,
@@ -364,11 +419,14 @@ mod win32_ui {
         ui_state: UiState,
         auth_machine: AuthStateMachine,
         session_token: Option<SessionToken>,
+        enrollment: EnrollmentState,
+        client_identity: Option<ClientIdentity>,
         displays: Vec<DisplayInfo>,
         controls: ControlHandles,
         capturing: bool,
         capture_tick_in_flight: bool,
-        capture_timer_interval_ms: u32,
+        capture_timer_interval_ms: u64,
+        capture_scheduler: Option<CaptureScheduler>,
         timer_tick_seq: u64,
         current_frame_number: u64,
         current_capture_duration_ms: u128,
@@ -382,6 +440,9 @@ mod win32_ui {
         last_prepared_json: Option<PathBuf>,
         preview_bitmap: Option<PreviewBitmap>,
         worker_runtime: Option<CaptureWorkerRuntime>,
+        upload_runtime: Option<UploadRuntime>,
+        capture_budget_adjustment: BudgetAdjustment,
+        uploads_submitted: u64,
         perf_stats: PerfStats,
     }
 
@@ -403,11 +464,14 @@ mod win32_ui {
                 ui_state,
                 auth_machine: AuthStateMachine::new(),
                 session_token: None,
+                enrollment: EnrollmentState::Unenrolled,
+                client_identity: None,
                 displays,
                 controls: ControlHandles::default(),
                 capturing: false,
                 capture_tick_in_flight: false,
                 capture_timer_interval_ms: 1_000,
+                capture_scheduler: None,
                 timer_tick_seq: 0,
                 current_frame_number: 0,
                 current_capture_duration_ms: 0,
@@ -421,6 +485,9 @@ mod win32_ui {
                 last_prepared_json: None,
                 preview_bitmap: None,
                 worker_runtime: None,
+                upload_runtime: None,
+                capture_budget_adjustment: BudgetAdjustment::default(),
+                uploads_submitted: 0,
                 perf_stats: PerfStats::default(),
             })
         }
@@ -454,15 +521,26 @@ mod win32_ui {
     /// Starts the UI event loop and blocks until the user closes the window.
     pub fn run_main_window() -> Result<(), String> {
         initialize_logger()?;
-        let capture_fps = capture_fps_from_env();
+        if let Err(error) = try_capture_config_from_env() {
+            log_error(
+                "bootstrap",
+                "capture_fps_invalid",
+                &format!("{error}; using the default rate"),
+            );
+        }
+        let capture_config = capture_config_from_env();
         log_info(
             "bootstrap",
             "startup",
             &format!(
-                "version={} capture_enabled={} capture_fps={} jpeg_quality={} preview_max={}x{} auth_endpoint={} exe={}",
+                "version={} git_hash={} target={} os_version={} capture_enabled={} capture_fps={} missed_tick_policy={:?} jpeg_quality={} preview_max={}x{} auth_endpoint={} exe={}",
                 app_version(),
+                BUILD_GIT_HASH,
+                BUILD_TARGET,
+                os_version(),
                 capture_enabled_from_env(),
-                capture_fps,
+                capture_config.fps(),
+                missed_tick_policy_from_env(),
                 MOSAIC_JPEG_QUALITY,
                 PREVIEW_MAX_WIDTH,
                 PREVIEW_MAX_HEIGHT,
@@ -473,7 +551,21 @@ mod win32_ui {
             ),
         );
 
-        let controller = AppController::new()?;
+        // Why:
+        // - The device key is created on first start so its public key can
+        //   be registered before the first signed upload.
+        let enrollment = startup_enrollment(load_device_key());
+        let transport_policy = load_transport_policy();
+        log_proxy_config();
+        let upload_runtime = start_upload_runtime(transport_policy.as_ref());
+
+        let mut controller = AppController::new()?;
+        controller.upload_runtime = upload_runtime;
+        controller.ui_state.device = device_state_for_ui(&enrollment);
+        controller.client_identity = transport_policy
+            .as_ref()
+            .and_then(|policy| policy.client_identity().cloned());
+        controller.enrollment = enrollment;
         APP_CONTROLLER.with(|slot| {
             *slot.borrow_mut() = Some(controller);
         });
@@ -575,9 +667,8 @@ mod win32_ui {
             ShowWindow(hwnd, SW_SHOW);
         }
 
-        refresh_status_texts().map_err(|error| {
-            log_error("ui", "refresh_status_texts", &error);
-            error
+        refresh_status_texts().inspect_err(|error| {
+            log_error("ui", "refresh_status_texts", error);
         })?;
 
         log_info("event_loop", "begin", "message loop started");
@@ -663,6 +754,7 @@ mod win32_ui {
                         &summary_with_process_snapshot(&controller.perf_stats),
                     );
                     shutdown_capture_worker(controller);
+                    drain_upload_events(controller);
                     Ok(())
                 });
                 log_info("ui", "destroy", "window destroyed; posting quit");
@@ -699,10 +791,12 @@ mod win32_ui {
                 "STATIC",
                 "Local Guard MVP Client",
                 static_style,
-                20,
-                16,
-                340,
-                24,
+                ControlBounds {
+                    x: 20,
+                    y: 16,
+                    width: 340,
+                    height: 24,
+                },
                 0,
             )?;
 
@@ -712,10 +806,12 @@ mod win32_ui {
                 "STATIC",
                 "Username:",
                 static_style,
-                20,
-                56,
-                100,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 56,
+                    width: 100,
+                    height: 22,
+                },
                 0,
             )?;
 
@@ -725,10 +821,12 @@ mod win32_ui {
                 "EDIT",
                 "",
                 edit_style,
-                120,
-                54,
-                240,
-                24,
+                ControlBounds {
+                    x: 120,
+                    y: 54,
+                    width: 240,
+                    height: 24,
+                },
                 CONTROL_ID_USERNAME_EDIT,
             )?;
 
@@ -738,10 +836,12 @@ mod win32_ui {
                 "STATIC",
                 "Password:",
                 static_style,
-                380,
-                56,
-                100,
-                22,
+                ControlBounds {
+                    x: 380,
+                    y: 56,
+                    width: 100,
+                    height: 22,
+                },
                 0,
             )?;
 
@@ -751,10 +851,12 @@ mod win32_ui {
                 "EDIT",
                 "",
                 password_style,
-                480,
-                54,
-                240,
-                24,
+                ControlBounds {
+                    x: 480,
+                    y: 54,
+                    width: 240,
+                    height: 24,
+                },
                 CONTROL_ID_PASSWORD_EDIT,
             )?;
 
@@ -764,10 +866,12 @@ mod win32_ui {
                 "BUTTON",
                 "Login",
                 button_style,
-                740,
-                52,
-                110,
-                28,
+                ControlBounds {
+                    x: 740,
+                    y: 52,
+                    width: 110,
+                    height: 28,
+                },
                 CONTROL_ID_LOGIN_BUTTON,
             )?;
 
@@ -777,23 +881,42 @@ mod win32_ui {
                 "BUTTON",
                 "I explicitly consent to screen capture.",
                 checkbox_style,
-                20,
-                98,
-                320,
-                24,
+                ControlBounds {
+                    x: 20,
+                    y: 98,
+                    width: 320,
+                    height: 24,
+                },
                 CONTROL_ID_CONSENT_CHECKBOX,
             )?;
 
+            controls.all_displays_checkbox = create_child_control(
+                hwnd,
+                instance,
+                "BUTTON",
+                "Capture all displays",
+                checkbox_style,
+                ControlBounds {
+                    x: 360,
+                    y: 98,
+                    width: 240,
+                    height: 24,
+                },
+                CONTROL_ID_ALL_DISPLAYS_CHECKBOX,
+            )?;
+
             let _display_label = create_child_control(
                 hwnd,
                 instance,
                 "STATIC",
                 "Display:",
                 static_style,
-                20,
-                138,
-                100,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 138,
+                    width: 100,
+                    height: 22,
+                },
                 0,
             )?;
 
@@ -803,10 +926,12 @@ mod win32_ui {
                 "COMBOBOX",
                 "",
                 combo_style,
-                120,
-                136,
-                300,
-                220,
+                ControlBounds {
+                    x: 120,
+                    y: 136,
+                    width: 300,
+                    height: 220,
+                },
                 CONTROL_ID_DISPLAY_COMBO,
             )?;
 
@@ -816,10 +941,12 @@ mod win32_ui {
                 "BUTTON",
                 "Start Capture",
                 button_style,
-                440,
-                134,
-                150,
-                30,
+                ControlBounds {
+                    x: 440,
+                    y: 134,
+                    width: 150,
+                    height: 30,
+                },
                 CONTROL_ID_START_BUTTON,
             )?;
 
@@ -829,10 +956,12 @@ mod win32_ui {
                 "BUTTON",
                 "Stop Capture",
                 button_style,
-                610,
-                134,
-                150,
-                30,
+                ControlBounds {
+                    x: 610,
+                    y: 134,
+                    width: 150,
+                    height: 30,
+                },
                 CONTROL_ID_STOP_BUTTON,
             )?;
 
@@ -842,10 +971,12 @@ mod win32_ui {
                 "STATIC",
                 "Runtime Status",
                 static_style,
-                20,
-                188,
-                180,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 188,
+                    width: 180,
+                    height: 22,
+                },
                 0,
             )?;
 
@@ -855,10 +986,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                218,
-                760,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 218,
+                    width: 760,
+                    height: 22,
+                },
                 0,
             )?;
             controls.auth_status = create_child_control(
@@ -867,10 +1000,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                246,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 246,
+                    width: 920,
+                    height: 22,
+                },
                 0,
             )?;
             controls.capture_status = create_child_control(
@@ -879,10 +1014,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                274,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 274,
+                    width: 920,
+                    height: 22,
+                },
                 0,
             )?;
             controls.network_status = create_child_control(
@@ -891,10 +1028,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                302,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 302,
+                    width: 920,
+                    height: 22,
+                },
                 0,
             )?;
             controls.upload_status = create_child_control(
@@ -903,10 +1042,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                330,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 330,
+                    width: 920,
+                    height: 22,
+                },
                 0,
             )?;
             controls.analysis_status = create_child_control(
@@ -915,10 +1056,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                358,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 358,
+                    width: 920,
+                    height: 22,
+                },
                 0,
             )?;
             controls.pipeline_status = create_child_control(
@@ -927,10 +1070,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                386,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 386,
+                    width: 920,
+                    height: 22,
+                },
                 0,
             )?;
             controls.frame_status = create_child_control(
@@ -939,10 +1084,12 @@ mod win32_ui {
                 "STATIC",
                 "",
                 static_style,
-                20,
-                414,
-                920,
-                22,
+                ControlBounds {
+                    x: 20,
+                    y: 414,
+                    width: 920,
+                    height: 22,
+                },
                 CONTROL_ID_FRAME_STATUS,
             )?;
 
@@ -952,10 +1099,12 @@ mod win32_ui {
                 "STATIC",
                 "Latest 3x3 mosaic preview (reduced):",
                 static_style,
-                PREVIEW_DRAW_X,
-                470,
-                360,
-                22,
+                ControlBounds {
+                    x: PREVIEW_DRAW_X,
+                    y: 470,
+                    width: 360,
+                    height: 22,
+                },
                 0,
             )?;
 
@@ -968,6 +1117,12 @@ mod win32_ui {
                     BST_UNCHECKED as usize,
                     0,
                 );
+                SendMessageW(
+                    controls.all_displays_checkbox,
+                    BM_SETCHECK,
+                    BST_UNCHECKED as usize,
+                    0,
+                );
             }
 
             for display in &controller.displays {
@@ -1027,19 +1182,16 @@ mod win32_ui {
         let notification = hiword(command_bits) as u32;
 
         let result = match control_id {
-            CONTROL_ID_LOGIN_BUTTON if notification == BN_CLICKED as u32 => handle_login_click(),
-            CONTROL_ID_CONSENT_CHECKBOX if notification == BN_CLICKED as u32 => {
-                handle_consent_toggle()
+            CONTROL_ID_LOGIN_BUTTON if notification == BN_CLICKED => handle_login_click(),
+            CONTROL_ID_CONSENT_CHECKBOX if notification == BN_CLICKED => handle_consent_toggle(),
+            CONTROL_ID_ALL_DISPLAYS_CHECKBOX if notification == BN_CLICKED => {
+                handle_capture_mode_toggle()
             }
-            CONTROL_ID_DISPLAY_COMBO if notification == CBN_SELCHANGE as u32 => {
+            CONTROL_ID_DISPLAY_COMBO if notification == CBN_SELCHANGE => {
                 handle_display_selection_change()
             }
-            CONTROL_ID_START_BUTTON if notification == BN_CLICKED as u32 => {
-                handle_start_capture(hwnd)
-            }
-            CONTROL_ID_STOP_BUTTON if notification == BN_CLICKED as u32 => {
-                handle_stop_capture(hwnd)
-            }
+            CONTROL_ID_START_BUTTON if notification == BN_CLICKED => handle_start_capture(hwnd),
+            CONTROL_ID_STOP_BUTTON if notification == BN_CLICKED => handle_stop_capture(hwnd),
             _ => Ok(()),
         };
 
@@ -1069,9 +1221,11 @@ mod win32_ui {
                 ),
             );
 
-            let auth_client =
-                AuthClient::new(AUTH_ENDPOINT, Arc::new(MockAuthTransport::default()))
-                    .map_err(|error| format!("auth client init failed: {error}"))?;
+            let mut auth_client = AuthClient::new(AUTH_ENDPOINT, Arc::new(MockAuthTransport))
+                .map_err(|error| format!("auth client init failed: {error}"))?;
+            if let Some(device_id) = controller.enrollment.device_id() {
+                auth_client = auth_client.with_device_id(device_id);
+            }
 
             let credentials = Credentials { username, password };
             match auth_client.login(&credentials, unix_timestamp_millis() as u64) {
@@ -1088,6 +1242,7 @@ mod win32_ui {
                     controller.auth_machine.logout();
                     controller.session_token = None;
                     sync_auth_state(controller);
+                    cancel_uploads_if_revoked(controller);
                     controller.ui_state.analysis_status = "Login failed.".to_string();
                     log_error("auth", "login_failed", &error.to_string());
                     Err(format!("login failed: {error}"))
@@ -1111,6 +1266,59 @@ mod win32_ui {
         })
     }
 
+    fn handle_capture_mode_toggle() -> Result<(), String> {
+        with_controller_mut(|controller| {
+            let checked = unsafe {
+                // Safety:
+                // - Control handle is valid and belongs to current window.
+                SendMessageW(controller.controls.all_displays_checkbox, BM_GETCHECK, 0, 0)
+            } as u32
+                == BST_CHECKED;
+
+            let capture_mode = if checked {
+                UiCaptureMode::AllDisplays
+            } else {
+                UiCaptureMode::SelectedDisplay
+            };
+            if controller.capturing && capture_mode != controller.ui_state.capture_mode {
+                // Why:
+                // - Switching mode mid-run would leave partially filled batches
+                //   for displays that are no longer captured.
+                // Failure mode:
+                // - Revert the checkbox and ask the operator to stop first.
+                let previous = if controller.ui_state.capture_mode == UiCaptureMode::AllDisplays {
+                    BST_CHECKED
+                } else {
+                    BST_UNCHECKED
+                };
+                unsafe {
+                    // Safety:
+                    // - Checkbox handle is valid.
+                    SendMessageW(
+                        controller.controls.all_displays_checkbox,
+                        BM_SETCHECK,
+                        previous as usize,
+                        0,
+                    );
+                }
+                return Err("stop capture before changing capture mode".to_string());
+            }
+
+            controller.ui_state.set_capture_mode(capture_mode);
+            unsafe {
+                // Safety:
+                // - Combo box handle is valid; selection is irrelevant in all-displays mode.
+                EnableWindow(controller.controls.display_combo, i32::from(!checked));
+            }
+            log_info(
+                "capture",
+                "mode_changed",
+                &format!("capture_mode={capture_mode:?}"),
+            );
+            Ok(())
+        })
+    }
+
     fn handle_display_selection_change() -> Result<(), String> {
         with_controller_mut(|controller| {
             let selection_index = unsafe {
@@ -1141,6 +1349,7 @@ mod win32_ui {
             sync_auth_state(controller);
 
             if !capture_enabled_from_env() {
+                cancel_uploads_if_revoked(controller);
                 controller.ui_state.capture = StageStatus::Degraded;
                 return Err(
                     "capture blocked by LOCAL_GUARD_CAPTURE_ENABLED kill-switch".to_string()
@@ -1154,15 +1363,23 @@ mod win32_ui {
 
             if !controller.ui_state.can_start_capture() {
                 return Err(
-                    "capture start blocked (requires authenticated session, consent, and display)"
+                    "capture start blocked (requires enrolled device, authenticated session, consent, and display)"
                         .to_string(),
                 );
             }
 
             ensure_capture_worker(controller, hwnd)?;
 
-            let fps = capture_fps_from_env();
-            let interval_ms = (1_000 / fps.max(1)).max(1);
+            // Why:
+            // - An exhausted data budget under the reduce-fps policy slows
+            //   capture from the first tick, not only after the next change.
+            controller.capture_budget_adjustment = budget_adjustment(upload_budget(controller));
+            let capture_config = controller
+                .capture_budget_adjustment
+                .capture_config(capture_config_from_env());
+            let missed_tick_policy = missed_tick_policy_from_env();
+            let interval_ms = capture_config.interval_ms();
+            let poll_ms = interval_ms.min(SCHEDULER_POLL_MS) as u32;
             if let Some(worker) = controller.worker_runtime.as_ref() {
                 worker
                     .command_tx
@@ -1197,7 +1414,7 @@ mod win32_ui {
             let timer = unsafe {
                 // Safety:
                 // - Main window handle is valid, timer id is process-local.
-                SetTimer(hwnd, TIMER_CAPTURE_ID, interval_ms, None)
+                SetTimer(hwnd, TIMER_CAPTURE_ID, poll_ms, None)
             };
             if timer == 0 {
                 controller.ui_state.capture = StageStatus::Degraded;
@@ -1205,6 +1422,11 @@ mod win32_ui {
             }
 
             controller.capture_timer_interval_ms = interval_ms;
+            controller.capture_scheduler = Some(CaptureScheduler::new(
+                capture_config,
+                missed_tick_policy,
+                Arc::new(SystemMonotonicClock::new()),
+            ));
             controller.capturing = true;
 
             unsafe {
@@ -1218,8 +1440,10 @@ mod win32_ui {
                 "capture",
                 "start",
                 &format!(
-                    "fps={fps} interval_ms={interval_ms} backend={} worker=enabled selected_display={} session_id_len={}",
+                    "fps={} interval_ms={interval_ms} poll_ms={poll_ms} missed_tick_policy={missed_tick_policy:?} backend={} worker=enabled capture_mode={:?} selected_display={} session_id_len={}",
+                    capture_config.fps(),
                     controller.capture_backend_name,
+                    controller.ui_state.capture_mode,
                     controller
                         .ui_state
                         .selected_display
@@ -1270,13 +1494,15 @@ mod win32_ui {
 
     fn handle_capture_timer_tick(hwnd: HWND) {
         let result = with_controller_mut(|controller| {
+            // Why:
+            // - The kill switch and session expiry also stop uploads already
+            //   dispatched, not just new captures.
+            cancel_uploads_if_revoked(controller);
+            drain_upload_events(controller);
             if !controller.capturing {
                 return Ok(());
             }
 
-            controller.perf_stats.timer_ticks_total =
-                controller.perf_stats.timer_ticks_total.saturating_add(1);
-
             sync_auth_state(controller);
             if controller.ui_state.auth != UiAuthState::Authenticated {
                 stop_capture_timer(hwnd, controller);
@@ -1297,15 +1523,29 @@ mod win32_ui {
                 return Ok(());
             }
 
-            let selected_display = controller
-                .ui_state
-                .selected_display
-                .clone()
-                .ok_or_else(|| "no display selected for capture tick".to_string())?;
+            apply_capture_budget_adjustment(controller);
 
+            // Why:
+            // - A tick still in flight leaves its deadlines unpolled; the
+            //   scheduler accounts them as missed on the next poll.
             if controller.capture_tick_in_flight {
-                controller.perf_stats.timer_ticks_skipped =
-                    controller.perf_stats.timer_ticks_skipped.saturating_add(1);
+                return Ok(());
+            }
+            let Some(tick) = controller
+                .capture_scheduler
+                .as_mut()
+                .and_then(CaptureScheduler::poll)
+            else {
+                return Ok(());
+            };
+            controller.perf_stats.timer_ticks_total =
+                controller.perf_stats.timer_ticks_total.saturating_add(1);
+
+            if tick.missed_before > 0 {
+                controller.perf_stats.timer_ticks_skipped = controller
+                    .perf_stats
+                    .timer_ticks_skipped
+                    .saturating_add(tick.missed_before);
                 let (pending_capture_queue, pending_stage_queue) = controller
                     .worker_runtime
                     .as_ref()
@@ -1320,14 +1560,25 @@ mod win32_ui {
                     "capture",
                     "tick_skipped",
                     &format!(
-                        "reason=in_flight skipped_total={} last_tick_seq={} pending_capture_queue={} pending_stage_queue={}",
+                        "reason=missed_deadline missed={} skipped_total={} deadline_seq={} lateness_ms={} last_tick_seq={} pending_capture_queue={} pending_stage_queue={}",
+                        tick.missed_before,
                         controller.perf_stats.timer_ticks_skipped,
+                        tick.sequence,
+                        tick.lateness.as_millis(),
                         controller.perf_stats.last_tick_seq,
                         pending_capture_queue,
                         pending_stage_queue
                     ),
                 );
-                return Ok(());
+            }
+
+            let display_ids = resolve_capture_targets(
+                capture_mode_for_ui(controller.ui_state.capture_mode),
+                &controller.displays,
+                controller.ui_state.selected_display.as_deref(),
+            );
+            if display_ids.is_empty() {
+                return Err("no display available for capture tick".to_string());
             }
 
             let session = controller
@@ -1339,7 +1590,7 @@ mod win32_ui {
 
             let command = WorkerCommand::CaptureTick {
                 timer_tick_seq,
-                display_id: selected_display,
+                display_ids,
                 session_id: session.session_id.clone(),
                 captured_at_ms: unix_timestamp_millis() as u64,
                 queued_at: Instant::now(),
@@ -1371,9 +1622,12 @@ mod win32_ui {
                 "capture",
                 "tick_dispatched",
                 &format!(
-                    "tick_seq={} dispatched_total={} selected_display={} pending_capture_queue={} pending_stage_queue={}",
+                    "tick_seq={} deadline_seq={} lateness_ms={} dispatched_total={} capture_mode={:?} selected_display={} pending_capture_queue={} pending_stage_queue={}",
                     timer_tick_seq,
+                    tick.sequence,
+                    tick.lateness.as_millis(),
                     controller.perf_stats.timer_ticks_dispatched,
+                    controller.ui_state.capture_mode,
                     controller
                         .ui_state
                         .selected_display
@@ -1424,6 +1678,7 @@ mod win32_ui {
                 }
             }
 
+            drain_upload_events(controller);
             let mut preview_changed = false;
             for event in drained_events {
                 match event {
@@ -1431,6 +1686,7 @@ mod win32_ui {
                         timer_tick_seq,
                         frame_number,
                         buffered_frames,
+                        display_count,
                         queue_wait_ms,
                         capture_duration_ms,
                         capture_lag_ms,
@@ -1492,10 +1748,11 @@ mod win32_ui {
                             "capture",
                             "frame_acquired",
                             &format!(
-                                "tick_seq={} frame={} buffered_frames={} frame_size={}x{} queue_wait_ms={} capture_lag_ms={} capture_ms={} pending_capture_queue={} pending_stage_queue={}",
+                                "tick_seq={} frame={} buffered_frames={} display_count={} frame_size={}x{} queue_wait_ms={} capture_lag_ms={} capture_ms={} pending_capture_queue={} pending_stage_queue={}",
                                 timer_tick_seq,
                                 frame_number,
                                 buffered_frames,
+                                display_count,
                                 frame_width,
                                 frame_height,
                                 queue_wait_ms,
@@ -1510,14 +1767,27 @@ mod win32_ui {
                         timer_tick_seq,
                         frame_number,
                         prepared_batches,
+                        batch_id,
+                        sequence_number,
                         mosaic_width,
                         mosaic_height,
                         batch_prepare_ms,
                         stage_queue_wait_ms,
                         pending_stage_queue,
+                        blank_tiles,
+                        capture_status,
+                        context_errors,
                         artifacts,
                     } => {
+                        for error in &context_errors {
+                            log_error(
+                                "upload_prep",
+                                "context_provider_failed",
+                                &format!("batch_id={batch_id} seq={sequence_number} {error}"),
+                            );
+                        }
                         controller.current_frame_number = frame_number;
+                        controller.ui_state.capture = capture_status;
                         controller.current_encode_duration_ms = batch_prepare_ms;
                         controller.prepared_batches = prepared_batches;
                         controller.frames_buffered = 0;
@@ -1525,14 +1795,22 @@ mod win32_ui {
                         controller.last_prepared_json = Some(artifacts.json_path.clone());
                         controller.preview_bitmap = Some(artifacts.preview_bitmap);
                         controller.ui_state.upload = StageStatus::Healthy;
-                        controller.ui_state.analysis_status = format!(
-                            "Prepared batch #{} for upload ({}x{}, jpeg={} bytes, json={} bytes).",
-                            prepared_batches,
-                            mosaic_width,
-                            mosaic_height,
-                            artifacts.jpeg_size_bytes,
-                            artifacts.json_size_bytes
-                        );
+                        controller.ui_state.analysis_status = if capture_status
+                            == StageStatus::Degraded
+                        {
+                            format!(
+                                "Prepared batch #{prepared_batches}, but every frame is blank (screen locked, off, or protected content)."
+                            )
+                        } else {
+                            format!(
+                                "Prepared batch #{} for upload ({}x{}, jpeg={} bytes, json={} bytes).",
+                                prepared_batches,
+                                mosaic_width,
+                                mosaic_height,
+                                artifacts.jpeg_size_bytes,
+                                artifacts.json_size_bytes
+                            )
+                        };
                         preview_changed = true;
                         controller.perf_stats.worker_events_total =
                             controller.perf_stats.worker_events_total.saturating_add(1);
@@ -1589,10 +1867,14 @@ mod win32_ui {
                             "upload_prep",
                             "artifact_ready",
                             &format!(
-                                "tick_seq={} prepared_batches={} jpeg={} json={} raw_rgb_bytes={} base64_chars={} batch_prepare_ms={} stage_queue_wait_ms={} stage_total_ms={} rgba_to_rgb_ms={} jpeg_encode_ms={} json_encode_ms={} disk_write_ms={} preview_build_ms={} pending_stage_queue={} jpeg_ratio={} base64_ratio={}",
+                                "tick_seq={} prepared_batches={} batch_id={} seq={} blank_tiles={} jpeg={} jpeg_quality={} json={} raw_rgb_bytes={} base64_chars={} batch_prepare_ms={} stage_queue_wait_ms={} stage_total_ms={} rgba_to_rgb_ms={} jpeg_encode_ms={} json_encode_ms={} disk_write_ms={} preview_build_ms={} pending_stage_queue={} jpeg_ratio={} base64_ratio={}",
                                 timer_tick_seq,
                                 prepared_batches,
+                                batch_id,
+                                sequence_number,
+                                blank_tiles,
                                 artifacts.jpeg_path.display(),
+                                artifacts.jpeg_quality,
                                 artifacts.json_path.display(),
                                 artifacts.raw_rgb_bytes,
                                 artifacts.base64_size_chars,
@@ -1615,6 +1897,7 @@ mod win32_ui {
                                 )
                             ),
                         );
+                        pump_upload_backlog(controller);
                         if prepared_batches % 5 == 0 {
                             log_info(
                                 "perf",
@@ -1683,6 +1966,33 @@ mod win32_ui {
     fn refresh_status_texts() -> Result<(), String> {
         with_controller_mut(|controller| {
             sync_auth_state(controller);
+            controller.ui_state.client_certificate = client_certificate_state_for_ui(
+                controller.client_identity.as_ref(),
+                u64::try_from(unix_timestamp_millis()).unwrap_or(u64::MAX),
+            );
+            controller.ui_state.data_budget = data_budget_state_for_ui(upload_budget(controller));
+            let breaker = controller
+                .upload_runtime
+                .as_ref()
+                .and_then(|upload| upload.client.circuit_breaker());
+            controller.ui_state.network = network_stage_status(breaker);
+            let network_detail = match (&controller.upload_runtime, breaker) {
+                (Some(upload), Some(breaker)) => {
+                    let snapshot = breaker.snapshot();
+                    format!(
+                        "endpoint={} | circuit={:?} | consecutive_failures={}",
+                        upload.client.endpoint(),
+                        snapshot.state,
+                        snapshot.consecutive_failures
+                    )
+                }
+                _ => "endpoint not configured".to_string(),
+            };
+            controller.ui_state.upload_rate_limit_bytes_per_sec = controller
+                .upload_runtime
+                .as_ref()
+                .and_then(|upload| upload.client.rate_limiter())
+                .map(|limiter| limiter.bytes_per_second());
 
             let runtime = project_runtime_status(&controller.ui_state);
             let auth_label = match controller.ui_state.auth {
@@ -1697,31 +2007,68 @@ mod win32_ui {
             );
             set_control_text(
                 controller.controls.auth_status,
-                &format!("Auth: {auth_label}"),
+                &format!(
+                    "Auth: {auth_label} | device={} ({})",
+                    runtime.device,
+                    controller.enrollment.device_id().unwrap_or("none")
+                ),
             );
             set_control_text(
                 controller.controls.capture_status,
                 &format!(
-                    "Capture: {} | backend={} | running={} | buffered_frames={} | in_flight={} | interval_ms={} | queue_wait_ms={} | capture_lag_ms={}",
+                    "Capture: {} | backend={} | running={} | buffered_frames={} | in_flight={} | interval_ms={} | missed_ticks={} | queue_wait_ms={} | capture_lag_ms={}",
                     runtime.capture,
                     controller.capture_backend_name,
                     controller.capturing,
                     controller.frames_buffered,
                     controller.capture_tick_in_flight,
                     controller.capture_timer_interval_ms,
+                    controller
+                        .capture_scheduler
+                        .as_ref()
+                        .map(|scheduler| scheduler.stats().ticks_missed)
+                        .unwrap_or(0),
                     controller.current_queue_wait_ms,
                     controller.current_capture_lag_ms
                 ),
             );
             set_control_text(
                 controller.controls.network_status,
-                &format!("Network: {} (endpoint not configured)", runtime.network),
+                &format!(
+                    "Network: {} | {network_detail} | client_cert={}{}",
+                    runtime.network,
+                    runtime.client_certificate,
+                    runtime
+                        .client_certificate_warning
+                        .as_deref()
+                        .map(|warning| format!(" | WARNING: {warning}"))
+                        .unwrap_or_default()
+                ),
             );
             set_control_text(
                 controller.controls.upload_status,
                 &format!(
-                    "Upload Prep: {} | prepared_batches={}",
-                    runtime.upload, controller.prepared_batches
+                    "Upload: {} | prepared_batches={} | submitted={} | backlog={} | in_flight={} | budget={} | rate_limit_bps={}{}",
+                    runtime.upload,
+                    controller.prepared_batches,
+                    controller.uploads_submitted,
+                    controller
+                        .upload_runtime
+                        .as_ref()
+                        .map_or(0, |upload| upload.backlog.len()),
+                    controller
+                        .upload_runtime
+                        .as_ref()
+                        .map_or(0, |upload| upload.dispatcher.in_flight()),
+                    runtime.data_budget,
+                    runtime
+                        .upload_rate_limit_bytes_per_sec
+                        .map_or_else(|| "off".to_string(), |rate| rate.to_string()),
+                    runtime
+                        .data_budget_warning
+                        .as_deref()
+                        .map(|warning| format!(" | WARNING: {warning}"))
+                        .unwrap_or_default()
                 ),
             );
             set_control_text(
@@ -1799,6 +2146,7 @@ mod win32_ui {
             }
             controller.capturing = false;
         }
+        controller.capture_scheduler = None;
     }
 
     fn ensure_capture_worker(controller: &mut AppController, hwnd: HWND) -> Result<(), String> {
@@ -1806,7 +2154,16 @@ mod win32_ui {
             return Ok(());
         }
 
-        let worker_runtime = spawn_capture_worker(hwnd)?;
+        let device_id = controller.enrollment.device_id().map(ToString::to_string);
+        let worker_runtime = spawn_capture_worker(
+            hwnd,
+            device_id,
+            upload_budget(controller).cloned(),
+            controller
+                .upload_runtime
+                .as_ref()
+                .map(|upload| upload.backlog.clone()),
+        )?;
         controller.worker_runtime = Some(worker_runtime);
         log_info("capture_worker", "spawned", "worker thread initialized");
         Ok(())
@@ -1825,7 +2182,203 @@ mod win32_ui {
         }
     }
 
-    fn spawn_capture_worker(hwnd: HWND) -> Result<CaptureWorkerRuntime, String> {
+    fn upload_budget(controller: &AppController) -> Option<&BudgetMeter> {
+        controller
+            .upload_runtime
+            .as_ref()
+            .and_then(|upload| upload.client.data_budget())
+    }
+
+    /// Rebuilds the capture scheduler when the data budget starts or stops
+    /// asking for a reduced capture rate.
+    fn apply_capture_budget_adjustment(controller: &mut AppController) {
+        let adjustment = budget_adjustment(upload_budget(controller));
+        let rate_changed = adjustment.reduce_fps != controller.capture_budget_adjustment.reduce_fps;
+        controller.capture_budget_adjustment = adjustment;
+        if !rate_changed {
+            return;
+        }
+        let Some(policy) = controller
+            .capture_scheduler
+            .as_ref()
+            .map(CaptureScheduler::policy)
+        else {
+            return;
+        };
+        let capture_config = adjustment.capture_config(capture_config_from_env());
+        controller.capture_scheduler = Some(CaptureScheduler::new(
+            capture_config,
+            policy,
+            Arc::new(SystemMonotonicClock::new()),
+        ));
+        controller.capture_timer_interval_ms = capture_config.interval_ms();
+        log_info(
+            "capture",
+            "budget_rate_change",
+            &format!(
+                "reduce_fps={} fps={} interval_ms={}",
+                adjustment.reduce_fps,
+                capture_config.fps(),
+                capture_config.interval_ms()
+            ),
+        );
+    }
+
+    /// Hands backlogged payloads to the dispatcher while the session,
+    /// budget, and in-flight window allow it.
+    fn pump_upload_backlog(controller: &mut AppController) {
+        let (Some(upload), Some(session)) = (&controller.upload_runtime, &controller.session_token)
+        else {
+            return;
+        };
+        let accepted = upload.backlog.pump(
+            &upload.dispatcher,
+            upload.client.data_budget(),
+            &session.access_token,
+        );
+        if accepted == 0 {
+            return;
+        }
+        log_info(
+            "upload",
+            "submitted",
+            &format!(
+                "accepted={accepted} backlog={} in_flight={}",
+                upload.backlog.len(),
+                upload.dispatcher.in_flight()
+            ),
+        );
+        controller.uploads_submitted = controller.uploads_submitted.saturating_add(accepted as u64);
+        controller.ui_state.upload = StageStatus::Running;
+    }
+
+    /// Logs finished uploads, persists budget counters, and updates the
+    /// upload stage status.
+    fn drain_upload_events(controller: &mut AppController) {
+        let Some(upload) = controller.upload_runtime.as_ref() else {
+            return;
+        };
+        let events: Vec<DispatchEvent> = upload.events.try_iter().collect();
+        if events.is_empty() {
+            retry_held_uploads(controller);
+            pump_upload_backlog(controller);
+            return;
+        }
+        let mut failed = false;
+        for event in &events {
+            match &event.outcome {
+                DispatchOutcome::Uploaded(report) => log_info(
+                    "upload",
+                    "uploaded",
+                    &format!(
+                        "ticket={} attempts={} chunks={} resumes={}",
+                        event.ticket.id(),
+                        report.attempts,
+                        report.chunks,
+                        report.resumes
+                    ),
+                ),
+                DispatchOutcome::Failed(error) => {
+                    failed = true;
+                    log_error(
+                        "upload",
+                        "failed",
+                        &format!("ticket={} error={error}", event.ticket.id()),
+                    );
+                    // Why:
+                    // - A payload that can succeed later keeps its session held
+                    //   so the server's chain stays in order; any other failure
+                    //   drops the batch and releases the session so later
+                    //   batches can still be sent.
+                    let retriable = classify_upload_error(error) == FailureClass::Retriable
+                        || matches!(error, UploadError::BudgetExhausted(_));
+                    if !retriable && upload.dispatcher.release_session(&event.session_id) {
+                        log_error(
+                            "upload",
+                            "session_released",
+                            &format!("ticket={} reason=permanent_failure", event.ticket.id()),
+                        );
+                    }
+                }
+                DispatchOutcome::Cancelled => log_info(
+                    "upload",
+                    "cancelled",
+                    &format!("ticket={}", event.ticket.id()),
+                ),
+            }
+        }
+        // Failure mode:
+        // - A failed save keeps counting in memory; the next drain retries.
+        if let (Some(store), Some(meter)) = (&upload.budget_store, upload.client.data_budget())
+            && let Err(error) = store.save(&meter.usage())
+        {
+            log_error("upload", "budget_usage_save_failed", &error.to_string());
+        }
+        controller.ui_state.upload = if failed {
+            StageStatus::Degraded
+        } else if upload.dispatcher.in_flight() > 0 {
+            StageStatus::Running
+        } else {
+            StageStatus::Healthy
+        };
+        retry_held_uploads(controller);
+        pump_upload_backlog(controller);
+    }
+
+    /// Re-sends payloads holding their session after a failure, once the
+    /// session, budget, and circuit breaker allow it.
+    fn retry_held_uploads(controller: &AppController) {
+        let (Some(upload), Some(session)) = (&controller.upload_runtime, &controller.session_token)
+        else {
+            return;
+        };
+        // Why:
+        // - A retry during the cool-down or under a queue-only budget would
+        //   fail at once; waiting keeps the log to one failure per outage.
+        let cooling_down = upload
+            .client
+            .circuit_breaker()
+            .is_some_and(|breaker| !breaker.retry_after().is_zero());
+        if cooling_down || budget_adjustment(upload.client.data_budget()).uploads_paused {
+            return;
+        }
+        for session_id in upload.dispatcher.held_sessions() {
+            if let Some(ticket) = upload
+                .dispatcher
+                .retry_held(&session_id, &session.access_token)
+            {
+                log_info("upload", "retry_held", &format!("ticket={}", ticket.id()));
+            }
+        }
+    }
+
+    /// Cancels dispatched uploads once the session or the kill switch no
+    /// longer permits them.
+    fn cancel_uploads_if_revoked(controller: &AppController) {
+        let Some(upload) = controller.upload_runtime.as_ref() else {
+            return;
+        };
+        let outstanding = cancel_uploads_unless_permitted(
+            &upload.dispatcher,
+            &upload.backlog,
+            &controller.auth_machine,
+            unix_timestamp_millis() as u64,
+        );
+        if outstanding > 0 {
+            log_info(
+                "upload",
+                "cancel_all",
+                &format!("outstanding={outstanding} reason=session_or_kill_switch"),
+            );
+        }
+    }
+
+    fn spawn_capture_worker(
+        hwnd: HWND,
+        device_id: Option<String>,
+        upload_budget: Option<BudgetMeter>,
+        upload_backlog: Option<UploadBacklog>,
+    ) -> Result<CaptureWorkerRuntime, String> {
         let (command_tx, command_rx) = mpsc::channel::<WorkerCommand>();
         let (event_tx, event_rx) = mpsc::channel::<WorkerEvent>();
         let (stage_tx, stage_rx) = mpsc::channel::<StageCommand>();
@@ -1834,6 +2387,82 @@ mod win32_ui {
         let hwnd_value = hwnd as isize;
         let stage_event_tx = event_tx.clone();
         let stage_pending_queue = Arc::clone(&pending_stage_batches);
+        // Why:
+        // - Mosaic buffers cycle compose -> stage -> pool, so steady state
+        //   does not allocate a mosaic per batch. Captured frames own the OS
+        //   buffer and are not pooled.
+        let frame_pool = FramePool::default();
+        let cursor_overlay = cursor_overlay_from_env();
+        let encoder_settings = BTreeMap::from([
+            ("mosaic_format".to_string(), "rgba8".to_string()),
+            ("artifact_format".to_string(), "jpeg".to_string()),
+            ("jpeg_quality".to_string(), MOSAIC_JPEG_QUALITY.to_string()),
+            (
+                "cursor_overlay".to_string(),
+                if cursor_overlay.is_some() {
+                    "on"
+                } else {
+                    "off"
+                }
+                .to_string(),
+            ),
+        ]);
+        // Why:
+        // - Resuming the persisted chain head keeps a restarted session's
+        //   payloads linked; an unreadable file is logged and the chain
+        //   restarts at the session root, which the server can detect.
+        // - Chains are per session: after a new login the saved head is
+        //   ignored and the new session starts at its own root.
+        let chain_store = runtime_artifact_dir().map(ChainStore::in_dir);
+        let resumed_chain = match &chain_store {
+            Ok(store) => match store.load() {
+                Ok(chain) => chain,
+                Err(error) => {
+                    log_error("upload_prep", "chain_state_load_failed", &error.to_string());
+                    None
+                }
+            },
+            Err(error) => {
+                log_error("upload_prep", "chain_state_unavailable", error);
+                None
+            }
+        };
+        // Why:
+        // - A malformed server key is kept as an error rather than dropped,
+        //   so staging fails closed instead of writing plaintext artifacts.
+        let artifact_sealer = body_sealer_from_env().map_err(|error| error.to_string());
+        match &artifact_sealer {
+            Ok(Some(sealer)) => log_info(
+                "upload_prep",
+                "artifact_sealing_enabled",
+                &format!("recipient_key_id={}", sealer.recipient_key_id()),
+            ),
+            Ok(None) => {}
+            Err(error) => log_error("upload_prep", "artifact_sealing_misconfigured", error),
+        }
+        let tick_options = CaptureTickOptions {
+            blank_retries: blank_frame_retries_from_env(),
+            blank_retry_delay: blank_frame_retry_delay_from_env(),
+            ..CaptureTickOptions::default()
+        };
+        let mut stage_assembler = PayloadAssembler::new()
+            .with_quality_thresholds(tick_options.thresholds)
+            .with_mosaic_options(MosaicOptions {
+                pool: Some(frame_pool.clone()),
+                cursor_overlay,
+                ..MosaicOptions::default()
+            })
+            .with_provenance(client_provenance(
+                RealCaptureBackend::NAME,
+                encoder_settings,
+            ))
+            .with_context(ContextCollector::platform_default(context_policy_from_env()));
+        if let Some(chain) = resumed_chain {
+            stage_assembler = stage_assembler.with_chain_state(chain);
+        }
+        if let Some(device_id) = device_id {
+            stage_assembler = stage_assembler.with_device_id(device_id);
+        }
 
         let stage_worker_join = std::thread::Builder::new()
             .name("local-guard-stage-worker".to_string())
@@ -1853,17 +2482,53 @@ mod win32_ui {
                             let stage_queue_wait_ms = queued_at.elapsed().as_millis();
                             let prepare_started = Instant::now();
 
-                            let payload = match batch_to_payload(&batch, &session_id) {
-                                Ok(payload) => payload,
-                                Err(error) => {
-                                    let _ = stage_event_tx.send(WorkerEvent::WorkerError(format!(
-                                        "batch-to-payload failed: {error}"
-                                    )));
-                                    notify_capture_worker_event(hwnd_value);
-                                    continue;
-                                }
-                            };
-                            let staged = match stage_payload_for_upload(&payload) {
+                            // Invariant:
+                            // - A batch the upload backlog has no room for is dropped
+                            //   before it is numbered and chained, so the server
+                            //   never sees a gap in the payload chain.
+                            if upload_backlog.as_ref().is_some_and(UploadBacklog::is_full) {
+                                log_error(
+                                    "upload_prep",
+                                    "batch_dropped",
+                                    &format!(
+                                        "tick_seq={timer_tick_seq} reason=upload_backlog_full"
+                                    ),
+                                );
+                                notify_capture_worker_event(hwnd_value);
+                                continue;
+                            }
+                            let (payload, context_errors) =
+                                match stage_assembler.assemble(&batch, &session_id) {
+                                    Ok(assembled) => {
+                                        if let Ok(store) = &chain_store
+                                            && let Err(error) = store.save(&assembled.chain)
+                                        {
+                                            log_error(
+                                                "upload_prep",
+                                                "chain_state_save_failed",
+                                                &error.to_string(),
+                                            );
+                                        }
+                                        (assembled.payload, assembled.context_errors)
+                                    }
+                                    Err(error) => {
+                                        let _ = stage_event_tx.send(WorkerEvent::WorkerError(
+                                            format!("batch-to-payload failed: {error}"),
+                                        ));
+                                        notify_capture_worker_event(hwnd_value);
+                                        continue;
+                                    }
+                                };
+                            // Why:
+                            // - The budget is re-read per batch so the lower-quality
+                            //   policy applies as soon as the limit is reached.
+                            let jpeg_quality = budget_adjustment(upload_budget.as_ref())
+                                .jpeg_quality(MOSAIC_JPEG_QUALITY);
+                            let staged = match stage_payload_for_upload(
+                                &payload,
+                                &artifact_sealer,
+                                jpeg_quality,
+                            ) {
                                 Ok(staged) => staged,
                                 Err(error) => {
                                     let _ = stage_event_tx.send(WorkerEvent::WorkerError(format!(
@@ -1874,19 +2539,34 @@ mod win32_ui {
                                 }
                             };
 
+                            // Why:
+                            // - The queued clone shares the mosaic buffer, so the
+                            //   pool only recycles it when uploads are off.
+                            if let Some(backlog) = &upload_backlog {
+                                backlog.push(payload.clone());
+                            }
                             prepared_batches = prepared_batches.saturating_add(1);
                             let _ = stage_event_tx.send(WorkerEvent::BatchPrepared {
                                 timer_tick_seq,
                                 frame_number,
                                 prepared_batches,
+                                batch_id: payload.metadata.batch_id.clone().unwrap_or_default(),
+                                sequence_number: payload.metadata.sequence_number.unwrap_or(0),
                                 mosaic_width: payload.mosaic_width,
                                 mosaic_height: payload.mosaic_height,
                                 batch_prepare_ms: prepare_started.elapsed().as_millis(),
                                 stage_queue_wait_ms,
                                 pending_stage_queue: stage_pending_queue.load(Ordering::Relaxed),
-                                artifacts: staged,
+                                blank_tiles: payload.metadata.blank_tiles.len(),
+                                capture_status: capture_status_for_metadata(&payload.metadata),
+                                context_errors: context_errors
+                                    .iter()
+                                    .map(ToString::to_string)
+                                    .collect(),
+                                artifacts: Box::new(staged),
                             });
                             notify_capture_worker_event(hwnd_value);
+                            frame_pool.release_shared(payload.mosaic_rgba);
                         }
                         StageCommand::ResetBatch => {
                             prepared_batches = 0;
@@ -1914,7 +2594,7 @@ mod win32_ui {
                         return;
                     }
                 };
-                let mut frame_batch = match FrameBatch::new(9) {
+                let mut frame_batch = match MultiDisplayFrameBatch::new(9) {
                     Ok(batch) => batch,
                     Err(error) => {
                         let _ = capture_event_tx.send(WorkerEvent::WorkerError(format!(
@@ -1931,7 +2611,7 @@ mod win32_ui {
                     match command {
                         WorkerCommand::CaptureTick {
                             timer_tick_seq,
-                            display_id,
+                            display_ids,
                             session_id,
                             captured_at_ms,
                             queued_at,
@@ -1939,32 +2619,49 @@ mod win32_ui {
                             capture_pending_queue.fetch_sub(1, Ordering::Relaxed);
                             let queue_wait_ms = queued_at.elapsed().as_millis();
                             let capture_started = Instant::now();
-                            let frame =
-                                match capture_backend.capture_frame(&display_id, captured_at_ms) {
-                                    Ok(frame) => frame,
-                                    Err(error) => {
-                                        let _ = capture_event_tx.send(WorkerEvent::WorkerError(
-                                            format!("frame capture failed: {error}"),
-                                        ));
-                                        notify_capture_worker_event(hwnd_value);
-                                        continue;
-                                    }
-                                };
-                            let frame_width = frame.width;
-                            let frame_height = frame.height;
 
-                            let maybe_batch = match frame_batch.push_frame(frame) {
-                                Ok(maybe_batch) => maybe_batch,
+                            // Why:
+                            // - All-displays mode captures every target with the same
+                            //   timestamp; each display feeds its own batch stream.
+                            // Failure mode:
+                            // - Any capture error aborts the whole tick so per-display
+                            //   batches never drift out of step.
+                            let outcome = match run_capture_tick_with(
+                                &capture_backend,
+                                &mut frame_batch,
+                                &display_ids,
+                                captured_at_ms,
+                                &tick_options,
+                            ) {
+                                Ok(outcome) => outcome,
                                 Err(error) => {
                                     let _ = capture_event_tx.send(WorkerEvent::WorkerError(
-                                        format!("frame batch push failed: {error}"),
+                                        format!("capture tick failed: {error}"),
                                     ));
                                     notify_capture_worker_event(hwnd_value);
                                     continue;
                                 }
                             };
+                            if !outcome.geometry_resets.is_empty() {
+                                log_info(
+                                    "capture_worker",
+                                    "geometry_reset",
+                                    &format!(
+                                        "displays={} frames_discarded={} frame={}x{}",
+                                        outcome.geometry_resets.join(","),
+                                        outcome.frames_discarded,
+                                        outcome.frame_width,
+                                        outcome.frame_height
+                                    ),
+                                );
+                            }
+                            let frame_width = outcome.frame_width;
+                            let frame_height = outcome.frame_height;
+                            let completed_batches = outcome.completed_batches;
                             frame_number = frame_number.saturating_add(1);
-                            let buffered_frames = frame_batch.len();
+                            let buffered_frames = display_ids
+                                .first()
+                                .map_or(0, |display_id| frame_batch.len_for(display_id));
                             let capture_duration_ms = capture_started.elapsed().as_millis();
                             let capture_lag_ms =
                                 unix_timestamp_millis().saturating_sub(captured_at_ms as u128);
@@ -1973,6 +2670,7 @@ mod win32_ui {
                                 timer_tick_seq,
                                 frame_number,
                                 buffered_frames,
+                                display_count: display_ids.len(),
                                 queue_wait_ms,
                                 capture_duration_ms,
                                 capture_lag_ms,
@@ -1985,12 +2683,12 @@ mod win32_ui {
                             });
                             notify_capture_worker_event(hwnd_value);
 
-                            if let Some(batch) = maybe_batch {
+                            for batch in completed_batches {
                                 capture_stage_pending_queue.fetch_add(1, Ordering::Relaxed);
                                 let stage_command = StageCommand::PrepareBatch {
                                     timer_tick_seq,
                                     frame_number,
-                                    session_id,
+                                    session_id: session_id.clone(),
                                     batch,
                                     queued_at: Instant::now(),
                                 };
@@ -2005,7 +2703,7 @@ mod win32_ui {
                         }
                         WorkerCommand::ResetBatch => {
                             frame_number = 0;
-                            if let Ok(new_batch) = FrameBatch::new(9) {
+                            if let Ok(new_batch) = MultiDisplayFrameBatch::new(9) {
                                 frame_batch = new_batch;
                             }
                             let _ = capture_stage_tx.send(StageCommand::ResetBatch);
@@ -2050,33 +2748,44 @@ mod win32_ui {
         })
     }
 
-    fn capture_fps_from_env() -> u32 {
-        std::env::var("LOCAL_GUARD_CAPTURE_FPS")
-            .ok()
-            .and_then(|value| value.trim().parse::<u32>().ok())
-            .filter(|fps| *fps > 0)
-            .unwrap_or(DEFAULT_CAPTURE_FPS)
-    }
-
-    fn stage_payload_for_upload(payload: &MosaicPayload) -> Result<StagedPayloadArtifacts, String> {
+    fn stage_payload_for_upload(
+        payload: &MosaicPayload,
+        sealer: &Result<Option<BodySealer>, String>,
+        jpeg_quality: u8,
+    ) -> Result<StagedPayloadArtifacts, String> {
         let stage_started = Instant::now();
+        let sealer = sealer
+            .as_ref()
+            .map_err(|error| format!("artifact sealing unavailable: {error}"))?
+            .as_ref();
         let base_dir = runtime_artifact_dir()?;
         std::fs::create_dir_all(&base_dir)
             .map_err(|error| format!("artifact directory create failed: {error}"))?;
 
         let stamp = timestamp_compact_utc();
-        let jpeg_path = base_dir.join(format!("{stamp}_mosaic.jpg"));
-        let json_path = base_dir.join(format!("{stamp}_payload.json"));
+        // Why: sealed artifacts get a distinct suffix so tooling never
+        // mistakes an envelope for a plaintext JPEG or payload.
+        let sealed_suffix = if sealer.is_some() { ".sealed.json" } else { "" };
+        let jpeg_path = base_dir.join(format!("{stamp}_mosaic.jpg{sealed_suffix}"));
+        let json_path = base_dir.join(format!("{stamp}_payload.json{sealed_suffix}"));
 
         let rgb_convert_started = Instant::now();
-        let mosaic_rgb = rgba_to_rgb(&payload.mosaic_rgba)?;
+        let mosaic_rgb = convert_pixels(
+            &payload.mosaic_rgba,
+            payload.mosaic_width,
+            payload.mosaic_height,
+            payload.mosaic_width as usize * PixelFormat::Rgba8.bytes_per_pixel(),
+            PixelFormat::Rgba8,
+            PixelFormat::Rgb8,
+        )
+        .map_err(|error| format!("mosaic RGB conversion failed: {error}"))?;
         let rgba_to_rgb_ms = rgb_convert_started.elapsed().as_millis();
         let raw_rgb_bytes = mosaic_rgb.len();
 
         let mut jpeg_bytes = Vec::new();
 
         let jpeg_encode_started = Instant::now();
-        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_bytes, MOSAIC_JPEG_QUALITY)
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_bytes, jpeg_quality)
             .encode(
                 &mosaic_rgb,
                 payload.mosaic_width,
@@ -2087,7 +2796,7 @@ mod win32_ui {
         let jpeg_encode_ms = jpeg_encode_started.elapsed().as_millis();
 
         let disk_write_started = Instant::now();
-        std::fs::write(&jpeg_path, &jpeg_bytes)
+        write_staged_artifact(&jpeg_path, &jpeg_bytes, sealer, "image/jpeg")
             .map_err(|error| format!("jpeg artifact write failed: {error}"))?;
         let jpeg_size_bytes = jpeg_bytes.len();
 
@@ -2101,14 +2810,14 @@ mod win32_ui {
             "mosaic_height": payload.mosaic_height,
             "mosaic_format": "jpeg",
             "mosaic_color_space": "RGB",
-            "mosaic_jpeg_quality": MOSAIC_JPEG_QUALITY,
+            "mosaic_jpeg_quality": jpeg_quality,
             "mosaic_jpeg_base64": jpeg_base64,
         });
         let payload_json = serde_json::to_vec(&payload_json)
             .map_err(|error| format!("json encode failed: {error}"))?;
         let json_encode_ms = json_encode_started.elapsed().as_millis();
         let json_size_bytes = payload_json.len();
-        std::fs::write(&json_path, payload_json)
+        write_staged_artifact(&json_path, &payload_json, sealer, "application/json")
             .map_err(|error| format!("json artifact write failed: {error}"))?;
         let disk_write_ms = disk_write_started.elapsed().as_millis();
 
@@ -2129,6 +2838,7 @@ mod win32_ui {
         Ok(StagedPayloadArtifacts {
             jpeg_path,
             json_path,
+            jpeg_quality,
             jpeg_size_bytes,
             json_size_bytes,
             base64_size_chars,
@@ -2138,20 +2848,20 @@ mod win32_ui {
         })
     }
 
-    fn rgba_to_rgb(rgba: &[u8]) -> Result<Vec<u8>, String> {
-        if rgba.len() % 4 != 0 {
-            return Err(format!(
-                "invalid RGBA buffer length {}; expected multiple of 4",
-                rgba.len()
-            ));
-        }
-
-        let mut rgb = Vec::with_capacity((rgba.len() / 4) * 3);
-        for px in rgba.chunks_exact(4) {
-            rgb.extend_from_slice(&px[..3]);
+    fn write_staged_artifact(
+        path: &Path,
+        bytes: &[u8],
+        sealer: Option<&BodySealer>,
+        content_type: &str,
+    ) -> Result<(), String> {
+        match sealer {
+            Some(sealer) => {
+                let sealed = seal_artifact(sealer, bytes, content_type)
+                    .map_err(|error| error.to_string())?;
+                std::fs::write(path, sealed).map_err(|error| error.to_string())
+            }
+            None => std::fs::write(path, bytes).map_err(|error| error.to_string()),
         }
-
-        Ok(rgb)
     }
 
     fn average_ms(total: u128, count: u64) -> u128 {
@@ -2326,6 +3036,247 @@ mod win32_ui {
         });
     }
 
+    fn load_transport_policy() -> Option<TransportSecurity> {
+        match transport_security_from_env() {
+            Ok(policy) => {
+                log_info(
+                    "bootstrap",
+                    "transport_policy",
+                    &format!(
+                        "pins={} backup_pins={} custom_ca={} min_tls={:?} endpoint_allowlist={} client_cert_not_after_ms={}",
+                        policy.pins().len(),
+                        policy.backup_pins().len(),
+                        policy.uses_custom_ca(),
+                        policy.min_tls_version(),
+                        EndpointAllowlist::builtin().entries().join(","),
+                        policy
+                            .client_identity()
+                            .map(|identity| identity.not_after_ms().to_string())
+                            .unwrap_or_else(|| "none".to_string())
+                    ),
+                );
+                Some(policy)
+            }
+            Err(error) => {
+                log_error("bootstrap", "transport_policy_invalid", &error.to_string());
+                None
+            }
+        }
+    }
+
+    fn log_proxy_config() {
+        // Why: `ProxyConfig` renders the password as `***`, so the proxy
+        // line is safe to log verbatim.
+        match proxy_from_env() {
+            Ok(Some(proxy)) => log_info("bootstrap", "proxy", &format!("proxy={proxy}")),
+            Ok(None) => log_info("bootstrap", "proxy", "proxy=direct"),
+            Err(error) => log_error("bootstrap", "proxy_invalid", &error.to_string()),
+        }
+    }
+
+    /// Builds the upload client and dispatcher from the environment.
+    ///
+    /// Failure mode:
+    /// - Invalid transport, proxy, sealing, or budget settings leave uploads
+    ///   off; batches are still staged on disk.
+    fn start_upload_runtime(transport_policy: Option<&TransportSecurity>) -> Option<UploadRuntime> {
+        let Some(endpoint) = ingest_url_from_env() else {
+            log_info(
+                "bootstrap",
+                "upload_disabled",
+                "LOCAL_GUARD_INGEST_URL unset; batches are staged only",
+            );
+            return None;
+        };
+        let Some(policy) = transport_policy else {
+            log_error("bootstrap", "upload_disabled", "transport policy invalid");
+            return None;
+        };
+        let mut connector = match HttpsConnector::new(policy) {
+            Ok(connector) => connector,
+            Err(error) => {
+                log_error("bootstrap", "upload_disabled", &error.to_string());
+                return None;
+            }
+        };
+        match proxy_from_env() {
+            Ok(Some(proxy)) => connector = connector.with_proxy(proxy),
+            Ok(None) => {}
+            Err(error) => {
+                log_error("bootstrap", "upload_disabled", &error.to_string());
+                return None;
+            }
+        }
+        let transport = Arc::new(HttpsUploadTransport::new(connector));
+        let clock: Arc<dyn UploadClock> = Arc::new(SystemUploadClock::new());
+        let mut client =
+            match UploadClient::new(endpoint, RetryPolicy::mvp_default(), transport.clone()) {
+                Ok(client) => client,
+                Err(error) => {
+                    log_error("bootstrap", "upload_disabled", &error.to_string());
+                    return None;
+                }
+            };
+        match body_sealer_from_env() {
+            Ok(Some(sealer)) => client = client.with_sealer(sealer),
+            Ok(None) => {}
+            Err(error) => {
+                log_error("bootstrap", "upload_disabled", &error.to_string());
+                return None;
+            }
+        }
+        if let Some(compression) = upload_compression_from_env() {
+            client = client.with_compression(compression);
+        }
+        if let Some(chunking) = upload_chunking_from_env() {
+            client = client.with_chunking(transport, chunking);
+        }
+        if let Some(limiter) = upload_rate_limiter_from_env(clock.clone()) {
+            client = client.with_rate_limit(limiter);
+        }
+        client = client.with_circuit_breaker(circuit_breaker_from_env(clock.clone()));
+
+        let mut budget_store = None;
+        if let Some(budget) = data_budget_from_env() {
+            // Why:
+            // - Unreadable counters disable uploads instead of restarting
+            //   from zero, which would reopen an exhausted budget.
+            let store = match runtime_artifact_dir() {
+                Ok(dir) => BudgetStore::in_dir(dir),
+                Err(error) => {
+                    log_error("bootstrap", "upload_disabled", &error);
+                    return None;
+                }
+            };
+            let usage = match store.load() {
+                Ok(usage) => usage,
+                Err(error) => {
+                    log_error("bootstrap", "upload_disabled", &error.to_string());
+                    return None;
+                }
+            };
+            client = client.with_data_budget(BudgetMeter::new(budget, usage, clock.clone()));
+            budget_store = Some(store);
+        }
+
+        let config = upload_dispatcher_config_from_env();
+        let (dispatcher, events) = UploadDispatcher::new(client.clone(), config);
+        log_info(
+            "bootstrap",
+            "upload_ready",
+            &format!(
+                "endpoint={} workers={} max_in_flight={} ordering={:?} compression={:?} rate_limit_bps={} budget_policy={}",
+                client.endpoint(),
+                config.workers(),
+                config.max_in_flight(),
+                config.ordering(),
+                client.content_encoding(),
+                client.rate_limiter().map_or_else(
+                    || "off".to_string(),
+                    |limiter| limiter.bytes_per_second().to_string()
+                ),
+                client
+                    .data_budget()
+                    .map_or("off", |meter| meter.budget().policy().as_str()),
+            ),
+        );
+        let backlog = upload_backlog_from_env();
+        log_info(
+            "bootstrap",
+            "upload_backlog",
+            &format!("max_bytes={}", backlog.max_bytes()),
+        );
+        Some(UploadRuntime {
+            client,
+            dispatcher,
+            events,
+            budget_store,
+            backlog,
+        })
+    }
+
+    fn load_device_key() -> Option<DeviceKey> {
+        let store = match runtime_artifact_dir() {
+            Ok(dir) => DeviceKeyStore::in_dir(dir),
+            Err(error) => {
+                log_error("bootstrap", "device_key_unavailable", &error);
+                return None;
+            }
+        };
+        match store.load_or_create() {
+            Ok(key) => {
+                log_info(
+                    "bootstrap",
+                    "device_key_ready",
+                    &format!("key_id={}", key.public_key().key_id()),
+                );
+                Some(key)
+            }
+            Err(error) => {
+                log_error("bootstrap", "device_key_failed", &error.to_string());
+                None
+            }
+        }
+    }
+
+    fn startup_enrollment(device_key: Option<DeviceKey>) -> EnrollmentState {
+        let Some(device_key) = device_key else {
+            log_error(
+                "bootstrap",
+                "enrollment_skipped",
+                "device key unavailable; capture stays blocked",
+            );
+            return EnrollmentState::Unenrolled;
+        };
+        let store = match runtime_artifact_dir() {
+            Ok(dir) => DeviceIdentityStore::in_dir(dir),
+            Err(error) => {
+                log_error("bootstrap", "enrollment_unavailable", &error);
+                return EnrollmentState::Unenrolled;
+            }
+        };
+
+        // Why:
+        // - The mock backend is the in-process stand-in service; the code
+        //   from the environment is issued to it so the local flow exercises
+        //   the same proof-of-possession path as production enrollment.
+        let enrollment_code = enrollment_code_from_env();
+        let service = LocalEnrollmentService::new();
+        if let Some(code) = &enrollment_code {
+            service.issue_code(code.clone());
+        }
+        let result = EnrollmentClient::new(ENROLLMENT_ENDPOINT, Arc::new(service))
+            .map_err(local_guard_app::AppError::from)
+            .and_then(|client| {
+                restore_enrollment(
+                    &store,
+                    &client,
+                    &device_key,
+                    enrollment_code.as_deref(),
+                    unix_timestamp_millis() as u64,
+                )
+            });
+        match result {
+            Ok(state) => {
+                log_info(
+                    "bootstrap",
+                    "enrollment_state",
+                    &format!(
+                        "state={:?} device_id={} key_id={}",
+                        device_state_for_ui(&state),
+                        state.device_id().unwrap_or("none"),
+                        device_key.public_key().key_id()
+                    ),
+                );
+                state
+            }
+            Err(error) => {
+                log_error("bootstrap", "enrollment_failed", &error.to_string());
+                EnrollmentState::Unenrolled
+            }
+        }
+    }
+
     fn runtime_artifact_dir() -> Result<PathBuf, String> {
         let exe_path = std::env::current_exe()
             .map_err(|error| format!("failed to resolve executable path: {error}"))?;
@@ -2335,16 +3286,22 @@ mod win32_ui {
         Ok(exe_dir.join("prepared_uploads"))
     }
 
+    /// Position and size of a child control in parent client coordinates.
+    #[derive(Debug, Clone, Copy)]
+    struct ControlBounds {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    }
+
     fn create_child_control(
         parent: HWND,
         instance: *mut c_void,
         class_name: &str,
         text: &str,
         style: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
+        bounds: ControlBounds,
         control_id: i32,
     ) -> Result<HWND, String> {
         let class_name_wide = to_wide(class_name);
@@ -2358,10 +3315,10 @@ mod win32_ui {
                 class_name_wide.as_ptr(),
                 text_wide.as_ptr(),
                 style,
-                x,
-                y,
-                width,
-                height,
+                bounds.x,
+                bounds.y,
+                bounds.width,
+                bounds.height,
                 parent,
                 control_id_to_hmenu(control_id),
                 instance,
@@ -0,0 +1,149 @@
+//! # Module: upload_backlog
+//!
+//! ## Purpose
+//! Holds assembled payloads until the upload dispatcher accepts them, so a
+//! paused data budget or a full in-flight window delays batches instead of
+//! losing them.
+//!
+//! ## Responsibilities
+//! - Queue payloads in assembly order, bounded by mosaic bytes.
+//! - Tell the producer when it is full, before the next batch is chained.
+//! - Hand queued payloads to the dispatcher in order while it has room and
+//!   the data budget allows sending.
+//!
+//! ## Invariants
+//! - Clones share one queue: the stage worker pushes, the UI thread pumps.
+//! - Payloads leave the queue only by being accepted by the dispatcher or
+//!   by [`UploadBacklog::clear`]; a later payload never overtakes an
+//!   earlier one.
+//! - The byte bound is checked before a push, so one payload may overshoot
+//!   it; a producer that checks [`UploadBacklog::is_full`] before chaining
+//!   never leaves a gap in the payload chain.
+//!
+//! ## Error model
+//! Infallible; a refused submission simply stays queued.
+//!
+//! ## Security and privacy notes
+//! Payloads are kept in memory only and dropped on logout or kill switch
+//! via [`UploadBacklog::clear`].
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use local_guard_core::MosaicPayload;
+use local_guard_upload::{BudgetMeter, UploadDispatcher};
+
+use crate::budget_adjustment;
+
+/// Default bound on queued mosaic bytes (512 MiB).
+pub const DEFAULT_UPLOAD_BACKLOG_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+struct Queue {
+    payloads: VecDeque<MosaicPayload>,
+    bytes: u64,
+}
+
+/// Shared FIFO of payloads waiting for the upload dispatcher.
+///
+/// # Ownership
+/// Cheap to clone; clones share one queue.
+#[derive(Debug, Clone)]
+pub struct UploadBacklog {
+    max_bytes: u64,
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl Default for UploadBacklog {
+    fn default() -> Self {
+        Self::new(DEFAULT_UPLOAD_BACKLOG_BYTES)
+    }
+}
+
+impl UploadBacklog {
+    /// Creates an empty backlog holding up to `max_bytes` of mosaic pixels
+    /// (minimum 1).
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes: max_bytes.max(1),
+            queue: Arc::new(Mutex::new(Queue::default())),
+        }
+    }
+
+    /// Returns the byte bound.
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Returns how many payloads are queued.
+    pub fn len(&self) -> usize {
+        self.lock().payloads.len()
+    }
+
+    /// Returns whether nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.lock().payloads.is_empty()
+    }
+
+    /// Returns queued mosaic bytes.
+    pub fn bytes(&self) -> u64 {
+        self.lock().bytes
+    }
+
+    /// Returns whether the byte bound is reached; check before chaining the
+    /// next batch and drop it unchained when full.
+    pub fn is_full(&self) -> bool {
+        self.lock().bytes >= self.max_bytes
+    }
+
+    /// Queues `payload` behind every payload already waiting.
+    pub fn push(&self, payload: MosaicPayload) {
+        let mut queue = self.lock();
+        queue.bytes = queue.bytes.saturating_add(payload.mosaic_rgba.len() as u64);
+        queue.payloads.push_back(payload);
+    }
+
+    /// Submits queued payloads to `dispatcher` in order until its window is
+    /// full; submits nothing while `budget` pauses uploads. Returns how many
+    /// payloads were accepted.
+    pub fn pump(
+        &self,
+        dispatcher: &UploadDispatcher,
+        budget: Option<&BudgetMeter>,
+        token: &str,
+    ) -> usize {
+        // Why: under the queue-only policy every send would be refused until
+        // the period resets, so payloads wait here instead of failing.
+        if budget_adjustment(budget).uploads_paused {
+            return 0;
+        }
+        let mut queue = self.lock();
+        let mut accepted = 0;
+        while let Some(payload) = queue.payloads.front() {
+            // Invariant: the clone shares the mosaic buffer, so a refused
+            // submission costs no copy and the payload stays at the front.
+            if dispatcher.try_submit(payload.clone(), token).is_none() {
+                break;
+            }
+            if let Some(payload) = queue.payloads.pop_front() {
+                queue.bytes = queue.bytes.saturating_sub(payload.mosaic_rgba.len() as u64);
+            }
+            accepted += 1;
+        }
+        accepted
+    }
+
+    /// Drops every queued payload, for logout or the kill switch. Returns
+    /// how many were dropped.
+    pub fn clear(&self) -> usize {
+        let mut queue = self.lock();
+        queue.bytes = 0;
+        std::mem::take(&mut queue.payloads).len()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,70 @@
+//! Integration tests for all-displays capture mode.
+
+use local_guard_app::{batch_to_payload, resolve_capture_targets, run_capture_tick};
+use local_guard_capture::{CaptureBackend, CaptureMode, DisplayInfo, SyntheticCaptureBackend};
+use local_guard_core::MultiDisplayFrameBatch;
+use local_guard_upload::idempotency_key_for_payload;
+
+fn three_displays() -> Vec<DisplayInfo> {
+    ["display-a", "display-b", "display-c"]
+        .iter()
+        .map(|id| DisplayInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            width: 2,
+            height: 2,
+        })
+        .collect()
+}
+
+#[test]
+fn all_displays_capture_tests_resolve_every_display_as_target() {
+    let displays = three_displays();
+
+    let all = resolve_capture_targets(CaptureMode::AllDisplays, &displays, Some("display-b"));
+    assert_eq!(all, vec!["display-a", "display-b", "display-c"]);
+
+    let selected =
+        resolve_capture_targets(CaptureMode::SelectedDisplay, &displays, Some("display-b"));
+    assert_eq!(selected, vec!["display-b"]);
+
+    let stale = resolve_capture_targets(CaptureMode::SelectedDisplay, &displays, Some("gone"));
+    assert!(stale.is_empty());
+}
+
+#[test]
+fn all_displays_capture_tests_emit_one_stream_per_display_with_distinct_keys() {
+    let backend = SyntheticCaptureBackend::with_displays(three_displays());
+    let mut batches = MultiDisplayFrameBatch::new(9).expect("batch set should build");
+
+    let targets = resolve_capture_targets(CaptureMode::AllDisplays, &backend.list_displays(), None);
+    let mut emitted = Vec::new();
+    for tick in 0..9_u64 {
+        let outcome = run_capture_tick(&backend, &mut batches, &targets, 1_000 + tick)
+            .expect("synthetic capture should work");
+        assert_eq!(outcome.frames_captured, 3);
+        emitted.extend(outcome.completed_batches);
+    }
+
+    assert_eq!(emitted.len(), 3);
+    assert!(batches.is_empty());
+
+    let payloads: Vec<_> = emitted
+        .iter()
+        .map(|batch| batch_to_payload(batch, "session-multi").expect("payload should build"))
+        .collect();
+    let screen_ids: Vec<_> = payloads
+        .iter()
+        .map(|payload| payload.metadata.screen_id.as_str())
+        .collect();
+    assert_eq!(screen_ids, vec!["display-a", "display-b", "display-c"]);
+
+    let mut keys: Vec<_> = payloads.iter().map(idempotency_key_for_payload).collect();
+    keys.sort();
+    keys.dedup();
+    assert_eq!(
+        keys.len(),
+        3,
+        "each display stream needs its own idempotency key"
+    );
+}
@@ -0,0 +1,203 @@
+//! Integration tests for batch identifiers, sequence numbers, and analysis
+//! response correlation.
+
+mod common;
+
+use std::collections::HashSet;
+
+use common::fixture_frames;
+use local_guard_app::{AppError, BatchLedger, PayloadAssembler, link_analysis_response};
+use local_guard_core::MosaicPayload;
+use local_guard_upload::idempotency_key_for_payload;
+
+fn assemble(assembler: &PayloadAssembler, session_id: &str) -> MosaicPayload {
+    assembler
+        .assemble(&fixture_frames(), session_id)
+        .expect("fixture batch should assemble")
+        .payload
+}
+
+fn analysis_response(request_id: &str, batch_id: Option<&str>) -> String {
+    let mut response = serde_json::json!({
+        "schema_version": "v1",
+        "request_id": request_id,
+        "model_results": [],
+        "categories": [{ "category": "credential_theft", "severity": 80 }]
+    });
+    if let Some(batch_id) = batch_id {
+        response["batch_id"] = serde_json::json!(batch_id);
+    }
+    response.to_string()
+}
+
+fn is_uuid_v4(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+    lengths == [8, 4, 4, 4, 12]
+        && groups.iter().all(|group| {
+            group
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+        })
+        && groups[2].starts_with('4')
+        && matches!(groups[3].chars().next(), Some('8' | '9' | 'a' | 'b'))
+}
+
+#[test]
+fn batch_correlation_tests_ids_are_unique_uuid_v4() {
+    let assembler = PayloadAssembler::new();
+    let mut seen = HashSet::new();
+    for _ in 0..32 {
+        let batch_id = assemble(&assembler, "session-a")
+            .metadata
+            .batch_id
+            .expect("assembled payload should carry a batch id");
+        assert!(is_uuid_v4(&batch_id), "{batch_id} should be a UUID v4");
+        assert!(seen.insert(batch_id), "batch ids must not repeat");
+    }
+}
+
+#[test]
+fn batch_correlation_tests_sequence_increments_and_resets_per_session() {
+    let assembler = PayloadAssembler::new();
+    let sequence = |session_id: &str| assemble(&assembler, session_id).metadata.sequence_number;
+
+    assert_eq!(sequence("session-a"), Some(1));
+    assert_eq!(sequence("session-a"), Some(2));
+    assert_eq!(sequence("session-a"), Some(3));
+    assert_eq!(sequence("session-b"), Some(1));
+    assert_eq!(sequence("session-b"), Some(2));
+}
+
+#[test]
+fn batch_correlation_tests_seeded_ids_are_deterministic() {
+    let first = PayloadAssembler::new().with_batch_id_seed(7);
+    let second = PayloadAssembler::new().with_batch_id_seed(7);
+    for _ in 0..3 {
+        assert_eq!(
+            assemble(&first, "session-a").metadata.batch_id,
+            assemble(&second, "session-a").metadata.batch_id
+        );
+    }
+}
+
+#[test]
+fn batch_correlation_tests_resumed_sequence_continues() {
+    let assembler = PayloadAssembler::new().with_resumed_sequence("session-a", 41);
+    assert_eq!(
+        assemble(&assembler, "session-a").metadata.sequence_number,
+        Some(42)
+    );
+}
+
+#[test]
+fn batch_correlation_tests_failed_assembly_consumes_no_number() {
+    let assembler = PayloadAssembler::new();
+    assert_eq!(
+        assemble(&assembler, "session-a").metadata.sequence_number,
+        Some(1)
+    );
+    assert!(assembler.assemble(&[], "session-a").is_err());
+    assert_eq!(
+        assemble(&assembler, "session-a").metadata.sequence_number,
+        Some(2)
+    );
+}
+
+#[test]
+fn batch_correlation_tests_identity_survives_json_round_trip() {
+    let payload = assemble(&PayloadAssembler::new(), "session-a");
+    let json = serde_json::to_value(&payload.metadata).expect("metadata should serialize");
+    assert_eq!(json["sequence_number"], 1);
+    assert_eq!(
+        json["batch_id"].as_str(),
+        payload.metadata.batch_id.as_deref()
+    );
+
+    let decoded: local_guard_core::BatchMetadata =
+        serde_json::from_value(json).expect("metadata should deserialize");
+    assert_eq!(decoded, payload.metadata);
+}
+
+#[test]
+fn batch_correlation_tests_ledger_links_echoed_batch_id() {
+    let assembler = PayloadAssembler::new();
+    let mut ledger = BatchLedger::default();
+    let payload = assemble(&assembler, "session-a");
+    let batch_id = payload.metadata.batch_id.clone().expect("batch id");
+    let record = ledger.record(&payload).expect("payload should be recorded");
+    assert_eq!(
+        record.idempotency_key,
+        idempotency_key_for_payload(&payload)
+    );
+    assert_eq!(record.sequence_number, 1);
+
+    let linked = link_analysis_response(
+        &mut ledger,
+        &analysis_response("req-1", Some(&batch_id)),
+        None,
+    )
+    .expect("response should link");
+    let batch = linked.batch.expect("batch should be known");
+    assert_eq!(batch.batch_id, batch_id);
+    assert_eq!(batch.request_id.as_deref(), Some("req-1"));
+    assert_eq!(linked.signals.len(), 1);
+    assert_eq!(
+        ledger
+            .find_by_request_id("req-1")
+            .map(|record| record.batch_id.as_str()),
+        Some(batch_id.as_str())
+    );
+}
+
+#[test]
+fn batch_correlation_tests_ledger_falls_back_to_uploaded_batch() {
+    let assembler = PayloadAssembler::new();
+    let mut ledger = BatchLedger::default();
+    let payload = assemble(&assembler, "session-a");
+    let batch_id = payload.metadata.batch_id.clone().expect("batch id");
+    ledger.record(&payload);
+
+    let linked = link_analysis_response(
+        &mut ledger,
+        &analysis_response("req-2", None),
+        Some(&batch_id),
+    )
+    .expect("response should link");
+    assert_eq!(linked.request_id, "req-2");
+    assert_eq!(linked.batch.map(|batch| batch.batch_id), Some(batch_id));
+
+    let unknown = link_analysis_response(&mut ledger, &analysis_response("req-3", None), None)
+        .expect("response without batch should still parse");
+    assert!(unknown.batch.is_none());
+}
+
+#[test]
+fn batch_correlation_tests_rejects_mismatched_batch_id() {
+    let mut ledger = BatchLedger::default();
+    let error = link_analysis_response(
+        &mut ledger,
+        &analysis_response("req-4", Some("9b2f6c1e-4d3a-4f8b-9a1c-2e5d7f9b0c13")),
+        Some("0a1b2c3d-4e5f-4a6b-8c7d-8e9f0a1b2c3d"),
+    )
+    .expect_err("mismatched batch id should be rejected");
+    assert!(matches!(error, AppError::Analysis(_)));
+}
+
+#[test]
+fn batch_correlation_tests_ledger_evicts_oldest() {
+    let assembler = PayloadAssembler::new();
+    let mut ledger = BatchLedger::new(2);
+    let ids: Vec<String> = (0..3)
+        .map(|_| {
+            let payload = assemble(&assembler, "session-a");
+            ledger.record(&payload);
+            payload.metadata.batch_id.expect("batch id")
+        })
+        .collect();
+
+    assert_eq!(ledger.len(), 2);
+    assert!(ledger.get(&ids[0]).is_none());
+    assert!(ledger.get(&ids[2]).is_some());
+    assert!(ledger.record(&common::fixture_payload()).is_none());
+}
@@ -0,0 +1,206 @@
+//! Integration tests for blank-frame detection and capture status projection.
+
+use local_guard_app::{
+    CaptureTickOptions, PayloadAssembler, batch_to_payload, blank_frame_retry_delay_from_env,
+    capture_status_for_metadata, run_capture_tick_with,
+};
+use local_guard_capture::{
+    CaptureBackend, CaptureError, DEFAULT_BLANK_RETRY_DELAY, DisplayInfo, FaultPlan,
+    FaultyCaptureBackend, SyntheticCaptureBackend, capture_frame_with_blank_retry,
+};
+use local_guard_core::QualityThresholds;
+use local_guard_core::{Frame, MultiDisplayFrameBatch};
+use local_guard_ui::StageStatus;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Backend that returns black frames for the first `black_frames` calls.
+struct FlickeringBackend {
+    black_frames: u32,
+    calls: Mutex<u32>,
+    stamps: Mutex<Vec<u64>>,
+}
+
+impl FlickeringBackend {
+    fn new(black_frames: u32) -> Self {
+        Self {
+            black_frames,
+            calls: Mutex::new(0),
+            stamps: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl CaptureBackend for FlickeringBackend {
+    fn list_displays(&self) -> Vec<DisplayInfo> {
+        vec![DisplayInfo {
+            id: "display-1".to_string(),
+            name: "Display".to_string(),
+            width: 2,
+            height: 2,
+        }]
+    }
+
+    fn capture_frame(&self, display_id: &str, captured_at_ms: u64) -> Result<Frame, CaptureError> {
+        let mut calls = self.calls.lock().expect("calls lock");
+        *calls += 1;
+        self.stamps
+            .lock()
+            .expect("stamps lock")
+            .push(captured_at_ms);
+        let rgba = if *calls <= self.black_frames {
+            [0, 0, 0, 255].repeat(4)
+        } else {
+            vec![
+                0, 0, 0, 255, 90, 90, 90, 255, 180, 180, 180, 255, 255, 255, 255, 255,
+            ]
+        };
+        Frame::new(display_id, 2, 2, captured_at_ms, rgba)
+            .map_err(|error| CaptureError::Backend(error.to_string()))
+    }
+}
+
+#[test]
+fn blank_frame_detection_tests_all_blank_batch_degrades_capture() {
+    // Synthetic frames are solid fills, i.e. what a locked workstation returns.
+    let backend = FaultyCaptureBackend::new(SyntheticCaptureBackend::new(), FaultPlan::default());
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|tick| {
+            backend
+                .capture_frame("display-1", 1_000 + tick)
+                .expect("capture should work")
+        })
+        .collect();
+
+    let payload = batch_to_payload(&frames, "session-blank").expect("payload should build");
+    assert_eq!(payload.metadata.blank_tiles, (0..9).collect::<Vec<_>>());
+    assert_eq!(
+        capture_status_for_metadata(&payload.metadata),
+        StageStatus::Degraded
+    );
+
+    let mut partial = payload.metadata.clone();
+    partial.blank_tiles.truncate(8);
+    assert_eq!(capture_status_for_metadata(&partial), StageStatus::Running);
+}
+
+#[test]
+fn blank_frame_detection_tests_assembler_uses_configured_thresholds() {
+    // Faint two-tone frames: near-uniform by default, content when strict.
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|tick| {
+            let rgba = (0..16)
+                .flat_map(|index| {
+                    let value = if index % 2 == 0 { 100 } else { 104 };
+                    [value, value, value, 255]
+                })
+                .collect::<Vec<u8>>();
+            Frame::new("display-1", 4, 4, 1_000 + tick, rgba).expect("frame should be valid")
+        })
+        .collect();
+    let strict = QualityThresholds {
+        black_max_luma: 0,
+        uniform_max_spread: 0,
+        near_uniform_tolerance: 0,
+        near_uniform_fraction: 1.0,
+        sample_stride: 1,
+    };
+
+    let default_payload = PayloadAssembler::new()
+        .assemble(&frames, "session-blank")
+        .expect("payload should build")
+        .payload;
+    assert_eq!(default_payload.metadata.blank_tiles.len(), 9);
+    let strict_payload = PayloadAssembler::new()
+        .with_quality_thresholds(strict)
+        .assemble(&frames, "session-blank")
+        .expect("payload should build")
+        .payload;
+    assert!(strict_payload.metadata.blank_tiles.is_empty());
+}
+
+#[test]
+fn blank_frame_detection_tests_retry_recovers_transient_black_frame() {
+    let backend = FlickeringBackend::new(2);
+    let targets = vec!["display-1".to_string()];
+    let mut batches = MultiDisplayFrameBatch::new(9).expect("batch set should build");
+    let options = CaptureTickOptions {
+        blank_retries: 3,
+        blank_retry_delay: Duration::ZERO,
+        ..CaptureTickOptions::default()
+    };
+
+    let outcome = run_capture_tick_with(&backend, &mut batches, &targets, 1_000, &options)
+        .expect("tick should succeed");
+    assert_eq!(outcome.blank_retries, 2);
+    assert_eq!(*backend.calls.lock().expect("calls lock"), 3);
+
+    // Retries are bounded; a persistently black display is still buffered.
+    let stuck = FlickeringBackend::new(u32::MAX);
+    let outcome = run_capture_tick_with(&stuck, &mut batches, &targets, 2_000, &options)
+        .expect("tick should succeed");
+    assert_eq!(outcome.blank_retries, 3);
+    assert_eq!(batches.len_for("display-1"), 2);
+}
+
+#[test]
+fn blank_frame_detection_tests_retry_waits_and_restamps_each_attempt() {
+    let backend = FlickeringBackend::new(2);
+    let delay = Duration::from_millis(20);
+    let started = Instant::now();
+
+    let checked = capture_frame_with_blank_retry(
+        &backend,
+        "display-1",
+        5_000,
+        &QualityThresholds::default(),
+        3,
+        delay,
+    )
+    .expect("capture should work");
+    assert_eq!(checked.retries, 2);
+    assert!(started.elapsed() >= delay * 2);
+
+    // Invariant: each attempt carries its own capture time.
+    let stamps = backend.stamps.lock().expect("stamps lock").clone();
+    assert_eq!(stamps.len(), 3);
+    assert_eq!(stamps[0], 5_000);
+    assert!(stamps[1] >= 5_020);
+    assert!(stamps[2] >= stamps[1] + 20);
+    assert_eq!(checked.frame.captured_at_ms, stamps[2]);
+}
+
+#[test]
+fn blank_frame_detection_tests_env_configures_retry_delay() {
+    let set = |value: Option<&str>| {
+        // Safety:
+        // - This is the only test in this binary that touches the process env.
+        // - The variable is removed before returning.
+        match value {
+            Some(value) => unsafe {
+                std::env::set_var("LOCAL_GUARD_BLANK_FRAME_RETRY_DELAY_MS", value)
+            },
+            None => unsafe { std::env::remove_var("LOCAL_GUARD_BLANK_FRAME_RETRY_DELAY_MS") },
+        }
+    };
+    set(None);
+    assert_eq!(
+        blank_frame_retry_delay_from_env(),
+        DEFAULT_BLANK_RETRY_DELAY
+    );
+    assert_eq!(
+        CaptureTickOptions::default().blank_retry_delay,
+        DEFAULT_BLANK_RETRY_DELAY
+    );
+    set(Some(" 120 "));
+    assert_eq!(
+        blank_frame_retry_delay_from_env(),
+        Duration::from_millis(120)
+    );
+    set(Some("soon"));
+    assert_eq!(
+        blank_frame_retry_delay_from_env(),
+        DEFAULT_BLANK_RETRY_DELAY
+    );
+    set(None);
+}
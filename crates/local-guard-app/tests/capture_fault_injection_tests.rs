@@ -0,0 +1,136 @@
+//! Integration tests for capture pipeline resilience under injected faults.
+
+use std::time::Duration;
+
+use local_guard_app::{AppError, batch_to_payload, capture_stage_status, run_capture_tick};
+use local_guard_capture::{
+    CaptureBackend, CaptureError, DisplayInfo, FaultPlan, FaultyCaptureBackend, GeometryChange,
+    SyntheticCaptureBackend,
+};
+use local_guard_core::MultiDisplayFrameBatch;
+use local_guard_ui::StageStatus;
+
+fn two_displays() -> Vec<DisplayInfo> {
+    ["display-a", "display-b"]
+        .iter()
+        .map(|id| DisplayInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            width: 4,
+            height: 4,
+        })
+        .collect()
+}
+
+fn faulty(plan: FaultPlan) -> FaultyCaptureBackend<SyntheticCaptureBackend> {
+    FaultyCaptureBackend::new(SyntheticCaptureBackend::with_displays(two_displays()), plan)
+}
+
+fn error_pattern(seed: u64) -> Vec<bool> {
+    let backend = faulty(FaultPlan {
+        seed,
+        backend_error_rate: 0.5,
+        ..FaultPlan::default()
+    });
+    (0..32)
+        .map(|tick| backend.capture_frame("display-a", tick).is_err())
+        .collect()
+}
+
+#[test]
+fn capture_fault_injection_tests_error_pattern_is_seed_deterministic() {
+    let first = error_pattern(7);
+    assert_eq!(first, error_pattern(7));
+    assert!(first.iter().any(|failed| *failed));
+    assert!(first.iter().any(|failed| !*failed));
+    assert_ne!(first, error_pattern(8));
+}
+
+#[test]
+fn capture_fault_injection_tests_geometry_change_restarts_batch() {
+    let backend = faulty(FaultPlan {
+        geometry_change: Some(GeometryChange {
+            after_frames: 4,
+            width: 2,
+            height: 2,
+        }),
+        ..FaultPlan::default()
+    });
+    let targets = vec!["display-a".to_string()];
+    let mut batches = MultiDisplayFrameBatch::new(9).expect("batch set should build");
+
+    let mut completed = Vec::new();
+    let mut resets = Vec::new();
+    let mut discarded = 0;
+    for tick in 0..13_u64 {
+        let outcome = run_capture_tick(&backend, &mut batches, &targets, 1_000 + tick)
+            .expect("geometry change should be recovered");
+        resets.extend(outcome.geometry_resets);
+        discarded += outcome.frames_discarded;
+        completed.extend(outcome.completed_batches);
+    }
+
+    assert_eq!(resets, vec!["display-a"]);
+    assert_eq!(discarded, 4);
+    assert_eq!(completed.len(), 1);
+    let payload = batch_to_payload(&completed[0], "session-fault").expect("payload should build");
+    assert_eq!(
+        (
+            payload.metadata.source_width,
+            payload.metadata.source_height
+        ),
+        (2, 2)
+    );
+    assert_eq!(backend.stats().geometry_changes, 9);
+}
+
+#[test]
+fn capture_fault_injection_tests_unknown_display_fails_whole_tick() {
+    let backend = faulty(FaultPlan {
+        unknown_display_after: Some(3),
+        ..FaultPlan::default()
+    });
+    let targets = vec!["display-a".to_string(), "display-b".to_string()];
+    let mut batches = MultiDisplayFrameBatch::new(9).expect("batch set should build");
+
+    let first = run_capture_tick(&backend, &mut batches, &targets, 1_000);
+    assert_eq!(capture_stage_status(&first), StageStatus::Running);
+
+    // Third delivered frame succeeds, fourth hits the injected unplug.
+    let second = run_capture_tick(&backend, &mut batches, &targets, 2_000);
+    assert!(matches!(
+        second,
+        Err(AppError::Capture(CaptureError::UnknownDisplay(ref id))) if id == "display-b"
+    ));
+    assert_eq!(capture_stage_status(&second), StageStatus::Degraded);
+    assert_eq!(batches.len_for("display-a"), 1);
+    assert_eq!(batches.len_for("display-b"), 1);
+}
+
+#[test]
+fn capture_fault_injection_tests_poisoned_lock_is_sticky() {
+    let backend = faulty(FaultPlan {
+        latency: Duration::from_millis(1),
+        poison_lock_after: Some(2),
+        ..FaultPlan::default()
+    });
+    let targets = vec!["display-a".to_string()];
+    let mut batches = MultiDisplayFrameBatch::new(9).expect("batch set should build");
+
+    for tick in 0..2_u64 {
+        run_capture_tick(&backend, &mut batches, &targets, tick).expect("before poison");
+    }
+    for tick in 2..5_u64 {
+        let result = run_capture_tick(&backend, &mut batches, &targets, tick);
+        assert!(matches!(
+            result,
+            Err(AppError::Capture(CaptureError::Backend(ref message))) if message.contains("poisoned")
+        ));
+    }
+
+    let stats = backend.stats();
+    assert_eq!(stats.frames_delivered, 2);
+    assert_eq!(stats.poisoned_errors, 3);
+    assert_eq!(stats.injected_latency, Duration::from_millis(2));
+    assert_eq!(batches.len_for("display-a"), 2);
+}
@@ -1,9 +1,107 @@
-//! Integration tests for capture scheduling.
+//! Integration tests for capture scheduling and its env configuration.
 
-use local_guard_app::schedule_capture;
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use local_guard_app::{
+    DEFAULT_CAPTURE_FPS, capture_config_from_env, parse_capture_fps, parse_missed_tick_policy,
+    schedule_capture, try_capture_config_from_env,
+};
+use local_guard_capture::{CaptureScheduler, MissedTickPolicy, VirtualClock};
 
 #[test]
 fn capture_scheduler_tests_generates_one_hz_schedule() {
     let times = schedule_capture(1, 1_000, 3).expect("schedule should be generated");
     assert_eq!(times, vec![1_000, 2_000, 3_000]);
 }
+
+#[test]
+fn capture_scheduler_tests_supports_fractional_fps() {
+    let config = parse_capture_fps("0.2").expect("fractional fps should parse");
+    assert_eq!(config.interval(), Duration::from_secs(5));
+    assert!(parse_capture_fps("0").is_err());
+    assert!(parse_capture_fps("5000").is_err());
+    assert!(parse_capture_fps("fast").is_err());
+}
+
+#[test]
+fn capture_scheduler_tests_does_not_drift_at_three_fps() {
+    let clock = Arc::new(VirtualClock::new());
+    let config = parse_capture_fps("3").expect("fps should parse");
+    let mut scheduler = CaptureScheduler::new(config, MissedTickPolicy::Skip, clock.clone());
+
+    // Poll every millisecond for 10 s of virtual time.
+    let mut fired = Vec::new();
+    for _ in 0..=10_000 {
+        if let Some(tick) = scheduler.poll() {
+            fired.push(tick);
+        }
+        clock.advance(Duration::from_millis(1));
+    }
+
+    assert_eq!(fired.len(), 31);
+    let last = fired.last().expect("ticks should fire");
+    assert_eq!(last.sequence, 30);
+    // 30 * 333.333... ms == 10 s exactly; a truncating 333 ms timer would be 10 ms early.
+    assert!(last.deadline >= Duration::from_millis(9_999));
+    assert_eq!(scheduler.stats().ticks_missed, 0);
+}
+
+#[test]
+fn capture_scheduler_tests_catch_up_replays_bounded_burst() {
+    let clock = Arc::new(VirtualClock::new());
+    let config = parse_capture_fps("10").expect("fps should parse");
+    let policy = parse_missed_tick_policy("catch-up:2").expect("policy should parse");
+    assert_eq!(policy, MissedTickPolicy::CatchUp { max_burst: 2 });
+    let mut scheduler = CaptureScheduler::new(config, policy, clock.clone());
+    assert!(scheduler.poll().is_some());
+
+    // Stall for 500 ms: deadlines 1..=5 pass, burst of 2 overdue replays allowed.
+    clock.advance(Duration::from_millis(500));
+    let sequences: Vec<u64> = std::iter::from_fn(|| scheduler.poll())
+        .map(|tick| tick.sequence)
+        .collect();
+
+    assert_eq!(sequences, vec![3, 4, 5]);
+    assert_eq!(scheduler.stats().ticks_missed, 2);
+    assert_eq!(
+        parse_missed_tick_policy("skip"),
+        Some(MissedTickPolicy::Skip)
+    );
+    assert_eq!(parse_missed_tick_policy("sometimes"), None);
+}
+
+#[test]
+fn capture_scheduler_tests_invalid_env_fps_falls_back() {
+    let _env = common::env_lock();
+    let name = "LOCAL_GUARD_CAPTURE_FPS";
+    let set = |value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - The variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+
+    set(None);
+    assert!(matches!(try_capture_config_from_env(), Ok(None)));
+    assert_eq!(capture_config_from_env().fps(), DEFAULT_CAPTURE_FPS);
+
+    set(Some("2"));
+    assert_eq!(capture_config_from_env().fps(), 2.0);
+
+    for invalid in ["fast", "0", "-1", "5000", "NaN"] {
+        set(Some(invalid));
+        assert!(
+            try_capture_config_from_env().is_err(),
+            "{invalid} is rejected"
+        );
+        assert_eq!(capture_config_from_env().fps(), DEFAULT_CAPTURE_FPS);
+    }
+    set(None);
+}
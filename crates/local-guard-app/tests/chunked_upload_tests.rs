@@ -0,0 +1,490 @@
+//! Integration tests for resumable chunked uploads: resume from the
+//! server's acknowledged offset after timeouts and lost acknowledgements,
+//! a finalize answer lost after commit, retry budget, single-shot fallback,
+//! and the HTTPS session protocol.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::fixture_payload;
+use local_guard_analysis_contract::IngestStatus;
+use local_guard_app::{batch_to_payload, upload_chunking_from_env};
+use local_guard_core::{Frame, MosaicPayload};
+use local_guard_transport::{HttpsConnector, TransportSecurity};
+use local_guard_upload::{
+    ChunkFault, ChunkingPolicy, CompressionPolicy, ContentEncoding, DEFAULT_CHUNK_SIZE_BYTES,
+    DEFAULT_CHUNKING_THRESHOLD_BYTES, HttpsUploadTransport, LocalChunkedIngest, RetryPolicy,
+    UploadClient, UploadError,
+};
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+const INGEST_HOST: &str = "ingest.local-guard.test";
+const CHUNK_SIZE: usize = 16 * 1024;
+
+fn chunked_client(server: &Arc<LocalChunkedIngest>, policy: RetryPolicy) -> UploadClient {
+    UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        policy,
+        server.clone(),
+    )
+    .expect("upload client")
+    .with_chunking(
+        server.clone(),
+        ChunkingPolicy::new()
+            .with_chunk_size_bytes(CHUNK_SIZE)
+            .with_min_body_bytes(0),
+    )
+}
+
+/// A 9-frame payload of `side` x `side` frames.
+fn payload(side: u32) -> MosaicPayload {
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|index| {
+            Frame::new(
+                "display-1",
+                side,
+                side,
+                1_000 + index,
+                vec![index as u8; (side * side * 4) as usize],
+            )
+            .expect("frame")
+        })
+        .collect();
+    batch_to_payload(&frames, "session-xyz").expect("payload")
+}
+
+#[test]
+fn chunked_upload_tests_resumes_from_acknowledged_offset() {
+    let payload = payload(64);
+    let body = payload.to_json_bytes().expect("canonical");
+    let expected_chunks = body.len().div_ceil(CHUNK_SIZE) as u32;
+    assert!(expected_chunks > 3, "fixture must span several chunks");
+
+    let server = Arc::new(LocalChunkedIngest::new());
+    server.inject_fault(ChunkFault::BeforeStore(UploadError::Timeout));
+    server.inject_fault(ChunkFault::BeforeStore(UploadError::Server(503)));
+    // Failure mode: the server stores the chunk but the ack is lost; the
+    // client must learn the offset from the server, not resend the chunk.
+    server.inject_fault(ChunkFault::AfterStore(UploadError::Transport(
+        "connection reset".to_string(),
+    )));
+
+    let uploader = chunked_client(&server, RetryPolicy::mvp_default());
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("chunked upload should resume");
+
+    assert_eq!(report.resumes, 3);
+    assert_eq!(report.attempts, 4);
+    // The chunk whose ack was lost is stored but never acknowledged.
+    assert_eq!(report.chunks, expected_chunks - 1);
+    let expected_offsets: Vec<u64> = (0..expected_chunks)
+        .map(|index| u64::from(index) * CHUNK_SIZE as u64)
+        .collect();
+    assert_eq!(server.chunk_offsets(), expected_offsets);
+
+    let received = server.received();
+    assert_eq!(received.len(), 1);
+    assert!(received[0].chunked);
+    assert_eq!(received[0].body, body);
+    assert_eq!(
+        received[0].idempotency_key,
+        uploader.idempotency_key(&payload)
+    );
+}
+
+#[test]
+fn chunked_upload_tests_resumes_share_the_retry_budget() {
+    let payload = payload(64);
+    let server = Arc::new(LocalChunkedIngest::new());
+    for _ in 0..3 {
+        server.inject_fault(ChunkFault::BeforeStore(UploadError::Timeout));
+    }
+    let two_retries = RetryPolicy {
+        max_retries: 2,
+        ..RetryPolicy::mvp_default()
+    };
+
+    let error = chunked_client(&server, two_retries)
+        .upload_payload(&payload, "token")
+        .expect_err("third timeout exhausts the budget");
+    assert_eq!(error, UploadError::Timeout);
+    assert!(server.received().is_empty());
+
+    // A permanent failure stops immediately without resuming.
+    let server = Arc::new(LocalChunkedIngest::new());
+    server.inject_fault(ChunkFault::BeforeStore(UploadError::Unauthorized));
+    let error = chunked_client(&server, RetryPolicy::mvp_default())
+        .upload_payload(&payload, "token")
+        .expect_err("unauthorized is permanent");
+    assert_eq!(error, UploadError::Unauthorized);
+    assert!(server.chunk_offsets().is_empty());
+}
+
+#[test]
+fn chunked_upload_tests_finalize_times_out_after_commit() {
+    let next_payload = payload(48);
+    let payload = payload(64);
+    let body = payload.to_json_bytes().expect("canonical");
+
+    // A server that keeps finalized sessions answers the resumed finalize
+    // as a duplicate of the committed upload.
+    let server = Arc::new(LocalChunkedIngest::new());
+    server.inject_fault(ChunkFault::AfterFinalize(UploadError::Timeout));
+    let report = chunked_client(&server, RetryPolicy::mvp_default())
+        .upload_payload(&payload, "token")
+        .expect("resume after a lost finalize answer");
+    assert_eq!(report.resumes, 1);
+    let ack = report.ack.expect("ingest ack");
+    assert_eq!(ack.status, IngestStatus::Duplicate);
+    let received = server.received();
+    assert_eq!(received.len(), 1);
+    assert!(received[0].chunked);
+    assert_eq!(received[0].body, body);
+
+    // A server that drops finalized sessions answers 404; the client
+    // resends single-shot under the same idempotency key.
+    let server = Arc::new(LocalChunkedIngest::new().forget_finalized_sessions());
+    server.inject_fault(ChunkFault::AfterFinalize(UploadError::Timeout));
+    let uploader = chunked_client(&server, RetryPolicy::mvp_default());
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("single-shot resend after the session is gone");
+    assert_eq!(report.resumes, 1);
+    assert_eq!(
+        report.ack.expect("ingest ack").status,
+        IngestStatus::Duplicate
+    );
+    let received = server.received();
+    assert_eq!(received.len(), 2);
+    assert!(received[0].chunked);
+    assert!(!received[1].chunked);
+    assert_eq!(received[1].body, body);
+    assert_eq!(
+        received[1].idempotency_key,
+        uploader.idempotency_key(&payload)
+    );
+
+    // Invariant: a lost finalize answer is not a missing chunking feature.
+    let chunk_count = server.chunk_offsets().len();
+    uploader
+        .upload_payload(&next_payload, "token")
+        .expect("next upload");
+    assert!(server.chunk_offsets().len() > chunk_count);
+}
+
+#[test]
+fn chunked_upload_tests_server_chunk_size_and_compression_are_honored() {
+    let payload = payload(64);
+    let server = Arc::new(LocalChunkedIngest::new().with_max_chunk_size_bytes(1024));
+    let uploader = chunked_client(&server, RetryPolicy::mvp_default())
+        .with_compression(CompressionPolicy::new(ContentEncoding::Gzip));
+
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("chunked upload");
+
+    let received = server.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].content_encoding, Some(ContentEncoding::Gzip));
+    assert_eq!(
+        report.chunks as usize,
+        received[0].body.len().div_ceil(1024)
+    );
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(received[0].body.as_slice())
+        .read_to_end(&mut decoded)
+        .expect("gzip body");
+    assert_eq!(decoded, payload.to_json_bytes().expect("canonical"));
+}
+
+#[test]
+fn chunked_upload_tests_falls_back_to_single_shot() {
+    let payload = payload(64);
+    let server = Arc::new(LocalChunkedIngest::single_shot_only());
+    let uploader = chunked_client(&server, RetryPolicy::mvp_default());
+
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("single-shot fallback");
+    assert_eq!(report.chunks, 0);
+    assert_eq!(report.attempts, 2);
+    assert!(uploader.chunking_unsupported());
+
+    // Invariant: the fallback is remembered, so later uploads skip the
+    // session request.
+    let report = uploader
+        .clone()
+        .upload_payload(&payload, "token")
+        .expect("second upload");
+    assert_eq!(report.attempts, 1);
+
+    let received = server.received();
+    assert_eq!(received.len(), 2);
+    assert!(received.iter().all(|upload| !upload.chunked));
+    assert_eq!(
+        received[0].body,
+        payload.to_json_bytes().expect("canonical")
+    );
+
+    // Bodies below the chunking threshold never open a session.
+    let server = Arc::new(LocalChunkedIngest::new());
+    let uploader = UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        RetryPolicy::mvp_default(),
+        server.clone(),
+    )
+    .expect("upload client")
+    .with_chunking(server.clone(), ChunkingPolicy::new());
+    let report = uploader
+        .upload_payload(&fixture_payload(), "token")
+        .expect("small upload");
+    assert_eq!(report.chunks, 0);
+    assert!(!uploader.chunking_unsupported());
+    assert!(!server.received()[0].chunked);
+}
+
+#[derive(Default)]
+struct SessionState {
+    body: Vec<u8>,
+    drop_next_ack: bool,
+}
+
+/// Local TLS ingest server. With `sessions`, it speaks the chunked protocol
+/// with a 1 KiB chunk cap and drops the connection instead of answering
+/// the second chunk; without, it answers the sessions route with 404.
+fn spawn_ingest(sessions: bool) -> (SocketAddr, String, Receiver<(String, Vec<u8>)>) {
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().expect("ca key"))
+        .expect("ca cert");
+    let leaf_key = KeyPair::generate().expect("leaf key");
+    let leaf = CertificateParams::new(vec![INGEST_HOST.to_string()])
+        .expect("leaf params")
+        .signed_by(&leaf_key, &ca)
+        .expect("leaf cert");
+    let config = Arc::new(
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("server versions")
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![leaf.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf_key.serialize_der())),
+            )
+            .expect("server cert"),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let (sender, requests) = channel();
+    let state = Mutex::new(SessionState::default());
+    std::thread::spawn(move || {
+        for tcp in listener.incoming().flatten() {
+            let connection = ServerConnection::new(Arc::clone(&config)).expect("connection");
+            let mut tls = StreamOwned::new(connection, tcp);
+            let Some((head, body)) = read_request(&mut tls) else {
+                continue;
+            };
+            let request_line = head.lines().next().unwrap_or_default().to_string();
+            let mut state = state.lock().expect("state lock");
+            let response = match request_line.split(' ').take(2).collect::<Vec<_>>()[..] {
+                ["post", "/r1/ingest/sessions"] if sessions => {
+                    state.drop_next_ack = true;
+                    "HTTP/1.1 201 Created\r\nUpload-Session: s-1\r\nUpload-Offset: 0\r\nUpload-Chunk-Size: 1024\r\nContent-Length: 0\r\n\r\n".to_string()
+                }
+                ["post", "/r1/ingest/sessions"] => {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string()
+                }
+                ["patch", "/r1/ingest/sessions/s-1"] => {
+                    state.body.extend_from_slice(&body);
+                    if state.drop_next_ack && state.body.len() > 1024 {
+                        // Failure mode: chunk stored, then the connection
+                        // drops before the acknowledgement.
+                        state.drop_next_ack = false;
+                        let _ = sender.send((head, body));
+                        continue;
+                    }
+                    format!(
+                        "HTTP/1.1 204 No Content\r\nUpload-Offset: {}\r\nContent-Length: 0\r\n\r\n",
+                        state.body.len()
+                    )
+                }
+                ["get", "/r1/ingest/sessions/s-1"] => format!(
+                    "HTTP/1.1 200 OK\r\nUpload-Offset: {}\r\nContent-Length: 0\r\n\r\n",
+                    state.body.len()
+                ),
+                ["post", "/r1/ingest/sessions/s-1/finalize"] | ["post", "/r1/ingest"] => {
+                    "HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n".to_string()
+                }
+                _ => "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n".to_string(),
+            };
+            drop(state);
+            let _ = sender.send((head, body));
+            let _ = tls.write_all(response.as_bytes());
+            tls.conn.send_close_notify();
+            let _ = tls.flush();
+        }
+    });
+    (addr, ca.pem(), requests)
+}
+
+/// Returns the lowercased head and the raw body.
+fn read_request(stream: &mut impl Read) -> Option<(String, Vec<u8>)> {
+    let mut raw = Vec::new();
+    let mut buffer = [0_u8; 4096];
+    loop {
+        if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&raw[..end]).to_ascii_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if raw.len() >= end + 4 + length {
+                return Some((head, raw[end + 4..end + 4 + length].to_vec()));
+            }
+        }
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => raw.extend_from_slice(&buffer[..read]),
+        }
+    }
+}
+
+fn https_client(addr: SocketAddr, ca_pem: &str) -> UploadClient {
+    let policy = TransportSecurity::new()
+        .with_ca_bundle_pem(ca_pem.as_bytes())
+        .expect("ca bundle");
+    let connector = HttpsConnector::new(&policy)
+        .expect("connector")
+        .with_timeout(Duration::from_secs(5))
+        .with_resolved_addr(INGEST_HOST, addr);
+    let transport = Arc::new(HttpsUploadTransport::new(connector));
+    UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        RetryPolicy::mvp_default(),
+        transport.clone(),
+    )
+    .expect("upload client")
+    .with_chunking(transport, ChunkingPolicy::new().with_min_body_bytes(0))
+}
+
+#[test]
+fn chunked_upload_tests_https_transport_speaks_session_protocol() {
+    let (addr, ca_pem, requests) = spawn_ingest(true);
+    let uploader = https_client(addr, &ca_pem);
+    let payload = payload(8);
+    let body = payload.to_json_bytes().expect("canonical");
+    assert!(body.len() > 2 * 1024, "fixture must span three chunks");
+
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("chunked upload over https");
+    assert_eq!(report.resumes, 1);
+
+    let requests: Vec<(String, Vec<u8>)> = requests.try_iter().collect();
+    let (create, _) = &requests[0];
+    assert!(create.starts_with("post /r1/ingest/sessions http/1.1"));
+    assert!(create.contains(&format!("upload-length: {}", body.len())));
+    assert!(create.contains(&format!(
+        "idempotency-key: {}",
+        uploader.idempotency_key(&payload)
+    )));
+    assert!(create.contains("authorization: bearer token"));
+
+    let lines: Vec<&str> = requests
+        .iter()
+        .map(|(head, _)| head.lines().next().unwrap_or_default())
+        .collect();
+    assert_eq!(lines[1], "patch /r1/ingest/sessions/s-1 http/1.1");
+    assert_eq!(lines[2], "patch /r1/ingest/sessions/s-1 http/1.1");
+    // The lost ack is recovered by asking the server for its offset.
+    assert_eq!(lines[3], "get /r1/ingest/sessions/s-1 http/1.1");
+    assert!(requests[3].0.contains("authorization: bearer token"));
+    assert!(requests[4].0.contains("upload-offset: 2048"));
+    assert_eq!(
+        lines.last().copied(),
+        Some("post /r1/ingest/sessions/s-1/finalize http/1.1")
+    );
+
+    let reassembled: Vec<u8> = requests
+        .iter()
+        .filter(|(head, _)| head.starts_with("patch "))
+        .flat_map(|(_, chunk)| chunk.clone())
+        .collect();
+    assert_eq!(reassembled, body);
+}
+
+#[test]
+fn chunked_upload_tests_https_transport_falls_back_on_404() {
+    let (addr, ca_pem, requests) = spawn_ingest(false);
+    let uploader = https_client(addr, &ca_pem);
+    let payload = fixture_payload();
+
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("single-shot fallback over https");
+    assert_eq!(report.chunks, 0);
+    assert!(uploader.chunking_unsupported());
+
+    let (create, _) = requests.recv().expect("session request");
+    assert!(create.starts_with("post /r1/ingest/sessions "));
+    let (single, body) = requests.recv().expect("single-shot request");
+    assert!(single.starts_with("post /r1/ingest http/1.1"));
+    assert_eq!(body, payload.to_json_bytes().expect("canonical"));
+}
+
+#[test]
+fn chunked_upload_tests_env_configures_policy() {
+    let _env = common::env_lock();
+    let set = |name: &str, value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+    let toggle = "LOCAL_GUARD_UPLOAD_CHUNKING";
+    let chunk = "LOCAL_GUARD_UPLOAD_CHUNK_BYTES";
+    let threshold = "LOCAL_GUARD_UPLOAD_CHUNKING_MIN_BYTES";
+
+    set(toggle, None);
+    set(chunk, None);
+    set(threshold, None);
+    let policy = upload_chunking_from_env().expect("on by default");
+    assert_eq!(policy.chunk_size_bytes(), DEFAULT_CHUNK_SIZE_BYTES);
+    assert_eq!(policy.min_body_bytes(), DEFAULT_CHUNKING_THRESHOLD_BYTES);
+
+    set(chunk, Some("65536"));
+    set(threshold, Some("131072"));
+    assert_eq!(
+        upload_chunking_from_env(),
+        Some(
+            ChunkingPolicy::new()
+                .with_chunk_size_bytes(65_536)
+                .with_min_body_bytes(131_072)
+        )
+    );
+
+    set(chunk, Some("0"));
+    set(threshold, Some("big"));
+    assert_eq!(upload_chunking_from_env(), Some(ChunkingPolicy::new()));
+
+    set(toggle, Some(" OFF "));
+    assert_eq!(upload_chunking_from_env(), None);
+
+    set(toggle, None);
+    set(chunk, None);
+    set(threshold, None);
+}
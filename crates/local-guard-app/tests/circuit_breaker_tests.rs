@@ -0,0 +1,251 @@
+//! Integration tests for the shared upload circuit breaker: outage
+//! short-circuiting across clients, half-open recovery on a virtual clock,
+//! chunked sends, the network stage projection, and env configuration.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use common::fixture_payload;
+use local_guard_app::{
+    APP_VERSION, circuit_breaker_from_env, network_stage_status, project_runtime_status,
+};
+use local_guard_ui::{StageStatus, UiState};
+use local_guard_upload::{
+    ChunkFault, ChunkingPolicy, CircuitBreaker, CircuitBreakerConfig, CircuitState,
+    DEFAULT_BREAKER_COOL_DOWN, DEFAULT_BREAKER_FAILURE_THRESHOLD, FailureClass, HttpResponse,
+    LocalChunkedIngest, RetryPolicy, UploadClient, UploadClock, UploadEnvelope, UploadError,
+    UploadTransport, VirtualUploadClock, classify_upload_error,
+};
+
+const ENDPOINT: &str = "https://ingest.local-guard.test/r1/ingest";
+
+#[derive(Debug, Default)]
+struct OutageServer {
+    down: AtomicBool,
+    sends: AtomicU32,
+}
+
+impl UploadTransport for OutageServer {
+    fn send(&self, _envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        self.sends.fetch_add(1, Ordering::SeqCst);
+        if self.down.load(Ordering::SeqCst) {
+            Err(UploadError::Server(503))
+        } else {
+            Ok(HttpResponse::new(200))
+        }
+    }
+}
+
+fn retry_twice() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 2,
+        base_delay_ms: 1,
+        max_delay_ms: 10,
+        jitter_ms: 0,
+    }
+}
+
+fn breaker(clock: &Arc<VirtualUploadClock>) -> CircuitBreaker {
+    CircuitBreaker::new(
+        CircuitBreakerConfig::new()
+            .with_failure_threshold(4)
+            .with_cool_down(Duration::from_secs(30)),
+        clock.clone(),
+    )
+}
+
+#[test]
+fn circuit_breaker_tests_outage_short_circuits_every_client() {
+    let clock = Arc::new(VirtualUploadClock::new());
+    let server = Arc::new(OutageServer::default());
+    server.down.store(true, Ordering::SeqCst);
+    let breaker = breaker(&clock);
+    let client = |breaker: &CircuitBreaker| {
+        UploadClient::new(ENDPOINT, retry_twice(), server.clone())
+            .expect("client")
+            .with_circuit_breaker(breaker.clone())
+    };
+    let first = client(&breaker);
+    let second = client(&breaker);
+    let payload = fixture_payload();
+
+    assert_eq!(
+        first.upload_payload(&payload, "token"),
+        Err(UploadError::Server(503))
+    );
+    assert_eq!(server.sends.load(Ordering::SeqCst), 3);
+
+    // The fourth failure opens the circuit mid-batch; the retry that
+    // follows is refused without reaching the server.
+    let error = second
+        .upload_payload(&payload, "token")
+        .expect_err("circuit opens");
+    assert_eq!(
+        error,
+        UploadError::CircuitOpen {
+            retry_after_ms: 30_000
+        }
+    );
+    assert_eq!(classify_upload_error(&error), FailureClass::Retriable);
+    assert_eq!(server.sends.load(Ordering::SeqCst), 4);
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(
+        first.upload_payload(&payload, "token"),
+        Err(UploadError::CircuitOpen {
+            retry_after_ms: 20_000
+        })
+    );
+    assert_eq!(server.sends.load(Ordering::SeqCst), 4);
+
+    // Recovery: after the cool-down a single probe closes the circuit.
+    server.down.store(false, Ordering::SeqCst);
+    clock.advance(Duration::from_secs(20));
+    let report = second
+        .upload_payload(&payload, "token")
+        .expect("probe succeeds");
+    assert_eq!(report.attempts, 1);
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(server.sends.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn circuit_breaker_tests_failed_probe_reopens() {
+    let clock = Arc::new(VirtualUploadClock::new());
+    let server = Arc::new(OutageServer::default());
+    server.down.store(true, Ordering::SeqCst);
+    let breaker = breaker(&clock);
+    let client = UploadClient::new(ENDPOINT, RetryPolicy::mvp_default(), server.clone())
+        .expect("client")
+        .with_circuit_breaker(breaker.clone());
+    let _ = client.upload_payload(&fixture_payload(), "token");
+    assert_eq!(breaker.state(), CircuitState::Open);
+    let sends = server.sends.load(Ordering::SeqCst);
+
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(
+        client.upload_payload(&fixture_payload(), "token"),
+        Err(UploadError::CircuitOpen {
+            retry_after_ms: 30_000
+        })
+    );
+    // Invariant: exactly one probe reached the server.
+    assert_eq!(server.sends.load(Ordering::SeqCst), sends + 1);
+    assert_eq!(breaker.state(), CircuitState::Open);
+}
+
+#[test]
+fn circuit_breaker_tests_guards_chunked_sends() {
+    let clock = Arc::new(VirtualUploadClock::new());
+    let server = Arc::new(LocalChunkedIngest::new());
+    let breaker = CircuitBreaker::new(
+        CircuitBreakerConfig::new().with_failure_threshold(1),
+        clock.clone(),
+    );
+    let client = UploadClient::new(ENDPOINT, RetryPolicy::mvp_default(), server.clone())
+        .expect("client")
+        .with_chunking(
+            server.clone(),
+            ChunkingPolicy::new()
+                .with_chunk_size_bytes(256)
+                .with_min_body_bytes(0),
+        )
+        .with_circuit_breaker(breaker.clone());
+
+    // Why: the session request succeeds and resets the streak, so a
+    // threshold of one lets a single chunk failure open the circuit.
+    server.inject_fault(ChunkFault::BeforeStore(UploadError::Timeout));
+    assert!(matches!(
+        client.upload_payload(&fixture_payload(), "token"),
+        Err(UploadError::CircuitOpen { .. })
+    ));
+    assert!(server.received().is_empty());
+
+    clock.advance(DEFAULT_BREAKER_COOL_DOWN);
+    client
+        .upload_payload(&fixture_payload(), "token")
+        .expect("chunked upload resumes after the cool-down");
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(server.received().len(), 1);
+}
+
+#[test]
+fn circuit_breaker_tests_network_stage_follows_breaker() {
+    let clock = Arc::new(VirtualUploadClock::new());
+    let breaker = CircuitBreaker::new(
+        CircuitBreakerConfig::new().with_failure_threshold(2),
+        clock.clone(),
+    );
+    let mut state = UiState::new(APP_VERSION);
+
+    assert_eq!(network_stage_status(None), StageStatus::Idle);
+    assert_eq!(network_stage_status(Some(&breaker)), StageStatus::Idle);
+
+    let record = |outcome: Result<(), &UploadError>| {
+        let admission = breaker.admit().expect("admitted");
+        breaker.record(admission, outcome);
+    };
+    record(Ok(()));
+    state.network = network_stage_status(Some(&breaker));
+    assert_eq!(project_runtime_status(&state).network, "Healthy");
+
+    record(Err(&UploadError::Timeout));
+    assert_eq!(network_stage_status(Some(&breaker)), StageStatus::Degraded);
+    record(Err(&UploadError::Timeout));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    state.network = network_stage_status(Some(&breaker));
+    assert_eq!(project_runtime_status(&state).network, "Degraded");
+
+    clock.advance(DEFAULT_BREAKER_COOL_DOWN);
+    record(Ok(()));
+    assert_eq!(network_stage_status(Some(&breaker)), StageStatus::Healthy);
+}
+
+#[test]
+fn circuit_breaker_tests_env_configures_thresholds() {
+    let _env = common::env_lock();
+    let set = |name: &str, value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+    let failures = "LOCAL_GUARD_UPLOAD_BREAKER_FAILURES";
+    let cool_down = "LOCAL_GUARD_UPLOAD_BREAKER_COOL_DOWN_SECS";
+    let clock: Arc<dyn UploadClock> = Arc::new(VirtualUploadClock::new());
+
+    set(failures, None);
+    set(cool_down, None);
+    let config = circuit_breaker_from_env(clock.clone()).config();
+    assert_eq!(
+        config.failure_threshold(),
+        DEFAULT_BREAKER_FAILURE_THRESHOLD
+    );
+    assert_eq!(config.cool_down(), DEFAULT_BREAKER_COOL_DOWN);
+
+    set(failures, Some(" 8 "));
+    set(cool_down, Some("120"));
+    let config = circuit_breaker_from_env(clock.clone()).config();
+    assert_eq!(config.failure_threshold(), 8);
+    assert_eq!(config.cool_down(), Duration::from_secs(120));
+
+    set(failures, Some("0"));
+    set(cool_down, Some("soon"));
+    let config = circuit_breaker_from_env(clock).config();
+    assert_eq!(
+        config.failure_threshold(),
+        DEFAULT_BREAKER_FAILURE_THRESHOLD
+    );
+    assert_eq!(config.cool_down(), DEFAULT_BREAKER_COOL_DOWN);
+
+    set(failures, None);
+    set(cool_down, None);
+}
@@ -0,0 +1,329 @@
+//! Integration tests for mutual TLS client certificates: environment
+//! loading, expiry projection into UI state, and uploads to a server that
+//! requires client auth.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use local_guard_app::{
+    AppError, CLIENT_CERT_EXPIRY_WARNING_MS, client_certificate_state_for_ui,
+    client_identity_from_env, project_runtime_status, transport_security_from_env,
+};
+use local_guard_transport::{ClientIdentity, HttpsConnector, TransportSecurity};
+use local_guard_ui::{UiClientCertificateState, UiState};
+use local_guard_upload::{HttpsUploadTransport, RetryPolicy, UploadClient, UploadError};
+use p12_keystore::{Certificate, KeyStore, KeyStoreEntry, PrivateKey, PrivateKeyChain};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedIssuer, DnType, IsCa, KeyPair, date_time_ymd,
+};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+
+const INGEST_HOST: &str = "ingest.local-guard.test";
+const DAY_MS: u64 = 86_400_000;
+const CLIENT_VARS: [&str; 3] = [
+    "LOCAL_GUARD_TLS_CLIENT_CERT",
+    "LOCAL_GUARD_TLS_CLIENT_KEY",
+    "LOCAL_GUARD_TLS_CLIENT_CERT_PASSWORD",
+];
+
+struct TestCa {
+    issuer: CertifiedIssuer<'static, KeyPair>,
+}
+
+struct Issued {
+    cert_pem: String,
+    key_pem: String,
+    cert_der: Vec<u8>,
+    key_der: Vec<u8>,
+}
+
+impl TestCa {
+    fn new(name: &str) -> Self {
+        let mut params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Self {
+            issuer: CertifiedIssuer::self_signed(params, KeyPair::generate().expect("ca key"))
+                .expect("ca cert"),
+        }
+    }
+
+    fn issue(&self, name: &str, not_after_year: Option<i32>) -> Issued {
+        let key = KeyPair::generate().expect("key");
+        let mut params = CertificateParams::new(vec![name.to_string()]).expect("params");
+        if let Some(year) = not_after_year {
+            params.not_before = date_time_ymd(2020, 1, 1);
+            params.not_after = date_time_ymd(year, 1, 1);
+        }
+        let cert = params.signed_by(&key, &self.issuer).expect("cert");
+        Issued {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+            cert_der: cert.der().to_vec(),
+            key_der: key.serialize_der(),
+        }
+    }
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "local-guard-client-cert-{name}-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("scratch dir");
+    dir
+}
+
+fn set_var(name: &str, value: impl AsRef<std::ffi::OsStr>) {
+    // Safety:
+    // - Tests that touch the env hold `common::env_lock()`, so no other
+    //   thread in this binary changes it concurrently.
+    // - Every variable is removed before returning.
+    unsafe { std::env::set_var(name, value) };
+}
+
+fn clear_vars() {
+    for name in CLIENT_VARS {
+        // Safety: see rationale above.
+        unsafe { std::env::remove_var(name) };
+    }
+}
+
+/// Accepts one connection that must present a client certificate issued by
+/// `client_ca`, and answers `202 Accepted`.
+fn spawn_mtls_server(server_ca: &TestCa, client_ca: &TestCa) -> SocketAddr {
+    let leaf = server_ca.issue(INGEST_HOST, None);
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(client_ca.issuer.der().clone())
+        .expect("client root");
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .expect("client verifier");
+    let config = Arc::new(
+        ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .expect("versions")
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(
+                vec![leaf.cert_der.into()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf.key_der)),
+            )
+            .expect("server cert"),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    std::thread::spawn(move || {
+        let Ok((tcp, _)) = listener.accept() else {
+            return;
+        };
+        let mut tls = StreamOwned::new(ServerConnection::new(config).expect("conn"), tcp);
+        let mut raw = Vec::new();
+        let mut buffer = [0_u8; 16 * 1024];
+        loop {
+            if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&raw[..end]).to_ascii_lowercase();
+                let length = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if raw.len() >= end + 4 + length {
+                    break;
+                }
+            }
+            match tls.read(&mut buffer) {
+                Ok(0) => return,
+                Ok(read) => raw.extend_from_slice(&buffer[..read]),
+                Err(_) => {
+                    // Why: drain instead of dropping unread bytes so the
+                    // kernel does not reset the connection before the
+                    // client reads the alert.
+                    let _ = tls.conn.write_tls(&mut tls.sock);
+                    let _ = tls.sock.shutdown(Shutdown::Write);
+                    let _ = tls.sock.set_read_timeout(Some(Duration::from_secs(2)));
+                    while matches!(tls.sock.read(&mut buffer), Ok(read) if read > 0) {}
+                    return;
+                }
+            }
+        }
+        let _ = tls.write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n");
+        tls.conn.send_close_notify();
+        let _ = tls.flush();
+    });
+    addr
+}
+
+fn upload_client(policy: &TransportSecurity, addr: SocketAddr) -> UploadClient {
+    let connector = HttpsConnector::new(policy)
+        .expect("connector")
+        .with_timeout(Duration::from_secs(5))
+        .with_resolved_addr(INGEST_HOST, addr);
+    UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        RetryPolicy::mvp_default(),
+        Arc::new(HttpsUploadTransport::new(connector)),
+    )
+    .expect("upload client")
+}
+
+#[test]
+fn client_certificate_tests_env_loads_pem_and_pkcs12_and_fails_closed() {
+    let _env = common::env_lock();
+    let ca = TestCa::new("device ca");
+    let issued = ca.issue("device-1", None);
+    let dir = scratch_dir("env");
+    let cert_path = dir.join("client.pem");
+    let key_path = dir.join("client.key");
+    let combined_path = dir.join("combined.pem");
+    std::fs::write(&cert_path, &issued.cert_pem).expect("write cert");
+    std::fs::write(&key_path, &issued.key_pem).expect("write key");
+    std::fs::write(
+        &combined_path,
+        format!("{}{}", issued.cert_pem, issued.key_pem),
+    )
+    .expect("write combined");
+
+    let mut store = KeyStore::new();
+    store.add_entry(
+        "device",
+        KeyStoreEntry::PrivateKeyChain(PrivateKeyChain::new(
+            b"device".to_vec(),
+            PrivateKey::from_der(&issued.key_der).expect("p12 key"),
+            [Certificate::from_der(&issued.cert_der).expect("p12 cert")],
+        )),
+    );
+    let p12_path = dir.join("client.p12");
+    std::fs::write(&p12_path, store.writer("s3cret").write().expect("p12")).expect("write p12");
+
+    clear_vars();
+    assert_eq!(client_identity_from_env().expect("unset is valid"), None);
+    assert!(
+        transport_security_from_env()
+            .expect("default policy")
+            .client_identity()
+            .is_none()
+    );
+
+    set_var("LOCAL_GUARD_TLS_CLIENT_CERT", &cert_path);
+    set_var("LOCAL_GUARD_TLS_CLIENT_KEY", &key_path);
+    let expected = client_identity_from_env()
+        .expect("pem pair")
+        .expect("identity configured");
+    assert_eq!(
+        transport_security_from_env()
+            .expect("policy with identity")
+            .client_identity(),
+        Some(&expected)
+    );
+
+    clear_vars();
+    set_var("LOCAL_GUARD_TLS_CLIENT_CERT", &combined_path);
+    assert_eq!(
+        client_identity_from_env().expect("combined pem"),
+        Some(expected.clone())
+    );
+
+    set_var("LOCAL_GUARD_TLS_CLIENT_CERT", &p12_path);
+    set_var("LOCAL_GUARD_TLS_CLIENT_CERT_PASSWORD", "s3cret");
+    assert_eq!(
+        client_identity_from_env().expect("pkcs12"),
+        Some(expected.clone())
+    );
+
+    for (name, value) in [
+        ("LOCAL_GUARD_TLS_CLIENT_CERT_PASSWORD", "wrong".into()),
+        ("LOCAL_GUARD_TLS_CLIENT_KEY", key_path.clone()),
+    ] {
+        set_var(name, value);
+        assert!(matches!(
+            client_identity_from_env(),
+            Err(AppError::TransportSecurity(_))
+        ));
+    }
+
+    clear_vars();
+    set_var("LOCAL_GUARD_TLS_CLIENT_CERT", dir.join("missing.pem"));
+    assert!(matches!(
+        transport_security_from_env(),
+        Err(AppError::TransportSecurity(_))
+    ));
+    clear_vars();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn client_certificate_tests_expiry_surfaces_status_and_warning() {
+    let ca = TestCa::new("device ca");
+    let issued = ca.issue("device-1", Some(2031));
+    let identity = ClientIdentity::from_pem(issued.cert_pem.as_bytes(), issued.key_pem.as_bytes())
+        .expect("identity");
+    let not_after = identity.not_after_ms();
+
+    let mut state = UiState::new("v0.1.0");
+    assert_eq!(
+        client_certificate_state_for_ui(None, not_after),
+        UiClientCertificateState::NotConfigured
+    );
+    state.client_certificate = client_certificate_state_for_ui(
+        Some(&identity),
+        not_after - CLIENT_CERT_EXPIRY_WARNING_MS - 1,
+    );
+    let runtime = project_runtime_status(&state);
+    assert_eq!(runtime.client_certificate, "Valid");
+    assert_eq!(runtime.client_certificate_warning, None);
+
+    state.client_certificate =
+        client_certificate_state_for_ui(Some(&identity), not_after - 5 * DAY_MS - 1);
+    assert_eq!(
+        state.client_certificate,
+        UiClientCertificateState::ExpiringSoon { days_left: 5 }
+    );
+    let runtime = project_runtime_status(&state);
+    assert_eq!(
+        runtime.client_certificate_warning.as_deref(),
+        Some("Client certificate expires in 5 day(s)")
+    );
+
+    state.client_certificate = client_certificate_state_for_ui(Some(&identity), not_after + 1);
+    let runtime = project_runtime_status(&state);
+    assert_eq!(runtime.client_certificate, "Expired");
+    assert!(runtime.client_certificate_warning.is_some());
+}
+
+#[test]
+fn client_certificate_tests_upload_requires_trusted_client_certificate() {
+    let server_ca = TestCa::new("server ca");
+    let device_ca = TestCa::new("device ca");
+    let device = device_ca.issue("device-1", None);
+    let identity = ClientIdentity::from_pem(device.cert_pem.as_bytes(), device.key_pem.as_bytes())
+        .expect("identity");
+    let policy = TransportSecurity::new()
+        .with_ca_bundle_pem(server_ca.issuer.pem().as_bytes())
+        .expect("ca bundle");
+
+    let addr = spawn_mtls_server(&server_ca, &device_ca);
+    let report = upload_client(&policy.clone().with_client_identity(identity), addr)
+        .upload_payload(&common::fixture_payload(), "token")
+        .expect("mutual tls upload should succeed");
+    assert_eq!(report.attempts, 1);
+
+    let addr = spawn_mtls_server(&server_ca, &device_ca);
+    let error = upload_client(&policy, addr)
+        .upload_payload(&common::fixture_payload(), "token")
+        .expect_err("upload without a client certificate must fail");
+    assert!(
+        matches!(error, UploadError::TransportSecurity(ref reason) if reason.contains("client certificate")),
+        "{error}"
+    );
+}
@@ -0,0 +1,86 @@
+//! Integration tests for client provenance stamped on payloads.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use local_guard_app::{
+    APP_VERSION, BUILD_GIT_HASH, BUILD_TARGET, PayloadAssembler, client_provenance,
+};
+use local_guard_capture::{
+    CaptureBackend, FaultPlan, FaultyCaptureBackend, RealCaptureBackend, SyntheticCaptureBackend,
+};
+use local_guard_upload::idempotency_key_for_payload;
+
+#[test]
+fn client_provenance_tests_embeds_build_and_runtime_values() {
+    let provenance = client_provenance(
+        SyntheticCaptureBackend::NAME,
+        BTreeMap::from([("jpeg_quality".to_string(), "9".to_string())]),
+    );
+
+    assert_eq!(provenance.app_version, APP_VERSION);
+    assert_eq!(provenance.git_hash, BUILD_GIT_HASH);
+    assert!(!provenance.git_hash.is_empty());
+    assert_eq!(provenance.target_triple, BUILD_TARGET);
+    assert!(BUILD_TARGET.contains(std::env::consts::ARCH));
+    assert_eq!(provenance.os_name, std::env::consts::OS);
+    assert!(!provenance.os_version.is_empty());
+    assert_eq!(provenance.capture_backend, "synthetic");
+    assert_eq!(
+        provenance
+            .encoder_settings
+            .get("jpeg_quality")
+            .map(String::as_str),
+        Some("9")
+    );
+}
+
+#[test]
+fn client_provenance_tests_backend_names() {
+    assert_eq!(SyntheticCaptureBackend::new().backend_name(), "synthetic");
+    assert_eq!(RealCaptureBackend::NAME, "real");
+    let faulty = FaultyCaptureBackend::new(SyntheticCaptureBackend::new(), FaultPlan::default());
+    assert_eq!(faulty.backend_name(), "synthetic");
+}
+
+#[test]
+fn client_provenance_tests_assembler_stamps_every_payload() {
+    let provenance = client_provenance(SyntheticCaptureBackend::NAME, BTreeMap::new());
+    let assembler = PayloadAssembler::new().with_provenance(provenance.clone());
+
+    let payload = assembler
+        .assemble(&common::fixture_frames(), "session-xyz")
+        .expect("payload should assemble")
+        .payload;
+    assert_eq!(payload.metadata.provenance, Some(provenance));
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&payload.to_json_bytes().expect("payload should encode"))
+            .expect("json should parse");
+    assert_eq!(
+        json["metadata"]["provenance"]["capture_backend"],
+        "synthetic"
+    );
+    assert!(
+        json["metadata"]["provenance"]
+            .get("encoder_settings")
+            .is_none()
+    );
+}
+
+#[test]
+fn client_provenance_tests_excluded_from_idempotency_key() {
+    let plain = common::fixture_payload();
+    let mut stamped = plain.clone();
+    stamped.metadata.provenance = Some(client_provenance("real", BTreeMap::new()));
+    let mut upgraded = stamped.clone();
+    if let Some(provenance) = upgraded.metadata.provenance.as_mut() {
+        provenance.app_version = "9.9.9".to_string();
+        provenance.git_hash = "ffffffffffff".to_string();
+    }
+
+    let key = idempotency_key_for_payload(&plain);
+    assert_eq!(idempotency_key_for_payload(&stamped), key);
+    assert_eq!(idempotency_key_for_payload(&upgraded), key);
+}
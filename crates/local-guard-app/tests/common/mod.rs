@@ -1,5 +1,7 @@
 //! Shared fixtures for app integration tests.
 
+use std::sync::{Mutex, MutexGuard};
+
 use local_guard_app::batch_to_payload;
 use local_guard_core::{Frame, MosaicPayload};
 
@@ -22,3 +24,15 @@ pub fn fixture_frames() -> Vec<Frame> {
 pub fn fixture_payload() -> MosaicPayload {
     batch_to_payload(&fixture_frames(), "session-xyz").expect("payload fixture should build")
 }
+
+/// Serializes tests that mutate the process environment.
+///
+/// Each integration test file is its own binary whose tests run on
+/// parallel threads; hold the guard for the whole test body.
+#[allow(dead_code)]
+pub fn env_lock() -> MutexGuard<'static, ()> {
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+    ENV_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -1,11 +1,12 @@
 //! Integration tests for consent gating behavior.
 
-use local_guard_ui::{UiAuthState, UiState};
+use local_guard_ui::{UiAuthState, UiDeviceState, UiState};
 
 #[test]
 fn consent_gate_tests_requires_explicit_consent_before_capture() {
     let mut state = UiState::new("v0.1.0");
     state.auth = UiAuthState::Authenticated;
+    state.device = UiDeviceState::Enrolled;
     state.select_display("display-1");
     assert!(!state.can_start_capture());
 
@@ -0,0 +1,226 @@
+//! Integration tests for context providers, policy gating, and payload
+//! enrichment.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use local_guard_app::PayloadAssembler;
+use local_guard_capture::{CaptureBackend, SyntheticCaptureBackend};
+use local_guard_context::{
+    ContextCollector, ContextError, ContextField, ContextPolicy, EnvContextProvider,
+    ProcContextProvider, SyntheticContextProvider,
+};
+use local_guard_core::{BatchContext, Frame, MosaicPayload};
+
+fn full_snapshot() -> BatchContext {
+    BatchContext {
+        foreground_process: Some("outlook.exe".to_string()),
+        hostname: Some("host-a".to_string()),
+        os_user: Some("alice".to_string()),
+        locale: Some("en_US.UTF-8".to_string()),
+        timezone: Some("Europe/Bucharest".to_string()),
+        idle_ms: Some(1_500),
+        extensions: BTreeMap::from([("synthetic.note".to_string(), "x".to_string())]),
+        ..BatchContext::default()
+    }
+}
+
+fn frames() -> Vec<Frame> {
+    let backend = SyntheticCaptureBackend::new();
+    (0..9_u64)
+        .map(|tick| {
+            backend
+                .capture_frame("display-1", 1_000 + tick)
+                .expect("capture should work")
+        })
+        .collect()
+}
+
+fn fake_proc_root(name: &str) -> PathBuf {
+    let root =
+        std::env::temp_dir().join(format!("local-guard-context-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("proc/self")).expect("create self");
+    std::fs::create_dir_all(root.join("proc/4242")).expect("create pid");
+    std::fs::create_dir_all(root.join("proc/sys/kernel")).expect("create sys");
+    std::fs::write(
+        root.join("proc/self/stat"),
+        "100 (my (odd) app) S 1 100 100 34816 4242 4194304 0 0",
+    )
+    .expect("write stat");
+    std::fs::write(root.join("proc/4242/comm"), "vim\n").expect("write comm");
+    std::fs::write(root.join("proc/sys/kernel/hostname"), "build-host\n").expect("write host");
+    std::fs::write(
+        root.join("proc/self/status"),
+        "Name:\tapp\nUid:\t1000\t1000\t1000\t1000\n",
+    )
+    .expect("write status");
+    std::fs::write(
+        root.join("passwd"),
+        "root:x:0:0:root:/root:/bin/sh\nalice:x:1000:1000::/home/alice:/bin/sh\n",
+    )
+    .expect("write passwd");
+    root
+}
+
+#[test]
+fn context_provider_tests_policy_parsing() {
+    assert!(ContextPolicy::parse("").expect("empty").is_disabled());
+    assert!(ContextPolicy::parse("none").expect("none").is_disabled());
+    assert_eq!(
+        ContextPolicy::parse("ALL").expect("all"),
+        ContextPolicy::all()
+    );
+
+    let policy = ContextPolicy::parse("hostname, idle_time").expect("list");
+    assert_eq!(
+        policy.fields().collect::<Vec<_>>(),
+        vec![ContextField::Hostname, ContextField::IdleTime]
+    );
+    assert_eq!(
+        ContextPolicy::parse("hostname,window_title"),
+        Err(ContextError::InvalidPolicy("window_title".to_string()))
+    );
+}
+
+#[test]
+fn context_provider_tests_disabled_policy_collects_nothing() {
+    let collector = ContextCollector::new(ContextPolicy::none())
+        .with_provider(SyntheticContextProvider::new(full_snapshot()));
+
+    assert_eq!(collector.collect().context, None);
+}
+
+#[test]
+fn context_provider_tests_policy_strips_disabled_fields() {
+    let policy = ContextPolicy::none()
+        .with_field(ContextField::ForegroundProcess)
+        .with_field(ContextField::IdleTime);
+    let collector =
+        ContextCollector::new(policy).with_provider(SyntheticContextProvider::new(full_snapshot()));
+
+    let context = collector.collect().context.expect("context should exist");
+    assert_eq!(context.foreground_process.as_deref(), Some("outlook.exe"));
+    assert_eq!(context.idle_ms, Some(1_500));
+    assert!(context.hostname.is_none());
+    assert!(context.os_user.is_none());
+    assert!(context.locale.is_none());
+    assert!(context.extensions.is_empty());
+}
+
+#[test]
+fn context_provider_tests_first_provider_wins_and_failures_are_reported() {
+    let collector = ContextCollector::new(ContextPolicy::all())
+        .with_provider(SyntheticContextProvider::failing("no display server").with_name("broken"))
+        .with_provider(SyntheticContextProvider::new(BatchContext {
+            hostname: Some("first".to_string()),
+            ..BatchContext::default()
+        }))
+        .with_provider(SyntheticContextProvider::new(BatchContext {
+            hostname: Some("second".to_string()),
+            locale: Some("de_DE".to_string()),
+            ..BatchContext::default()
+        }));
+
+    let collection = collector.collect();
+    let context = collection.context.expect("context should exist");
+    assert_eq!(context.hostname.as_deref(), Some("first"));
+    assert_eq!(context.locale.as_deref(), Some("de_DE"));
+    assert_eq!(
+        collection.errors,
+        vec![ContextError::Unavailable {
+            provider: "broken".to_string(),
+            reason: "no display server".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn context_provider_tests_proc_provider_reads_fake_root() {
+    let root = fake_proc_root("proc");
+    let provider = ProcContextProvider::new()
+        .with_root(root.join("proc"))
+        .with_passwd(root.join("passwd"));
+    let collector = ContextCollector::new(ContextPolicy::all()).with_provider(provider);
+
+    let context = collector.collect().context.expect("context should exist");
+    assert_eq!(context.foreground_process.as_deref(), Some("vim"));
+    assert_eq!(context.hostname.as_deref(), Some("build-host"));
+    assert_eq!(context.os_user.as_deref(), Some("alice"));
+    assert!(context.idle_ms.is_none());
+
+    let _ = std::fs::remove_dir_all(root);
+}
+
+#[test]
+fn context_provider_tests_proc_provider_missing_root_is_unavailable() {
+    let collector = ContextCollector::new(ContextPolicy::all())
+        .with_provider(ProcContextProvider::new().with_root("/nonexistent/local-guard-proc"));
+
+    let collection = collector.collect();
+    assert!(collection.context.is_none());
+    assert!(matches!(
+        collection.errors.as_slice(),
+        [ContextError::Unavailable { provider, .. }] if provider == "proc"
+    ));
+}
+
+#[test]
+fn context_provider_tests_env_provider_uses_fallback_order() {
+    let vars = BTreeMap::from([
+        ("LANG".to_string(), "fr_FR.UTF-8".to_string()),
+        ("LC_ALL".to_string(), "  ".to_string()),
+        ("TZ".to_string(), "UTC".to_string()),
+        ("LOGNAME".to_string(), "bob".to_string()),
+        ("COMPUTERNAME".to_string(), "WORKSTATION".to_string()),
+    ]);
+    let collector = ContextCollector::new(ContextPolicy::all())
+        .with_provider(EnvContextProvider::with_vars(vars));
+
+    let context = collector.collect().context.expect("context should exist");
+    assert_eq!(context.locale.as_deref(), Some("fr_FR.UTF-8"));
+    assert_eq!(context.timezone.as_deref(), Some("UTC"));
+    assert_eq!(context.os_user.as_deref(), Some("bob"));
+    assert_eq!(context.hostname.as_deref(), Some("WORKSTATION"));
+}
+
+#[test]
+fn context_provider_tests_assembler_attaches_context_to_metadata() {
+    let assembler = PayloadAssembler::new().with_context(
+        ContextCollector::new(ContextPolicy::none().with_field(ContextField::Timezone))
+            .with_provider(SyntheticContextProvider::new(full_snapshot())),
+    );
+
+    let assembled = assembler
+        .assemble(&frames(), "session-context")
+        .expect("payload should assemble");
+    assert!(assembled.context_errors.is_empty());
+
+    let json = assembled
+        .payload
+        .to_json_bytes()
+        .expect("payload should encode");
+    let value: serde_json::Value = serde_json::from_slice(&json).expect("json should parse");
+    assert_eq!(
+        value["metadata"]["context"],
+        serde_json::json!({ "schema_version": "v1", "timezone": "Europe/Bucharest" })
+    );
+    let decoded = MosaicPayload::from_json_bytes(&json).expect("payload should decode");
+    assert_eq!(decoded.metadata.context, assembled.payload.metadata.context);
+}
+
+#[test]
+fn context_provider_tests_assembler_without_context_keeps_v1_shape() {
+    let assembled = PayloadAssembler::new()
+        .assemble(&frames(), "session-context")
+        .expect("payload should assemble");
+
+    let value: serde_json::Value = serde_json::from_slice(
+        &assembled
+            .payload
+            .to_json_bytes()
+            .expect("payload should encode"),
+    )
+    .expect("json should parse");
+    assert!(value["metadata"].get("context").is_none());
+}
@@ -0,0 +1,154 @@
+//! Integration tests for cursor state capture, metadata, and mosaic overlay.
+
+use local_guard_app::{batch_to_payload, batch_to_payload_with, parse_cursor_overlay};
+use local_guard_capture::{
+    CaptureBackend, FaultPlan, FaultyCaptureBackend, GeometryChange, SyntheticCaptureBackend,
+    SyntheticCursor,
+};
+use local_guard_core::{CursorState, Frame, QualityThresholds};
+use local_guard_mosaic::{CursorOverlay, MosaicOptions};
+
+fn capture_batch(backend: &dyn CaptureBackend) -> Vec<Frame> {
+    (0..9_u64)
+        .map(|tick| {
+            backend
+                .capture_frame("display-1", 1_000 + tick)
+                .expect("capture should work")
+        })
+        .collect()
+}
+
+#[test]
+fn cursor_tracking_tests_synthetic_cursor_moves_and_lands_in_metadata() {
+    let backend = SyntheticCaptureBackend::new().with_cursor(SyntheticCursor {
+        step_x: 1,
+        step_y: 2,
+        hide_every: 3,
+    });
+    let frames = capture_batch(&backend);
+    let payload = batch_to_payload(&frames, "session-cursor").expect("payload should build");
+
+    let cursors = &payload.metadata.tile_cursors;
+    assert_eq!(cursors.len(), 9);
+    assert_eq!(
+        cursors[0],
+        Some(CursorState {
+            x: 0,
+            y: 0,
+            visible: true
+        })
+    );
+    assert_eq!(
+        cursors[1],
+        Some(CursorState {
+            x: 1,
+            y: 2,
+            visible: true
+        })
+    );
+    assert_eq!(
+        cursors[4],
+        Some(CursorState {
+            x: 0,
+            y: 0,
+            visible: true
+        })
+    );
+    let hidden: Vec<usize> = cursors
+        .iter()
+        .enumerate()
+        .filter(|(_, cursor)| cursor.is_some_and(|cursor| !cursor.visible))
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(hidden, vec![2, 5, 8]);
+    assert_eq!(backend.cursor_state("display-1"), cursors[8]);
+}
+
+#[test]
+fn cursor_tracking_tests_metadata_omits_cursors_when_not_reported() {
+    let backend = SyntheticCaptureBackend::new();
+    let frames = capture_batch(&backend);
+    let payload = batch_to_payload(&frames, "session-cursor").expect("payload should build");
+
+    assert!(backend.cursor_state("display-1").is_none());
+    assert!(payload.metadata.tile_cursors.is_empty());
+    let json: serde_json::Value =
+        serde_json::from_slice(&payload.to_json_bytes().expect("payload should encode"))
+            .expect("payload json should parse");
+    assert!(json["metadata"].get("tile_cursors").is_none());
+}
+
+#[test]
+fn cursor_tracking_tests_overlay_marks_only_visible_cursor_tiles() {
+    let backend = SyntheticCaptureBackend::new().with_cursor(SyntheticCursor {
+        hide_every: 2,
+        ..SyntheticCursor::default()
+    });
+    let frames = capture_batch(&backend);
+    let options = MosaicOptions {
+        cursor_overlay: Some(CursorOverlay {
+            radius: 0,
+            color: [255, 0, 255, 255],
+        }),
+        ..MosaicOptions::default()
+    };
+
+    let plain = batch_to_payload(&frames, "session-cursor").expect("payload should build");
+    let marked = batch_to_payload_with(
+        &frames,
+        "session-cursor",
+        &options,
+        &QualityThresholds::default(),
+    )
+    .expect("payload should build");
+
+    let pixel = |payload: &local_guard_core::MosaicPayload, x: usize, y: usize| {
+        let offset = (y * payload.mosaic_width as usize + x) * 4;
+        payload.mosaic_rgba[offset..offset + 4].to_vec()
+    };
+    // Tile 0: cursor visible at (0, 0). Tile 1: hidden at (1, 1).
+    assert_eq!(pixel(&marked, 0, 0), vec![255, 0, 255, 255]);
+    assert_eq!(pixel(&marked, 5, 1), pixel(&plain, 5, 1));
+    assert_eq!(marked.metadata, plain.metadata);
+}
+
+#[test]
+fn cursor_tracking_tests_geometry_change_scales_cursor() {
+    let backend = FaultyCaptureBackend::new(
+        SyntheticCaptureBackend::new().with_cursor(SyntheticCursor {
+            step_x: 3,
+            step_y: 3,
+            hide_every: 0,
+        }),
+        FaultPlan {
+            geometry_change: Some(GeometryChange {
+                after_frames: 1,
+                width: 8,
+                height: 8,
+            }),
+            ..FaultPlan::default()
+        },
+    );
+
+    let _ = backend.capture_frame("display-1", 1).expect("capture");
+    let resized = backend.capture_frame("display-1", 2).expect("capture");
+
+    assert_eq!((resized.width, resized.height), (8, 8));
+    assert_eq!(
+        resized.cursor,
+        Some(CursorState {
+            x: 6,
+            y: 6,
+            visible: true
+        })
+    );
+}
+
+#[test]
+fn cursor_tracking_tests_overlay_flag_parsing() {
+    assert_eq!(parse_cursor_overlay("1"), Some(CursorOverlay::default()));
+    assert_eq!(parse_cursor_overlay(" ON "), Some(CursorOverlay::default()));
+    assert_eq!(parse_cursor_overlay("true"), Some(CursorOverlay::default()));
+    assert_eq!(parse_cursor_overlay("0"), None);
+    assert_eq!(parse_cursor_overlay("maybe"), None);
+}
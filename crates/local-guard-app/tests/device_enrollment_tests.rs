@@ -0,0 +1,279 @@
+//! Integration tests for device enrollment, device-bound login, and the
+//! enrollment capture gate.
+
+mod common;
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use local_guard_app::{
+    PayloadAssembler, device_allows_capture, device_state_for_ui, restore_enrollment,
+};
+use local_guard_auth::{
+    AuthClient, AuthError, AuthTransport, Credentials, DeviceIdentityStore, DeviceStatus,
+    EnrollmentClient, EnrollmentRequest, EnrollmentResponse, EnrollmentState, EnrollmentTransport,
+    LocalEnrollmentService, LoginRequest, LoginResponse, validate_enrollment_endpoint,
+};
+use local_guard_crypto::DeviceKey;
+use local_guard_ui::UiDeviceState;
+
+const ENDPOINT: &str = "https://auth.local-guard.test/r1/device-enroll";
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("local-guard-enroll-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn client(service: &Arc<LocalEnrollmentService>) -> EnrollmentClient {
+    EnrollmentClient::new(ENDPOINT, service.clone()).expect("enrollment client should build")
+}
+
+/// Enrollment backend that cannot be reached for status refreshes.
+struct OfflineEnrollment(Arc<LocalEnrollmentService>);
+
+impl EnrollmentTransport for OfflineEnrollment {
+    fn enroll(
+        &self,
+        endpoint: &str,
+        request: &EnrollmentRequest,
+    ) -> Result<EnrollmentResponse, AuthError> {
+        self.0.enroll(endpoint, request)
+    }
+
+    fn device_status(&self, _endpoint: &str, _device_id: &str) -> Result<DeviceStatus, AuthError> {
+        Err(AuthError::Transport("offline".to_string()))
+    }
+}
+
+#[derive(Default)]
+struct RecordingAuthTransport {
+    last_request: Mutex<Option<LoginRequest>>,
+}
+
+impl AuthTransport for RecordingAuthTransport {
+    fn authenticate(
+        &self,
+        _endpoint: &str,
+        request: &LoginRequest,
+    ) -> Result<LoginResponse, AuthError> {
+        *self.last_request.lock().expect("request lock") = Some(request.clone());
+        Ok(LoginResponse {
+            access_token: "token".to_string(),
+            session_id: "session-1".to_string(),
+            expires_in_seconds: 60,
+        })
+    }
+}
+
+#[test]
+fn device_enrollment_tests_enrolls_with_single_use_code() {
+    let service = Arc::new(LocalEnrollmentService::new());
+    service.issue_code("code-1");
+    let key = DeviceKey::from_seed([3; 32]);
+
+    let identity = client(&service)
+        .enroll("code-1", &key, 1_000)
+        .expect("enrollment should succeed");
+    assert_eq!(identity.device_id, "device-000001");
+    assert_eq!(identity.key_id, key.public_key().key_id());
+    assert_eq!(identity.enrolled_at_ms, 1_000);
+    assert_eq!(
+        service.registered_key(&identity.device_id),
+        Some(key.public_key())
+    );
+
+    let reuse = client(&service).enroll("code-1", &DeviceKey::from_seed([4; 32]), 2_000);
+    assert!(matches!(reuse, Err(AuthError::EnrollmentRejected(_))));
+    assert!(matches!(
+        client(&service).enroll("  ", &key, 2_000),
+        Err(AuthError::EmptyCredential)
+    ));
+}
+
+#[test]
+fn device_enrollment_tests_forged_proof_does_not_burn_code() {
+    let service = Arc::new(LocalEnrollmentService::new());
+    service.issue_code("code-1");
+    let victim = DeviceKey::from_seed([5; 32]);
+    let attacker = DeviceKey::from_seed([6; 32]);
+
+    let forged = EnrollmentRequest {
+        enrollment_code: "code-1".to_string(),
+        public_key: victim.public_key().to_hex(),
+        key_id: victim.public_key().key_id(),
+        proof: attacker.sign_enrollment_proof("code-1").to_hex(),
+    };
+    assert!(matches!(
+        service.enroll(ENDPOINT, &forged),
+        Err(AuthError::EnrollmentRejected(_))
+    ));
+
+    client(&service)
+        .enroll("code-1", &victim, 1_000)
+        .expect("legitimate enrollment should still succeed");
+}
+
+#[test]
+fn device_enrollment_tests_revocation_blocks_capture() {
+    let service = Arc::new(LocalEnrollmentService::new());
+    service.issue_code("code-1");
+    let key = DeviceKey::from_seed([7; 32]);
+    let identity = client(&service)
+        .enroll("code-1", &key, 1_000)
+        .expect("enrollment should succeed");
+
+    let mut state = EnrollmentState::from_identity(Some(identity.clone()));
+    assert!(device_allows_capture(&state));
+    assert_eq!(device_state_for_ui(&state), UiDeviceState::Enrolled);
+
+    assert!(service.revoke(&identity.device_id));
+    let status = client(&service)
+        .refresh_status(&identity)
+        .expect("status should refresh");
+    assert_eq!(status, DeviceStatus::Revoked);
+    state.on_status(status);
+    assert!(!device_allows_capture(&state));
+    assert_eq!(device_state_for_ui(&state), UiDeviceState::Revoked);
+
+    state.on_status(DeviceStatus::Active);
+    assert!(matches!(state, EnrollmentState::Revoked(_)));
+    assert_eq!(
+        device_state_for_ui(&EnrollmentState::Unenrolled),
+        UiDeviceState::Unenrolled
+    );
+}
+
+#[test]
+fn device_enrollment_tests_restore_persists_identity() {
+    let dir = scratch_dir("restore");
+    let store = DeviceIdentityStore::in_dir(&dir);
+    let service = Arc::new(LocalEnrollmentService::new());
+    let key = DeviceKey::from_seed([8; 32]);
+
+    let state = restore_enrollment(&store, &client(&service), &key, None, 1_000)
+        .expect("restore without code should succeed");
+    assert_eq!(state, EnrollmentState::Unenrolled);
+
+    service.issue_code("code-1");
+    let state = restore_enrollment(&store, &client(&service), &key, Some("code-1"), 1_000)
+        .expect("enrollment should succeed");
+    let device_id = state.device_id().expect("device id").to_string();
+
+    let restarted = restore_enrollment(&store, &client(&service), &key, None, 2_000)
+        .expect("restart should restore identity");
+    assert_eq!(restarted.device_id(), Some(device_id.as_str()));
+    assert!(restarted.allows_capture());
+
+    service.revoke(&device_id);
+    let revoked = restore_enrollment(&store, &client(&service), &key, None, 3_000)
+        .expect("restore should succeed");
+    assert!(matches!(revoked, EnrollmentState::Revoked(_)));
+
+    let other_key = DeviceKey::from_seed([9; 32]);
+    let rotated = restore_enrollment(&store, &client(&service), &other_key, None, 4_000)
+        .expect("restore should succeed");
+    assert_eq!(rotated, EnrollmentState::Unenrolled);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn device_enrollment_tests_revocation_survives_offline_restart() {
+    let dir = scratch_dir("revoked-offline");
+    let store = DeviceIdentityStore::in_dir(&dir);
+    let service = Arc::new(LocalEnrollmentService::new());
+    let offline = EnrollmentClient::new(ENDPOINT, Arc::new(OfflineEnrollment(service.clone())))
+        .expect("enrollment client should build");
+    let key = DeviceKey::from_seed([10; 32]);
+    service.issue_code("code-1");
+    let state = restore_enrollment(&store, &client(&service), &key, Some("code-1"), 1_000)
+        .expect("enrollment should succeed");
+    let device_id = state.device_id().expect("device id").to_string();
+    assert_eq!(
+        store.load().expect("load").map(|identity| identity.status),
+        Some(DeviceStatus::Active)
+    );
+
+    service.revoke(&device_id);
+    let revoked = restore_enrollment(&store, &client(&service), &key, None, 2_000)
+        .expect("restore should succeed");
+    assert!(matches!(revoked, EnrollmentState::Revoked(_)));
+    assert_eq!(
+        store.load().expect("load").map(|identity| identity.status),
+        Some(DeviceStatus::Revoked)
+    );
+
+    // Invariant: a failed status refresh does not resurrect a revoked device.
+    let restarted = restore_enrollment(&store, &offline, &key, None, 3_000)
+        .expect("offline restore should succeed");
+    assert!(matches!(restarted, EnrollmentState::Revoked(_)));
+    assert!(!device_allows_capture(&restarted));
+
+    // Only a new enrollment replaces the revoked identity.
+    service.issue_code("code-2");
+    let reenrolled = restore_enrollment(&store, &offline, &key, Some("code-2"), 4_000)
+        .expect("re-enrollment should succeed");
+    assert!(reenrolled.allows_capture());
+    assert_ne!(reenrolled.device_id(), Some(device_id.as_str()));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn device_enrollment_tests_legacy_identity_loads_active() {
+    let dir = scratch_dir("legacy");
+    let store = DeviceIdentityStore::in_dir(&dir);
+    std::fs::create_dir_all(&dir).expect("create dir");
+    std::fs::write(
+        store.path(),
+        br#"{"device_id":"device-000001","key_id":"key-1","enrolled_at_ms":1000}"#,
+    )
+    .expect("write legacy identity");
+
+    let identity = store.load().expect("load").expect("identity");
+    assert_eq!(identity.status, DeviceStatus::Active);
+    assert!(EnrollmentState::from_identity(Some(identity)).allows_capture());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn device_enrollment_tests_login_and_payload_carry_device_id() {
+    let transport = Arc::new(RecordingAuthTransport::default());
+    let auth = AuthClient::new(
+        "https://auth.local-guard.test/r1/cstore-auth",
+        transport.clone(),
+    )
+    .expect("auth client should build")
+    .with_device_id("device-000042");
+    auth.login(
+        &Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        },
+        0,
+    )
+    .expect("login should succeed");
+    let request = transport
+        .last_request
+        .lock()
+        .expect("request lock")
+        .clone()
+        .expect("request should be recorded");
+    assert_eq!(request.device_id.as_deref(), Some("device-000042"));
+
+    let payload = PayloadAssembler::new()
+        .with_device_id("device-000042")
+        .assemble(&common::fixture_frames(), "session-1")
+        .expect("fixture batch should assemble")
+        .payload;
+    assert_eq!(payload.metadata.device_id.as_deref(), Some("device-000042"));
+    let json = serde_json::to_value(&payload.metadata).expect("metadata should serialize");
+    assert_eq!(json["device_id"], "device-000042");
+}
+
+#[test]
+fn device_enrollment_tests_endpoint_policy() {
+    validate_enrollment_endpoint(ENDPOINT).expect("endpoint should pass");
+    assert!(validate_enrollment_endpoint("http://auth.local-guard.test/r1/device-enroll").is_err());
+    assert!(validate_enrollment_endpoint("https://auth.local-guard.test/r1/other").is_err());
+}
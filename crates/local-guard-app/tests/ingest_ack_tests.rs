@@ -0,0 +1,204 @@
+//! Integration tests for ingest acknowledgements: acks from single-shot and
+//! chunked uploads, duplicates, immediate analysis, ledger linkage, contract
+//! violations, and legacy servers without an acknowledgement body.
+
+mod common;
+
+use std::sync::Arc;
+
+use common::fixture_frames;
+use local_guard_analysis_contract::IngestStatus;
+use local_guard_app::{BatchLedger, PayloadAssembler, link_analysis_response, link_ingest_ack};
+use local_guard_core::MosaicPayload;
+use local_guard_upload::{
+    ChunkingPolicy, FailureClass, HttpResponse, LocalChunkedIngest, RetryPolicy, UploadClient,
+    UploadEnvelope, UploadError, UploadTransport, classify_upload_error,
+    idempotency_key_for_payload,
+};
+use serde_json::Value;
+
+const ENDPOINT: &str = "https://ingest.local-guard.test/r1/ingest";
+
+fn client(transport: Arc<dyn UploadTransport>) -> UploadClient {
+    UploadClient::new(ENDPOINT, RetryPolicy::mvp_default(), transport).expect("client")
+}
+
+fn assembled() -> MosaicPayload {
+    PayloadAssembler::new()
+        .assemble(&fixture_frames(), "session-a")
+        .expect("fixture batch should assemble")
+        .payload
+}
+
+fn ack_fixture() -> Value {
+    let raw = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-response.valid.json"
+    ))
+    .expect("fixture should be readable");
+    serde_json::from_str(&raw).expect("fixture should be json")
+}
+
+#[test]
+fn ingest_ack_tests_single_shot_ack_links_later_analysis() {
+    let server = Arc::new(LocalChunkedIngest::single_shot_only());
+    let payload = assembled();
+    let mut ledger = BatchLedger::default();
+    ledger.record(&payload);
+
+    let report = client(server.clone())
+        .upload_payload(&payload, "token")
+        .expect("upload");
+    let ack = report.ack.expect("server acknowledges");
+    assert_eq!(ack.status, IngestStatus::Accepted);
+    assert_eq!(ack.request_id, "req-0001");
+    assert_eq!(
+        ack.idempotency_key.as_deref(),
+        Some(idempotency_key_for_payload(&payload).as_str())
+    );
+
+    // No analysis yet: the ack links the request by idempotency key.
+    assert_eq!(
+        link_ingest_ack(&mut ledger, &ack, None).expect("link"),
+        None
+    );
+    let batch_id = payload.metadata.batch_id.clone().expect("batch id");
+    assert_eq!(
+        ledger
+            .find_by_request_id("req-0001")
+            .map(|record| record.batch_id.clone()),
+        Some(batch_id.clone())
+    );
+
+    // The analysis that follows names only the request id.
+    let analysis = serde_json::json!({
+        "schema_version": "v1",
+        "request_id": "req-0001",
+        "categories": [{ "category": "credential_theft", "severity": 80 }]
+    });
+    let linked =
+        link_analysis_response(&mut ledger, &analysis.to_string(), None).expect("analysis links");
+    assert_eq!(linked.batch.map(|batch| batch.batch_id), Some(batch_id));
+}
+
+#[test]
+fn ingest_ack_tests_duplicate_and_chunked_uploads_acknowledge() {
+    let server = Arc::new(LocalChunkedIngest::new());
+    let uploader = client(server.clone()).with_chunking(
+        server.clone(),
+        ChunkingPolicy::new()
+            .with_chunk_size_bytes(256)
+            .with_min_body_bytes(0),
+    );
+    let payload = assembled();
+
+    let first = uploader.upload_payload(&payload, "token").expect("upload");
+    assert!(first.chunks > 1);
+    let first = first.ack.expect("finalize acknowledges");
+    assert_eq!(first.status, IngestStatus::Accepted);
+
+    let replay = uploader
+        .upload_payload(&payload, "token")
+        .expect("replay")
+        .ack
+        .expect("replay acknowledges");
+    assert_eq!(replay.status, IngestStatus::Duplicate);
+    assert_eq!(replay.request_id, first.request_id);
+}
+
+#[test]
+fn ingest_ack_tests_immediate_analysis_links_signals() {
+    let server = Arc::new(LocalChunkedIngest::single_shot_only());
+    let payload = assembled();
+    let batch_id = payload.metadata.batch_id.clone().expect("batch id");
+    let mut ack = ack_fixture();
+    ack["idempotency_key"] = Value::String(idempotency_key_for_payload(&payload));
+    ack["batch_id"] = Value::String(batch_id.clone());
+    ack["analysis"]["batch_id"] = Value::String(batch_id.clone());
+    server.set_ack_body(ack.to_string());
+    let mut ledger = BatchLedger::default();
+    ledger.record(&payload);
+
+    let ack = client(server)
+        .upload_payload(&payload, "token")
+        .expect("upload")
+        .ack
+        .expect("ack");
+    let linked = link_ingest_ack(&mut ledger, &ack, Some(&batch_id))
+        .expect("link")
+        .expect("immediate analysis");
+    assert_eq!(linked.request_id, "req-123");
+    assert_eq!(linked.batch.map(|batch| batch.batch_id), Some(batch_id));
+    assert_eq!(linked.signals.len(), 1);
+
+    assert!(link_ingest_ack(&mut ledger, &ack, Some("another-batch")).is_err());
+}
+
+#[test]
+fn ingest_ack_tests_rejects_contract_violations() {
+    let server = Arc::new(LocalChunkedIngest::single_shot_only());
+    let payload = assembled();
+    let uploader = client(server.clone());
+
+    // The fixture echoes another upload's idempotency key.
+    server.set_ack_body(ack_fixture().to_string());
+    let error = uploader
+        .upload_payload(&payload, "token")
+        .expect_err("foreign key");
+    assert!(matches!(error, UploadError::IngestAck(_)));
+    assert_eq!(classify_upload_error(&error), FailureClass::Permanent);
+
+    let mut ack = ack_fixture();
+    ack["idempotency_key"] = Value::String(idempotency_key_for_payload(&payload));
+    server.set_ack_body(ack.to_string());
+    assert!(matches!(
+        uploader.upload_payload(&payload, "token"),
+        Err(UploadError::IngestAck(message)) if message.contains("batch_id")
+    ));
+
+    server.set_ack_body(r#"{"schema_version":"v1","status":"accepted"}"#);
+    assert!(matches!(
+        uploader.upload_payload(&payload, "token"),
+        Err(UploadError::IngestAck(_))
+    ));
+}
+
+struct LegacyServer {
+    content_type: Option<&'static str>,
+    body: &'static str,
+}
+
+impl UploadTransport for LegacyServer {
+    fn send(&self, _envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        let mut response = HttpResponse::new(200);
+        response.headers.extend(
+            self.content_type
+                .map(|value| ("content-type".to_string(), value.to_string())),
+        );
+        response.body = self.body.as_bytes().to_vec();
+        Ok(response)
+    }
+}
+
+#[test]
+fn ingest_ack_tests_legacy_responses_carry_no_ack() {
+    for (content_type, body) in [
+        (None, ""),
+        (None, r#"{"ok":true}"#),
+        (Some("text/plain"), "stored"),
+        (Some("application/json; charset=utf-8"), "  "),
+    ] {
+        let report = client(Arc::new(LegacyServer { content_type, body }))
+            .upload_payload(&assembled(), "token")
+            .expect("legacy upload succeeds");
+        assert_eq!(report.ack, None, "{content_type:?} {body:?}");
+    }
+
+    let report = client(Arc::new(LegacyServer {
+        content_type: Some("Application/JSON; charset=utf-8"),
+        body: r#"{"schema_version":"v1","request_id":"req-7","status":"accepted"}"#,
+    }))
+    .upload_payload(&assembled(), "token")
+    .expect("upload");
+    assert_eq!(report.ack.map(|ack| ack.request_id), Some("req-7".into()));
+}
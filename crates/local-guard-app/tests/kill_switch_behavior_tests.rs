@@ -1,11 +1,15 @@
 //! Integration tests for runtime kill-switch behavior.
 
+mod common;
+
 use local_guard_app::capture_enabled_from_env;
 
 #[test]
 fn kill_switch_behavior_tests_disables_capture_when_env_is_false() {
+    let _env = common::env_lock();
     // Safety:
-    // - Integration tests mutate process env in a single-threaded test body.
+    // - Tests that touch the env hold `common::env_lock()`, so no other
+    //   thread in this binary changes it concurrently.
     // - We reset the variable before returning.
     unsafe { std::env::set_var("LOCAL_GUARD_CAPTURE_ENABLED", "false") };
     assert!(!capture_enabled_from_env());
@@ -0,0 +1,174 @@
+//! Integration tests for payload hash chaining and chain persistence.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::fixture_frames;
+use local_guard_app::{AppError, ChainStore, PayloadAssembler};
+use local_guard_core::{
+    ChainState, CoreError, MosaicPayload, chain_root_hash, verify_payload_chain,
+    verify_payload_chain_from,
+};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("local-guard-chain-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn assemble_chain(
+    assembler: &PayloadAssembler,
+    session_id: &str,
+    count: usize,
+) -> Vec<MosaicPayload> {
+    (0..count)
+        .map(|_| {
+            let assembled = assembler
+                .assemble(&fixture_frames(), session_id)
+                .expect("fixture batch should assemble");
+            assert_eq!(assembler.chain_state().as_ref(), Some(&assembled.chain));
+            assembled.payload
+        })
+        .collect()
+}
+
+#[test]
+fn payload_chain_tests_assembled_payloads_verify() {
+    let assembler = PayloadAssembler::new();
+    let payloads = assemble_chain(&assembler, "session-a", 3);
+
+    assert_eq!(
+        payloads[0].metadata.prev_payload_hash.as_deref(),
+        Some(chain_root_hash("session-a").as_str())
+    );
+    let head = verify_payload_chain(&payloads).expect("assembled chain should verify");
+    assert_eq!(Some(head), assembler.chain_state());
+}
+
+#[test]
+fn payload_chain_tests_new_session_restarts_at_root() {
+    let assembler = PayloadAssembler::new();
+    assemble_chain(&assembler, "session-a", 2);
+    let payloads = assemble_chain(&assembler, "session-b", 2);
+
+    assert_eq!(payloads[0].metadata.sequence_number, Some(1));
+    verify_payload_chain(&payloads).expect("new session should start a fresh chain");
+}
+
+#[test]
+fn payload_chain_tests_restart_continues_persisted_chain() {
+    let dir = scratch_dir("restart");
+    let store = ChainStore::in_dir(&dir);
+    assert_eq!(store.load().expect("missing file is not an error"), None);
+
+    let before_restart = PayloadAssembler::new();
+    let mut payloads = Vec::new();
+    for _ in 0..2 {
+        let assembled = before_restart
+            .assemble(&fixture_frames(), "session-a")
+            .expect("fixture batch should assemble");
+        store
+            .save(&assembled.chain)
+            .expect("chain state should save");
+        payloads.push(assembled.payload);
+    }
+
+    let resumed = store
+        .load()
+        .expect("chain state should load")
+        .expect("chain state should exist");
+    let after_restart = PayloadAssembler::new().with_chain_state(resumed);
+    payloads.extend(assemble_chain(&after_restart, "session-a", 2));
+
+    let head = verify_payload_chain(&payloads).expect("chain should survive restart");
+    assert_eq!(head.sequence_number, 4);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn payload_chain_tests_restart_with_new_session_starts_at_root() {
+    let dir = scratch_dir("new-session");
+    let store = ChainStore::in_dir(&dir);
+    let before_restart = PayloadAssembler::new();
+    let old_session = assemble_chain(&before_restart, "session-a", 2);
+    store
+        .save(&before_restart.chain_state().expect("chain head"))
+        .expect("chain state should save");
+
+    // The restart needed a new login, so capture continues as session-b.
+    let resumed = store
+        .load()
+        .expect("chain state should load")
+        .expect("chain state should exist");
+    let after_restart = PayloadAssembler::new().with_chain_state(resumed);
+    let assembled = after_restart
+        .assemble(&fixture_frames(), "session-b")
+        .expect("fixture batch should assemble");
+    store
+        .save(&assembled.chain)
+        .expect("chain state should save");
+
+    // Invariant: chains are per session, rooted at login.
+    assert_eq!(assembled.payload.metadata.sequence_number, Some(1));
+    assert_eq!(
+        assembled.payload.metadata.prev_payload_hash.as_deref(),
+        Some(chain_root_hash("session-b").as_str())
+    );
+    verify_payload_chain(&[assembled.payload]).expect("new session chain should verify");
+    verify_payload_chain(&old_session).expect("old session chain is unaffected");
+    assert_eq!(
+        store.load().expect("chain state should load"),
+        Some(assembled.chain)
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn payload_chain_tests_resumed_sequence_without_hash_is_detected() {
+    let before_restart = PayloadAssembler::new();
+    let payloads = assemble_chain(&before_restart, "session-a", 2);
+    let anchor = verify_payload_chain(&payloads).expect("chain should verify");
+
+    let after_restart = PayloadAssembler::new().with_resumed_sequence("session-a", 2);
+    let continued = assemble_chain(&after_restart, "session-a", 1);
+    assert_eq!(continued[0].metadata.sequence_number, Some(3));
+
+    let error = verify_payload_chain_from(&anchor, &continued)
+        .expect_err("a chain reset must be detectable");
+    assert!(matches!(error, CoreError::ChainBroken { index: 0, .. }));
+}
+
+#[test]
+fn payload_chain_tests_store_rejects_corrupt_file() {
+    let dir = scratch_dir("corrupt");
+    std::fs::create_dir_all(&dir).expect("scratch dir should be created");
+    let store = ChainStore::in_dir(&dir);
+    std::fs::write(store.path(), b"{ not json").expect("corrupt file should be written");
+
+    assert!(matches!(store.load(), Err(AppError::Persistence(_))));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn payload_chain_tests_store_save_replaces_atomically() {
+    let dir = scratch_dir("replace");
+    let store = ChainStore::in_dir(&dir);
+    let first = ChainState::root("session-a");
+    let second = ChainState {
+        sequence_number: 7,
+        ..ChainState::root("session-a")
+    };
+
+    store.save(&first).expect("first save should succeed");
+    store.save(&second).expect("second save should succeed");
+
+    assert_eq!(store.load().expect("state should load"), Some(second));
+    let leftovers: Vec<_> = std::fs::read_dir(&dir)
+        .expect("scratch dir should exist")
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name())
+        .collect();
+    assert_eq!(leftovers.len(), 1, "temp file should be renamed away");
+    let _ = std::fs::remove_dir_all(&dir);
+}
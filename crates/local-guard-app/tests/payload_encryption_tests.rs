@@ -0,0 +1,205 @@
+//! Integration tests for end-to-end payload sealing of upload bodies and
+//! staged artifacts.
+
+mod common;
+
+use std::sync::Arc;
+
+use local_guard_app::{AppError, body_sealer_from_env, parse_server_public_key, seal_artifact};
+use local_guard_crypto::{
+    BodySealer, CryptoError, DeviceKey, PayloadSigner, SEALED_CONTENT_TYPE, SealedEnvelope,
+    ServerSecretKey, open_sealed_envelope, open_sealed_json, parse_signature_header,
+    verify_payload_signature,
+};
+use local_guard_upload::{
+    HttpResponse, PAYLOAD_CONTENT_TYPE, RetryPolicy, UploadClient, UploadEnvelope, UploadError,
+    UploadTransport,
+};
+
+struct NoopTransport;
+
+impl UploadTransport for NoopTransport {
+    fn send(&self, _envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        Ok(HttpResponse::new(200))
+    }
+}
+
+fn client() -> UploadClient {
+    UploadClient::new(
+        "https://ingest.local-guard.test/r1/ingest",
+        RetryPolicy::mvp_default(),
+        Arc::new(NoopTransport),
+    )
+    .expect("upload client should build")
+}
+
+fn server_key() -> ServerSecretKey {
+    ServerSecretKey::from_bytes([0x42; 32])
+}
+
+#[test]
+fn payload_encryption_tests_plaintext_by_default() {
+    let payload = common::fixture_payload();
+    let envelope = client()
+        .build_envelope(&payload, "token")
+        .expect("envelope should build");
+
+    assert_eq!(envelope.content_type, PAYLOAD_CONTENT_TYPE);
+    assert_eq!(
+        envelope.body,
+        payload.to_json_bytes().expect("payload should encode")
+    );
+    assert!(client().sealer_key_id().is_none());
+}
+
+#[test]
+fn payload_encryption_tests_sealed_body_round_trips() {
+    let secret = server_key();
+    let payload = common::fixture_payload();
+    let sealing_client = client().with_sealer(BodySealer::new(secret.public_key()));
+    assert_eq!(
+        sealing_client.sealer_key_id(),
+        Some(secret.public_key().key_id())
+    );
+
+    let envelope = sealing_client
+        .build_envelope(&payload, "token")
+        .expect("envelope should build");
+    assert_eq!(envelope.content_type, SEALED_CONTENT_TYPE);
+
+    let sealed = SealedEnvelope::from_json_bytes(&envelope.body).expect("body is an envelope");
+    assert_eq!(sealed.recipient_key_id, secret.public_key().key_id());
+    assert_eq!(sealed.content_type, PAYLOAD_CONTENT_TYPE);
+    assert_eq!(
+        open_sealed_envelope(&secret, &sealed).expect("body should open"),
+        payload.to_json_bytes().expect("payload should encode")
+    );
+}
+
+#[test]
+fn payload_encryption_tests_signature_covers_plaintext_after_decrypt() {
+    let secret = server_key();
+    let key = Arc::new(DeviceKey::from_seed([9; 32]));
+    let envelope = client()
+        .with_signer(key.clone())
+        .with_sealer(BodySealer::new(secret.public_key()))
+        .build_envelope(&common::fixture_payload(), "token")
+        .expect("envelope should build");
+
+    let plaintext = open_sealed_json(&secret, &envelope.body).expect("body should open");
+    let header = parse_signature_header(
+        envelope
+            .signature_header
+            .as_deref()
+            .expect("signed envelope"),
+    )
+    .expect("header should parse");
+    assert_eq!(header.key_id, key.key_id());
+    verify_payload_signature(&key.public_key(), &plaintext, &header.signature)
+        .expect("signature should verify over decrypted body");
+}
+
+#[test]
+fn payload_encryption_tests_each_seal_is_unique() {
+    let sealer = BodySealer::new(server_key().public_key());
+    let first = sealer.seal(b"same", "text/plain").expect("seal");
+    let second = sealer.seal(b"same", "text/plain").expect("seal");
+
+    assert_ne!(first.ephemeral_public_key, second.ephemeral_public_key);
+    assert_ne!(first.nonce, second.nonce);
+    assert_ne!(first.ciphertext, second.ciphertext);
+}
+
+#[test]
+fn payload_encryption_tests_tampering_and_wrong_key_are_rejected() {
+    let secret = server_key();
+    let sealed = BodySealer::new(secret.public_key())
+        .seal(b"{\"schema_version\":\"v1\"}", PAYLOAD_CONTENT_TYPE)
+        .expect("seal");
+
+    let mut relabeled = sealed.clone();
+    relabeled.content_type = "image/jpeg".to_string();
+    assert_eq!(
+        open_sealed_envelope(&secret, &relabeled),
+        Err(CryptoError::DecryptionFailed)
+    );
+
+    let mut swapped_ephemeral = sealed.clone();
+    swapped_ephemeral.ephemeral_public_key = server_key().public_key().to_hex();
+    assert_eq!(
+        open_sealed_envelope(&secret, &swapped_ephemeral),
+        Err(CryptoError::DecryptionFailed)
+    );
+
+    let other = ServerSecretKey::from_bytes([0x43; 32]);
+    assert_eq!(
+        open_sealed_envelope(&other, &sealed),
+        Err(CryptoError::DecryptionFailed)
+    );
+
+    let mut future = sealed;
+    future.version = "v2".to_string();
+    assert!(matches!(
+        open_sealed_envelope(&secret, &future),
+        Err(CryptoError::InvalidEnvelope(_))
+    ));
+}
+
+#[test]
+fn payload_encryption_tests_low_order_server_key_is_refused() {
+    let sealer = BodySealer::new(parse_server_public_key(&"00".repeat(32)).expect("32 bytes"));
+    assert!(matches!(
+        sealer.seal(b"secret", PAYLOAD_CONTENT_TYPE),
+        Err(CryptoError::EncryptionFailed(_))
+    ));
+}
+
+#[test]
+fn payload_encryption_tests_staged_artifact_opens_with_content_type() {
+    let secret = server_key();
+    let sealer = BodySealer::new(secret.public_key());
+    let jpeg = [0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10];
+
+    let bytes = seal_artifact(&sealer, &jpeg, "image/jpeg").expect("artifact should seal");
+    let envelope = SealedEnvelope::from_json_bytes(&bytes).expect("artifact is an envelope");
+    assert_eq!(envelope.content_type, "image/jpeg");
+    assert_eq!(
+        open_sealed_envelope(&secret, &envelope).expect("artifact should open"),
+        jpeg
+    );
+}
+
+#[test]
+fn payload_encryption_tests_server_key_env_parsing() {
+    let _env = common::env_lock();
+    let public_key = server_key().public_key();
+
+    // Safety:
+    // - Tests that touch the env hold `common::env_lock()`, so no other
+    //   thread in this binary changes it concurrently.
+    // - We reset the variable before returning.
+    unsafe { std::env::remove_var("LOCAL_GUARD_SERVER_PUBLIC_KEY") };
+    assert!(body_sealer_from_env().expect("unset is valid").is_none());
+
+    // Safety: see rationale above.
+    unsafe {
+        std::env::set_var(
+            "LOCAL_GUARD_SERVER_PUBLIC_KEY",
+            format!(" {} ", public_key.to_hex()),
+        )
+    };
+    let sealer = body_sealer_from_env()
+        .expect("valid key")
+        .expect("sealing enabled");
+    assert_eq!(sealer.recipient_key_id(), public_key.key_id());
+
+    // Safety: see rationale above.
+    unsafe { std::env::set_var("LOCAL_GUARD_SERVER_PUBLIC_KEY", "not-hex") };
+    assert!(matches!(
+        body_sealer_from_env(),
+        Err(AppError::Crypto(CryptoError::InvalidKey(_)))
+    ));
+
+    // Safety: see rationale above.
+    unsafe { std::env::remove_var("LOCAL_GUARD_SERVER_PUBLIC_KEY") };
+}
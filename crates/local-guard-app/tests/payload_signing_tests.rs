@@ -0,0 +1,137 @@
+//! Integration tests for device keys and signed upload envelopes.
+
+mod common;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use local_guard_crypto::{
+    CryptoError, DeviceKey, DeviceKeyStore, PayloadSigner, parse_signature_header,
+    verify_payload_signature,
+};
+use local_guard_upload::{
+    HttpResponse, RetryPolicy, UploadClient, UploadEnvelope, UploadError, UploadTransport,
+};
+
+struct NoopTransport;
+
+impl UploadTransport for NoopTransport {
+    fn send(&self, _envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        Ok(HttpResponse::new(200))
+    }
+}
+
+fn client() -> UploadClient {
+    UploadClient::new(
+        "https://ingest.local-guard.test/r1/ingest",
+        RetryPolicy::mvp_default(),
+        Arc::new(NoopTransport),
+    )
+    .expect("upload client should build")
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "local-guard-device-key-{name}-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn payload_signing_tests_unsigned_by_default() {
+    let envelope = client()
+        .build_envelope(&common::fixture_payload(), "token")
+        .expect("envelope should build");
+    assert!(envelope.signature_header.is_none());
+}
+
+#[test]
+fn payload_signing_tests_signed_envelope_verifies() {
+    let key = Arc::new(DeviceKey::from_seed([9; 32]));
+    let client = client().with_signer(key.clone());
+    assert_eq!(client.signer_key_id(), Some(key.key_id()));
+
+    let payload = common::fixture_payload();
+    let envelope = client
+        .build_envelope(&payload, "token")
+        .expect("envelope should build");
+    let header = parse_signature_header(
+        envelope
+            .signature_header
+            .as_deref()
+            .expect("signature header should be set"),
+    )
+    .expect("signature header should parse");
+
+    assert_eq!(header.key_id, key.key_id());
+    assert_eq!(
+        envelope.body,
+        payload.to_json_bytes().expect("payload should encode")
+    );
+    verify_payload_signature(&key.public_key(), &envelope.body, &header.signature)
+        .expect("signature should verify over the body");
+
+    let mut tampered = envelope.body.clone();
+    tampered.push(b' ');
+    assert_eq!(
+        verify_payload_signature(&key.public_key(), &tampered, &header.signature),
+        Err(CryptoError::VerificationFailed)
+    );
+}
+
+#[test]
+fn payload_signing_tests_store_keeps_identity_across_restarts() {
+    let dir = scratch_dir("persist");
+    let store = DeviceKeyStore::in_dir(&dir);
+    assert!(store.load().expect("missing key is not an error").is_none());
+
+    let created = store.load_or_create().expect("key should be created");
+    let reloaded = DeviceKeyStore::in_dir(&dir)
+        .load_or_create()
+        .expect("key should reload");
+    assert_eq!(created.public_key(), reloaded.public_key());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(store.path())
+            .expect("key file should exist")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o077, 0, "key file must not be group/world readable");
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn payload_signing_tests_store_rejects_tampered_file() {
+    let dir = scratch_dir("tampered");
+    let store = DeviceKeyStore::in_dir(&dir);
+    store
+        .save(&DeviceKey::from_seed([1; 32]))
+        .expect("key should save");
+
+    let raw = std::fs::read_to_string(store.path()).expect("key file should be readable");
+    let other = DeviceKey::from_seed([2; 32]).public_key().to_hex();
+    let original = DeviceKey::from_seed([1; 32]).public_key().to_hex();
+    std::fs::write(store.path(), raw.replace(&original, &other))
+        .expect("key file should be written");
+    assert!(matches!(store.load(), Err(CryptoError::KeyStore(_))));
+
+    std::fs::write(store.path(), b"not json").expect("key file should be written");
+    assert!(matches!(
+        store.load_or_create(),
+        Err(CryptoError::KeyStore(_))
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn payload_signing_tests_debug_redacts_secret() {
+    let key = DeviceKey::from_seed([0xab; 32]);
+    let debug = format!("{key:?}");
+    assert!(debug.contains(&key.key_id()));
+    assert!(!debug.contains("abababab"));
+}
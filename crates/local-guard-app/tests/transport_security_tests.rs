@@ -1,9 +1,319 @@
-//! Integration tests for transport security URL checks.
+//! Integration tests for transport security URL checks, policy
+//! configuration, and pinned auth/ingest connections.
 
-use local_guard_app::is_https_endpoint;
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, channel};
+use std::time::Duration;
+
+use local_guard_app::{AppError, is_https_endpoint, parse_pin_list, transport_security_from_env};
+use local_guard_auth::{AuthClient, AuthError, Credentials, HttpsAuthTransport};
+use local_guard_transport::{HttpsConnector, SpkiPin, TlsVersion, TransportSecurity};
+use local_guard_upload::{
+    FailureClass, HttpsUploadTransport, RetryPolicy, UploadClient, UploadError,
+    classify_upload_error,
+};
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair, PublicKeyData};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+const INGEST_HOST: &str = "ingest.local-guard.test";
+const AUTH_HOST: &str = "auth.local-guard.test";
+
+struct LocalTlsServer {
+    addr: SocketAddr,
+    requests: Receiver<String>,
+    accepted: Arc<AtomicUsize>,
+    ca_pem: String,
+    leaf_pin: SpkiPin,
+}
+
+impl LocalTlsServer {
+    fn spawn(host: &str, status_line: &'static str, body: &'static str) -> Self {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().expect("ca key"))
+            .expect("ca cert");
+        let leaf_key = KeyPair::generate().expect("leaf key");
+        let leaf = CertificateParams::new(vec![host.to_string()])
+            .expect("leaf params")
+            .signed_by(&leaf_key, &ca)
+            .expect("leaf cert");
+        let config = Arc::new(
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .expect("server versions")
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![leaf.der().clone()],
+                    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf_key.serialize_der())),
+                )
+                .expect("server cert"),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let (sender, requests) = channel();
+        let accepted_counter = Arc::clone(&accepted);
+        std::thread::spawn(move || {
+            for tcp in listener.incoming().flatten() {
+                accepted_counter.fetch_add(1, Ordering::SeqCst);
+                let connection =
+                    ServerConnection::new(Arc::clone(&config)).expect("server connection");
+                let mut tls = StreamOwned::new(connection, tcp);
+                let Some(request) = read_request(&mut tls) else {
+                    continue;
+                };
+                let _ = sender.send(request);
+                let response = format!(
+                    "HTTP/1.1 {status_line}\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = tls.write_all(response.as_bytes());
+                tls.conn.send_close_notify();
+                let _ = tls.flush();
+            }
+        });
+
+        Self {
+            addr,
+            requests,
+            accepted,
+            ca_pem: ca.pem(),
+            leaf_pin: SpkiPin::from_spki_der(&leaf_key.subject_public_key_info()),
+        }
+    }
+
+    fn policy(&self, pins: Vec<SpkiPin>) -> TransportSecurity {
+        TransportSecurity::new()
+            .with_ca_bundle_pem(self.ca_pem.as_bytes())
+            .expect("ca bundle")
+            .with_pins(pins, vec![SpkiPin::from_spki_der(b"offline backup key")])
+            .expect("policy")
+    }
+
+    fn connector(&self, host: &str, pins: Vec<SpkiPin>) -> HttpsConnector {
+        HttpsConnector::new(&self.policy(pins))
+            .expect("connector")
+            .with_timeout(Duration::from_secs(5))
+            .with_resolved_addr(host, self.addr)
+    }
+}
+
+fn read_request(stream: &mut impl Read) -> Option<String> {
+    let mut raw = Vec::new();
+    let mut buffer = [0_u8; 4096];
+    loop {
+        if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&raw[..end]).to_ascii_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if raw.len() >= end + 4 + length {
+                return Some(String::from_utf8_lossy(&raw).into_owned());
+            }
+        }
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => raw.extend_from_slice(&buffer[..read]),
+        }
+    }
+}
+
+fn upload_client(connector: HttpsConnector) -> UploadClient {
+    UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        RetryPolicy::mvp_default(),
+        Arc::new(HttpsUploadTransport::new(connector)),
+    )
+    .expect("upload client")
+}
 
 #[test]
 fn transport_security_tests_rejects_non_https_endpoints() {
-    assert!(is_https_endpoint("https://api.example.test/ingest"));
+    assert!(is_https_endpoint(
+        "https://ingest.local-guard.test/r1/ingest"
+    ));
     assert!(!is_https_endpoint("http://api.example.test/ingest"));
 }
+
+#[test]
+fn transport_security_tests_pinned_upload_sends_envelope_headers() {
+    let server = LocalTlsServer::spawn(INGEST_HOST, "202 Accepted", "");
+    let client = upload_client(server.connector(INGEST_HOST, vec![server.leaf_pin]));
+    let payload = common::fixture_payload();
+
+    let report = client
+        .upload_payload(&payload, "token-123")
+        .expect("pinned upload should succeed");
+    assert_eq!(report.attempts, 1);
+
+    let request = server.requests.recv().expect("request received");
+    assert!(request.starts_with("POST /r1/ingest HTTP/1.1\r\n"));
+    assert!(request.contains("Authorization: Bearer token-123\r\n"));
+    assert!(request.contains("Content-Type: application/json\r\n"));
+    assert!(request.contains(&format!(
+        "Idempotency-Key: {}\r\n",
+        client.idempotency_key(&payload)
+    )));
+}
+
+#[test]
+fn transport_security_tests_unpinned_upload_fails_permanently_without_retry() {
+    let server = LocalTlsServer::spawn(INGEST_HOST, "202 Accepted", "");
+    let client = upload_client(
+        server.connector(INGEST_HOST, vec![SpkiPin::from_spki_der(b"some other key")]),
+    );
+
+    let error = client
+        .upload_payload(&common::fixture_payload(), "token-123")
+        .expect_err("unpinned server must be rejected");
+    assert!(matches!(error, UploadError::TransportSecurity(_)));
+    assert_eq!(classify_upload_error(&error), FailureClass::Permanent);
+    assert_eq!(server.accepted.load(Ordering::SeqCst), 1);
+    assert!(server.requests.try_recv().is_err());
+}
+
+#[test]
+fn transport_security_tests_upload_status_codes_map_to_error_classes() {
+    for (status_line, expected) in [
+        ("401 Unauthorized", UploadError::Unauthorized),
+        ("400 Bad Request", UploadError::Client(400)),
+    ] {
+        let server = LocalTlsServer::spawn(INGEST_HOST, status_line, "");
+        let client = upload_client(server.connector(INGEST_HOST, vec![server.leaf_pin]));
+        assert_eq!(
+            client.upload_payload(&common::fixture_payload(), "token"),
+            Err(expected)
+        );
+    }
+}
+
+#[test]
+fn transport_security_tests_pinned_auth_login_round_trips() {
+    let server = LocalTlsServer::spawn(
+        AUTH_HOST,
+        "200 OK",
+        r#"{"access_token":"access","session_id":"session-1","expires_in_seconds":60}"#,
+    );
+    let client = AuthClient::new(
+        format!("https://{AUTH_HOST}/r1/cstore-auth"),
+        Arc::new(HttpsAuthTransport::new(
+            server.connector(AUTH_HOST, vec![server.leaf_pin]),
+        )),
+    )
+    .expect("auth client");
+
+    let token = client
+        .login(
+            &Credentials {
+                username: "user".to_string(),
+                password: "secret".to_string(),
+            },
+            1_000,
+        )
+        .expect("login should succeed");
+    assert_eq!(token.session_id, "session-1");
+    assert_eq!(token.expires_at_ms, 61_000);
+    let request = server.requests.recv().expect("request received");
+    assert!(request.ends_with(r#"{"username":"user","password":"secret"}"#));
+}
+
+#[test]
+fn transport_security_tests_unpinned_auth_never_sends_credentials() {
+    let server = LocalTlsServer::spawn(AUTH_HOST, "200 OK", "{}");
+    let client = AuthClient::new(
+        format!("https://{AUTH_HOST}/r1/cstore-auth"),
+        Arc::new(HttpsAuthTransport::new(server.connector(
+            AUTH_HOST,
+            vec![SpkiPin::from_spki_der(b"some other key")],
+        ))),
+    )
+    .expect("auth client");
+
+    let result = client.login(
+        &Credentials {
+            username: "user".to_string(),
+            password: "secret".to_string(),
+        },
+        1_000,
+    );
+    assert!(matches!(result, Err(AuthError::TransportSecurity(_))));
+    assert!(server.requests.try_recv().is_err());
+}
+
+#[test]
+fn transport_security_tests_policy_from_env_fails_closed() {
+    let _env = common::env_lock();
+    let primary = SpkiPin::from_spki_der(b"primary");
+    let backup = SpkiPin::from_spki_der(b"backup");
+    let vars = [
+        "LOCAL_GUARD_TLS_PINS",
+        "LOCAL_GUARD_TLS_BACKUP_PINS",
+        "LOCAL_GUARD_TLS_CA_BUNDLE",
+        "LOCAL_GUARD_TLS_MIN_VERSION",
+    ];
+    let set = |name: &str, value: String| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        unsafe { std::env::set_var(name, value) };
+    };
+    let clear = || {
+        for name in vars {
+            // Safety: see rationale above.
+            unsafe { std::env::remove_var(name) };
+        }
+    };
+
+    clear();
+    let policy = transport_security_from_env().expect("defaults are valid");
+    assert!(!policy.is_pinned());
+    assert_eq!(policy.min_tls_version(), TlsVersion::Tls12);
+
+    set("LOCAL_GUARD_TLS_PINS", format!("{primary}"));
+    set("LOCAL_GUARD_TLS_BACKUP_PINS", format!(" {backup} ,"));
+    set("LOCAL_GUARD_TLS_MIN_VERSION", "1.3".to_string());
+    let policy = transport_security_from_env().expect("pinned policy");
+    assert_eq!(policy.pins(), &[primary]);
+    assert_eq!(policy.backup_pins(), &[backup]);
+    assert_eq!(policy.min_tls_version(), TlsVersion::Tls13);
+
+    set("LOCAL_GUARD_TLS_MIN_VERSION", "1.0".to_string());
+    assert!(matches!(
+        transport_security_from_env(),
+        Err(AppError::TransportSecurity(_))
+    ));
+
+    set("LOCAL_GUARD_TLS_MIN_VERSION", "1.2".to_string());
+    set("LOCAL_GUARD_TLS_BACKUP_PINS", String::new());
+    assert!(matches!(
+        transport_security_from_env(),
+        Err(AppError::TransportSecurity(_))
+    ));
+
+    clear();
+    set(
+        "LOCAL_GUARD_TLS_CA_BUNDLE",
+        std::env::temp_dir()
+            .join(format!("local-guard-missing-ca-{}.pem", std::process::id()))
+            .display()
+            .to_string(),
+    );
+    assert!(matches!(
+        transport_security_from_env(),
+        Err(AppError::TransportSecurity(_))
+    ));
+    clear();
+
+    assert!(parse_pin_list("sha256/not-a-pin").is_err());
+    assert_eq!(parse_pin_list(" , ").expect("empty list"), Vec::new());
+}
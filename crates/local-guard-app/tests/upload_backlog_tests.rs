@@ -0,0 +1,96 @@
+//! Integration tests for the upload backlog: payloads deferred by a paused
+//! data budget are uploaded once it resets, the byte bound, and env
+//! configuration.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::fixture_payload;
+use local_guard_app::{DEFAULT_UPLOAD_BACKLOG_BYTES, UploadBacklog, upload_backlog_from_env};
+use local_guard_upload::{
+    BudgetExhaustionPolicy, BudgetMeter, BudgetUsage, DataBudget, DispatchOutcome,
+    DispatcherConfig, LocalChunkedIngest, RetryPolicy, UploadClient, UploadDispatcher,
+    VirtualUploadClock,
+};
+
+const ENDPOINT: &str = "https://ingest.local-guard.test/r1/ingest";
+const WAIT: Duration = Duration::from_secs(5);
+/// 2026-10-18T12:00:00Z.
+const NOON_UNIX_MS: u64 = 1_792_324_800_000;
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[test]
+fn upload_backlog_tests_paused_budget_defers_until_reset() {
+    let clock = Arc::new(VirtualUploadClock::starting_at_unix_ms(NOON_UNIX_MS));
+    let server = Arc::new(LocalChunkedIngest::new());
+    let payload = fixture_payload();
+    let daily_limit = 2 * payload.to_json_bytes().expect("canonical").len() as u64;
+    let meter = BudgetMeter::new(
+        DataBudget::new(BudgetExhaustionPolicy::QueueOnly).with_daily_limit_bytes(daily_limit),
+        BudgetUsage::default(),
+        clock.clone(),
+    );
+    meter.record(daily_limit);
+    let uploader = UploadClient::new(ENDPOINT, RetryPolicy::mvp_default(), server.clone())
+        .expect("client")
+        .with_data_budget(meter.clone());
+    let (dispatcher, events) = UploadDispatcher::new(uploader, DispatcherConfig::new());
+    let backlog = UploadBacklog::default();
+
+    backlog.push(payload.clone());
+    backlog.push(payload.clone());
+    assert_eq!(backlog.pump(&dispatcher, Some(&meter), "token"), 0);
+    assert_eq!(backlog.len(), 2);
+    assert_eq!(backlog.bytes(), 2 * payload.mosaic_rgba.len() as u64);
+    assert_eq!(dispatcher.in_flight(), 0);
+
+    clock.advance(DAY);
+    assert_eq!(backlog.pump(&dispatcher, Some(&meter), "token"), 2);
+    assert!(backlog.is_empty());
+    assert_eq!(backlog.bytes(), 0);
+    for _ in 0..2 {
+        let event = events.recv_timeout(WAIT).expect("completion event");
+        assert!(matches!(event.outcome, DispatchOutcome::Uploaded(_)));
+    }
+    assert_eq!(server.received().len(), 2);
+}
+
+#[test]
+fn upload_backlog_tests_byte_bound_and_clear() {
+    let payload = fixture_payload();
+    let backlog = UploadBacklog::new(2 * payload.mosaic_rgba.len() as u64);
+
+    backlog.push(payload.clone());
+    assert!(!backlog.is_full());
+    backlog.push(payload.clone());
+    assert!(backlog.is_full());
+    assert_eq!(backlog.clear(), 2);
+    assert!(backlog.is_empty() && !backlog.is_full());
+    assert_eq!(UploadBacklog::new(0).max_bytes(), 1);
+}
+
+#[test]
+fn upload_backlog_tests_env_configures_bound() {
+    let _env = common::env_lock();
+    let name = "LOCAL_GUARD_UPLOAD_BACKLOG_BYTES";
+    let read = |value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - The variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+        let max_bytes = upload_backlog_from_env().max_bytes();
+        unsafe { std::env::remove_var(name) };
+        max_bytes
+    };
+
+    assert_eq!(read(None), DEFAULT_UPLOAD_BACKLOG_BYTES);
+    assert_eq!(read(Some(" 1048576 ")), 1_048_576);
+    assert_eq!(read(Some("0")), DEFAULT_UPLOAD_BACKLOG_BYTES);
+    assert_eq!(read(Some("lots")), DEFAULT_UPLOAD_BACKLOG_BYTES);
+}
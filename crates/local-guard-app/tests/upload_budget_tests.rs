@@ -0,0 +1,368 @@
+//! Integration tests for upload pacing and data budgets: token-bucket
+//! pacing on a virtual clock and its cancellation, queue-only refusal and
+//! period reset, persisted counters across restarts, exhaustion
+//! adjustments, and the runtime status projection.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::fixture_payload;
+use local_guard_app::{
+    APP_VERSION, BUDGET_REDUCED_FPS_DIVISOR, BudgetStore, batch_to_payload, budget_adjustment,
+    data_budget_from_env, data_budget_state_for_ui, project_runtime_status,
+    upload_rate_limiter_from_env,
+};
+use local_guard_capture::CaptureConfig;
+use local_guard_core::{Frame, MosaicPayload};
+use local_guard_ui::{UiBudgetAction, UiBudgetPeriod, UiDataBudgetState, UiState};
+use local_guard_upload::{
+    BudgetExhaustionPolicy, BudgetMeter, BudgetPeriod, BudgetUsage, CANCEL_POLL_INTERVAL,
+    CancellationToken, ChunkingPolicy, DEGRADED_ALLOWANCE_PERCENT, DataBudget, LocalChunkedIngest,
+    RateLimiter, RetryPolicy, UploadClient, UploadClock, UploadError, VirtualUploadClock,
+};
+
+const INGEST_HOST: &str = "ingest.local-guard.test";
+/// 2026-10-18T12:00:00Z.
+const NOON_UNIX_MS: u64 = 1_792_324_800_000;
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn client(server: &Arc<LocalChunkedIngest>) -> UploadClient {
+    UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        RetryPolicy::mvp_default(),
+        server.clone(),
+    )
+    .expect("upload client")
+}
+
+fn large_payload() -> MosaicPayload {
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|index| {
+            Frame::new(
+                "display-1",
+                64,
+                64,
+                1_000 + index,
+                vec![index as u8; 64 * 64 * 4],
+            )
+            .expect("frame")
+        })
+        .collect();
+    batch_to_payload(&frames, "session-xyz").expect("payload")
+}
+
+fn meter(budget: DataBudget, usage: BudgetUsage, clock: &Arc<VirtualUploadClock>) -> BudgetMeter {
+    BudgetMeter::new(budget, usage, clock.clone())
+}
+
+#[test]
+fn upload_budget_tests_rate_limit_paces_chunks() {
+    let clock = Arc::new(VirtualUploadClock::new());
+    let server = Arc::new(LocalChunkedIngest::new());
+    let limiter = RateLimiter::new(64 * 1024, clock.clone());
+    let uploader = client(&server)
+        .with_chunking(
+            server.clone(),
+            ChunkingPolicy::new()
+                .with_chunk_size_bytes(16 * 1024)
+                .with_min_body_bytes(0),
+        )
+        .with_rate_limit(limiter);
+    let payload = large_payload();
+    let body_len = payload.to_json_bytes().expect("canonical").len() as u64;
+
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("paced upload");
+    assert!(report.chunks > 4);
+
+    // Invariant: after the initial one-second burst, bytes go out at the
+    // configured rate.
+    let expected = Duration::from_secs_f64((body_len - 64 * 1024) as f64 / (64.0 * 1024.0));
+    let slept = clock.slept();
+    assert!(
+        slept.abs_diff(expected) < Duration::from_millis(5),
+        "slept {slept:?}, expected {expected:?}"
+    );
+    assert_eq!(server.received()[0].body.len() as u64, body_len);
+}
+
+/// Virtual clock that cancels `token` on its first sleep, standing in for a
+/// logout during the pacing wait.
+struct LogoutDuringSleep {
+    inner: VirtualUploadClock,
+    token: CancellationToken,
+}
+
+impl UploadClock for LogoutDuringSleep {
+    fn now(&self) -> Duration {
+        self.inner.now()
+    }
+
+    fn unix_time_ms(&self) -> u64 {
+        self.inner.unix_time_ms()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.inner.sleep(duration);
+        self.token.cancel();
+    }
+}
+
+#[test]
+fn upload_budget_tests_cancellation_interrupts_pacing() {
+    let token = CancellationToken::new();
+    let clock = Arc::new(LogoutDuringSleep {
+        inner: VirtualUploadClock::starting_at_unix_ms(NOON_UNIX_MS),
+        token: token.clone(),
+    });
+    let server = Arc::new(LocalChunkedIngest::new());
+    let meter = BudgetMeter::new(
+        DataBudget::new(BudgetExhaustionPolicy::QueueOnly).with_daily_limit_bytes(u64::MAX),
+        BudgetUsage::default(),
+        clock.clone(),
+    );
+    let uploader = client(&server)
+        .with_rate_limit(RateLimiter::new(1_024, clock.clone()))
+        .with_data_budget(meter.clone())
+        .with_cancellation(token);
+
+    assert_eq!(
+        uploader.upload_payload(&large_payload(), "token"),
+        Err(UploadError::Cancelled)
+    );
+    // Invariant: the wait ends within one poll slice, and the cancelled
+    // body is neither sent nor counted.
+    assert_eq!(clock.inner.slept(), CANCEL_POLL_INTERVAL);
+    assert!(server.received().is_empty());
+    assert_eq!(meter.usage().day_bytes, 0);
+}
+
+#[test]
+fn upload_budget_tests_queue_only_refuses_until_period_resets() {
+    let clock = Arc::new(VirtualUploadClock::starting_at_unix_ms(NOON_UNIX_MS));
+    let server = Arc::new(LocalChunkedIngest::new());
+    let payload = fixture_payload();
+    let body_len = payload.to_json_bytes().expect("canonical").len() as u64;
+    let budget =
+        DataBudget::new(BudgetExhaustionPolicy::QueueOnly).with_daily_limit_bytes(body_len + 1);
+    let uploader = client(&server).with_data_budget(meter(budget, BudgetUsage::default(), &clock));
+
+    uploader
+        .upload_payload(&payload, "token")
+        .expect("first upload fits");
+    // Failure mode: a budget is checked before each send, so the second
+    // body may overshoot it by one upload but never by more.
+    uploader
+        .upload_payload(&payload, "token")
+        .expect("second upload starts under the limit");
+    let error = uploader
+        .upload_payload(&payload, "token")
+        .expect_err("budget exhausted");
+    assert_eq!(error, UploadError::BudgetExhausted(BudgetPeriod::Daily));
+    assert_eq!(server.received().len(), 2);
+
+    let adjustment = budget_adjustment(uploader.data_budget());
+    assert!(adjustment.uploads_paused);
+    assert!(!adjustment.reduce_fps && !adjustment.lower_quality);
+
+    clock.advance(DAY);
+    uploader
+        .upload_payload(&payload, "token")
+        .expect("a new UTC day resets the daily counter");
+    let usage = uploader.data_budget().expect("meter").usage();
+    assert_eq!(usage.day, "2026-10-19");
+    assert_eq!(usage.day_bytes, body_len);
+    assert_eq!(usage.month_bytes, 3 * body_len);
+}
+
+#[test]
+fn upload_budget_tests_counters_survive_restart() {
+    let dir = std::env::temp_dir().join(format!("lg-budget-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let store = BudgetStore::in_dir(&dir);
+    assert_eq!(store.load().expect("absent file"), BudgetUsage::default());
+
+    let clock = Arc::new(VirtualUploadClock::starting_at_unix_ms(NOON_UNIX_MS));
+    let server = Arc::new(LocalChunkedIngest::new());
+    let body_len = fixture_payload().to_json_bytes().expect("canonical").len() as u64;
+    let budget =
+        DataBudget::new(BudgetExhaustionPolicy::QueueOnly).with_monthly_limit_bytes(body_len);
+    let uploader =
+        client(&server).with_data_budget(meter(budget, store.load().expect("load"), &clock));
+    uploader
+        .upload_payload(&fixture_payload(), "token")
+        .expect("upload");
+    store
+        .save(&uploader.data_budget().expect("meter").usage())
+        .expect("save");
+
+    // A restarted client resumes from the saved counters.
+    let restarted =
+        client(&server).with_data_budget(meter(budget, store.load().expect("reload"), &clock));
+    assert_eq!(
+        restarted.upload_payload(&fixture_payload(), "token"),
+        Err(UploadError::BudgetExhausted(BudgetPeriod::Monthly))
+    );
+
+    // Failure mode: a corrupt file must not read as zero usage.
+    std::fs::write(store.path(), b"{not json").expect("corrupt");
+    assert!(store.load().is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn upload_budget_tests_degrading_policies_keep_uploading() {
+    let clock = Arc::new(VirtualUploadClock::starting_at_unix_ms(NOON_UNIX_MS));
+    let server = Arc::new(LocalChunkedIngest::new());
+    let exhausted = BudgetUsage {
+        day: "2026-10-18".to_string(),
+        day_bytes: 10,
+        month: "2026-10".to_string(),
+        month_bytes: 10,
+    };
+
+    let reduce_fps = meter(
+        DataBudget::new(BudgetExhaustionPolicy::ReduceFps).with_daily_limit_bytes(10),
+        exhausted.clone(),
+        &clock,
+    );
+    let uploader = client(&server).with_data_budget(reduce_fps);
+    uploader
+        .upload_payload(&fixture_payload(), "token")
+        .expect("reduce-fps keeps uploading");
+    // Invariant: degraded uploads stop once they have used the allowance
+    // on top of the limit too.
+    assert_eq!(
+        uploader.upload_payload(&fixture_payload(), "token"),
+        Err(UploadError::BudgetExhausted(BudgetPeriod::Daily))
+    );
+    let adjustment = budget_adjustment(uploader.data_budget());
+    assert!(adjustment.reduce_fps && !adjustment.uploads_paused);
+    let config = CaptureConfig::new(2).expect("config");
+    assert_eq!(
+        adjustment.capture_config(config).interval(),
+        config.interval() * BUDGET_REDUCED_FPS_DIVISOR
+    );
+    assert_eq!(adjustment.jpeg_quality(9), 9);
+
+    let lower_quality = meter(
+        DataBudget::new(BudgetExhaustionPolicy::LowerQuality).with_daily_limit_bytes(10),
+        exhausted,
+        &clock,
+    );
+    assert_eq!(lower_quality.check_send(), Ok(()));
+    lower_quality.record(10 * DEGRADED_ALLOWANCE_PERCENT / 100);
+    assert_eq!(
+        lower_quality.check_send(),
+        Err(UploadError::BudgetExhausted(BudgetPeriod::Daily))
+    );
+    let adjustment = budget_adjustment(Some(&lower_quality));
+    assert!(adjustment.lower_quality);
+    assert_eq!(adjustment.jpeg_quality(9), 4);
+    assert_eq!(adjustment.jpeg_quality(1), 1);
+    assert_eq!(adjustment.capture_config(config), config);
+
+    assert_eq!(budget_adjustment(None), Default::default());
+}
+
+#[test]
+fn upload_budget_tests_runtime_status_projects_budget_and_rate() {
+    let clock = Arc::new(VirtualUploadClock::starting_at_unix_ms(NOON_UNIX_MS));
+    let budget = DataBudget::new(BudgetExhaustionPolicy::QueueOnly).with_daily_limit_bytes(100);
+    let meter = meter(budget, BudgetUsage::default(), &clock);
+    let mut state = UiState::new(APP_VERSION);
+
+    state.data_budget = data_budget_state_for_ui(None);
+    let status = project_runtime_status(&state);
+    assert_eq!(status.data_budget, "Unlimited");
+    assert_eq!(status.data_budget_warning, None);
+    assert_eq!(status.upload_rate_limit_bytes_per_sec, None);
+
+    meter.record(85);
+    state.data_budget = data_budget_state_for_ui(Some(&meter));
+    state.upload_rate_limit_bytes_per_sec = Some(65_536);
+    let status = project_runtime_status(&state);
+    assert_eq!(status.data_budget, "Within { used_percent: 85 }");
+    assert_eq!(
+        status.data_budget_warning.as_deref(),
+        Some("Upload data budget 85% used")
+    );
+    assert_eq!(status.upload_rate_limit_bytes_per_sec, Some(65_536));
+
+    meter.record(15);
+    state.data_budget = data_budget_state_for_ui(Some(&meter));
+    assert_eq!(
+        state.data_budget,
+        UiDataBudgetState::Exhausted {
+            period: UiBudgetPeriod::Daily,
+            action: UiBudgetAction::QueueOnly,
+        }
+    );
+    assert!(
+        project_runtime_status(&state)
+            .data_budget_warning
+            .is_some_and(|warning| warning.contains("queued"))
+    );
+}
+
+#[test]
+fn upload_budget_tests_env_configures_rate_and_budget() {
+    let _env = common::env_lock();
+    let set = |name: &str, value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+    let rate = "LOCAL_GUARD_UPLOAD_RATE_LIMIT_BPS";
+    let daily = "LOCAL_GUARD_UPLOAD_DAILY_BUDGET_BYTES";
+    let monthly = "LOCAL_GUARD_UPLOAD_MONTHLY_BUDGET_BYTES";
+    let policy = "LOCAL_GUARD_UPLOAD_BUDGET_POLICY";
+    let clock: Arc<dyn UploadClock> = Arc::new(VirtualUploadClock::new());
+
+    for name in [rate, daily, monthly, policy] {
+        set(name, None);
+    }
+    assert!(upload_rate_limiter_from_env(clock.clone()).is_none());
+    assert_eq!(data_budget_from_env(), None);
+
+    set(rate, Some(" 131072 "));
+    assert_eq!(
+        upload_rate_limiter_from_env(clock.clone()).map(|limiter| limiter.bytes_per_second()),
+        Some(131_072)
+    );
+    set(rate, Some("0"));
+    assert!(upload_rate_limiter_from_env(clock.clone()).is_none());
+
+    set(daily, Some("5000000"));
+    assert_eq!(
+        data_budget_from_env(),
+        Some(DataBudget::new(BudgetExhaustionPolicy::QueueOnly).with_daily_limit_bytes(5_000_000))
+    );
+    set(monthly, Some("100000000"));
+    set(policy, Some("Reduce-FPS"));
+    assert_eq!(
+        data_budget_from_env(),
+        Some(
+            DataBudget::new(BudgetExhaustionPolicy::ReduceFps)
+                .with_daily_limit_bytes(5_000_000)
+                .with_monthly_limit_bytes(100_000_000)
+        )
+    );
+    set(policy, Some("throttle"));
+    assert_eq!(
+        data_budget_from_env().map(|budget| budget.policy()),
+        Some(BudgetExhaustionPolicy::QueueOnly)
+    );
+
+    for name in [rate, daily, monthly, policy] {
+        set(name, None);
+    }
+}
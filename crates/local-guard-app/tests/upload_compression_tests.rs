@@ -0,0 +1,391 @@
+//! Integration tests for upload body compression: thresholds, idempotency
+//! and signature stability, encoding negotiation with older servers, and
+//! the `Content-Encoding` header on the HTTPS transport.
+
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use local_guard_app::{batch_to_payload, upload_compression_from_env};
+use local_guard_core::{Frame, MosaicPayload};
+use local_guard_crypto::{
+    BodySealer, DeviceKey, SEALED_CONTENT_TYPE, ServerSecretKey, parse_signature_header,
+    verify_payload_signature,
+};
+use local_guard_transport::{HttpsConnector, TransportSecurity};
+use local_guard_upload::{
+    CompressionPolicy, ContentEncoding, DEFAULT_COMPRESSION_THRESHOLD_BYTES, HttpResponse,
+    HttpsUploadTransport, RetryPolicy, UploadClient, UploadEnvelope, UploadError, UploadTransport,
+};
+use rcgen::{BasicConstraints, CertificateParams, CertifiedIssuer, IsCa, KeyPair};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+const INGEST_HOST: &str = "ingest.local-guard.test";
+
+/// Stand-in for an ingest server that only decodes `accepts` and answers
+/// other encodings with 415 plus `advertised` as `Accept-Encoding`.
+struct EncodingServer {
+    accepts: Vec<ContentEncoding>,
+    advertised: &'static str,
+    seen: Mutex<Vec<Option<ContentEncoding>>>,
+}
+
+impl EncodingServer {
+    fn new(accepts: Vec<ContentEncoding>, advertised: &'static str) -> Arc<Self> {
+        Arc::new(Self {
+            accepts,
+            advertised,
+            seen: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn seen(&self) -> Vec<Option<ContentEncoding>> {
+        self.seen.lock().expect("seen lock").clone()
+    }
+}
+
+impl UploadTransport for EncodingServer {
+    fn send(&self, envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        self.seen
+            .lock()
+            .expect("seen lock")
+            .push(envelope.content_encoding);
+        match envelope.content_encoding {
+            Some(encoding) if !self.accepts.contains(&encoding) => Err(
+                UploadError::UnsupportedEncoding(self.advertised.to_string()),
+            ),
+            _ => Ok(HttpResponse::new(200)),
+        }
+    }
+}
+
+fn client(transport: Arc<dyn UploadTransport>, policy: RetryPolicy) -> UploadClient {
+    UploadClient::new(
+        format!("https://{INGEST_HOST}/r1/ingest"),
+        policy,
+        transport,
+    )
+    .expect("upload client")
+}
+
+/// A payload well above the default compression threshold.
+fn large_payload() -> MosaicPayload {
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|index| {
+            Frame::new(
+                "display-1",
+                64,
+                64,
+                1_000 + index,
+                vec![index as u8; 64 * 64 * 4],
+            )
+            .expect("frame")
+        })
+        .collect();
+    batch_to_payload(&frames, "session-xyz").expect("payload")
+}
+
+fn decompress(encoding: Option<ContentEncoding>, body: &[u8]) -> Vec<u8> {
+    match encoding {
+        None => body.to_vec(),
+        Some(ContentEncoding::Gzip) => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(body)
+                .read_to_end(&mut decoded)
+                .expect("gzip body");
+            decoded
+        }
+        Some(ContentEncoding::Zstd) => zstd::decode_all(body).expect("zstd body"),
+    }
+}
+
+#[test]
+fn upload_compression_tests_compresses_large_bodies_and_keeps_key_and_signature() {
+    let payload = large_payload();
+    let canonical = payload.to_json_bytes().expect("canonical");
+    let key = Arc::new(DeviceKey::from_seed([4; 32]));
+    let plain = client(EncodingServer::new(vec![], ""), RetryPolicy::mvp_default())
+        .with_signer(key.clone())
+        .build_envelope(&payload, "token")
+        .expect("plain envelope");
+    assert_eq!(plain.content_encoding, None);
+
+    for encoding in [ContentEncoding::Gzip, ContentEncoding::Zstd] {
+        let envelope = client(EncodingServer::new(vec![], ""), RetryPolicy::mvp_default())
+            .with_signer(key.clone())
+            .with_compression(CompressionPolicy::new(encoding))
+            .build_envelope(&payload, "token")
+            .expect("compressed envelope");
+
+        assert_eq!(envelope.content_encoding, Some(encoding));
+        assert!(envelope.body.len() < canonical.len() / 4, "{encoding:?}");
+        assert_eq!(decompress(Some(encoding), &envelope.body), canonical);
+        assert_eq!(envelope.idempotency_key, plain.idempotency_key);
+        assert_eq!(envelope.signature_header, plain.signature_header);
+
+        let header = parse_signature_header(envelope.signature_header.as_deref().expect("signed"))
+            .expect("signature header");
+        verify_payload_signature(
+            &key.public_key(),
+            &decompress(Some(encoding), &envelope.body),
+            &header.signature,
+        )
+        .expect("signature covers the uncompressed body");
+    }
+}
+
+#[test]
+fn upload_compression_tests_skips_bodies_below_threshold() {
+    let payload = common::fixture_payload();
+    let size = payload.to_json_bytes().expect("canonical").len();
+    let transport = EncodingServer::new(vec![ContentEncoding::Gzip], "gzip");
+    let build = |min_size_bytes| {
+        client(transport.clone(), RetryPolicy::mvp_default())
+            .with_compression(
+                CompressionPolicy::new(ContentEncoding::Gzip).with_min_size_bytes(min_size_bytes),
+            )
+            .build_envelope(&payload, "token")
+            .expect("envelope")
+    };
+
+    let skipped = build(size + 1);
+    assert_eq!(skipped.content_encoding, None);
+    assert_eq!(skipped.body.len(), size);
+    assert_eq!(build(size).content_encoding, Some(ContentEncoding::Gzip));
+}
+
+#[test]
+fn upload_compression_tests_sealed_bodies_are_not_compressed() {
+    let payload = large_payload();
+    let sealer = BodySealer::new(ServerSecretKey::from_bytes([0x42; 32]).public_key());
+    let envelope = client(EncodingServer::new(vec![], ""), RetryPolicy::mvp_default())
+        .with_sealer(sealer)
+        .with_compression(CompressionPolicy::new(ContentEncoding::Zstd).with_min_size_bytes(0))
+        .build_envelope(&payload, "token")
+        .expect("sealed envelope");
+
+    // Invariant: neither plaintext nor ciphertext is compressed when sealing.
+    assert_eq!(envelope.content_type, SEALED_CONTENT_TYPE);
+    assert_eq!(envelope.content_encoding, None);
+}
+
+#[test]
+fn upload_compression_tests_negotiates_down_for_older_servers() {
+    let payload = large_payload();
+    let no_retries = RetryPolicy {
+        max_retries: 0,
+        ..RetryPolicy::mvp_default()
+    };
+
+    let gzip_only = EncodingServer::new(vec![ContentEncoding::Gzip], "gzip, identity");
+    let uploader = client(gzip_only.clone(), no_retries)
+        .with_compression(CompressionPolicy::new(ContentEncoding::Zstd));
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("fallback to gzip should succeed without retry budget");
+    assert_eq!(report.attempts, 2);
+    assert_eq!(uploader.content_encoding(), Some(ContentEncoding::Gzip));
+    uploader
+        .clone()
+        .upload_payload(&payload, "token")
+        .expect("second upload");
+    assert_eq!(
+        gzip_only.seen(),
+        vec![
+            Some(ContentEncoding::Zstd),
+            Some(ContentEncoding::Gzip),
+            Some(ContentEncoding::Gzip),
+        ]
+    );
+
+    let identity_only = EncodingServer::new(vec![], "");
+    let uploader = client(identity_only.clone(), no_retries)
+        .with_compression(CompressionPolicy::new(ContentEncoding::Zstd));
+    uploader
+        .upload_payload(&payload, "token")
+        .expect("fallback to identity should succeed");
+    assert_eq!(uploader.content_encoding(), None);
+    assert_eq!(
+        identity_only.seen(),
+        vec![Some(ContentEncoding::Zstd), None]
+    );
+
+    // Failure mode: a server that lists an encoding it then rejects cannot
+    // loop the client; each encoding is tried at most once.
+    let liar = EncodingServer::new(vec![], "zstd, gzip");
+    let uploader = client(liar.clone(), no_retries)
+        .with_compression(CompressionPolicy::new(ContentEncoding::Zstd));
+    uploader
+        .upload_payload(&payload, "token")
+        .expect("identity");
+    assert_eq!(
+        liar.seen(),
+        vec![
+            Some(ContentEncoding::Zstd),
+            Some(ContentEncoding::Gzip),
+            None
+        ]
+    );
+}
+
+/// Local TLS ingest server that answers zstd bodies with 415 and
+/// `Accept-Encoding: gzip`, and everything else with 202.
+fn spawn_gzip_only_ingest() -> (SocketAddr, String, Receiver<(String, Vec<u8>)>) {
+    let mut ca_params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = CertifiedIssuer::self_signed(ca_params, KeyPair::generate().expect("ca key"))
+        .expect("ca cert");
+    let leaf_key = KeyPair::generate().expect("leaf key");
+    let leaf = CertificateParams::new(vec![INGEST_HOST.to_string()])
+        .expect("leaf params")
+        .signed_by(&leaf_key, &ca)
+        .expect("leaf cert");
+    let config = Arc::new(
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("server versions")
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![leaf.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf_key.serialize_der())),
+            )
+            .expect("server cert"),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let (sender, requests) = channel();
+    std::thread::spawn(move || {
+        for tcp in listener.incoming().flatten() {
+            let connection = ServerConnection::new(Arc::clone(&config)).expect("connection");
+            let mut tls = StreamOwned::new(connection, tcp);
+            let Some((head, body)) = read_request(&mut tls) else {
+                continue;
+            };
+            let response: &[u8] = if head.contains("content-encoding: zstd") {
+                b"HTTP/1.1 415 Unsupported Media Type\r\nAccept-Encoding: gzip\r\nContent-Length: 0\r\n\r\n"
+            } else {
+                b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n"
+            };
+            let _ = sender.send((head, body));
+            let _ = tls.write_all(response);
+            tls.conn.send_close_notify();
+            let _ = tls.flush();
+        }
+    });
+    (addr, ca.pem(), requests)
+}
+
+/// Returns the lowercased head and the raw body.
+fn read_request(stream: &mut impl Read) -> Option<(String, Vec<u8>)> {
+    let mut raw = Vec::new();
+    let mut buffer = [0_u8; 4096];
+    loop {
+        if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&raw[..end]).to_ascii_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if raw.len() >= end + 4 + length {
+                return Some((head, raw[end + 4..end + 4 + length].to_vec()));
+            }
+        }
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => raw.extend_from_slice(&buffer[..read]),
+        }
+    }
+}
+
+#[test]
+fn upload_compression_tests_https_transport_sends_header_and_honors_415() {
+    let (addr, ca_pem, requests) = spawn_gzip_only_ingest();
+    let policy = TransportSecurity::new()
+        .with_ca_bundle_pem(ca_pem.as_bytes())
+        .expect("ca bundle");
+    let connector = HttpsConnector::new(&policy)
+        .expect("connector")
+        .with_timeout(Duration::from_secs(5))
+        .with_resolved_addr(INGEST_HOST, addr);
+    let uploader = client(
+        Arc::new(HttpsUploadTransport::new(connector)),
+        RetryPolicy::mvp_default(),
+    )
+    .with_compression(CompressionPolicy::new(ContentEncoding::Zstd));
+
+    let payload = large_payload();
+    let report = uploader
+        .upload_payload(&payload, "token")
+        .expect("upload should fall back to gzip");
+    assert_eq!(report.attempts, 2);
+
+    let (first, _) = requests.recv().expect("zstd request");
+    assert!(first.contains("content-encoding: zstd"));
+    let (second, body) = requests.recv().expect("gzip request");
+    assert!(second.contains("content-encoding: gzip"));
+    assert!(second.contains(&format!(
+        "idempotency-key: {}\r\n",
+        uploader.idempotency_key(&payload)
+    )));
+    assert_eq!(
+        decompress(Some(ContentEncoding::Gzip), &body),
+        payload.to_json_bytes().expect("canonical")
+    );
+}
+
+#[test]
+fn upload_compression_tests_env_selects_encoding_and_threshold() {
+    let _env = common::env_lock();
+    let set = |name: &str, value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+    let encoding = "LOCAL_GUARD_UPLOAD_COMPRESSION";
+    let threshold = "LOCAL_GUARD_UPLOAD_COMPRESSION_MIN_BYTES";
+
+    set(encoding, None);
+    set(threshold, None);
+    assert_eq!(upload_compression_from_env(), None);
+
+    set(encoding, Some(" ZSTD "));
+    assert_eq!(
+        upload_compression_from_env(),
+        Some(CompressionPolicy::new(ContentEncoding::Zstd))
+    );
+    assert_eq!(
+        upload_compression_from_env().map(|policy| policy.min_size_bytes()),
+        Some(DEFAULT_COMPRESSION_THRESHOLD_BYTES)
+    );
+
+    set(encoding, Some("gzip"));
+    set(threshold, Some("4096"));
+    assert_eq!(
+        upload_compression_from_env(),
+        Some(CompressionPolicy::new(ContentEncoding::Gzip).with_min_size_bytes(4096))
+    );
+    set(threshold, Some("lots"));
+    assert_eq!(
+        upload_compression_from_env().map(|policy| policy.min_size_bytes()),
+        Some(DEFAULT_COMPRESSION_THRESHOLD_BYTES)
+    );
+
+    for disabled in ["off", "brotli", ""] {
+        set(encoding, Some(disabled));
+        assert_eq!(upload_compression_from_env(), None, "{disabled}");
+    }
+    set(encoding, None);
+    set(threshold, None);
+}
@@ -0,0 +1,492 @@
+//! Integration tests for the concurrent upload dispatcher: worker and
+//! in-flight bounds, per-session ordering and holds after a failure,
+//! completion events, backlogged batches sent once the window frees,
+//! cancellation on logout and kill switch, and env configuration.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use local_guard_app::{
+    UploadBacklog, batch_to_payload, cancel_uploads_unless_permitted,
+    upload_dispatcher_config_from_env,
+};
+use local_guard_auth::{AuthStateMachine, SessionToken};
+use local_guard_core::{Frame, MosaicPayload};
+use local_guard_upload::{
+    DEFAULT_MAX_IN_FLIGHT, DEFAULT_UPLOAD_WORKERS, DispatchEvent, DispatchOutcome,
+    DispatcherConfig, HttpResponse, RetryPolicy, UploadClient, UploadDispatcher, UploadEnvelope,
+    UploadError, UploadOrdering, UploadTransport, idempotency_key_for_payload,
+};
+
+const ENDPOINT: &str = "https://ingest.local-guard.test/r1/ingest";
+const WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+struct ServerState {
+    open: bool,
+    fail: bool,
+    active: Vec<String>,
+    max_active: usize,
+    sessions: BTreeMap<String, String>,
+    sends: Vec<String>,
+    session_overlap: bool,
+}
+
+/// Ingest stand-in that holds every send until opened.
+#[derive(Debug, Default)]
+struct GatedServer {
+    state: Mutex<ServerState>,
+    changed: Condvar,
+}
+
+impl GatedServer {
+    fn lock(&self) -> MutexGuard<'_, ServerState> {
+        self.state.lock().expect("server lock")
+    }
+
+    fn set(&self, open: bool, fail: bool) {
+        let mut state = self.lock();
+        state.open = open;
+        state.fail = fail;
+        self.changed.notify_all();
+    }
+
+    fn wait_for_active(&self, count: usize) {
+        let state = self.lock();
+        let (state, timeout) = self
+            .changed
+            .wait_timeout_while(state, WAIT, |state| state.active.len() != count)
+            .expect("server lock");
+        assert!(
+            !timeout.timed_out(),
+            "expected {count} active sends, saw {}",
+            state.active.len()
+        );
+    }
+}
+
+impl UploadTransport for GatedServer {
+    fn send(&self, envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        let key = envelope.idempotency_key.clone();
+        let mut state = self.lock();
+        let session = state.sessions.get(&key).cloned();
+        let overlap = state
+            .active
+            .iter()
+            .any(|active| state.sessions.get(active) == session.as_ref());
+        state.session_overlap |= overlap;
+        state.active.push(key.clone());
+        state.max_active = state.max_active.max(state.active.len());
+        state.sends.push(key.clone());
+        self.changed.notify_all();
+
+        let mut state = self
+            .changed
+            .wait_while(state, |state| !state.open)
+            .expect("server lock");
+        state.active.retain(|active| *active != key);
+        self.changed.notify_all();
+        if state.fail {
+            Err(UploadError::Timeout)
+        } else {
+            Ok(HttpResponse::new(200))
+        }
+    }
+}
+
+fn payload(session: &str, sequence: u64) -> MosaicPayload {
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|index| {
+            Frame::new(
+                "display-1",
+                1,
+                1,
+                1_000 + sequence * 100 + index,
+                vec![index as u8, 0, 0, 255],
+            )
+            .expect("frame")
+        })
+        .collect();
+    batch_to_payload(&frames, session).expect("payload")
+}
+
+fn client(server: &Arc<GatedServer>) -> UploadClient {
+    UploadClient::new(
+        ENDPOINT,
+        RetryPolicy {
+            max_retries: 2,
+            base_delay_ms: 1,
+            max_delay_ms: 10,
+            jitter_ms: 0,
+        },
+        server.clone(),
+    )
+    .expect("client")
+}
+
+fn collect(events: &Receiver<DispatchEvent>, count: usize) -> Vec<DispatchEvent> {
+    (0..count)
+        .map(|_| events.recv_timeout(WAIT).expect("completion event"))
+        .collect()
+}
+
+#[test]
+fn upload_dispatcher_tests_bounds_workers_and_window() {
+    let server = Arc::new(GatedServer::default());
+    let (dispatcher, events) = UploadDispatcher::new(
+        client(&server),
+        DispatcherConfig::new()
+            .with_workers(3)
+            .with_max_in_flight(4)
+            .with_ordering(UploadOrdering::Unordered),
+    );
+
+    let tickets: Vec<_> = (0..4)
+        .map(|sequence| {
+            dispatcher
+                .try_submit(payload("session-a", sequence), "token")
+                .expect("window has room")
+        })
+        .collect();
+    assert_eq!(
+        dispatcher.try_submit(payload("session-a", 4), "token"),
+        None
+    );
+    server.wait_for_active(3);
+    assert_eq!(dispatcher.in_flight(), 4);
+
+    server.set(true, false);
+    dispatcher.wait_idle();
+    let mut completed: Vec<_> = collect(&events, 4)
+        .into_iter()
+        .map(|event| {
+            assert!(matches!(event.outcome, DispatchOutcome::Uploaded(_)));
+            event.ticket
+        })
+        .collect();
+    completed.sort();
+    assert_eq!(completed, tickets);
+    assert_eq!(server.lock().max_active, 3);
+}
+
+#[test]
+fn upload_dispatcher_tests_preserves_per_session_order() {
+    let server = Arc::new(GatedServer::default());
+    let payloads: Vec<_> = (0..4)
+        .flat_map(|sequence| {
+            [
+                payload("session-a", sequence),
+                payload("session-b", sequence),
+            ]
+        })
+        .collect();
+    {
+        let mut state = server.lock();
+        for payload in &payloads {
+            state.sessions.insert(
+                idempotency_key_for_payload(payload),
+                payload.metadata.session_id.clone(),
+            );
+        }
+    }
+    let (dispatcher, events) =
+        UploadDispatcher::new(client(&server), DispatcherConfig::new().with_workers(4));
+    for payload in payloads.clone() {
+        dispatcher.submit(payload, "token");
+    }
+
+    // Invariant: one send per session at a time, so two are active.
+    server.wait_for_active(2);
+    server.set(true, false);
+    let events = collect(&events, payloads.len());
+    let state = server.lock();
+    assert!(!state.session_overlap);
+    assert_eq!(state.max_active, 2);
+
+    for session in ["session-a", "session-b"] {
+        let expected: Vec<_> = payloads
+            .iter()
+            .filter(|payload| payload.metadata.session_id == session)
+            .map(idempotency_key_for_payload)
+            .collect();
+        let sent: Vec<_> = state
+            .sends
+            .iter()
+            .filter(|key| state.sessions.get(*key).map(String::as_str) == Some(session))
+            .cloned()
+            .collect();
+        assert_eq!(sent, expected);
+        let reported: Vec<_> = events
+            .iter()
+            .filter(|event| event.session_id == session)
+            .map(|event| event.idempotency_key.clone())
+            .collect();
+        assert_eq!(reported, expected);
+    }
+}
+
+#[test]
+fn upload_dispatcher_tests_cancel_all_stops_queued_and_in_flight() {
+    let server = Arc::new(GatedServer::default());
+    let (dispatcher, events) =
+        UploadDispatcher::new(client(&server), DispatcherConfig::new().with_workers(1));
+    for sequence in 0..3 {
+        dispatcher.submit(payload("session-a", sequence), "token");
+    }
+    server.wait_for_active(1);
+
+    assert_eq!(dispatcher.cancel_all(), 3);
+    let queued = collect(&events, 2);
+    assert!(
+        queued
+            .iter()
+            .all(|event| event.outcome == DispatchOutcome::Cancelled)
+    );
+
+    // The running send fails transiently; its retry sees the cancellation
+    // instead of reaching the server again.
+    server.set(true, true);
+    let running = collect(&events, 1);
+    assert_eq!(running[0].outcome, DispatchOutcome::Cancelled);
+    assert_eq!(server.lock().sends.len(), 1);
+
+    // Work submitted after cancellation runs normally.
+    server.set(true, false);
+    let ticket = dispatcher.submit(payload("session-a", 9), "token");
+    let event = collect(&events, 1).remove(0);
+    assert_eq!(event.ticket, ticket);
+    assert!(matches!(event.outcome, DispatchOutcome::Uploaded(_)));
+    assert_eq!(dispatcher.in_flight(), 0);
+}
+
+#[test]
+fn upload_dispatcher_tests_failed_payload_holds_its_session() {
+    let server = Arc::new(GatedServer::default());
+    let payloads: Vec<_> = (0..3)
+        .map(|sequence| payload("session-a", sequence))
+        .collect();
+    let keys: Vec<_> = payloads.iter().map(idempotency_key_for_payload).collect();
+    let (dispatcher, events) =
+        UploadDispatcher::new(client(&server), DispatcherConfig::new().with_workers(2));
+    for payload in payloads.clone() {
+        dispatcher.submit(payload, "token");
+    }
+    server.wait_for_active(1);
+
+    // The first payload exhausts its retries; the rest of the session waits.
+    server.set(true, true);
+    let failed = collect(&events, 1).remove(0);
+    assert_eq!(failed.idempotency_key, keys[0]);
+    assert_eq!(
+        failed.outcome,
+        DispatchOutcome::Failed(UploadError::Timeout)
+    );
+    assert_eq!(dispatcher.held_sessions(), vec!["session-a".to_string()]);
+    assert_eq!(dispatcher.in_flight(), 2);
+    assert!(
+        events.recv_timeout(Duration::from_millis(100)).is_err(),
+        "no payload runs ahead of the failed one"
+    );
+    assert!(server.lock().sends.iter().all(|key| *key == keys[0]));
+
+    // Other sessions are not held.
+    server.set(true, false);
+    dispatcher.submit(payload("session-b", 0), "token");
+    assert_eq!(collect(&events, 1)[0].session_id, "session-b");
+
+    let retry = dispatcher
+        .retry_held("session-a", "fresh-token")
+        .expect("session is held");
+    assert_eq!(dispatcher.retry_held("session-a", "fresh-token"), None);
+    let resent = collect(&events, 3);
+    assert_eq!(resent[0].ticket, retry);
+    assert_eq!(
+        resent
+            .iter()
+            .map(|event| event.idempotency_key.clone())
+            .collect::<Vec<_>>(),
+        keys
+    );
+    assert!(
+        resent
+            .iter()
+            .all(|event| matches!(event.outcome, DispatchOutcome::Uploaded(_)))
+    );
+
+    // Releasing drops the failed payload and lets the session continue.
+    server.set(true, true);
+    dispatcher.submit(payload("session-a", 3), "token");
+    collect(&events, 1);
+    server.set(true, false);
+    let next = dispatcher.submit(payload("session-a", 4), "token");
+    assert!(events.recv_timeout(Duration::from_millis(100)).is_err());
+    assert!(dispatcher.release_session("session-a"));
+    assert!(!dispatcher.release_session("session-a"));
+    assert_eq!(collect(&events, 1)[0].ticket, next);
+    assert!(dispatcher.held_sessions().is_empty());
+}
+
+#[test]
+fn upload_dispatcher_tests_backlog_waits_for_full_window() {
+    let server = Arc::new(GatedServer::default());
+    let (dispatcher, events) = UploadDispatcher::new(
+        client(&server),
+        DispatcherConfig::new()
+            .with_workers(1)
+            .with_max_in_flight(1),
+    );
+    let backlog = UploadBacklog::default();
+    let first = payload("session-a", 0);
+    let second = payload("session-a", 1);
+
+    backlog.push(first.clone());
+    backlog.push(second.clone());
+    assert_eq!(backlog.pump(&dispatcher, None, "token"), 1);
+    server.wait_for_active(1);
+    // Failure mode: the window is full, so the second batch stays queued
+    // instead of being dropped.
+    assert_eq!(backlog.pump(&dispatcher, None, "token"), 0);
+    assert_eq!(backlog.len(), 1);
+
+    server.set(true, false);
+    assert!(matches!(
+        collect(&events, 1)[0].outcome,
+        DispatchOutcome::Uploaded(_)
+    ));
+    assert_eq!(backlog.pump(&dispatcher, None, "token"), 1);
+    assert!(backlog.is_empty());
+    assert!(matches!(
+        collect(&events, 1)[0].outcome,
+        DispatchOutcome::Uploaded(_)
+    ));
+    assert_eq!(
+        server.lock().sends,
+        vec![
+            idempotency_key_for_payload(&first),
+            idempotency_key_for_payload(&second)
+        ]
+    );
+}
+
+#[test]
+fn upload_dispatcher_tests_logout_and_kill_switch_cancel() {
+    let _env = common::env_lock();
+    let set = |name: &str, value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+    let kill_switch = "LOCAL_GUARD_CAPTURE_ENABLED";
+    set(kill_switch, None);
+    let server = Arc::new(GatedServer::default());
+    let (dispatcher, events) =
+        UploadDispatcher::new(client(&server), DispatcherConfig::new().with_workers(1));
+    let backlog = UploadBacklog::default();
+    let mut auth = AuthStateMachine::new();
+    auth.on_login_success(SessionToken {
+        access_token: "token".to_string(),
+        session_id: "session-a".to_string(),
+        expires_at_ms: 10_000,
+    });
+
+    dispatcher.submit(payload("session-a", 0), "token");
+    dispatcher.submit(payload("session-a", 1), "token");
+    backlog.push(payload("session-a", 2));
+    server.wait_for_active(1);
+    assert_eq!(
+        cancel_uploads_unless_permitted(&dispatcher, &backlog, &auth, 5_000),
+        0
+    );
+
+    set(kill_switch, Some("off"));
+    assert_eq!(
+        cancel_uploads_unless_permitted(&dispatcher, &backlog, &auth, 5_000),
+        3
+    );
+    assert!(backlog.is_empty());
+    set(kill_switch, None);
+    assert_eq!(collect(&events, 1)[0].outcome, DispatchOutcome::Cancelled);
+
+    server.set(true, false);
+    // Failure mode: a send already on the wire completes and says so.
+    assert!(matches!(
+        collect(&events, 1)[0].outcome,
+        DispatchOutcome::Uploaded(_)
+    ));
+
+    server.set(false, true);
+    let ticket = dispatcher.submit(payload("session-a", 3), "token");
+    server.wait_for_active(1);
+    auth.logout();
+    assert_eq!(
+        cancel_uploads_unless_permitted(&dispatcher, &backlog, &auth, 5_000),
+        1
+    );
+    server.set(true, true);
+    let event = collect(&events, 1).remove(0);
+    assert_eq!(event.ticket, ticket);
+    assert_eq!(event.outcome, DispatchOutcome::Cancelled);
+}
+
+#[test]
+fn upload_dispatcher_tests_env_configures_dispatcher() {
+    let _env = common::env_lock();
+    let set = |name: &str, value: Option<&str>| {
+        // Safety:
+        // - Tests that touch the env hold `common::env_lock()`, so no other
+        //   thread in this binary changes it concurrently.
+        // - Every variable is removed before returning.
+        match value {
+            Some(value) => unsafe { std::env::set_var(name, value) },
+            None => unsafe { std::env::remove_var(name) },
+        }
+    };
+    let workers = "LOCAL_GUARD_UPLOAD_WORKERS";
+    let window = "LOCAL_GUARD_UPLOAD_MAX_IN_FLIGHT";
+    let ordering = "LOCAL_GUARD_UPLOAD_ORDERING";
+    for name in [workers, window, ordering] {
+        set(name, None);
+    }
+    let config = upload_dispatcher_config_from_env();
+    assert_eq!(config.workers(), DEFAULT_UPLOAD_WORKERS);
+    assert_eq!(config.max_in_flight(), DEFAULT_MAX_IN_FLIGHT);
+    assert_eq!(config.ordering(), UploadOrdering::PerSession);
+
+    set(workers, Some(" 6 "));
+    set(window, Some("24"));
+    set(ordering, Some("NONE"));
+    assert_eq!(
+        upload_dispatcher_config_from_env(),
+        DispatcherConfig::new()
+            .with_workers(6)
+            .with_max_in_flight(24)
+            .with_ordering(UploadOrdering::Unordered)
+    );
+
+    set(workers, Some("0"));
+    set(window, Some("-3"));
+    set(ordering, Some("strict"));
+    let config = upload_dispatcher_config_from_env();
+    assert_eq!(config.workers(), DEFAULT_UPLOAD_WORKERS);
+    assert_eq!(config.max_in_flight(), DEFAULT_MAX_IN_FLIGHT);
+    assert_eq!(config.ordering(), UploadOrdering::PerSession);
+
+    set(window, Some("0"));
+    assert_eq!(
+        upload_dispatcher_config_from_env().max_in_flight(),
+        DEFAULT_MAX_IN_FLIGHT
+    );
+
+    for name in [workers, window, ordering] {
+        set(name, None);
+    }
+}
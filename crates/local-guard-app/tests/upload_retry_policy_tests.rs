@@ -4,7 +4,9 @@ mod common;
 
 use std::sync::{Arc, Mutex};
 
-use local_guard_upload::{RetryPolicy, UploadClient, UploadEnvelope, UploadError, UploadTransport};
+use local_guard_upload::{
+    HttpResponse, RetryPolicy, UploadClient, UploadEnvelope, UploadError, UploadTransport,
+};
 
 #[derive(Debug)]
 struct FlakyTransport {
@@ -12,13 +14,13 @@ struct FlakyTransport {
 }
 
 impl UploadTransport for FlakyTransport {
-    fn send(&self, _envelope: &UploadEnvelope) -> Result<(), UploadError> {
+    fn send(&self, _envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
         let mut attempts = self.attempts.lock().expect("attempt lock should work");
         *attempts += 1;
         if *attempts < 3 {
             Err(UploadError::Timeout)
         } else {
-            Ok(())
+            Ok(HttpResponse::new(200))
         }
     }
 }
@@ -30,7 +32,7 @@ fn upload_retry_policy_tests_recovers_from_transient_failures() {
         attempts: Mutex::new(0),
     });
     let client = UploadClient::new(
-        "https://api.example.test/ingest",
+        "https://ingest.local-guard.test/r1/ingest",
         RetryPolicy {
             max_retries: 3,
             base_delay_ms: 1,
@@ -0,0 +1,470 @@
+//! # Module: enrollment
+//!
+//! ## Purpose
+//! Registers the device public key with the backend once, using a one-time
+//! enrollment code, and tracks whether the device may capture.
+//!
+//! ## Responsibilities
+//! - Build proof-of-possession enrollment requests ([`EnrollmentClient`]).
+//! - Persist the server-issued [`DeviceIdentity`] ([`DeviceIdentityStore`]).
+//! - Gate capture on enrollment status ([`EnrollmentState`]).
+//! - Provide an in-process stand-in backend ([`LocalEnrollmentService`]).
+//!
+//! ## Invariants
+//! - Capture is allowed only in [`EnrollmentState::Enrolled`].
+//! - A revoked device never returns to enrolled without a new enrollment;
+//!   revocation is persisted with the identity so it survives restarts.
+//! - Enrollment codes are single use in the stand-in service.
+//!
+//! ## Error model
+//! Endpoint policy, transport, rejection, and storage failures return
+//! [`AuthError`].
+//!
+//! ## Security and privacy notes
+//! The enrollment code is sent once and never stored. The request carries a
+//! signature over the code and public key, so an intercepted code cannot
+//! enroll a different key.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use local_guard_crypto::{DeviceKey, DevicePublicKey, PayloadSignature, verify_enrollment_proof};
+use local_guard_transport::EndpointAllowlist;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{AuthError, check_endpoint_allowed};
+
+/// Required enrollment path suffix for v1.
+pub const REQUIRED_ENROLLMENT_PATH: &str = "/r1/device-enroll";
+
+/// File name used for the device identity inside the runtime directory.
+pub const DEVICE_IDENTITY_FILE_NAME: &str = "device-identity.json";
+
+/// Enrollment request forwarded to the enrollment transport.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrollmentRequest {
+    /// One-time enrollment code issued by an administrator.
+    pub enrollment_code: String,
+    /// Hex-encoded Ed25519 device public key.
+    pub public_key: String,
+    /// Device key id derived from the public key.
+    pub key_id: String,
+    /// Hex-encoded proof-of-possession signature over code and key.
+    pub proof: String,
+}
+
+/// Server-side device status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceStatus {
+    /// Device may capture and upload.
+    #[default]
+    Active,
+    /// Device was revoked by an administrator.
+    Revoked,
+}
+
+/// Enrollment response returned by the enrollment transport.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrollmentResponse {
+    /// Server-issued device identifier.
+    pub device_id: String,
+    /// Status of the newly enrolled device.
+    pub status: DeviceStatus,
+}
+
+/// Persisted identity of an enrolled device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    /// Server-issued device identifier.
+    pub device_id: String,
+    /// Key id of the registered device key.
+    pub key_id: String,
+    /// Epoch milliseconds when enrollment completed.
+    pub enrolled_at_ms: u64,
+    /// Last status reported by the server.
+    ///
+    /// Identities written before this field existed load as active.
+    #[serde(default)]
+    pub status: DeviceStatus,
+}
+
+/// Device enrollment state used by the capture gate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnrollmentState {
+    /// No device identity exists.
+    Unenrolled,
+    /// Device is enrolled and active.
+    Enrolled(DeviceIdentity),
+    /// Device was revoked; capture stays blocked.
+    Revoked(DeviceIdentity),
+}
+
+impl EnrollmentState {
+    /// Returns the state for a stored identity (`None` means unenrolled).
+    pub fn from_identity(identity: Option<DeviceIdentity>) -> Self {
+        match identity {
+            None => Self::Unenrolled,
+            Some(identity) if identity.status == DeviceStatus::Revoked => Self::Revoked(identity),
+            Some(identity) => Self::Enrolled(identity),
+        }
+    }
+
+    /// Returns the device identity, when one was issued.
+    pub fn identity(&self) -> Option<&DeviceIdentity> {
+        match self {
+            Self::Unenrolled => None,
+            Self::Enrolled(identity) | Self::Revoked(identity) => Some(identity),
+        }
+    }
+
+    /// Returns the device id, when one was issued.
+    pub fn device_id(&self) -> Option<&str> {
+        self.identity().map(|identity| identity.device_id.as_str())
+    }
+
+    /// Applies a server status refresh.
+    ///
+    /// # Returns
+    /// `true` when the device was newly revoked and the identity should be
+    /// saved again so the revocation survives a restart.
+    pub fn on_status(&mut self, status: DeviceStatus) -> bool {
+        match (&*self, status) {
+            (Self::Enrolled(identity), DeviceStatus::Revoked) => {
+                *self = Self::Revoked(DeviceIdentity {
+                    status: DeviceStatus::Revoked,
+                    ..identity.clone()
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` when the device may capture.
+    pub fn allows_capture(&self) -> bool {
+        matches!(self, Self::Enrolled(_))
+    }
+}
+
+/// Abstract transport used by the enrollment client.
+pub trait EnrollmentTransport: Send + Sync {
+    /// Registers a device key.
+    fn enroll(
+        &self,
+        endpoint: &str,
+        request: &EnrollmentRequest,
+    ) -> Result<EnrollmentResponse, AuthError>;
+
+    /// Returns the current status of `device_id`.
+    fn device_status(&self, endpoint: &str, device_id: &str) -> Result<DeviceStatus, AuthError>;
+}
+
+/// Enrollment client that validates endpoint policy and runs enrollment.
+#[derive(Clone)]
+pub struct EnrollmentClient {
+    endpoint: String,
+    transport: std::sync::Arc<dyn EnrollmentTransport>,
+}
+
+impl EnrollmentClient {
+    /// Creates a validated enrollment client whose endpoint must pass the
+    /// compiled-in allowlist ([`EndpointAllowlist::builtin`]).
+    ///
+    /// # Errors
+    /// Returns [`AuthError::InvalidEndpoint`] when the URL is not HTTPS or
+    /// does not end with [`REQUIRED_ENROLLMENT_PATH`].
+    /// Returns [`AuthError::EndpointNotAllowed`] for endpoints outside the
+    /// allowlist.
+    pub fn new(
+        endpoint: impl Into<String>,
+        transport: std::sync::Arc<dyn EnrollmentTransport>,
+    ) -> Result<Self, AuthError> {
+        Self::new_with_allowlist(endpoint, transport, &EndpointAllowlist::builtin())
+    }
+
+    /// Creates a validated enrollment client whose endpoint must pass
+    /// `allowlist`.
+    ///
+    /// # Errors
+    /// Same as [`EnrollmentClient::new`].
+    pub fn new_with_allowlist(
+        endpoint: impl Into<String>,
+        transport: std::sync::Arc<dyn EnrollmentTransport>,
+        allowlist: &EndpointAllowlist,
+    ) -> Result<Self, AuthError> {
+        let endpoint = endpoint.into();
+        validate_enrollment_endpoint(&endpoint)?;
+        check_endpoint_allowed(allowlist, &endpoint)?;
+        Ok(Self {
+            endpoint,
+            transport,
+        })
+    }
+
+    /// Enrolls `key` with a one-time `enrollment_code`.
+    ///
+    /// # Errors
+    /// Returns [`AuthError::EmptyCredential`] for a blank code,
+    /// [`AuthError::DeviceRevoked`] when the server reports the device
+    /// revoked, [`AuthError::InvalidResponse`] for a blank device id, and
+    /// propagates transport errors.
+    pub fn enroll(
+        &self,
+        enrollment_code: &str,
+        key: &DeviceKey,
+        now_ms: u64,
+    ) -> Result<DeviceIdentity, AuthError> {
+        let enrollment_code = enrollment_code.trim();
+        if enrollment_code.is_empty() {
+            return Err(AuthError::EmptyCredential);
+        }
+
+        let public_key = key.public_key();
+        let response = self.transport.enroll(
+            &self.endpoint,
+            &EnrollmentRequest {
+                enrollment_code: enrollment_code.to_string(),
+                public_key: public_key.to_hex(),
+                key_id: public_key.key_id(),
+                proof: key.sign_enrollment_proof(enrollment_code).to_hex(),
+            },
+        )?;
+
+        if response.device_id.trim().is_empty() {
+            return Err(AuthError::InvalidResponse(
+                "enrollment response missing device id".to_string(),
+            ));
+        }
+        if response.status == DeviceStatus::Revoked {
+            return Err(AuthError::DeviceRevoked);
+        }
+
+        Ok(DeviceIdentity {
+            device_id: response.device_id,
+            key_id: public_key.key_id(),
+            enrolled_at_ms: now_ms,
+            status: DeviceStatus::Active,
+        })
+    }
+
+    /// Fetches the server status of `identity`.
+    ///
+    /// # Errors
+    /// Propagates transport errors.
+    pub fn refresh_status(&self, identity: &DeviceIdentity) -> Result<DeviceStatus, AuthError> {
+        self.transport
+            .device_status(&self.endpoint, &identity.device_id)
+    }
+
+    /// Returns configured enrollment endpoint.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+/// Validates v1 enrollment endpoint constraints.
+///
+/// # Errors
+/// Returns [`AuthError::InvalidEndpoint`] for non-HTTPS or path mismatch.
+pub fn validate_enrollment_endpoint(endpoint: &str) -> Result<(), AuthError> {
+    let parsed = Url::parse(endpoint)
+        .map_err(|error| AuthError::InvalidEndpoint(format!("invalid enrollment url: {error}")))?;
+
+    if parsed.scheme() != "https" {
+        return Err(AuthError::InvalidEndpoint(
+            "enrollment endpoint must use https".to_string(),
+        ));
+    }
+
+    if !parsed.path().ends_with(REQUIRED_ENROLLMENT_PATH) {
+        return Err(AuthError::InvalidEndpoint(format!(
+            "enrollment endpoint path must end with {REQUIRED_ENROLLMENT_PATH}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// File-backed storage for the enrolled device identity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceIdentityStore {
+    path: PathBuf,
+}
+
+impl DeviceIdentityStore {
+    /// Creates a store backed by the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a store for [`DEVICE_IDENTITY_FILE_NAME`] inside `dir`.
+    pub fn in_dir(dir: impl AsRef<Path>) -> Self {
+        Self::new(dir.as_ref().join(DEVICE_IDENTITY_FILE_NAME))
+    }
+
+    /// Returns the backing file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the stored identity; `Ok(None)` when the device is unenrolled.
+    ///
+    /// # Errors
+    /// Returns [`AuthError::Storage`] when the file is unreadable or corrupt.
+    pub fn load(&self) -> Result<Option<DeviceIdentity>, AuthError> {
+        let raw = match std::fs::read(&self.path) {
+            Ok(raw) => raw,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(self.io_error("read", error)),
+        };
+        serde_json::from_slice(&raw).map(Some).map_err(|error| {
+            AuthError::Storage(format!("failed to decode {}: {error}", self.path.display()))
+        })
+    }
+
+    /// Atomically writes `identity`.
+    ///
+    /// # Errors
+    /// Returns [`AuthError::Storage`] when I/O fails.
+    pub fn save(&self, identity: &DeviceIdentity) -> Result<(), AuthError> {
+        let encoded = serde_json::to_vec_pretty(identity)
+            .map_err(|error| AuthError::Storage(format!("failed to encode identity: {error}")))?;
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|error| self.io_error("create dir for", error))?;
+        }
+        let mut temp_name = self.path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+        let mut file =
+            std::fs::File::create(&temp_path).map_err(|error| self.io_error("write", error))?;
+        file.write_all(&encoded)
+            .and_then(|()| file.sync_all())
+            .map_err(|error| self.io_error("write", error))?;
+        drop(file);
+        std::fs::rename(&temp_path, &self.path).map_err(|error| self.io_error("replace", error))
+    }
+
+    /// Removes the stored identity (for example after revocation).
+    ///
+    /// # Errors
+    /// Returns [`AuthError::Storage`] when the file exists but cannot be
+    /// removed.
+    pub fn clear(&self) -> Result<(), AuthError> {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(self.io_error("remove", error)),
+        }
+    }
+
+    fn io_error(&self, action: &str, error: std::io::Error) -> AuthError {
+        AuthError::Storage(format!(
+            "failed to {action} {}: {error}",
+            self.path.display()
+        ))
+    }
+}
+
+#[derive(Debug, Default)]
+struct LocalEnrollmentState {
+    open_codes: BTreeSet<String>,
+    devices: BTreeMap<String, (DevicePublicKey, DeviceStatus)>,
+    next_device: u64,
+}
+
+/// In-process stand-in for the backend enrollment endpoint.
+///
+/// Issues single-use codes, verifies proof of possession, hands out
+/// sequential device ids, and supports revocation. Used by tests and by the
+/// desktop app's mock backend.
+#[derive(Debug, Default)]
+pub struct LocalEnrollmentService {
+    state: Mutex<LocalEnrollmentState>,
+}
+
+impl LocalEnrollmentService {
+    /// Creates a service with no open codes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `code` redeemable once.
+    pub fn issue_code(&self, code: impl Into<String>) {
+        self.lock().open_codes.insert(code.into());
+    }
+
+    /// Marks `device_id` revoked; returns `false` for unknown devices.
+    pub fn revoke(&self, device_id: &str) -> bool {
+        match self.lock().devices.get_mut(device_id) {
+            Some((_, status)) => {
+                *status = DeviceStatus::Revoked;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the public key registered for `device_id`.
+    pub fn registered_key(&self, device_id: &str) -> Option<DevicePublicKey> {
+        self.lock().devices.get(device_id).map(|(key, _)| *key)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LocalEnrollmentState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl EnrollmentTransport for LocalEnrollmentService {
+    fn enroll(
+        &self,
+        _endpoint: &str,
+        request: &EnrollmentRequest,
+    ) -> Result<EnrollmentResponse, AuthError> {
+        let public_key = DevicePublicKey::from_hex(&request.public_key)
+            .map_err(|error| AuthError::EnrollmentRejected(error.to_string()))?;
+        let proof = PayloadSignature::from_hex(&request.proof)
+            .map_err(|error| AuthError::EnrollmentRejected(error.to_string()))?;
+
+        let mut state = self.lock();
+        if !state.open_codes.contains(&request.enrollment_code) {
+            return Err(AuthError::EnrollmentRejected(
+                "enrollment code is unknown or already used".to_string(),
+            ));
+        }
+        verify_enrollment_proof(&public_key, &request.enrollment_code, &proof)
+            .map_err(|_| AuthError::EnrollmentRejected("proof of possession failed".to_string()))?;
+
+        // Invariant:
+        // - The code is consumed only after the proof verified, so a forged
+        //   request cannot burn a legitimate user's code.
+        state.open_codes.remove(&request.enrollment_code);
+        state.next_device = state.next_device.saturating_add(1);
+        let device_id = format!("device-{:06}", state.next_device);
+        state
+            .devices
+            .insert(device_id.clone(), (public_key, DeviceStatus::Active));
+        Ok(EnrollmentResponse {
+            device_id,
+            status: DeviceStatus::Active,
+        })
+    }
+
+    fn device_status(&self, _endpoint: &str, device_id: &str) -> Result<DeviceStatus, AuthError> {
+        self.lock()
+            .devices
+            .get(device_id)
+            .map(|(_, status)| *status)
+            .ok_or_else(|| AuthError::InvalidResponse(format!("unknown device {device_id}")))
+    }
+}
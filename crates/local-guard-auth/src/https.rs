@@ -0,0 +1,76 @@
+//! # Module: https
+//!
+//! ## Purpose
+//! Sends login requests over HTTPS under the shared transport security
+//! policy.
+//!
+//! ## Responsibilities
+//! - Encode [`LoginRequest`] as JSON and decode [`LoginResponse`].
+//! - Map HTTP status codes and transport failures onto [`AuthError`].
+//!
+//! ## Invariants
+//! - Credentials are only written after the TLS handshake has verified the
+//!   server (enforced by [`HttpsConnector`]).
+//!
+//! ## Error model
+//! Trust failures return [`AuthError::TransportSecurity`]; other network
+//! failures and non-2xx statuses return [`AuthError::Transport`]; malformed
+//! bodies return [`AuthError::InvalidResponse`].
+//!
+//! ## Security and privacy notes
+//! Response bodies are never copied into errors, since they may echo
+//! credentials or tokens.
+
+use local_guard_transport::{HttpRequest, HttpsConnector, TransportError};
+
+use crate::{AuthError, AuthTransport, LoginRequest, LoginResponse};
+
+/// [`AuthTransport`] backed by an [`HttpsConnector`].
+#[derive(Debug, Clone)]
+pub struct HttpsAuthTransport {
+    connector: HttpsConnector,
+}
+
+impl HttpsAuthTransport {
+    /// Creates a transport sending through `connector`.
+    pub fn new(connector: HttpsConnector) -> Self {
+        Self { connector }
+    }
+}
+
+impl AuthTransport for HttpsAuthTransport {
+    fn authenticate(
+        &self,
+        endpoint: &str,
+        request: &LoginRequest,
+    ) -> Result<LoginResponse, AuthError> {
+        let body = serde_json::to_vec(request)
+            .map_err(|error| AuthError::Transport(format!("request encoding failed: {error}")))?;
+        let response = self
+            .connector
+            .send(
+                &HttpRequest::post(endpoint, body)
+                    .with_header("Content-Type", "application/json")
+                    .with_header("Accept", "application/json"),
+            )
+            .map_err(AuthError::from)?;
+
+        if !(200..=299).contains(&response.status) {
+            return Err(AuthError::Transport(format!(
+                "auth endpoint returned status {}",
+                response.status
+            )));
+        }
+        serde_json::from_slice(&response.body)
+            .map_err(|error| AuthError::InvalidResponse(format!("malformed login body: {error}")))
+    }
+}
+
+impl From<TransportError> for AuthError {
+    fn from(error: TransportError) -> Self {
+        match error {
+            TransportError::Security(error) => AuthError::TransportSecurity(error.to_string()),
+            other => AuthError::Transport(other.to_string()),
+        }
+    }
+}
@@ -6,9 +6,14 @@
 //! `local-guard`.
 //!
 //! ## Responsibilities
-//! - Validate auth endpoint policy (`/r1/cstore-auth`, HTTPS).
-//! - Execute login requests through an injectable transport abstraction.
+//! - Validate auth endpoint policy (`/r1/cstore-auth`, HTTPS, and the
+//!   endpoint host allowlist).
+//! - Execute login requests through an injectable transport abstraction,
+//!   with an HTTPS implementation under the shared transport security
+//!   policy ([`HttpsAuthTransport`]).
 //! - Model safe session transitions used to gate capture.
+//! - Enroll the device key and track device status ([`EnrollmentClient`],
+//!   [`EnrollmentState`]).
 //!
 //! ## Data flow
 //! UI collects credentials -> [`AuthClient::login`] sends request through
@@ -25,6 +30,8 @@
 //!
 //! ## Security and privacy notes
 //! This crate does not log credentials or token values.
+//! Auth and enrollment endpoints outside the compiled-in allowlist are
+//! refused when clients are built, so credentials cannot be redirected.
 //! Callers are expected to keep credential inputs ephemeral.
 //!
 //! ## Example
@@ -37,10 +44,21 @@
 
 use std::sync::Arc;
 
+use local_guard_transport::EndpointAllowlist;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use url::Url;
 
+mod enrollment;
+mod https;
+
+pub use enrollment::{
+    DEVICE_IDENTITY_FILE_NAME, DeviceIdentity, DeviceIdentityStore, DeviceStatus, EnrollmentClient,
+    EnrollmentRequest, EnrollmentResponse, EnrollmentState, EnrollmentTransport,
+    LocalEnrollmentService, REQUIRED_ENROLLMENT_PATH, validate_enrollment_endpoint,
+};
+pub use https::HttpsAuthTransport;
+
 /// Required auth path suffix for v1.
 pub const REQUIRED_AUTH_PATH: &str = "/r1/cstore-auth";
 
@@ -60,6 +78,9 @@ pub struct LoginRequest {
     pub username: String,
     /// Password/secret for auth verification.
     pub password: String,
+    /// Enrolled device identifier, when the device is enrolled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
 }
 
 /// Login response payload returned by auth transport.
@@ -170,26 +191,50 @@ pub trait AuthTransport: Send + Sync {
 pub struct AuthClient {
     endpoint: String,
     transport: Arc<dyn AuthTransport>,
+    device_id: Option<String>,
 }
 
 impl AuthClient {
-    /// Creates a validated auth client.
+    /// Creates a validated auth client whose endpoint must pass the
+    /// compiled-in allowlist ([`EndpointAllowlist::builtin`]).
     ///
     /// # Errors
     /// Returns [`AuthError::InvalidEndpoint`] when URL is not HTTPS or does not
     /// include required `/r1/cstore-auth` path.
+    /// Returns [`AuthError::EndpointNotAllowed`] for hosts or ports outside
+    /// the allowlist, IP literals, and userinfo.
     pub fn new(
         endpoint: impl Into<String>,
         transport: Arc<dyn AuthTransport>,
+    ) -> Result<Self, AuthError> {
+        Self::new_with_allowlist(endpoint, transport, &EndpointAllowlist::builtin())
+    }
+
+    /// Creates a validated auth client whose endpoint must pass `allowlist`.
+    ///
+    /// # Errors
+    /// Same as [`AuthClient::new`].
+    pub fn new_with_allowlist(
+        endpoint: impl Into<String>,
+        transport: Arc<dyn AuthTransport>,
+        allowlist: &EndpointAllowlist,
     ) -> Result<Self, AuthError> {
         let endpoint = endpoint.into();
         validate_auth_endpoint(&endpoint)?;
+        check_endpoint_allowed(allowlist, &endpoint)?;
         Ok(Self {
             endpoint,
             transport,
+            device_id: None,
         })
     }
 
+    /// Sends `device_id` with every login request.
+    pub fn with_device_id(mut self, device_id: impl Into<String>) -> Self {
+        self.device_id = Some(device_id.into());
+        self
+    }
+
     /// Executes login and converts server response into a session token.
     ///
     /// # Errors
@@ -205,6 +250,7 @@ impl AuthClient {
             &LoginRequest {
                 username: credentials.username.clone(),
                 password: credentials.password.clone(),
+                device_id: self.device_id.clone(),
             },
         )?;
 
@@ -252,21 +298,48 @@ pub fn validate_auth_endpoint(endpoint: &str) -> Result<(), AuthError> {
     Ok(())
 }
 
+/// Checks `endpoint` against `allowlist`, mapping rejections to
+/// [`AuthError::EndpointNotAllowed`].
+pub(crate) fn check_endpoint_allowed(
+    allowlist: &EndpointAllowlist,
+    endpoint: &str,
+) -> Result<(), AuthError> {
+    allowlist
+        .check(endpoint)
+        .map_err(|error| AuthError::EndpointNotAllowed(error.to_string()))
+}
+
 /// Errors produced by auth client/state logic.
 #[derive(Debug, Error)]
 pub enum AuthError {
     /// Endpoint violates security or contract requirements.
     #[error("invalid endpoint: {0}")]
     InvalidEndpoint(String),
+    /// Endpoint host, port, or form is outside the endpoint allowlist.
+    #[error("endpoint not allowed: {0}")]
+    EndpointNotAllowed(String),
     /// Credentials are missing/blank.
     #[error("username and password must be non-empty")]
     EmptyCredential,
     /// Transport failure from auth backend.
     #[error("auth transport failure: {0}")]
     Transport(String),
+    /// Server failed the transport security policy (certificate, pin, or
+    /// TLS version).
+    #[error("auth transport security failure: {0}")]
+    TransportSecurity(String),
     /// Response payload violated auth contract expectations.
     #[error("invalid auth response: {0}")]
     InvalidResponse(String),
+    /// Backend refused the enrollment code or proof.
+    #[error("enrollment rejected: {0}")]
+    EnrollmentRejected(String),
+    /// Device identity was revoked by the backend.
+    #[error("device has been revoked")]
+    DeviceRevoked,
+    /// Local identity file could not be read or written.
+    #[error("device identity storage failure: {0}")]
+    Storage(String),
 }
 
 #[cfg(test)]
@@ -0,0 +1,214 @@
+//! Benchmark-style check of steady-state large allocations with a frame
+//! pool, for the synthetic backend and (on Windows) the real capture path.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use local_guard_capture::{CaptureBackend, DisplayInfo, SyntheticCaptureBackend};
+use local_guard_core::{Frame, FramePool};
+use local_guard_mosaic::{MosaicOptions, compose_temporal_mosaic_with};
+
+/// Allocations at or above this size count as pixel-buffer allocations.
+const LARGE_ALLOCATION_BYTES: usize = 64 * 1024;
+
+thread_local! {
+    static TRACKING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static LARGE_ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+struct CountingAllocator;
+
+// Safety:
+// - Delegates every call to the system allocator; counting only touches
+//   const-initialized thread-locals, which never allocate.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count(size: usize) {
+    if !TRACKING.with(Cell::get) {
+        return;
+    }
+    ALLOCATIONS.with(|counter| counter.set(counter.get() + 1));
+    if size >= LARGE_ALLOCATION_BYTES {
+        LARGE_ALLOCATIONS.with(|counter| counter.set(counter.get() + 1));
+    }
+}
+
+/// Runs `work` and returns (all, large) allocations it made on this thread.
+fn allocations_during(work: impl FnOnce()) -> (u64, u64) {
+    ALLOCATIONS.with(|counter| counter.set(0));
+    LARGE_ALLOCATIONS.with(|counter| counter.set(0));
+    TRACKING.with(|tracking| tracking.set(true));
+    work();
+    TRACKING.with(|tracking| tracking.set(false));
+    (
+        ALLOCATIONS.with(Cell::get),
+        LARGE_ALLOCATIONS.with(Cell::get),
+    )
+}
+
+/// Runs `batches` capture+mosaic cycles and returns large allocations made.
+fn large_allocations_for(batches: usize, pool: Option<FramePool>) -> u64 {
+    let display = DisplayInfo {
+        id: "display-1".to_string(),
+        name: "Bench Display".to_string(),
+        width: 256,
+        height: 256,
+    };
+    let mut backend = SyntheticCaptureBackend::with_displays(vec![display]);
+    if let Some(pool) = &pool {
+        backend = backend.with_pool(pool.clone());
+    }
+    let options = MosaicOptions {
+        pool: pool.clone(),
+        ..MosaicOptions::default()
+    };
+
+    let run_batch = |batch_index: usize| {
+        let frames: Vec<Frame> = (0..9)
+            .map(|tick| {
+                backend
+                    .capture_frame("display-1", (batch_index * 9 + tick) as u64)
+                    .expect("synthetic capture should work")
+            })
+            .collect();
+        let mosaic =
+            compose_temporal_mosaic_with(&frames, &options).expect("mosaic should compose");
+        if let Some(pool) = &pool {
+            pool.release_shared(mosaic.pixels);
+        }
+    };
+
+    // Warm-up fills the pool; only steady state is measured.
+    run_batch(0);
+    allocations_during(|| {
+        for batch_index in 1..=batches {
+            run_batch(batch_index);
+        }
+    })
+    .1
+}
+
+#[test]
+fn frame_pool_allocations_steady_state_is_allocation_free() {
+    let unpooled = large_allocations_for(20, None);
+    let pool = FramePool::default();
+    let pooled = large_allocations_for(20, Some(pool.clone()));
+
+    println!("benchmark_large_allocations_unpooled={unpooled}");
+    println!("benchmark_large_allocations_pooled={pooled}");
+    println!("benchmark_pool_stats={:?}", pool.stats());
+
+    // 9 frames + 1 mosaic per batch without a pool.
+    assert_eq!(unpooled, 20 * 10);
+    assert_eq!(pooled, 0);
+    assert!(pool.stats().reused >= 20 * 10);
+}
+
+#[test]
+fn frame_pool_allocations_dropping_pooled_frame_does_not_allocate() {
+    let pool = FramePool::new(4);
+    let pooled_frame = || {
+        let mut buffer = pool.acquire(256 * 256 * 4);
+        buffer.resize(256 * 256 * 4, 1);
+        Frame::new("display-1", 256, 256, 1, buffer)
+            .expect("frame should be valid")
+            .with_pool(&pool)
+    };
+    // Warm-up sizes the pool's idle list; only the steady-state drop counts.
+    drop(pooled_frame());
+    let frame = pooled_frame();
+
+    let (allocations, _) = allocations_during(|| drop(frame));
+    assert_eq!(allocations, 0);
+    assert_eq!(pool.available(), 1);
+}
+
+#[test]
+fn frame_pool_allocations_idle_bytes_stay_under_cap() {
+    let frame_bytes = 256 * 256 * 4;
+    let pool = FramePool::default().with_max_bytes(3 * frame_bytes);
+    let backend = SyntheticCaptureBackend::with_displays(vec![DisplayInfo {
+        id: "display-1".to_string(),
+        name: "Bench Display".to_string(),
+        width: 256,
+        height: 256,
+    }])
+    .with_pool(pool.clone());
+
+    let frames: Vec<Frame> = (0..9)
+        .map(|tick| {
+            backend
+                .capture_frame("display-1", tick)
+                .expect("synthetic capture should work")
+        })
+        .collect();
+    drop(frames);
+
+    assert_eq!(pool.available(), 3);
+    assert!(pool.idle_bytes() <= pool.max_bytes());
+    assert_eq!(pool.stats().discarded, 6);
+}
+
+/// Real capture path: OS-allocated frame buffers are not pooled, so the
+/// pool only recycles mosaics and stays within its byte cap.
+#[cfg(windows)]
+#[test]
+fn frame_pool_allocations_real_capture_keeps_pool_to_mosaics() {
+    use local_guard_capture::RealCaptureBackend;
+
+    // Failure mode: headless runners have no display; nothing to measure.
+    let Ok(backend) = RealCaptureBackend::discover() else {
+        return;
+    };
+    let display = backend.list_displays().remove(0);
+    let pool = FramePool::default();
+    let options = MosaicOptions {
+        pool: Some(pool.clone()),
+        ..MosaicOptions::default()
+    };
+    let run_batch = |batch_index: u64| {
+        let frames: Vec<Frame> = (0..9)
+            .map(|tick| {
+                backend
+                    .capture_frame(&display.id, batch_index * 9 + tick)
+                    .expect("real capture should work")
+            })
+            .collect();
+        let mosaic =
+            compose_temporal_mosaic_with(&frames, &options).expect("mosaic should compose");
+        pool.release_shared(mosaic.pixels);
+    };
+
+    run_batch(0);
+    for batch_index in 1..=5 {
+        run_batch(batch_index);
+    }
+    println!("benchmark_real_capture_pool_stats={:?}", pool.stats());
+
+    assert!(pool.available() <= 1, "only the mosaic buffer is pooled");
+    assert!(pool.idle_bytes() <= pool.max_bytes());
+    assert_eq!(pool.stats().reused, 5);
+}
@@ -31,10 +31,18 @@ fn benchmark_pipeline_smoke_prints_latency() {
                 source_height: 64,
                 session_id: "bench-session".to_string(),
                 frame_count: 9,
+                batch_id: None,
+                sequence_number: None,
+                prev_payload_hash: None,
+                device_id: None,
+                blank_tiles: Vec::new(),
+                tile_cursors: Vec::new(),
+                context: None,
+                provenance: None,
             },
             mosaic_width: mosaic.width,
             mosaic_height: mosaic.height,
-            mosaic_rgba: mosaic.rgba,
+            mosaic_rgba: mosaic.pixels,
         };
         key_lengths += idempotency_key_for_payload(&payload).len();
     }
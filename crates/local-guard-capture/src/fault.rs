@@ -0,0 +1,295 @@
+//! # Module: fault
+//!
+//! ## Purpose
+//! Wraps any [`CaptureBackend`] with a seeded, configurable fault plan so the
+//! pipeline's failure paths (batching, worker error events, status
+//! projection) can be exercised deterministically in tests.
+//!
+//! ## Responsibilities
+//! - Inject latency with optional seeded jitter.
+//! - Inject intermittent [`CaptureError::Backend`] failures at a fixed rate.
+//! - Simulate a display disappearing after N delivered frames.
+//! - Simulate a resolution change after N delivered frames.
+//! - Simulate a poisoned backend lock that fails every later capture.
+//!
+//! ## Invariants
+//! - The same [`FaultPlan`] (including `seed`) and the same call sequence
+//!   always produce the same faults.
+//! - With [`FaultPlan::default`] the decorator is transparent.
+//! - Lock poisoning is sticky: once triggered, every later capture fails.
+//!
+//! ## Error model
+//! Injected failures use the same [`CaptureError`] variants real backends
+//! return; errors from the wrapped backend pass through unchanged.
+//!
+//! ## Security and privacy notes
+//! Test-only decorator, compiled only with the `fault-injection` feature.
+//! It never persists frame bytes and only reshapes pixels in memory when
+//! simulating geometry changes.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use local_guard_core::{CursorState, Frame};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::{CaptureBackend, CaptureError, DisplayInfo};
+
+/// Simulated resolution change applied to frames after a threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeometryChange {
+    /// Delivered frames before the new geometry takes effect.
+    pub after_frames: u64,
+    /// New frame width in pixels.
+    pub width: u32,
+    /// New frame height in pixels.
+    pub height: u32,
+}
+
+/// Seeded description of which faults to inject.
+///
+/// # Semantics
+/// Frame thresholds count frames successfully delivered by the decorator
+/// (across all displays), not capture calls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultPlan {
+    /// Seed for jitter and error-rate draws.
+    pub seed: u64,
+    /// Fixed latency added before every capture.
+    pub latency: Duration,
+    /// Upper bound of extra uniform random latency per capture.
+    pub latency_jitter: Duration,
+    /// Probability in `[0, 1]` that a capture fails with
+    /// [`CaptureError::Backend`].
+    pub backend_error_rate: f64,
+    /// Report [`CaptureError::UnknownDisplay`] once this many frames were
+    /// delivered.
+    pub unknown_display_after: Option<u64>,
+    /// Change frame geometry once the threshold is reached.
+    pub geometry_change: Option<GeometryChange>,
+    /// Poison the backend lock once this many frames were delivered.
+    pub poison_lock_after: Option<u64>,
+}
+
+impl Default for FaultPlan {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            latency: Duration::ZERO,
+            latency_jitter: Duration::ZERO,
+            backend_error_rate: 0.0,
+            unknown_display_after: None,
+            geometry_change: None,
+            poison_lock_after: None,
+        }
+    }
+}
+
+/// Counters describing injected faults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FaultStats {
+    /// Capture calls received.
+    pub calls: u64,
+    /// Frames returned to the caller.
+    pub frames_delivered: u64,
+    /// Injected intermittent backend failures.
+    pub backend_errors: u64,
+    /// Injected unknown-display failures.
+    pub unknown_display_errors: u64,
+    /// Frames returned with changed geometry.
+    pub geometry_changes: u64,
+    /// Captures rejected because the simulated lock was poisoned.
+    pub poisoned_errors: u64,
+    /// Total injected latency.
+    pub injected_latency: Duration,
+}
+
+#[derive(Debug)]
+struct FaultState {
+    rng: StdRng,
+    poisoned: bool,
+    stats: FaultStats,
+}
+
+/// Capture backend decorator that injects faults from a [`FaultPlan`].
+///
+/// # Concurrency
+/// Fault decisions are made under one internal lock, so concurrent callers
+/// are serialized and the fault sequence stays deterministic per call order.
+#[derive(Debug)]
+pub struct FaultyCaptureBackend<B: CaptureBackend> {
+    inner: B,
+    plan: FaultPlan,
+    state: Mutex<FaultState>,
+}
+
+impl<B: CaptureBackend> FaultyCaptureBackend<B> {
+    /// Wraps `inner` with the given fault plan.
+    pub fn new(inner: B, plan: FaultPlan) -> Self {
+        let rng = StdRng::seed_from_u64(plan.seed);
+        Self {
+            inner,
+            plan,
+            state: Mutex::new(FaultState {
+                rng,
+                poisoned: false,
+                stats: FaultStats::default(),
+            }),
+        }
+    }
+
+    /// Returns the configured fault plan.
+    pub fn plan(&self) -> &FaultPlan {
+        &self.plan
+    }
+
+    /// Returns the wrapped backend.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Returns a snapshot of injected fault counters.
+    pub fn stats(&self) -> FaultStats {
+        self.state
+            .lock()
+            .map_or_else(|poisoned| poisoned.into_inner().stats, |state| state.stats)
+    }
+}
+
+impl<B: CaptureBackend> CaptureBackend for FaultyCaptureBackend<B> {
+    fn backend_name(&self) -> &str {
+        self.inner.backend_name()
+    }
+
+    fn list_displays(&self) -> Vec<DisplayInfo> {
+        // Why:
+        // - Real backends snapshot display metadata at discovery, so a
+        //   removed or resized display keeps its stale entry here too.
+        self.inner.list_displays()
+    }
+
+    fn cursor_state(&self, display_id: &str) -> Option<CursorState> {
+        self.inner.cursor_state(display_id)
+    }
+
+    fn capture_frame(&self, display_id: &str, captured_at_ms: u64) -> Result<Frame, CaptureError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| CaptureError::Backend("fault state lock poisoned".to_string()))?;
+        state.stats.calls = state.stats.calls.saturating_add(1);
+        let delivered = state.stats.frames_delivered;
+
+        if state.poisoned
+            || self
+                .plan
+                .poison_lock_after
+                .is_some_and(|after| delivered >= after)
+        {
+            state.poisoned = true;
+            state.stats.poisoned_errors = state.stats.poisoned_errors.saturating_add(1);
+            return Err(CaptureError::Backend(
+                "screen handle lock poisoned (injected)".to_string(),
+            ));
+        }
+
+        if self
+            .plan
+            .unknown_display_after
+            .is_some_and(|after| delivered >= after)
+        {
+            state.stats.unknown_display_errors =
+                state.stats.unknown_display_errors.saturating_add(1);
+            return Err(CaptureError::UnknownDisplay(display_id.to_string()));
+        }
+
+        let jitter_nanos = self.plan.latency_jitter.as_nanos().min(u64::MAX as u128) as u64;
+        let jitter = if jitter_nanos == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(state.rng.random_range(0..=jitter_nanos))
+        };
+        let latency = self.plan.latency.saturating_add(jitter);
+        if !latency.is_zero() {
+            state.stats.injected_latency = state.stats.injected_latency.saturating_add(latency);
+            std::thread::sleep(latency);
+        }
+
+        // Invariant:
+        // - Rates outside `[0, 1]` (or NaN) are clamped so the draw never panics.
+        let error_rate = if self.plan.backend_error_rate.is_nan() {
+            0.0
+        } else {
+            self.plan.backend_error_rate.clamp(0.0, 1.0)
+        };
+        if error_rate > 0.0 && state.rng.random::<f64>() < error_rate {
+            state.stats.backend_errors = state.stats.backend_errors.saturating_add(1);
+            return Err(CaptureError::Backend(
+                "injected transient capture failure".to_string(),
+            ));
+        }
+
+        let mut frame = self.inner.capture_frame(display_id, captured_at_ms)?;
+        if let Some(change) = self
+            .plan
+            .geometry_change
+            .filter(|change| delivered >= change.after_frames)
+        {
+            frame = resize_nearest(&frame, change.width, change.height)?;
+            state.stats.geometry_changes = state.stats.geometry_changes.saturating_add(1);
+        }
+
+        state.stats.frames_delivered = delivered.saturating_add(1);
+        Ok(frame)
+    }
+}
+
+fn resize_nearest(frame: &Frame, width: u32, height: u32) -> Result<Frame, CaptureError> {
+    let (src_width, src_height) = (frame.width as usize, frame.height as usize);
+    let (dst_width, dst_height) = (width as usize, height as usize);
+    let bytes_per_pixel = frame.format.bytes_per_pixel();
+    let mut pixels = vec![0; dst_width * dst_height * bytes_per_pixel];
+
+    if src_width > 0 && src_height > 0 && dst_width > 0 {
+        for (y, out_row) in pixels
+            .chunks_exact_mut(dst_width * bytes_per_pixel)
+            .enumerate()
+        {
+            let Some(src_row) = frame.row((y * src_height / dst_height) as u32) else {
+                continue;
+            };
+            for (x, out) in out_row.chunks_exact_mut(bytes_per_pixel).enumerate() {
+                let offset = (x * src_width / dst_width) * bytes_per_pixel;
+                out.copy_from_slice(&src_row[offset..offset + bytes_per_pixel]);
+            }
+        }
+    }
+
+    let resized = Frame::with_format(
+        frame.screen_id.clone(),
+        width,
+        height,
+        frame.captured_at_ms,
+        frame.format,
+        dst_width * bytes_per_pixel,
+        pixels,
+    )
+    .map_err(|error| CaptureError::Backend(error.to_string()))?;
+
+    // Why:
+    // - A real resolution change moves the hotspot with the content, so the
+    //   cursor is scaled the same way as the pixels.
+    Ok(match frame.cursor {
+        Some(cursor) => resized.with_cursor(CursorState {
+            x: scale_coordinate(cursor.x, src_width, dst_width),
+            y: scale_coordinate(cursor.y, src_height, dst_height),
+            visible: cursor.visible,
+        }),
+        None => resized,
+    })
+}
+
+fn scale_coordinate(value: i32, src: usize, dst: usize) -> i32 {
+    (i64::from(value) * dst as i64 / src.max(1) as i64) as i32
+}
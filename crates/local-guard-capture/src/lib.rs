@@ -8,7 +8,16 @@
 //! - Define a backend-agnostic capture trait.
 //! - Expose real display capture on supported platforms.
 //! - Expose deterministic synthetic capture for CI and unit tests.
+//! - Support single-display and all-displays capture modes.
 //! - Provide FPS scheduling helpers used by the app orchestrator.
+//! - Drive drift-free capture cadence with missed-tick accounting
+//!   ([`CaptureScheduler`]).
+//! - Inject seeded capture faults for resilience tests
+//!   (`FaultyCaptureBackend`, behind the `fault-injection` feature).
+//! - Optionally retry captures that come back blank
+//!   ([`capture_frame_with_blank_retry`]).
+//! - Optionally report cursor position and visibility per frame
+//!   ([`CaptureBackend::cursor_state`]).
 //!
 //! ## Data flow
 //! App selects a display -> backend captures [`local_guard_core::Frame`] at
@@ -26,10 +35,27 @@
 //! Capture backends must avoid persisting raw frame bytes to disk for MVP.
 
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use local_guard_core::Frame;
+use local_guard_core::{
+    CursorState, Frame, FramePool, FrameQuality, QualityThresholds, classify_frame,
+};
 use thiserror::Error;
 
+#[cfg(feature = "fault-injection")]
+mod fault;
+mod scheduler;
+
+#[cfg(feature = "fault-injection")]
+pub use fault::{FaultPlan, FaultStats, FaultyCaptureBackend, GeometryChange};
+pub use scheduler::{
+    CaptureScheduler, DEFAULT_LATE_TOLERANCE, MissedTickPolicy, MonotonicClock, ScheduledTick,
+    SchedulerStats, SystemMonotonicClock, VirtualClock,
+};
+
+/// Highest supported capture rate; keeps the interval at or above 1 ms.
+pub const MAX_CAPTURE_FPS: f64 = 1_000.0;
+
 /// Metadata describing one available display.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DisplayInfo {
@@ -44,32 +70,97 @@ pub struct DisplayInfo {
 }
 
 /// Capture configuration used by schedulers.
+///
+/// # Invariants
+/// - The frame interval is stored with nanosecond precision, so fractional
+///   rates (for example one frame every 5 s) and non-divisor rates (3 FPS)
+///   are represented exactly enough for drift-free scheduling.
+/// - The interval is always within `[1 ms, u64::MAX ns]`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CaptureConfig {
-    /// Frames captured per second.
-    pub fps: u32,
+    interval: Duration,
 }
 
 impl CaptureConfig {
-    /// Creates validated capture configuration.
+    /// Creates validated capture configuration from an integer frame rate.
     ///
     /// # Errors
-    /// Returns [`CaptureError::InvalidFps`] when `fps == 0`.
+    /// Returns [`CaptureError::InvalidFps`] when `fps == 0` or
+    /// `fps > MAX_CAPTURE_FPS`.
     pub fn new(fps: u32) -> Result<Self, CaptureError> {
-        if fps == 0 {
+        Self::from_fps(fps as f64)
+    }
+
+    /// Creates validated capture configuration from a fractional frame rate.
+    ///
+    /// # Parameters
+    /// - `fps`: frames per second; `0.2` means one frame every 5 s.
+    ///
+    /// # Errors
+    /// Returns [`CaptureError::InvalidFps`] when `fps` is not finite, not
+    /// positive, above [`MAX_CAPTURE_FPS`], or so small that the interval
+    /// overflows.
+    pub fn from_fps(fps: f64) -> Result<Self, CaptureError> {
+        if !fps.is_finite() || fps <= 0.0 || fps > MAX_CAPTURE_FPS {
+            return Err(CaptureError::InvalidFps);
+        }
+
+        let interval_nanos = (1_000_000_000.0 / fps).round();
+        if interval_nanos >= u64::MAX as f64 {
             return Err(CaptureError::InvalidFps);
         }
-        Ok(Self { fps })
+        Ok(Self {
+            interval: Duration::from_nanos(interval_nanos as u64),
+        })
     }
 
-    /// Returns capture interval in milliseconds.
+    /// Creates validated capture configuration from a frame interval.
+    ///
+    /// # Errors
+    /// Returns [`CaptureError::InvalidFps`] when `interval` is shorter than
+    /// 1 ms (the [`MAX_CAPTURE_FPS`] limit).
+    pub fn from_interval(interval: Duration) -> Result<Self, CaptureError> {
+        if interval < Duration::from_millis(1) || interval.as_nanos() > u64::MAX as u128 {
+            return Err(CaptureError::InvalidFps);
+        }
+        Ok(Self { interval })
+    }
+
+    /// Returns the exact frame interval.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns capture interval in whole milliseconds (never zero).
     pub fn interval_ms(&self) -> u64 {
-        1_000 / self.fps as u64
+        (self.interval.as_millis() as u64).max(1)
+    }
+
+    /// Returns the effective frame rate.
+    ///
+    /// Replaces the former public `fps` field; a whole rate passed to
+    /// [`Self::new`] is returned unchanged.
+    pub fn fps(&self) -> f64 {
+        1.0 / self.interval.as_secs_f64()
+    }
+}
+
+impl Default for CaptureConfig {
+    /// One frame per second.
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+        }
     }
 }
 
 /// Trait implemented by concrete capture providers.
 pub trait CaptureBackend: Send + Sync {
+    /// Short backend name reported in payload provenance.
+    fn backend_name(&self) -> &str {
+        "custom"
+    }
+
     /// Enumerates available displays.
     fn list_displays(&self) -> Vec<DisplayInfo>;
 
@@ -78,6 +169,32 @@ pub trait CaptureBackend: Send + Sync {
     /// # Errors
     /// Returns [`CaptureError::UnknownDisplay`] when display id is invalid.
     fn capture_frame(&self, display_id: &str, captured_at_ms: u64) -> Result<Frame, CaptureError>;
+
+    /// Reports the current cursor in `display_id` frame coordinates.
+    ///
+    /// # Semantics
+    /// Backends that support cursor reporting also attach this state to
+    /// frames returned by [`CaptureBackend::capture_frame`]. The default
+    /// returns `None` (cursor not reported).
+    fn cursor_state(&self, display_id: &str) -> Option<CursorState> {
+        let _ = display_id;
+        None
+    }
+}
+
+/// Which displays one capture tick should acquire.
+///
+/// # Semantics
+/// - `SelectedDisplay` captures only the operator-selected display.
+/// - `AllDisplays` captures every enumerated display per tick; each display
+///   produces its own batch/mosaic stream keyed by `screen_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureMode {
+    /// Capture one operator-selected display.
+    #[default]
+    SelectedDisplay,
+    /// Capture every display on every tick.
+    AllDisplays,
 }
 
 /// Real display capture backend for supported desktop targets.
@@ -85,6 +202,10 @@ pub trait CaptureBackend: Send + Sync {
 /// # Notes
 /// The backend snapshots display metadata at initialization and reacquires
 /// current screen handles for each capture call.
+///
+/// Frames own the buffer the OS capture call allocated and are not pooled:
+/// that allocation happens regardless, and frame-sized buffers are never a
+/// fit for mosaic output, so returning them would only grow the pool.
 #[derive(Debug)]
 pub struct RealCaptureBackend {
     displays: Vec<RealDisplayRecord>,
@@ -96,10 +217,15 @@ pub struct RealCaptureBackend {
 struct RealDisplayRecord {
     #[cfg(windows)]
     index: usize,
+    #[cfg(windows)]
+    origin: (i32, i32),
     info: DisplayInfo,
 }
 
 impl RealCaptureBackend {
+    /// Name reported by [`CaptureBackend::backend_name`].
+    pub const NAME: &'static str = "real";
+
     /// Discovers currently available displays and creates a real capture backend.
     ///
     /// # Errors
@@ -127,6 +253,8 @@ impl RealCaptureBackend {
                 displays.push(RealDisplayRecord {
                     #[cfg(windows)]
                     index,
+                    #[cfg(windows)]
+                    origin: (screen.display_info.x, screen.display_info.y),
                     info: DisplayInfo {
                         id: format!("real-display-{index}"),
                         name: format!("Display {}", index + 1),
@@ -152,6 +280,10 @@ impl RealCaptureBackend {
 }
 
 impl CaptureBackend for RealCaptureBackend {
+    fn backend_name(&self) -> &str {
+        Self::NAME
+    }
+
     fn list_displays(&self) -> Vec<DisplayInfo> {
         self.displays
             .iter()
@@ -159,6 +291,24 @@ impl CaptureBackend for RealCaptureBackend {
             .collect()
     }
 
+    fn cursor_state(&self, display_id: &str) -> Option<CursorState> {
+        let record = self
+            .displays
+            .iter()
+            .find(|record| record.info.id == display_id)?;
+
+        #[cfg(windows)]
+        {
+            windows_cursor_state(record, record.info.width, record.info.height)
+        }
+
+        #[cfg(not(windows))]
+        {
+            let _ = record;
+            None
+        }
+    }
+
     fn capture_frame(&self, display_id: &str, captured_at_ms: u64) -> Result<Frame, CaptureError> {
         let record = self
             .displays
@@ -211,8 +361,12 @@ impl CaptureBackend for RealCaptureBackend {
             let height = captured.height();
             let rgba = captured.into_raw();
 
-            Frame::new(record.info.id.clone(), width, height, captured_at_ms, rgba)
-                .map_err(|error| CaptureError::Backend(error.to_string()))
+            let mut frame = Frame::new(record.info.id.clone(), width, height, captured_at_ms, rgba)
+                .map_err(|error| CaptureError::Backend(error.to_string()))?;
+            if let Some(cursor) = windows_cursor_state(record, width, height) {
+                frame = frame.with_cursor(cursor);
+            }
+            Ok(frame)
         }
 
         #[cfg(not(windows))]
@@ -226,14 +380,102 @@ impl CaptureBackend for RealCaptureBackend {
     }
 }
 
+/// Reads the OS cursor and maps it into `record` frame coordinates.
+///
+/// # Notes
+/// Desktop coordinates are shifted by the display origin and scaled by the
+/// ratio between the captured image and the enumerated display size, which
+/// keeps the hotspot aligned when the image is captured at a different scale.
+#[cfg(windows)]
+fn windows_cursor_state(
+    record: &RealDisplayRecord,
+    frame_width: u32,
+    frame_height: u32,
+) -> Option<CursorState> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{CURSOR_SHOWING, CURSORINFO, GetCursorInfo};
+
+    // Safety:
+    // - `CURSORINFO` is plain data; `cbSize` must be set before the call and
+    //   the pointer is valid for the duration of the call.
+    let mut info: CURSORINFO = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<CURSORINFO>() as u32;
+    if unsafe { GetCursorInfo(&mut info) } == 0 {
+        return None;
+    }
+
+    let scale = |offset: i32, frame: u32, display: u32| -> i32 {
+        (i64::from(offset) * i64::from(frame) / i64::from(display.max(1))) as i32
+    };
+    Some(CursorState {
+        x: scale(
+            info.ptScreenPos.x - record.origin.0,
+            frame_width,
+            record.info.width,
+        ),
+        y: scale(
+            info.ptScreenPos.y - record.origin.1,
+            frame_height,
+            record.info.height,
+        ),
+        visible: info.flags & CURSOR_SHOWING != 0,
+    })
+}
+
+/// Deterministic cursor motion simulated by [`SyntheticCaptureBackend`].
+///
+/// # Semantics
+/// For the n-th capture (1-based, shared across displays) the hotspot sits at
+/// `((n - 1) * step_x) % width, ((n - 1) * step_y) % height`. When
+/// `hide_every` is non-zero, every `hide_every`-th capture reports a hidden
+/// cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyntheticCursor {
+    /// Horizontal pixels moved per capture.
+    pub step_x: u32,
+    /// Vertical pixels moved per capture.
+    pub step_y: u32,
+    /// Hide the cursor on every n-th capture; `0` never hides it.
+    pub hide_every: u64,
+}
+
+impl Default for SyntheticCursor {
+    fn default() -> Self {
+        Self {
+            step_x: 1,
+            step_y: 1,
+            hide_every: 0,
+        }
+    }
+}
+
+impl SyntheticCursor {
+    /// Returns the cursor for capture number `sequence` on `display`.
+    pub fn state_at(&self, sequence: u64, display: &DisplayInfo) -> CursorState {
+        let step = sequence.saturating_sub(1);
+        let along = |stride: u32, extent: u32| -> i32 {
+            (step.wrapping_mul(u64::from(stride)) % u64::from(extent.max(1))) as i32
+        };
+        CursorState {
+            x: along(self.step_x, display.width),
+            y: along(self.step_y, display.height),
+            visible: self.hide_every == 0 || !sequence.is_multiple_of(self.hide_every),
+        }
+    }
+}
+
 /// Deterministic synthetic backend for test and CI usage.
 #[derive(Debug)]
 pub struct SyntheticCaptureBackend {
     displays: Vec<DisplayInfo>,
     sequence: Mutex<u64>,
+    pool: Option<FramePool>,
+    cursor: Option<SyntheticCursor>,
 }
 
 impl SyntheticCaptureBackend {
+    /// Name reported by [`CaptureBackend::backend_name`].
+    pub const NAME: &'static str = "synthetic";
+
     /// Creates synthetic backend with one default display.
     pub fn new() -> Self {
         Self {
@@ -244,6 +486,8 @@ impl SyntheticCaptureBackend {
                 height: 4,
             }],
             sequence: Mutex::new(0),
+            pool: None,
+            cursor: None,
         }
     }
 
@@ -252,8 +496,22 @@ impl SyntheticCaptureBackend {
         Self {
             displays,
             sequence: Mutex::new(0),
+            pool: None,
+            cursor: None,
         }
     }
+
+    /// Draws frame buffers from `pool` and returns them there on drop.
+    pub fn with_pool(mut self, pool: FramePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Simulates a moving cursor attached to every captured frame.
+    pub fn with_cursor(mut self, cursor: SyntheticCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
 }
 
 impl Default for SyntheticCaptureBackend {
@@ -263,10 +521,24 @@ impl Default for SyntheticCaptureBackend {
 }
 
 impl CaptureBackend for SyntheticCaptureBackend {
+    fn backend_name(&self) -> &str {
+        Self::NAME
+    }
+
     fn list_displays(&self) -> Vec<DisplayInfo> {
         self.displays.clone()
     }
 
+    fn cursor_state(&self, display_id: &str) -> Option<CursorState> {
+        let cursor = self.cursor?;
+        let display = self
+            .displays
+            .iter()
+            .find(|display| display.id == display_id)?;
+        let sequence = *self.sequence.lock().ok()?;
+        Some(cursor.state_at(sequence.max(1), display))
+    }
+
     fn capture_frame(&self, display_id: &str, captured_at_ms: u64) -> Result<Frame, CaptureError> {
         let display = self
             .displays
@@ -282,35 +554,110 @@ impl CaptureBackend for SyntheticCaptureBackend {
 
         let byte = (*sequence % 255) as u8;
         let rgba_len = (display.width as usize) * (display.height as usize) * 4;
-        let rgba = vec![byte; rgba_len];
+        let rgba = match &self.pool {
+            Some(pool) => {
+                let mut buffer = pool.acquire(rgba_len);
+                buffer.resize(rgba_len, byte);
+                buffer
+            }
+            None => vec![byte; rgba_len],
+        };
 
-        Frame::new(
+        let mut frame = Frame::new(
             display.id.clone(),
             display.width,
             display.height,
             captured_at_ms,
             rgba,
         )
-        .map_err(|error| CaptureError::Backend(error.to_string()))
+        .map_err(|error| CaptureError::Backend(error.to_string()))?;
+        if let Some(cursor) = &self.cursor {
+            frame = frame.with_cursor(cursor.state_at(*sequence, display));
+        }
+        Ok(match &self.pool {
+            Some(pool) => frame.with_pool(pool),
+            None => frame,
+        })
+    }
+}
+
+/// Frame returned by [`capture_frame_with_blank_retry`] with its quality.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedFrame {
+    /// Last captured frame (blank when every attempt was blank).
+    pub frame: Frame,
+    /// Quality classification of `frame`.
+    pub quality: FrameQuality,
+    /// Extra capture attempts made because earlier frames were blank.
+    pub retries: u32,
+}
+
+/// Default pause before re-capturing a blank frame.
+pub const DEFAULT_BLANK_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Captures one frame and re-captures up to `max_retries` times while blank.
+///
+/// # Why
+/// Transient black frames (display wake, compositor hiccup) often clear
+/// within a few milliseconds, so each re-capture waits `retry_delay` first;
+/// persistent ones (locked session, protected content) do not clear, and are
+/// returned as-is so metadata can flag them.
+///
+/// # Timestamps
+/// The first attempt is stamped `captured_at_ms`; each retry is stamped
+/// `captured_at_ms` plus the time elapsed since the first attempt.
+///
+/// # Errors
+/// Returns the first [`CaptureError`] from the backend.
+pub fn capture_frame_with_blank_retry(
+    backend: &dyn CaptureBackend,
+    display_id: &str,
+    captured_at_ms: u64,
+    thresholds: &QualityThresholds,
+    max_retries: u32,
+    retry_delay: Duration,
+) -> Result<CheckedFrame, CaptureError> {
+    let started = Instant::now();
+    let mut attempt_at_ms = captured_at_ms;
+    let mut retries = 0;
+    loop {
+        let frame = backend.capture_frame(display_id, attempt_at_ms)?;
+        let quality = classify_frame(&frame, thresholds);
+        if !quality.is_blank() || retries >= max_retries {
+            return Ok(CheckedFrame {
+                frame,
+                quality,
+                retries,
+            });
+        }
+        retries += 1;
+        std::thread::sleep(retry_delay);
+        let elapsed_ms = started.elapsed().as_millis().min(u64::MAX as u128) as u64;
+        attempt_at_ms = captured_at_ms.saturating_add(elapsed_ms);
     }
 }
 
 /// Computes deterministic schedule timestamps for fixed-FPS capture.
 ///
 /// # Returns
-/// Vector of `count` timestamps starting at `start_ms` with `interval_ms` spacing.
+/// Vector of `count` timestamps starting at `start_ms`. Each timestamp is
+/// derived from the exact interval (`start + index * interval`), so
+/// non-divisor rates such as 3 FPS do not accumulate truncation drift.
 pub fn scheduled_capture_times(config: CaptureConfig, start_ms: u64, count: usize) -> Vec<u64> {
-    let interval = config.interval_ms();
+    let interval_nanos = config.interval().as_nanos();
     (0..count)
-        .map(|index| start_ms.saturating_add(interval.saturating_mul(index as u64)))
+        .map(|index| {
+            let offset_ms = interval_nanos.saturating_mul(index as u128) / 1_000_000;
+            start_ms.saturating_add(offset_ms.min(u64::MAX as u128) as u64)
+        })
         .collect()
 }
 
 /// Capture layer error type.
 #[derive(Debug, Error)]
 pub enum CaptureError {
-    /// FPS must be positive.
-    #[error("invalid fps: must be greater than zero")]
+    /// FPS must be finite, positive, and at most [`MAX_CAPTURE_FPS`].
+    #[error("invalid fps: must be greater than zero and at most 1000")]
     InvalidFps,
     /// Requested display is unknown to backend.
     #[error("unknown display: {0}")]
@@ -336,4 +683,49 @@ mod tests {
         assert_eq!(frame.height, 4);
         assert_eq!(frame.captured_at_ms, 42);
     }
+
+    #[test]
+    fn capture_config_keeps_whole_fps_and_interval() {
+        let config = CaptureConfig::new(5).expect("valid fps");
+        assert_eq!(config.fps(), 5.0);
+        assert_eq!(config.interval_ms(), 200);
+        assert_eq!(
+            CaptureConfig::default(),
+            CaptureConfig::new(1).expect("valid fps")
+        );
+    }
+
+    #[test]
+    fn scheduler_skip_policy_counts_missed_ticks() {
+        let clock = std::sync::Arc::new(VirtualClock::new());
+        let config = CaptureConfig::new(10).expect("valid fps");
+        let mut scheduler = CaptureScheduler::new(config, MissedTickPolicy::Skip, clock.clone());
+
+        let first = scheduler.poll().expect("first tick fires immediately");
+        assert_eq!(first.sequence, 0);
+        assert!(scheduler.poll().is_none());
+
+        // Stall for 350 ms: deadlines 1, 2, 3 pass; 1 and 2 are skipped.
+        clock.advance(Duration::from_millis(350));
+        let tick = scheduler.poll().expect("overdue tick fires");
+        assert_eq!(tick.sequence, 3);
+        assert_eq!(tick.missed_before, 2);
+        assert_eq!(tick.lateness, Duration::from_millis(50));
+        assert_eq!(scheduler.stats().ticks_missed, 2);
+        assert_eq!(scheduler.time_until_next(), Duration::from_millis(50));
+    }
+
+    #[cfg(feature = "fault-injection")]
+    #[test]
+    fn faulty_backend_with_default_plan_is_transparent() {
+        let backend =
+            FaultyCaptureBackend::new(SyntheticCaptureBackend::new(), FaultPlan::default());
+        for tick in 0..5 {
+            let frame = backend
+                .capture_frame("display-1", tick)
+                .expect("no faults should be injected");
+            assert_eq!((frame.width, frame.height), (4, 4));
+        }
+        assert_eq!(backend.stats().frames_delivered, 5);
+    }
 }
@@ -0,0 +1,310 @@
+//! # Module: scheduler
+//!
+//! ## Purpose
+//! Drives capture cadence from absolute deadlines on a monotonic clock instead
+//! of a re-armed relative timer, so the schedule never drifts.
+//!
+//! ## Responsibilities
+//! - Abstract monotonic time behind [`MonotonicClock`] so tests can use a
+//!   [`VirtualClock`].
+//! - Compute deadline `n` as `start + n * interval` (no accumulated rounding).
+//! - Detect late and missed ticks and resolve them with a [`MissedTickPolicy`].
+//! - Keep cumulative [`SchedulerStats`] for runtime diagnostics.
+//!
+//! ## Invariants
+//! - Deadlines are strictly increasing; a deadline is fired at most once.
+//! - `poll` never fires a tick before its deadline.
+//! - Every deadline that passes is either fired or counted as missed.
+//!
+//! ## Error model
+//! The scheduler itself is infallible; invalid cadence is rejected earlier by
+//! [`CaptureConfig`] constructors.
+//!
+//! ## Security and privacy notes
+//! Scheduling handles timestamps only; no frame content passes through here.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::CaptureConfig;
+
+/// Default lateness after which a fired tick is counted as late.
+pub const DEFAULT_LATE_TOLERANCE: Duration = Duration::from_millis(50);
+
+/// Source of monotonic time for the capture scheduler.
+///
+/// # Contract
+/// - `now` returns elapsed time since an arbitrary, fixed origin.
+/// - Successive calls never go backwards.
+pub trait MonotonicClock: Send + Sync {
+    /// Returns current monotonic time since the clock origin.
+    fn now(&self) -> Duration;
+}
+
+/// Production clock backed by [`std::time::Instant`].
+#[derive(Debug, Clone, Copy)]
+pub struct SystemMonotonicClock {
+    origin: Instant,
+}
+
+impl SystemMonotonicClock {
+    /// Creates a clock whose origin is the moment of construction.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemMonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonotonicClock for SystemMonotonicClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Manually advanced clock for deterministic scheduler tests.
+///
+/// # Ownership
+/// Share it as `Arc<VirtualClock>`: hand one clone to the scheduler and keep
+/// another in the test to call [`VirtualClock::advance`].
+#[derive(Debug, Default)]
+pub struct VirtualClock {
+    now: Mutex<Duration>,
+}
+
+impl VirtualClock {
+    /// Creates a virtual clock at time zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by `delta`.
+    pub fn advance(&self, delta: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = now.saturating_add(delta);
+        }
+    }
+
+    /// Sets the clock to `at` unless that would move time backwards.
+    pub fn set(&self, at: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            *now = (*now).max(at);
+        }
+    }
+}
+
+impl MonotonicClock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now.lock().map_or(Duration::ZERO, |now| *now)
+    }
+}
+
+/// How the scheduler resolves deadlines that passed while nobody polled.
+///
+/// # Semantics
+/// - `Skip`: fire once for the most recent passed deadline and count every
+///   older passed deadline as missed. Suited to live capture where stale
+///   frames have no value.
+/// - `CatchUp`: fire each passed deadline on consecutive polls, oldest first,
+///   but at most `max_burst` overdue deadlines; older ones are counted missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedTickPolicy {
+    /// Drop overdue deadlines and resume at the latest one.
+    #[default]
+    Skip,
+    /// Replay overdue deadlines, bounded by `max_burst`.
+    CatchUp {
+        /// Maximum overdue deadlines replayed after a stall.
+        max_burst: u32,
+    },
+}
+
+/// One tick released by [`CaptureScheduler::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledTick {
+    /// Zero-based deadline index since the scheduler was started.
+    pub sequence: u64,
+    /// Absolute deadline on the scheduler clock.
+    pub deadline: Duration,
+    /// Clock time at which the tick was released.
+    pub fired_at: Duration,
+    /// `fired_at - deadline`.
+    pub lateness: Duration,
+    /// Deadlines dropped immediately before this tick.
+    pub missed_before: u64,
+}
+
+/// Cumulative scheduler counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SchedulerStats {
+    /// Ticks released to the caller.
+    pub ticks_fired: u64,
+    /// Deadlines dropped by the missed-tick policy.
+    pub ticks_missed: u64,
+    /// Released ticks whose lateness exceeded the late tolerance.
+    pub ticks_late: u64,
+    /// Largest observed lateness.
+    pub max_lateness: Duration,
+}
+
+/// Drift-free capture scheduler based on absolute deadlines.
+///
+/// # Purpose
+/// Replaces relative re-armed timers (which accumulate jitter and truncate
+/// sub-millisecond intervals) with `start + n * interval` deadlines and
+/// explicit missed-tick accounting.
+///
+/// # Usage
+/// Call [`CaptureScheduler::poll`] from any periodic wake-up (UI timer,
+/// worker loop). A returned [`ScheduledTick`] means "capture now".
+/// [`CaptureScheduler::time_until_next`] tells the caller how long it may
+/// sleep.
+pub struct CaptureScheduler {
+    config: CaptureConfig,
+    policy: MissedTickPolicy,
+    clock: Arc<dyn MonotonicClock>,
+    late_tolerance: Duration,
+    start: Duration,
+    next_index: u64,
+    stats: SchedulerStats,
+}
+
+impl CaptureScheduler {
+    /// Creates a scheduler anchored at the clock's current time.
+    ///
+    /// The first deadline equals the anchor, so the first `poll` fires
+    /// immediately.
+    pub fn new(
+        config: CaptureConfig,
+        policy: MissedTickPolicy,
+        clock: Arc<dyn MonotonicClock>,
+    ) -> Self {
+        let start = clock.now();
+        Self {
+            config,
+            policy,
+            clock,
+            late_tolerance: DEFAULT_LATE_TOLERANCE,
+            start,
+            next_index: 0,
+            stats: SchedulerStats::default(),
+        }
+    }
+
+    /// Overrides the lateness threshold used for `ticks_late` accounting.
+    pub fn with_late_tolerance(mut self, late_tolerance: Duration) -> Self {
+        self.late_tolerance = late_tolerance;
+        self
+    }
+
+    /// Re-anchors the schedule at the current clock time and clears stats.
+    pub fn restart(&mut self) {
+        self.start = self.clock.now();
+        self.next_index = 0;
+        self.stats = SchedulerStats::default();
+    }
+
+    /// Releases the next due tick, if any.
+    ///
+    /// # Returns
+    /// - `None` when the next deadline is still in the future.
+    /// - `Some(tick)` when a deadline has passed; overdue deadlines are
+    ///   resolved according to the configured [`MissedTickPolicy`].
+    pub fn poll(&mut self) -> Option<ScheduledTick> {
+        let now = self.clock.now();
+        let deadline = self.deadline_for(self.next_index);
+        if now < deadline {
+            return None;
+        }
+
+        // Invariant:
+        // - `overdue` counts deadlines after `deadline` that are also <= now.
+        let interval_nanos = self.config.interval().as_nanos().max(1);
+        let overdue = ((now - deadline).as_nanos() / interval_nanos) as u64;
+
+        let missed = match self.policy {
+            MissedTickPolicy::Skip => overdue,
+            MissedTickPolicy::CatchUp { max_burst } => overdue.saturating_sub(max_burst as u64),
+        };
+        self.next_index = self.next_index.saturating_add(missed);
+
+        let sequence = self.next_index;
+        let deadline = self.deadline_for(sequence);
+        let lateness = now.saturating_sub(deadline);
+        self.next_index = self.next_index.saturating_add(1);
+
+        self.stats.ticks_fired = self.stats.ticks_fired.saturating_add(1);
+        self.stats.ticks_missed = self.stats.ticks_missed.saturating_add(missed);
+        if lateness > self.late_tolerance {
+            self.stats.ticks_late = self.stats.ticks_late.saturating_add(1);
+        }
+        self.stats.max_lateness = self.stats.max_lateness.max(lateness);
+
+        Some(ScheduledTick {
+            sequence,
+            deadline,
+            fired_at: now,
+            lateness,
+            missed_before: missed,
+        })
+    }
+
+    /// Returns time remaining until the next deadline (zero when overdue).
+    pub fn time_until_next(&self) -> Duration {
+        self.deadline_for(self.next_index)
+            .saturating_sub(self.clock.now())
+    }
+
+    /// Returns the absolute clock time of the next deadline.
+    pub fn next_deadline(&self) -> Duration {
+        self.deadline_for(self.next_index)
+    }
+
+    /// Returns cumulative counters since start or last restart.
+    pub fn stats(&self) -> SchedulerStats {
+        self.stats
+    }
+
+    /// Returns configured cadence.
+    pub fn config(&self) -> CaptureConfig {
+        self.config
+    }
+
+    /// Returns configured missed-tick policy.
+    pub fn policy(&self) -> MissedTickPolicy {
+        self.policy
+    }
+
+    fn deadline_for(&self, index: u64) -> Duration {
+        // Why:
+        // - Multiplying from the anchor (instead of adding intervals) keeps
+        //   rounding error constant instead of accumulating per tick.
+        let offset_nanos = self
+            .config
+            .interval()
+            .as_nanos()
+            .saturating_mul(index as u128)
+            .min(u64::MAX as u128) as u64;
+        self.start
+            .saturating_add(Duration::from_nanos(offset_nanos))
+    }
+}
+
+impl std::fmt::Debug for CaptureScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureScheduler")
+            .field("config", &self.config)
+            .field("policy", &self.policy)
+            .field("late_tolerance", &self.late_tolerance)
+            .field("start", &self.start)
+            .field("next_index", &self.next_index)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
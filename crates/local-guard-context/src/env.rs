@@ -0,0 +1,80 @@
+//! # Module: env
+//!
+//! ## Purpose
+//! Portable context provider reading well-known environment variables.
+//!
+//! ## Responsibilities
+//! - Locale from `LC_ALL`, `LC_MESSAGES`, then `LANG`.
+//! - Timezone from `TZ`.
+//! - OS user from `USER`, `LOGNAME`, then `USERNAME`.
+//! - Hostname from `HOSTNAME`, then `COMPUTERNAME`.
+//!
+//! ## Invariants
+//! - Empty variables are treated as unset.
+//!
+//! ## Error model
+//! Infallible; unset variables leave fields `None`.
+//!
+//! ## Security and privacy notes
+//! Only the listed variables are read; the rest of the environment (which
+//! may hold secrets) is never inspected.
+
+use std::collections::BTreeMap;
+
+use local_guard_core::BatchContext;
+
+use crate::{ContextError, ContextField, ContextPolicy, ContextProvider};
+
+/// Context provider backed by process environment variables.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EnvContextProvider {
+    overrides: Option<BTreeMap<String, String>>,
+}
+
+impl EnvContextProvider {
+    /// Creates a provider reading the process environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a provider reading only `vars` (for tests and replays).
+    pub fn with_vars(vars: BTreeMap<String, String>) -> Self {
+        Self {
+            overrides: Some(vars),
+        }
+    }
+
+    fn first_of(&self, names: &[&str]) -> Option<String> {
+        names.iter().find_map(|name| {
+            let value = match &self.overrides {
+                Some(vars) => vars.get(*name).cloned(),
+                None => std::env::var(name).ok(),
+            }?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        })
+    }
+}
+
+impl ContextProvider for EnvContextProvider {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    fn collect(&self, policy: &ContextPolicy) -> Result<BatchContext, ContextError> {
+        let mut context = BatchContext::default();
+        if policy.allows(ContextField::Locale) {
+            context.locale = self.first_of(&["LC_ALL", "LC_MESSAGES", "LANG"]);
+        }
+        if policy.allows(ContextField::Timezone) {
+            context.timezone = self.first_of(&["TZ"]);
+        }
+        if policy.allows(ContextField::OsUser) {
+            context.os_user = self.first_of(&["USER", "LOGNAME", "USERNAME"]);
+        }
+        if policy.allows(ContextField::Hostname) {
+            context.hostname = self.first_of(&["HOSTNAME", "COMPUTERNAME"]);
+        }
+        Ok(context)
+    }
+}
@@ -0,0 +1,335 @@
+#![warn(missing_docs)]
+//! # local-guard-context
+//!
+//! ## Purpose
+//! Collects host context (foreground process, hostname, OS user, locale,
+//! timezone, idle time) that enriches batch metadata for analysis.
+//!
+//! ## Responsibilities
+//! - Define the pluggable [`ContextProvider`] trait.
+//! - Gate every field behind an explicit [`ContextPolicy`].
+//! - Merge provider output into one [`BatchContext`] via [`ContextCollector`].
+//! - Ship `/proc`, environment, Windows, and synthetic providers.
+//!
+//! ## Data flow
+//! Payload assembly -> [`ContextCollector::collect`] -> providers in
+//! registration order -> policy filter -> `BatchMetadata::context`.
+//!
+//! ## Ownership and lifetimes
+//! Providers are owned by the collector as boxed trait objects; collected
+//! context is an owned value with no borrowed OS state.
+//!
+//! ## Error model
+//! A failing provider yields a [`ContextError`] in
+//! [`ContextCollection::errors`] without blocking the other providers or the
+//! batch itself.
+//!
+//! ## Security and privacy notes
+//! Collection is opt-in: the default policy enables no field, and fields not
+//! enabled are stripped even if a provider returned them. Providers never
+//! read window titles or process arguments.
+//!
+//! ## Example
+//! ```rust
+//! use local_guard_context::{ContextCollector, ContextField, ContextPolicy, SyntheticContextProvider};
+//! use local_guard_core::BatchContext;
+//!
+//! let snapshot = BatchContext {
+//!     hostname: Some("host-a".to_string()),
+//!     os_user: Some("alice".to_string()),
+//!     ..BatchContext::default()
+//! };
+//! let collector = ContextCollector::new(ContextPolicy::none().with_field(ContextField::Hostname))
+//!     .with_provider(SyntheticContextProvider::new(snapshot));
+//! let context = collector.collect().context.expect("hostname is enabled");
+//! assert_eq!(context.hostname.as_deref(), Some("host-a"));
+//! assert!(context.os_user.is_none());
+//! ```
+
+use std::collections::BTreeSet;
+
+use local_guard_core::BatchContext;
+use thiserror::Error;
+
+mod env;
+mod proc;
+mod synthetic;
+#[cfg(windows)]
+mod windows;
+
+pub use env::EnvContextProvider;
+pub use proc::ProcContextProvider;
+pub use synthetic::SyntheticContextProvider;
+#[cfg(windows)]
+pub use windows::WindowsContextProvider;
+
+/// One individually enable-able context field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContextField {
+    /// Executable name of the foreground process.
+    ForegroundProcess,
+    /// Host name.
+    Hostname,
+    /// Logged-in OS user name.
+    OsUser,
+    /// Locale identifier.
+    Locale,
+    /// Timezone identifier.
+    Timezone,
+    /// Time since last user input.
+    IdleTime,
+    /// Provider-specific extension values.
+    Extensions,
+}
+
+impl ContextField {
+    /// Every field, in canonical order.
+    pub const ALL: [ContextField; 7] = [
+        Self::ForegroundProcess,
+        Self::Hostname,
+        Self::OsUser,
+        Self::Locale,
+        Self::Timezone,
+        Self::IdleTime,
+        Self::Extensions,
+    ];
+
+    /// Returns the configuration name of this field.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::ForegroundProcess => "foreground_process",
+            Self::Hostname => "hostname",
+            Self::OsUser => "os_user",
+            Self::Locale => "locale",
+            Self::Timezone => "timezone",
+            Self::IdleTime => "idle_time",
+            Self::Extensions => "extensions",
+        }
+    }
+
+    /// Parses a configuration name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|field| field.name() == normalized)
+    }
+}
+
+/// Set of context fields allowed to leave the machine.
+///
+/// # Semantics
+/// The default policy enables nothing, which disables collection entirely.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ContextPolicy {
+    fields: BTreeSet<ContextField>,
+}
+
+impl ContextPolicy {
+    /// Returns a policy with every field disabled.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Returns a policy with every field enabled.
+    pub fn all() -> Self {
+        Self {
+            fields: ContextField::ALL.into_iter().collect(),
+        }
+    }
+
+    /// Returns this policy with `field` enabled.
+    pub fn with_field(mut self, field: ContextField) -> Self {
+        self.fields.insert(field);
+        self
+    }
+
+    /// Returns `true` when `field` may be collected.
+    pub fn allows(&self, field: ContextField) -> bool {
+        self.fields.contains(&field)
+    }
+
+    /// Returns `true` when no field is enabled.
+    pub fn is_disabled(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns enabled fields in canonical order.
+    pub fn fields(&self) -> impl Iterator<Item = ContextField> + '_ {
+        self.fields.iter().copied()
+    }
+
+    /// Parses a policy from a comma-separated field list.
+    ///
+    /// `all` enables every field; empty input or `none` disables collection.
+    ///
+    /// # Errors
+    /// Returns [`ContextError::InvalidPolicy`] for unknown field names.
+    pub fn parse(raw: &str) -> Result<Self, ContextError> {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("none") {
+            return Ok(Self::none());
+        }
+        if trimmed.eq_ignore_ascii_case("all") {
+            return Ok(Self::all());
+        }
+
+        trimmed
+            .split(',')
+            .filter(|name| !name.trim().is_empty())
+            .try_fold(Self::none(), |policy, name| {
+                ContextField::from_name(name)
+                    .map(|field| policy.with_field(field))
+                    .ok_or_else(|| ContextError::InvalidPolicy(name.trim().to_string()))
+            })
+    }
+
+    /// Returns `context` with every disabled field cleared.
+    pub fn apply(&self, mut context: BatchContext) -> BatchContext {
+        if !self.allows(ContextField::ForegroundProcess) {
+            context.foreground_process = None;
+        }
+        if !self.allows(ContextField::Hostname) {
+            context.hostname = None;
+        }
+        if !self.allows(ContextField::OsUser) {
+            context.os_user = None;
+        }
+        if !self.allows(ContextField::Locale) {
+            context.locale = None;
+        }
+        if !self.allows(ContextField::Timezone) {
+            context.timezone = None;
+        }
+        if !self.allows(ContextField::IdleTime) {
+            context.idle_ms = None;
+        }
+        if !self.allows(ContextField::Extensions) {
+            context.extensions.clear();
+        }
+        context
+    }
+}
+
+/// Source of host context values.
+///
+/// # Contract
+/// Implementations should skip work for fields `policy` does not allow and
+/// leave fields they cannot determine as `None` instead of failing.
+pub trait ContextProvider: Send + Sync {
+    /// Stable provider name used in errors and extension keys.
+    fn name(&self) -> &str;
+
+    /// Collects the fields this provider supports.
+    ///
+    /// # Errors
+    /// Returns [`ContextError::Unavailable`] when the provider cannot run at
+    /// all on this host.
+    fn collect(&self, policy: &ContextPolicy) -> Result<BatchContext, ContextError>;
+}
+
+/// Result of one [`ContextCollector::collect`] call.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ContextCollection {
+    /// Merged, policy-filtered context; `None` when nothing was collected.
+    pub context: Option<BatchContext>,
+    /// Providers that failed; their fields are simply absent.
+    pub errors: Vec<ContextError>,
+}
+
+/// Runs registered providers and merges their output under a policy.
+///
+/// # Semantics
+/// Providers run in registration order and the first provider to set a
+/// field wins.
+pub struct ContextCollector {
+    policy: ContextPolicy,
+    providers: Vec<Box<dyn ContextProvider>>,
+}
+
+impl ContextCollector {
+    /// Creates a collector without providers.
+    pub fn new(policy: ContextPolicy) -> Self {
+        Self {
+            policy,
+            providers: Vec::new(),
+        }
+    }
+
+    /// Creates a collector with the providers available on this platform.
+    ///
+    /// Windows registers [`WindowsContextProvider`] and Linux registers
+    /// [`ProcContextProvider`], each followed by [`EnvContextProvider`].
+    pub fn platform_default(policy: ContextPolicy) -> Self {
+        let collector = Self::new(policy);
+        #[cfg(windows)]
+        let collector = collector.with_provider(WindowsContextProvider::new());
+        #[cfg(target_os = "linux")]
+        let collector = collector.with_provider(ProcContextProvider::new());
+        collector.with_provider(EnvContextProvider::new())
+    }
+
+    /// Registers one more provider.
+    pub fn with_provider(mut self, provider: impl ContextProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    /// Returns the active policy.
+    pub fn policy(&self) -> &ContextPolicy {
+        &self.policy
+    }
+
+    /// Returns registered provider names in run order.
+    pub fn provider_names(&self) -> Vec<&str> {
+        self.providers
+            .iter()
+            .map(|provider| provider.name())
+            .collect()
+    }
+
+    /// Collects context from every provider.
+    pub fn collect(&self) -> ContextCollection {
+        let mut collection = ContextCollection::default();
+        if self.policy.is_disabled() {
+            return collection;
+        }
+
+        let mut merged = BatchContext::default();
+        for provider in &self.providers {
+            match provider.collect(&self.policy) {
+                Ok(context) => merged.merge_missing(context),
+                Err(error) => collection.errors.push(error),
+            }
+        }
+
+        let merged = self.policy.apply(merged);
+        collection.context = (!merged.is_empty()).then_some(merged);
+        collection
+    }
+}
+
+impl std::fmt::Debug for ContextCollector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContextCollector")
+            .field("policy", &self.policy)
+            .field("providers", &self.provider_names())
+            .finish()
+    }
+}
+
+/// Error type for context collection.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ContextError {
+    /// Policy string names an unknown field.
+    #[error("unknown context field: {0}")]
+    InvalidPolicy(String),
+    /// Provider cannot run on this host.
+    #[error("context provider {provider} unavailable: {reason}")]
+    Unavailable {
+        /// Provider name.
+        provider: String,
+        /// Human-readable cause.
+        reason: String,
+    },
+}
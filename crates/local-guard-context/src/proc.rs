@@ -0,0 +1,132 @@
+//! # Module: proc
+//!
+//! ## Purpose
+//! Linux context provider backed by the `/proc` filesystem.
+//!
+//! ## Responsibilities
+//! - Resolve the foreground process of the controlling terminal
+//!   (`tpgid` from `/proc/self/stat`, then `/proc/<tpgid>/comm`).
+//! - Read the hostname from `/proc/sys/kernel/hostname`.
+//! - Resolve the real user id from `/proc/self/status` via a passwd file.
+//!
+//! ## Invariants
+//! - Only fields allowed by the policy are read.
+//! - Missing or malformed entries leave the field `None`.
+//!
+//! ## Error model
+//! Returns [`ContextError::Unavailable`] only when the proc root itself is
+//! missing; individual lookups degrade to `None`.
+//!
+//! ## Security and privacy notes
+//! Only `comm` (executable name) is read; command lines and environments of
+//! other processes are never touched.
+
+use std::path::{Path, PathBuf};
+
+use local_guard_core::BatchContext;
+
+use crate::{ContextError, ContextField, ContextPolicy, ContextProvider};
+
+/// Context provider reading Linux `/proc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcContextProvider {
+    root: PathBuf,
+    passwd: PathBuf,
+}
+
+impl ProcContextProvider {
+    /// Creates a provider reading `/proc` and `/etc/passwd`.
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("/proc"),
+            passwd: PathBuf::from("/etc/passwd"),
+        }
+    }
+
+    /// Reads proc entries below `root` instead of `/proc`.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Resolves user ids through `passwd` instead of `/etc/passwd`.
+    pub fn with_passwd(mut self, passwd: impl Into<PathBuf>) -> Self {
+        self.passwd = passwd.into();
+        self
+    }
+
+    fn foreground_process(&self) -> Option<String> {
+        let stat = read_trimmed(&self.root.join("self/stat"))?;
+        // Why:
+        // - `comm` (field 2) may contain spaces and parentheses, so fields are
+        //   counted from the last `)`: state, ppid, pgrp, session, tty_nr,
+        //   tpgid.
+        let tpgid: i64 = stat
+            .rsplit_once(')')?
+            .1
+            .split_whitespace()
+            .nth(5)?
+            .parse()
+            .ok()?;
+        if tpgid <= 0 {
+            return None;
+        }
+        read_trimmed(&self.root.join(tpgid.to_string()).join("comm"))
+    }
+
+    fn os_user(&self) -> Option<String> {
+        let status = std::fs::read_to_string(self.root.join("self/status")).ok()?;
+        let uid = status
+            .lines()
+            .find_map(|line| line.strip_prefix("Uid:"))?
+            .split_whitespace()
+            .next()?
+            .to_string();
+        let passwd = std::fs::read_to_string(&self.passwd).unwrap_or_default();
+        let name = passwd.lines().find_map(|line| {
+            let mut parts = line.split(':');
+            let name = parts.next()?;
+            (parts.nth(1)? == uid).then(|| name.to_string())
+        });
+        Some(name.unwrap_or_else(|| format!("uid:{uid}")))
+    }
+}
+
+impl Default for ProcContextProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextProvider for ProcContextProvider {
+    fn name(&self) -> &str {
+        "proc"
+    }
+
+    fn collect(&self, policy: &ContextPolicy) -> Result<BatchContext, ContextError> {
+        if !self.root.is_dir() {
+            return Err(ContextError::Unavailable {
+                provider: self.name().to_string(),
+                reason: format!("{} is not a directory", self.root.display()),
+            });
+        }
+
+        let mut context = BatchContext::default();
+        if policy.allows(ContextField::ForegroundProcess) {
+            context.foreground_process = self.foreground_process();
+        }
+        if policy.allows(ContextField::Hostname) {
+            context.hostname = read_trimmed(&self.root.join("sys/kernel/hostname"));
+        }
+        if policy.allows(ContextField::OsUser) {
+            context.os_user = self.os_user();
+        }
+        Ok(context)
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    let value = std::fs::read_to_string(path).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -0,0 +1,71 @@
+//! # Module: synthetic
+//!
+//! ## Purpose
+//! Deterministic context provider for tests and CI.
+//!
+//! ## Responsibilities
+//! - Return a fixed [`BatchContext`] snapshot.
+//! - Optionally fail to exercise degraded collection paths.
+//!
+//! ## Invariants
+//! - Output depends only on the configured snapshot and policy.
+//!
+//! ## Error model
+//! A provider built with [`SyntheticContextProvider::failing`] always
+//! returns [`ContextError::Unavailable`].
+//!
+//! ## Security and privacy notes
+//! Holds caller-provided values only; reads nothing from the host.
+
+use local_guard_core::BatchContext;
+
+use crate::{ContextError, ContextPolicy, ContextProvider};
+
+/// Provider returning a fixed context snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntheticContextProvider {
+    name: String,
+    snapshot: BatchContext,
+    failure: Option<String>,
+}
+
+impl SyntheticContextProvider {
+    /// Creates a provider that always returns `snapshot`.
+    pub fn new(snapshot: BatchContext) -> Self {
+        Self {
+            name: "synthetic".to_string(),
+            snapshot,
+            failure: None,
+        }
+    }
+
+    /// Creates a provider that always fails with `reason`.
+    pub fn failing(reason: impl Into<String>) -> Self {
+        Self {
+            failure: Some(reason.into()),
+            ..Self::new(BatchContext::default())
+        }
+    }
+
+    /// Overrides the provider name reported in errors.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl ContextProvider for SyntheticContextProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn collect(&self, policy: &ContextPolicy) -> Result<BatchContext, ContextError> {
+        match &self.failure {
+            Some(reason) => Err(ContextError::Unavailable {
+                provider: self.name.clone(),
+                reason: reason.clone(),
+            }),
+            None => Ok(policy.apply(self.snapshot.clone())),
+        }
+    }
+}
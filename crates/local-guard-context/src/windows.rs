@@ -0,0 +1,106 @@
+//! # Module: windows
+//!
+//! ## Purpose
+//! Windows context provider for the foreground process and input idle time.
+//!
+//! ## Responsibilities
+//! - Resolve the executable name owning the foreground window.
+//! - Compute milliseconds since the last keyboard or mouse input.
+//!
+//! ## Invariants
+//! - Only fields allowed by the policy are queried.
+//! - Process handles are closed on every path.
+//!
+//! ## Error model
+//! Failed Win32 calls leave the field `None`; the provider never fails.
+//!
+//! ## Security and privacy notes
+//! Only the executable file name is reported; window titles, full paths,
+//! and command lines are never collected.
+
+use local_guard_core::BatchContext;
+use windows_sys::Win32::Foundation::CloseHandle;
+use windows_sys::Win32::System::SystemInformation::GetTickCount;
+use windows_sys::Win32::System::Threading::{
+    OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, QueryFullProcessImageNameW,
+};
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+use crate::{ContextError, ContextField, ContextPolicy, ContextProvider};
+
+/// Context provider backed by Win32 APIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WindowsContextProvider;
+
+impl WindowsContextProvider {
+    /// Creates the provider.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ContextProvider for WindowsContextProvider {
+    fn name(&self) -> &str {
+        "windows"
+    }
+
+    fn collect(&self, policy: &ContextPolicy) -> Result<BatchContext, ContextError> {
+        let mut context = BatchContext::default();
+        if policy.allows(ContextField::ForegroundProcess) {
+            context.foreground_process = foreground_process();
+        }
+        if policy.allows(ContextField::IdleTime) {
+            context.idle_ms = idle_ms();
+        }
+        Ok(context)
+    }
+}
+
+fn foreground_process() -> Option<String> {
+    // Safety:
+    // - All calls take plain values or pointers to locals that outlive the
+    //   call; the process handle is closed before returning.
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+        let mut pid = 0_u32;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        if pid == 0 {
+            return None;
+        }
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut buffer = [0_u16; 1024];
+        let mut len = buffer.len() as u32;
+        let ok =
+            QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buffer.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        if ok == 0 {
+            return None;
+        }
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        path.rsplit(['\\', '/']).next().map(str::to_string)
+    }
+}
+
+fn idle_ms() -> Option<u64> {
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    // Safety:
+    // - `info` is a valid, initialized local with `cbSize` set.
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Why:
+    // - Both values are 32-bit tick counts that wrap every ~49.7 days;
+    //   wrapping subtraction stays correct across one wrap.
+    let now = unsafe { GetTickCount() };
+    Some(u64::from(now.wrapping_sub(info.dwTime)))
+}
@@ -44,3 +44,241 @@ fn analysis_fixture_matches_schema() {
         "analysis fixture should validate against schema"
     );
 }
+
+#[test]
+fn ingest_schema_accepts_tile_cursors() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-request.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ));
+    let frame_count = fixture["metadata"]["frame_count"]
+        .as_u64()
+        .expect("fixture frame_count should be an integer");
+    let mut cursors = vec![serde_json::json!({ "x": 10, "y": -2, "visible": true })];
+    cursors.resize(frame_count as usize, Value::Null);
+    fixture["metadata"]["tile_cursors"] = Value::Array(cursors);
+    assert!(
+        validator.is_valid(&fixture),
+        "tile cursors should validate against schema"
+    );
+
+    fixture["metadata"]["tile_cursors"][0] = serde_json::json!({ "x": 1, "y": 1 });
+    assert!(
+        !validator.is_valid(&fixture),
+        "cursor entries must carry visibility"
+    );
+}
+
+#[test]
+fn ingest_schema_accepts_versioned_context() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-request.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ));
+    fixture["metadata"]["context"] = serde_json::json!({
+        "schema_version": "v1",
+        "foreground_process": "outlook.exe",
+        "idle_ms": 1500,
+        "extensions": { "synthetic.note": "x" }
+    });
+    assert!(
+        validator.is_valid(&fixture),
+        "context should validate against schema"
+    );
+
+    fixture["metadata"]["context"]["window_title"] = Value::String("secret".to_string());
+    assert!(
+        !validator.is_valid(&fixture),
+        "unknown named context fields must be rejected"
+    );
+}
+
+#[test]
+fn ingest_schema_accepts_client_provenance() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-request.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ));
+    fixture["metadata"]["provenance"] = serde_json::json!({
+        "app_version": "0.1.0",
+        "git_hash": "0123456789ab",
+        "target_triple": "x86_64-pc-windows-gnu",
+        "os_name": "windows",
+        "os_version": "10.0.22631",
+        "capture_backend": "real",
+        "encoder_settings": { "jpeg_quality": "9" }
+    });
+    assert!(
+        validator.is_valid(&fixture),
+        "provenance should validate against schema"
+    );
+
+    fixture["metadata"]["provenance"]
+        .as_object_mut()
+        .expect("provenance should be an object")
+        .remove("git_hash");
+    assert!(
+        !validator.is_valid(&fixture),
+        "provenance must carry the build git hash"
+    );
+}
+
+#[test]
+fn ingest_schema_accepts_batch_identity() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-request.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ));
+    fixture["metadata"]["batch_id"] = serde_json::json!("9b2f6c1e-4d3a-4f8b-9a1c-2e5d7f9b0c13");
+    fixture["metadata"]["sequence_number"] = serde_json::json!(1);
+    assert!(
+        validator.is_valid(&fixture),
+        "batch identity should validate against schema"
+    );
+
+    fixture["metadata"]["sequence_number"] = serde_json::json!(0);
+    assert!(!validator.is_valid(&fixture), "sequence numbers start at 1");
+
+    fixture["metadata"]["sequence_number"] = serde_json::json!(1);
+    fixture["metadata"]["batch_id"] = serde_json::json!("batch-1");
+    assert!(!validator.is_valid(&fixture), "batch_id must be a UUID v4");
+}
+
+#[test]
+fn analysis_schema_accepts_echoed_batch_id() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/analysis-response.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/analysis-response.valid.json"
+    ));
+    fixture["batch_id"] = serde_json::json!("9b2f6c1e-4d3a-4f8b-9a1c-2e5d7f9b0c13");
+    assert!(
+        validator.is_valid(&fixture),
+        "echoed batch_id should validate against schema"
+    );
+}
+
+#[test]
+fn ingest_schema_accepts_prev_payload_hash() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-request.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ));
+    fixture["metadata"]["prev_payload_hash"] = serde_json::json!("ab".repeat(32));
+    assert!(
+        validator.is_valid(&fixture),
+        "prev_payload_hash should validate against schema"
+    );
+
+    fixture["metadata"]["prev_payload_hash"] = serde_json::json!("AB".repeat(32));
+    assert!(
+        !validator.is_valid(&fixture),
+        "prev_payload_hash must be lowercase hex SHA-256"
+    );
+}
+
+#[test]
+fn ingest_schema_accepts_device_id() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-request.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ));
+    fixture["metadata"]["device_id"] = serde_json::json!("device-000001");
+    assert!(
+        validator.is_valid(&fixture),
+        "device_id should validate against schema"
+    );
+
+    fixture["metadata"]["device_id"] = serde_json::json!("");
+    assert!(!validator.is_valid(&fixture), "device_id must not be empty");
+}
+
+#[test]
+fn ingest_response_fixtures_match_schema() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-response.schema.json"
+    ));
+    for fixture in [
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../contracts/fixtures/ingest-response.valid.json"
+        ),
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../contracts/fixtures/ingest-response.duplicate.valid.json"
+        ),
+    ] {
+        assert!(
+            validator.is_valid(&load_json(fixture)),
+            "{fixture} should validate against schema"
+        );
+    }
+}
+
+#[test]
+fn ingest_response_embeds_analysis_response() {
+    let analysis = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/analysis-response.schema.json"
+    ));
+    let fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-response.valid.json"
+    ));
+    assert!(
+        analysis.is_valid(&fixture["analysis"]),
+        "embedded analysis should validate against the analysis schema"
+    );
+}
+
+#[test]
+fn ingest_response_schema_rejects_unknown_status() {
+    let validator = compile_validator(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/ingest-response.schema.json"
+    ));
+    let mut fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-response.duplicate.valid.json"
+    ));
+    fixture["status"] = serde_json::json!("rejected");
+    assert!(
+        !validator.is_valid(&fixture),
+        "status must be a known value"
+    );
+
+    fixture["status"] = serde_json::json!("accepted");
+    fixture["idempotency_key"] = serde_json::json!("not-a-key");
+    assert!(
+        !validator.is_valid(&fixture),
+        "idempotency_key must be lowercase hex SHA-256"
+    );
+}
@@ -0,0 +1,136 @@
+//! Validates the shared sealed envelope vectors and schema against the
+//! client implementation.
+
+use jsonschema::JSONSchema;
+use local_guard_crypto::{
+    BodySealer, CryptoError, SEAL_DOMAIN, SealedEnvelope, ServerSecretKey, open_sealed_envelope,
+};
+use serde_json::Value;
+
+fn load_json(path: &str) -> Value {
+    let raw = std::fs::read_to_string(path).expect("json file should be readable");
+    serde_json::from_str(&raw).expect("json file should be valid")
+}
+
+fn load_vectors() -> Value {
+    load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/sealed-envelope.vectors.json"
+    ))
+}
+
+fn compile_schema() -> JSONSchema {
+    let schema = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/sealed-envelope.schema.json"
+    ));
+    JSONSchema::compile(&schema).expect("schema should compile")
+}
+
+fn field<'a>(case: &'a Value, name: &str) -> &'a str {
+    case[name]
+        .as_str()
+        .expect("vector field should be a string")
+}
+
+fn envelope(case: &Value) -> SealedEnvelope {
+    serde_json::from_value(case["envelope"].clone()).expect("envelope should deserialize")
+}
+
+fn server_secret(vectors: &Value) -> ServerSecretKey {
+    ServerSecretKey::from_hex(field(vectors, "server_secret_key")).expect("secret should parse")
+}
+
+#[test]
+fn sealed_envelope_fixture_matches_schema() {
+    let validator = compile_schema();
+    let fixture = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/sealed-envelope.valid.json"
+    ));
+    assert!(validator.is_valid(&fixture));
+}
+
+#[test]
+fn sealed_envelope_schema_rejects_unknown_fields_and_bad_nonce() {
+    let validator = compile_schema();
+    let base = load_json(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/sealed-envelope.valid.json"
+    ));
+
+    let mut extra = base.clone();
+    extra["plaintext"] = serde_json::json!("leak");
+    assert!(!validator.is_valid(&extra));
+
+    let mut short_nonce = base.clone();
+    short_nonce["nonce"] = serde_json::json!("3333");
+    assert!(!validator.is_valid(&short_nonce));
+
+    let mut wrong_algorithm = base;
+    wrong_algorithm["algorithm"] = serde_json::json!("aes-256-gcm");
+    assert!(!validator.is_valid(&wrong_algorithm));
+}
+
+#[test]
+fn sealed_envelope_vectors_domain_and_keys_match() {
+    let vectors = load_vectors();
+    let expected_domain: String = SEAL_DOMAIN
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    assert_eq!(field(&vectors, "domain_hex"), expected_domain);
+
+    let public_key = server_secret(&vectors).public_key();
+    assert_eq!(public_key.to_hex(), field(&vectors, "server_public_key"));
+    assert_eq!(public_key.key_id(), field(&vectors, "recipient_key_id"));
+}
+
+#[test]
+fn sealed_envelope_vectors_valid_cases_reproduce_and_open() {
+    let vectors = load_vectors();
+    let secret = server_secret(&vectors);
+    let sealer = BodySealer::new(secret.public_key());
+    let validator = compile_schema();
+
+    for case in vectors["valid"].as_array().expect("valid cases") {
+        let expected = envelope(case);
+        assert!(validator.is_valid(&case["envelope"]));
+
+        let ephemeral: [u8; 32] = hex_decode(field(case, "ephemeral_secret"))
+            .try_into()
+            .expect("ephemeral secret should be 32 bytes");
+        let nonce: [u8; 12] = hex_decode(&expected.nonce)
+            .try_into()
+            .expect("nonce should be 12 bytes");
+        let plaintext = field(case, "plaintext").as_bytes();
+        let sealed = sealer
+            .seal_with(plaintext, &expected.content_type, ephemeral, nonce)
+            .expect("vector should seal");
+        assert_eq!(sealed, expected);
+
+        let opened = open_sealed_envelope(&secret, &expected).expect("vector should open");
+        assert_eq!(opened, plaintext);
+    }
+}
+
+#[test]
+fn sealed_envelope_vectors_invalid_cases_are_rejected() {
+    let vectors = load_vectors();
+    let secret = server_secret(&vectors);
+    for case in vectors["invalid"].as_array().expect("invalid cases") {
+        assert_eq!(
+            open_sealed_envelope(&secret, &envelope(case)),
+            Err(CryptoError::DecryptionFailed),
+            "{}",
+            field(case, "reason")
+        );
+    }
+}
+
+fn hex_decode(value: &str) -> Vec<u8> {
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&value[index..index + 2], 16).expect("valid hex"))
+        .collect()
+}
@@ -0,0 +1,91 @@
+//! Validates the shared payload signature vectors against the client
+//! implementation.
+
+use local_guard_crypto::{
+    CryptoError, DeviceKey, DevicePublicKey, PAYLOAD_SIGNATURE_DOMAIN, PayloadSignature,
+    PayloadSigner, format_signature_header, parse_signature_header, verify_payload_signature,
+};
+use serde_json::Value;
+
+fn load_vectors() -> Value {
+    let raw = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/payload-signature.vectors.json"
+    ))
+    .expect("vector file should be readable");
+    serde_json::from_str(&raw).expect("vector file should be valid json")
+}
+
+fn field<'a>(case: &'a Value, name: &str) -> &'a str {
+    case[name]
+        .as_str()
+        .expect("vector field should be a string")
+}
+
+#[test]
+fn signature_vectors_domain_matches() {
+    let vectors = load_vectors();
+    assert_eq!(
+        field(&vectors, "domain_hex"),
+        hex_encode(PAYLOAD_SIGNATURE_DOMAIN)
+    );
+}
+
+#[test]
+fn signature_vectors_valid_cases_reproduce_and_verify() {
+    let vectors = load_vectors();
+    let cases = vectors["valid"].as_array().expect("valid cases");
+    assert!(!cases.is_empty());
+
+    for case in cases {
+        let seed: [u8; 32] = hex_decode(field(case, "seed"))
+            .try_into()
+            .expect("seed should be 32 bytes");
+        let key = DeviceKey::from_seed(seed);
+        let payload = field(case, "payload").as_bytes();
+
+        assert_eq!(key.public_key().to_hex(), field(case, "public_key"));
+        assert_eq!(key.key_id(), field(case, "key_id"));
+        let signature = key.sign_payload(payload);
+        assert_eq!(signature.to_hex(), field(case, "signature"));
+        assert_eq!(
+            format_signature_header(&key.key_id(), &signature),
+            field(case, "header")
+        );
+
+        let header = parse_signature_header(field(case, "header")).expect("header should parse");
+        assert_eq!(header.key_id, field(case, "key_id"));
+        let public_key =
+            DevicePublicKey::from_hex(field(case, "public_key")).expect("public key should parse");
+        verify_payload_signature(&public_key, payload, &header.signature)
+            .expect("vector signature should verify");
+    }
+}
+
+#[test]
+fn signature_vectors_invalid_cases_are_rejected() {
+    let vectors = load_vectors();
+    for case in vectors["invalid"].as_array().expect("invalid cases") {
+        let public_key =
+            DevicePublicKey::from_hex(field(case, "public_key")).expect("public key should parse");
+        let signature =
+            PayloadSignature::from_hex(field(case, "signature")).expect("signature should parse");
+        assert_eq!(
+            verify_payload_signature(&public_key, field(case, "payload").as_bytes(), &signature),
+            Err(CryptoError::VerificationFailed),
+            "{}",
+            field(case, "reason")
+        );
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn hex_decode(value: &str) -> Vec<u8> {
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&value[index..index + 2], 16).expect("valid hex"))
+        .collect()
+}
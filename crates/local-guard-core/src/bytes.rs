@@ -0,0 +1,131 @@
+//! # Module: bytes
+//!
+//! ## Purpose
+//! Provides [`SharedBytes`], an immutable, reference-counted byte buffer so
+//! frames and payloads can fan out to upload, spool, preview, and metrics
+//! without copying pixel data.
+//!
+//! ## Responsibilities
+//! - Make clones O(1) by sharing one allocation.
+//! - Recover the owned `Vec<u8>` when the last reference is released, so
+//!   buffers can return to a [`crate::FramePool`].
+//! - Serialize exactly like `Vec<u8>` to keep the v1 wire contract.
+//!
+//! ## Invariants
+//! - Contents never change while more than one handle is alive; only a
+//!   sole owner being dropped may move the buffer out.
+//! - `Debug` output never includes the bytes themselves.
+//!
+//! ## Error model
+//! All operations are infallible; [`SharedBytes::try_into_vec`] returns the
+//! handle back when other references are still alive.
+//!
+//! ## Security and privacy notes
+//! Pixel bytes are kept out of `Debug` output so accidental logging of a
+//! frame or payload does not leak screen content.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Immutable, cheaply clonable byte buffer.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct SharedBytes(Arc<Vec<u8>>);
+
+impl SharedBytes {
+    /// Wraps an owned buffer without copying it.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(Arc::new(bytes))
+    }
+
+    /// Returns the bytes as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    /// Returns the number of live handles sharing this buffer.
+    pub fn ref_count(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+
+    /// Returns `true` when both handles share the same allocation.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Recovers the owned buffer when this is the only handle.
+    ///
+    /// # Errors
+    /// Returns `self` unchanged while other handles are alive.
+    pub fn try_into_vec(self) -> Result<Vec<u8>, Self> {
+        Arc::try_unwrap(self.0).map_err(Self)
+    }
+
+    /// Moves the buffer out when this is the only handle, leaving the handle
+    /// empty; used by owners that are being dropped.
+    pub(crate) fn take_unique(&mut self) -> Option<Vec<u8>> {
+        Arc::get_mut(&mut self.0).map(std::mem::take)
+    }
+
+    /// Returns the owned buffer, copying only when other handles are alive.
+    pub fn into_vec(self) -> Vec<u8> {
+        Arc::unwrap_or_clone(self.0)
+    }
+}
+
+impl From<Vec<u8>> for SharedBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl Deref for SharedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl PartialEq<Vec<u8>> for SharedBytes {
+    fn eq(&self, other: &Vec<u8>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl PartialEq<[u8]> for SharedBytes {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl std::fmt::Debug for SharedBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBytes")
+            .field("len", &self.len())
+            .field("refs", &self.ref_count())
+            .finish()
+    }
+}
+
+impl Serialize for SharedBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Why:
+        // - Matches `Vec<u8>` serialization (a sequence of integers), which the
+        //   v1 ingest contract expects for `mosaic_rgba`.
+        self.0.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(Self::new)
+    }
+}
@@ -0,0 +1,179 @@
+//! # Module: chain
+//!
+//! ## Purpose
+//! Links consecutive payloads of a session into a tamper-evident hash chain
+//! so a verifier can prove a session arrived complete and in order.
+//!
+//! ## Responsibilities
+//! - Derive the per-session chain root ([`chain_root_hash`]).
+//! - Hash one payload including its link to the predecessor
+//!   ([`payload_chain_hash`]).
+//! - Track the chain head ([`ChainState`]) and verify payload sequences
+//!   ([`verify_payload_chain`], [`verify_payload_chain_from`]).
+//!
+//! ## Invariants
+//! - Payload `n` carries `prev_payload_hash = payload_chain_hash(n - 1)`;
+//!   payload 1 carries the session root.
+//! - The hash covers schema version, full metadata (including
+//!   `prev_payload_hash`), mosaic geometry, and pixel bytes, so altering,
+//!   dropping, or reordering any payload breaks every later link.
+//!
+//! ## Error model
+//! Verification failures return [`CoreError::ChainBroken`] with the index of
+//! the first offending payload.
+//!
+//! ## Security and privacy notes
+//! Hashes are SHA-256 hex digests; they reveal nothing about pixels. The
+//! chain is tamper-evident, not authenticated: anyone can recompute it, so
+//! it proves completeness and order, not origin.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{CoreError, MosaicPayload};
+
+/// Domain separator for chain roots and payload hashes.
+const CHAIN_DOMAIN_V1: &[u8] = b"local-guard-chain-v1";
+
+/// Head of a session's payload chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainState {
+    /// Session the chain belongs to.
+    pub session_id: String,
+    /// Sequence number of the last chained payload (`0` at the root).
+    pub sequence_number: u64,
+    /// Hash the next payload must carry as `prev_payload_hash`.
+    pub head_hash: String,
+}
+
+impl ChainState {
+    /// Returns the chain state at login, before any payload.
+    pub fn root(session_id: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            sequence_number: 0,
+            head_hash: chain_root_hash(session_id),
+        }
+    }
+
+    /// Returns `true` when `payload` is the next link of this chain.
+    pub fn accepts(&self, payload: &MosaicPayload) -> bool {
+        let metadata = &payload.metadata;
+        metadata.session_id == self.session_id
+            && metadata.sequence_number == Some(self.sequence_number.saturating_add(1))
+            && metadata.prev_payload_hash.as_deref() == Some(self.head_hash.as_str())
+    }
+
+    /// Returns the state after appending `payload`.
+    ///
+    /// # Errors
+    /// Returns [`CoreError::ChainBroken`] (index `0`) when `payload` does
+    /// not link to this state, or [`CoreError::Codec`] when hashing fails.
+    pub fn advance(&self, payload: &MosaicPayload) -> Result<Self, CoreError> {
+        if !self.accepts(payload) {
+            return Err(CoreError::ChainBroken {
+                index: 0,
+                reason: link_mismatch_reason(self, payload),
+            });
+        }
+        Ok(Self {
+            session_id: self.session_id.clone(),
+            sequence_number: self.sequence_number.saturating_add(1),
+            head_hash: payload_chain_hash(payload)?,
+        })
+    }
+}
+
+/// Returns the chain root for `session_id` (hex SHA-256).
+pub fn chain_root_hash(session_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(CHAIN_DOMAIN_V1);
+    hasher.update(b"/root/");
+    hasher.update(session_id.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Returns the chain hash of `payload` (hex SHA-256).
+///
+/// # Semantics
+/// Covers schema version, the canonical JSON encoding of the metadata
+/// (which includes `prev_payload_hash`), mosaic geometry, and pixel bytes.
+///
+/// # Errors
+/// Returns [`CoreError::Codec`] when metadata serialization fails.
+pub fn payload_chain_hash(payload: &MosaicPayload) -> Result<String, CoreError> {
+    let metadata = serde_json::to_vec(&payload.metadata).map_err(CoreError::Codec)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(CHAIN_DOMAIN_V1);
+    hasher.update(b"/payload/");
+    // Why:
+    // - Length prefixes keep variable-length fields from shifting into
+    //   each other and producing equal digests for different payloads.
+    hasher.update((payload.schema_version.len() as u64).to_le_bytes());
+    hasher.update(payload.schema_version.as_bytes());
+    hasher.update((metadata.len() as u64).to_le_bytes());
+    hasher.update(&metadata);
+    hasher.update(payload.mosaic_width.to_le_bytes());
+    hasher.update(payload.mosaic_height.to_le_bytes());
+    hasher.update((payload.mosaic_rgba.len() as u64).to_le_bytes());
+    hasher.update(&payload.mosaic_rgba);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Verifies a complete session chain starting at sequence number 1.
+///
+/// Returns the chain head after the last payload. An empty slice is not a
+/// chain and is rejected.
+///
+/// # Errors
+/// Returns [`CoreError::ChainBroken`] for the first payload that is missing
+/// its link, belongs to another session, skips or repeats a sequence
+/// number, or does not carry its predecessor's hash.
+pub fn verify_payload_chain(payloads: &[MosaicPayload]) -> Result<ChainState, CoreError> {
+    let first = payloads.first().ok_or(CoreError::ChainBroken {
+        index: 0,
+        reason: "no payloads to verify".to_string(),
+    })?;
+    verify_payload_chain_from(&ChainState::root(&first.metadata.session_id), payloads)
+}
+
+/// Verifies that `payloads` continue the chain at `anchor`.
+///
+/// Use this to verify incrementally: pass the state returned by the
+/// previous call.
+///
+/// # Errors
+/// Same as [`verify_payload_chain`]; indices are relative to `payloads`.
+pub fn verify_payload_chain_from(
+    anchor: &ChainState,
+    payloads: &[MosaicPayload],
+) -> Result<ChainState, CoreError> {
+    let mut state = anchor.clone();
+    for (index, payload) in payloads.iter().enumerate() {
+        state = state.advance(payload).map_err(|error| match error {
+            CoreError::ChainBroken { reason, .. } => CoreError::ChainBroken { index, reason },
+            other => other,
+        })?;
+    }
+    Ok(state)
+}
+
+fn link_mismatch_reason(state: &ChainState, payload: &MosaicPayload) -> String {
+    let metadata = &payload.metadata;
+    let expected_sequence = state.sequence_number.saturating_add(1);
+    if metadata.session_id != state.session_id {
+        return "payload belongs to another session".to_string();
+    }
+    match (
+        metadata.sequence_number,
+        metadata.prev_payload_hash.as_deref(),
+    ) {
+        (None, _) => "payload has no sequence number".to_string(),
+        (_, None) => "payload has no previous payload hash".to_string(),
+        (Some(found), _) if found != expected_sequence => {
+            format!("expected sequence number {expected_sequence}, found {found}")
+        }
+        _ => "previous payload hash does not match the chain head".to_string(),
+    }
+}
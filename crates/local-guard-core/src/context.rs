@@ -0,0 +1,102 @@
+//! # Module: context
+//!
+//! ## Purpose
+//! Defines the schema-versioned host context attached to batch metadata
+//! (foreground process, hostname, OS user, locale, timezone, idle time).
+//!
+//! ## Responsibilities
+//! - Carry optional, individually collected context fields.
+//! - Carry provider-specific extension values without a schema change.
+//! - Merge partial snapshots from several providers.
+//!
+//! ## Invariants
+//! - Every field is optional; absent fields are omitted from JSON.
+//! - `schema_version` identifies the layout of the named fields; new named
+//!   fields require a version bump, new extension keys do not.
+//!
+//! ## Error model
+//! Context is plain data; collection errors live in the provider layer.
+//!
+//! ## Security and privacy notes
+//! Context can identify a user or host. Fields are only present when a
+//! provider collected them under an explicit policy; this module never
+//! fills values on its own.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Current layout version of [`BatchContext`].
+pub const CONTEXT_SCHEMA_VERSION_V1: &str = "v1";
+
+/// Host context observed while a batch was assembled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchContext {
+    /// Layout version of the named fields.
+    pub schema_version: String,
+    /// Executable name of the foreground process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground_process: Option<String>,
+    /// Host name of the capturing machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Logged-in OS user name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub os_user: Option<String>,
+    /// Locale identifier (for example `en_US.UTF-8`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Timezone identifier (for example `Europe/Bucharest`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// Milliseconds since the last user input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_ms: Option<u64>,
+    /// Provider-specific values keyed as `<provider>.<key>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, String>,
+}
+
+impl Default for BatchContext {
+    fn default() -> Self {
+        Self {
+            schema_version: CONTEXT_SCHEMA_VERSION_V1.to_string(),
+            foreground_process: None,
+            hostname: None,
+            os_user: None,
+            locale: None,
+            timezone: None,
+            idle_ms: None,
+            extensions: BTreeMap::new(),
+        }
+    }
+}
+
+impl BatchContext {
+    /// Returns `true` when no field or extension is set.
+    pub fn is_empty(&self) -> bool {
+        self.foreground_process.is_none()
+            && self.hostname.is_none()
+            && self.os_user.is_none()
+            && self.locale.is_none()
+            && self.timezone.is_none()
+            && self.idle_ms.is_none()
+            && self.extensions.is_empty()
+    }
+
+    /// Fills fields still missing here from `other`.
+    ///
+    /// # Semantics
+    /// First value wins: fields and extension keys already set are kept.
+    pub fn merge_missing(&mut self, other: BatchContext) {
+        self.foreground_process = self.foreground_process.take().or(other.foreground_process);
+        self.hostname = self.hostname.take().or(other.hostname);
+        self.os_user = self.os_user.take().or(other.os_user);
+        self.locale = self.locale.take().or(other.locale);
+        self.timezone = self.timezone.take().or(other.timezone);
+        self.idle_ms = self.idle_ms.or(other.idle_ms);
+        for (key, value) in other.extensions {
+            self.extensions.entry(key).or_insert(value);
+        }
+    }
+}
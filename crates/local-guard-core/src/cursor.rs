@@ -0,0 +1,46 @@
+//! # Module: cursor
+//!
+//! ## Purpose
+//! Describes the pointer position and visibility observed when a frame was
+//! captured, so analysis can reason about where the user is about to click.
+//!
+//! ## Responsibilities
+//! - Define [`CursorState`] in frame-local pixel coordinates.
+//! - Answer whether a cursor lies inside a frame.
+//!
+//! ## Invariants
+//! - Coordinates are relative to the frame's top-left pixel and may lie
+//!   outside the frame when the pointer is on another display.
+//! - `visible` reflects OS cursor visibility only, not frame bounds.
+//!
+//! ## Error model
+//! Cursor state is plain data; nothing here fails.
+//!
+//! ## Security and privacy notes
+//! Only pointer coordinates are recorded; cursor shape bitmaps are never
+//! captured.
+
+use serde::{Deserialize, Serialize};
+
+/// Pointer position and visibility at capture time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CursorState {
+    /// Horizontal hotspot position in frame pixels.
+    pub x: i32,
+    /// Vertical hotspot position in frame pixels.
+    pub y: i32,
+    /// Whether the OS was showing the cursor.
+    pub visible: bool,
+}
+
+impl CursorState {
+    /// Returns `true` when the hotspot lies inside a `width` x `height` frame.
+    pub fn is_within(&self, width: u32, height: u32) -> bool {
+        self.x >= 0 && self.y >= 0 && (self.x as u32) < width && (self.y as u32) < height
+    }
+
+    /// Returns `true` when the cursor is visible and inside the frame.
+    pub fn is_visible_within(&self, width: u32, height: u32) -> bool {
+        self.visible && self.is_within(width, height)
+    }
+}
@@ -6,7 +6,16 @@
 //!
 //! ## Responsibilities
 //! - Represent captured frames and bounded frame batches.
+//! - Route all-displays capture into independent per-display batches.
 //! - Build deterministic batch metadata used by upload payloads.
+//! - Classify blank (black/uniform) frames and flag them in metadata.
+//! - Recycle large pixel buffers through a [`FramePool`].
+//! - Share immutable pixel buffers across stages via [`SharedBytes`].
+//! - Describe and convert pixel layouts ([`PixelFormat`], row stride).
+//! - Carry per-frame cursor position and visibility ([`CursorState`]).
+//! - Carry schema-versioned host context for a batch ([`BatchContext`]).
+//! - Identify the producing client build ([`ClientProvenance`]).
+//! - Chain consecutive payloads by hash and verify chains ([`ChainState`]).
 //! - Encode/decode versioned mosaic payloads for transport.
 //!
 //! ## Data flow
@@ -15,8 +24,11 @@
 //! mosaic bytes into [`MosaicPayload`].
 //!
 //! ## Ownership and lifetimes
-//! Frames and payloads own their backing buffers (`Vec<u8>`) to avoid hidden
-//! borrow/lifetime coupling between async pipeline stages.
+//! Frames and payloads hold pixels in [`SharedBytes`]: immutable,
+//! reference-counted buffers with no borrow/lifetime coupling between
+//! pipeline stages, so cloning a payload for upload, spool, or preview never
+//! copies pixels. A frame built with [`Frame::with_pool`] hands its buffer
+//! back to the pool when the last reference drops.
 //!
 //! ## Error model
 //! Validation failures (shape mismatch, empty session id, invalid capacity)
@@ -38,14 +50,55 @@
 //! assert_eq!(deterministic_tile_order(9).unwrap(), vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
 //! ```
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+mod bytes;
+mod chain;
+mod context;
+mod cursor;
+mod pixel;
+mod pool;
+mod provenance;
+mod quality;
+
+pub use bytes::SharedBytes;
+pub use chain::{
+    ChainState, chain_root_hash, payload_chain_hash, verify_payload_chain,
+    verify_payload_chain_from,
+};
+pub use context::{BatchContext, CONTEXT_SCHEMA_VERSION_V1};
+pub use cursor::CursorState;
+pub use pixel::{
+    PixelFormat, convert_pixels, convert_pixels_into, convert_row, validate_pixel_layout,
+};
+pub use pool::{DEFAULT_POOL_MAX_BUFFERS, DEFAULT_POOL_MAX_BYTES, FramePool, FramePoolStats};
+pub use provenance::ClientProvenance;
+pub use quality::{
+    DEFAULT_SAMPLE_STRIDE, FrameQuality, MIN_QUALITY_SAMPLES, QualityThresholds, classify_frame,
+    classify_rgba,
+};
+
 /// Canonical schema tag for v1 mosaic payloads.
 pub const SCHEMA_VERSION_V1: &str = "v1";
 
 /// Represents one captured frame from a selected display.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// # Layout
+/// Pixels are stored in [`Frame::format`] with [`Frame::stride`] bytes per
+/// row; rows may carry trailing padding as produced by the OS.
+///
+/// # Equality
+/// Equality compares frame content only; the optional pool handle is ignored.
+///
+/// # Serialization
+/// Deserialization validates the layout like [`Frame::with_format`]. Frames
+/// written before strides existed (pixels under `rgba`, no `stride`) still
+/// load as packed frames.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "FrameWire")]
 pub struct Frame {
     /// Stable display identity from capture backend.
     pub screen_id: String,
@@ -55,12 +108,23 @@ pub struct Frame {
     pub height: u32,
     /// Capture time in Unix epoch milliseconds.
     pub captured_at_ms: u64,
-    /// Raw RGBA pixel buffer (`width * height * 4` bytes).
-    pub rgba: Vec<u8>,
+    /// Pixel layout of [`Frame::pixels`].
+    #[serde(default)]
+    pub format: PixelFormat,
+    /// Bytes between the starts of consecutive rows.
+    pub stride: usize,
+    /// Raw pixel buffer (`stride` bytes per row; the last row may omit padding).
+    pub pixels: SharedBytes,
+    /// Cursor observed at capture time; `None` when the backend does not
+    /// report cursor state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<CursorState>,
+    #[serde(skip)]
+    pool: Option<FramePool>,
 }
 
 impl Frame {
-    /// Constructs a validated frame.
+    /// Constructs a validated, tightly packed RGBA frame.
     ///
     /// # Errors
     /// Returns [`CoreError::InvalidFrameShape`] when the pixel buffer length is
@@ -70,24 +134,179 @@ impl Frame {
         width: u32,
         height: u32,
         captured_at_ms: u64,
-        rgba: Vec<u8>,
+        rgba: impl Into<SharedBytes>,
     ) -> Result<Self, CoreError> {
-        let expected_len = required_rgba_len(width, height)?;
-        if rgba.len() != expected_len {
-            return Err(CoreError::InvalidFrameShape {
-                expected: expected_len,
-                actual: rgba.len(),
-            });
-        }
+        let stride = PixelFormat::Rgba8.row_bytes(width)?;
+        Self::with_format(
+            screen_id,
+            width,
+            height,
+            captured_at_ms,
+            PixelFormat::Rgba8,
+            stride,
+            rgba,
+        )
+    }
+
+    /// Constructs a validated frame in any [`PixelFormat`] and row stride.
+    ///
+    /// # Errors
+    /// Returns [`CoreError::InvalidStride`] when `stride` is shorter than one
+    /// row, or [`CoreError::InvalidFrameShape`] when the buffer length does
+    /// not match `stride` and `height`.
+    pub fn with_format(
+        screen_id: impl Into<String>,
+        width: u32,
+        height: u32,
+        captured_at_ms: u64,
+        format: PixelFormat,
+        stride: usize,
+        pixels: impl Into<SharedBytes>,
+    ) -> Result<Self, CoreError> {
+        let pixels = pixels.into();
+        validate_pixel_layout(format, width, height, stride, pixels.len())?;
 
         Ok(Self {
             screen_id: screen_id.into(),
             width,
             height,
             captured_at_ms,
-            rgba,
+            format,
+            stride,
+            pixels,
+            cursor: None,
+            pool: None,
         })
     }
+
+    /// Attaches cursor state observed at capture time.
+    pub fn with_cursor(mut self, cursor: CursorState) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Attaches a pool that receives the pixel buffer when the frame drops.
+    pub fn with_pool(mut self, pool: &FramePool) -> Self {
+        self.pool = Some(pool.clone());
+        self
+    }
+
+    /// Returns the packed length of one row (`width * bytes_per_pixel`).
+    pub fn row_bytes(&self) -> usize {
+        self.width as usize * self.format.bytes_per_pixel()
+    }
+
+    /// Returns `true` when rows carry no padding.
+    pub fn is_packed(&self) -> bool {
+        self.stride == self.row_bytes()
+    }
+
+    /// Returns row `y` without padding, or `None` when `y >= height` or the
+    /// buffer is too short for it (fields are public and may be edited after
+    /// validation).
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        if y >= self.height {
+            return None;
+        }
+        let offset = (y as usize).checked_mul(self.stride)?;
+        self.pixels
+            .get(offset..offset.checked_add(self.row_bytes())?)
+    }
+
+    /// Returns a tightly packed copy of this frame in `format`.
+    ///
+    /// # Errors
+    /// Returns shape errors from [`convert_pixels`].
+    pub fn to_format(&self, format: PixelFormat) -> Result<Self, CoreError> {
+        let pixels = convert_pixels(
+            &self.pixels,
+            self.width,
+            self.height,
+            self.stride,
+            self.format,
+            format,
+        )?;
+        let mut converted = Self::with_format(
+            self.screen_id.clone(),
+            self.width,
+            self.height,
+            self.captured_at_ms,
+            format,
+            format.row_bytes(self.width)?,
+            pixels,
+        )?;
+        converted.cursor = self.cursor;
+        Ok(converted)
+    }
+}
+
+/// Serialized form of [`Frame`], accepted before validation.
+#[derive(Deserialize)]
+struct FrameWire {
+    screen_id: String,
+    width: u32,
+    height: u32,
+    captured_at_ms: u64,
+    #[serde(default)]
+    format: PixelFormat,
+    /// Missing in frames written before strides existed; packed rows then.
+    #[serde(default)]
+    stride: Option<usize>,
+    #[serde(alias = "rgba")]
+    pixels: SharedBytes,
+    #[serde(default)]
+    cursor: Option<CursorState>,
+}
+
+impl TryFrom<FrameWire> for Frame {
+    type Error = CoreError;
+
+    fn try_from(wire: FrameWire) -> Result<Self, Self::Error> {
+        let stride = match wire.stride {
+            Some(stride) => stride,
+            None => wire.format.row_bytes(wire.width)?,
+        };
+        let frame = Self::with_format(
+            wire.screen_id,
+            wire.width,
+            wire.height,
+            wire.captured_at_ms,
+            wire.format,
+            stride,
+            wire.pixels,
+        )?;
+        Ok(match wire.cursor {
+            Some(cursor) => frame.with_cursor(cursor),
+            None => frame,
+        })
+    }
+}
+
+impl PartialEq for Frame {
+    fn eq(&self, other: &Self) -> bool {
+        self.screen_id == other.screen_id
+            && self.width == other.width
+            && self.height == other.height
+            && self.captured_at_ms == other.captured_at_ms
+            && self.format == other.format
+            && self.stride == other.stride
+            && self.pixels == other.pixels
+            && self.cursor == other.cursor
+    }
+}
+
+impl Eq for Frame {}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        // Why: taking the buffer out of the shared handle, rather than
+        // swapping in a fresh handle, keeps drop allocation-free.
+        if let Some(pool) = self.pool.take()
+            && let Some(buffer) = self.pixels.take_unique()
+        {
+            pool.release(buffer);
+        }
+    }
 }
 
 /// Bounded buffer that emits complete frame batches.
@@ -166,6 +385,111 @@ impl FrameBatch {
     }
 }
 
+/// Set of independent per-display frame batches for all-displays capture.
+///
+/// # Purpose
+/// All-displays capture produces one frame per display per tick. Instead of
+/// stitching a virtual-desktop frame, each display feeds its own
+/// [`FrameBatch`], so every display yields its own mosaic stream with its own
+/// `screen_id` (and therefore its own idempotency key).
+///
+/// # Invariants
+/// - Every inner batch shares the same capacity.
+/// - A frame is routed only to the batch keyed by its `screen_id`; frames from
+///   different displays never mix inside one emitted batch.
+/// - Inner batches are stored in a `BTreeMap`, so iteration order over screen
+///   ids is deterministic.
+#[derive(Debug, Clone)]
+pub struct MultiDisplayFrameBatch {
+    capacity: usize,
+    batches: BTreeMap<String, FrameBatch>,
+}
+
+impl MultiDisplayFrameBatch {
+    /// Creates an empty multi-display batch set.
+    ///
+    /// # Errors
+    /// Returns [`CoreError::InvalidBatchCapacity`] when `capacity == 0`.
+    pub fn new(capacity: usize) -> Result<Self, CoreError> {
+        if capacity == 0 {
+            return Err(CoreError::InvalidBatchCapacity);
+        }
+
+        Ok(Self {
+            capacity,
+            batches: BTreeMap::new(),
+        })
+    }
+
+    /// Routes one frame into the batch of its display.
+    ///
+    /// # Returns
+    /// - `Ok(None)` when that display's batch is not yet full.
+    /// - `Ok(Some(Vec<Frame>))` when that display's batch reached capacity.
+    ///
+    /// # Errors
+    /// Returns [`CoreError::BatchInvariantViolation`] when the frame geometry
+    /// differs from frames already buffered for the same display (for
+    /// example after a resolution change mid-batch).
+    pub fn push_frame(&mut self, frame: Frame) -> Result<Option<Vec<Frame>>, CoreError> {
+        if !self.batches.contains_key(&frame.screen_id) {
+            self.batches
+                .insert(frame.screen_id.clone(), FrameBatch::new(self.capacity)?);
+        }
+
+        match self.batches.get_mut(&frame.screen_id) {
+            Some(batch) => batch.push_frame(frame),
+            None => Err(CoreError::BatchInvariantViolation(
+                "display batch vanished during routing".to_string(),
+            )),
+        }
+    }
+
+    /// Drops frames buffered for one display and returns how many were dropped.
+    ///
+    /// # Why
+    /// After a resolution change the partial batch can never complete, so
+    /// callers discard it and let the display start a fresh window.
+    pub fn reset_display(&mut self, screen_id: &str) -> usize {
+        self.batches
+            .remove(screen_id)
+            .map_or(0, |batch| batch.len())
+    }
+
+    /// Returns `(width, height)` of frames buffered for one display, if any.
+    pub fn buffered_geometry(&self, screen_id: &str) -> Option<(u32, u32)> {
+        self.batches
+            .get(screen_id)
+            .and_then(|batch| batch.frames.first())
+            .map(|frame| (frame.width, frame.height))
+    }
+
+    /// Returns number of frames buffered for one display.
+    pub fn len_for(&self, screen_id: &str) -> usize {
+        self.batches.get(screen_id).map_or(0, FrameBatch::len)
+    }
+
+    /// Returns total buffered frame count across all displays.
+    pub fn len(&self) -> usize {
+        self.batches.values().map(FrameBatch::len).sum()
+    }
+
+    /// Returns `true` when no display has buffered frames.
+    pub fn is_empty(&self) -> bool {
+        self.batches.values().all(FrameBatch::is_empty)
+    }
+
+    /// Returns configured per-display batch capacity.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns display ids that have been seen, in deterministic order.
+    pub fn screen_ids(&self) -> Vec<String> {
+        self.batches.keys().cloned().collect()
+    }
+}
+
 /// Metadata attached to each upload payload.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BatchMetadata {
@@ -183,6 +507,38 @@ pub struct BatchMetadata {
     pub session_id: String,
     /// Number of frames used in the batch.
     pub frame_count: usize,
+    /// Unique batch identifier (UUID v4) assigned during payload assembly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Per-session batch counter starting at 1; gaps reveal lost batches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence_number: Option<u64>,
+    /// Chain hash of the previous payload in the session (the session root
+    /// for the first payload); see [`verify_payload_chain`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_payload_hash: Option<String>,
+    /// Server-issued id of the enrolled device that produced the batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    /// Tile indices (chronological order) whose frame was classified blank.
+    ///
+    /// Omitted from JSON when empty so v1 consumers see no change for
+    /// normal batches.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blank_tiles: Vec<usize>,
+    /// Cursor state per tile (chronological order).
+    ///
+    /// Empty (and omitted from JSON) when no frame in the batch carries
+    /// cursor state; otherwise holds exactly `frame_count` entries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tile_cursors: Vec<Option<CursorState>>,
+    /// Host context collected while the batch was assembled; omitted when
+    /// no context provider is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<BatchContext>,
+    /// Client build and environment that produced the batch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<ClientProvenance>,
 }
 
 /// Versioned payload sent to protected ingest API.
@@ -197,7 +553,7 @@ pub struct MosaicPayload {
     /// Mosaic image height in pixels.
     pub mosaic_height: u32,
     /// Mosaic pixel bytes in RGBA row-major layout.
-    pub mosaic_rgba: Vec<u8>,
+    pub mosaic_rgba: SharedBytes,
 }
 
 impl MosaicPayload {
@@ -232,6 +588,8 @@ pub fn deterministic_tile_order(batch_size: usize) -> Result<Vec<usize>, CoreErr
 
 /// Computes batch metadata from a completed frame set.
 ///
+/// Blank tiles are detected with [`QualityThresholds::default`].
+///
 /// # Errors
 /// Returns [`CoreError::EmptyFrameSet`] when `frames` is empty.
 /// Returns [`CoreError::BatchInvariantViolation`] when frames mismatch by
@@ -239,6 +597,18 @@ pub fn deterministic_tile_order(batch_size: usize) -> Result<Vec<usize>, CoreErr
 pub fn build_metadata(
     frames: &[Frame],
     session_id: impl Into<String>,
+) -> Result<BatchMetadata, CoreError> {
+    build_metadata_with_thresholds(frames, session_id, &QualityThresholds::default())
+}
+
+/// Computes batch metadata using caller-provided blank-frame thresholds.
+///
+/// # Errors
+/// Same as [`build_metadata`].
+pub fn build_metadata_with_thresholds(
+    frames: &[Frame],
+    session_id: impl Into<String>,
+    thresholds: &QualityThresholds,
 ) -> Result<BatchMetadata, CoreError> {
     if frames.is_empty() {
         return Err(CoreError::EmptyFrameSet);
@@ -275,6 +645,23 @@ pub fn build_metadata(
         source_height: first.height,
         session_id,
         frame_count: frames.len(),
+        batch_id: None,
+        sequence_number: None,
+        prev_payload_hash: None,
+        device_id: None,
+        blank_tiles: frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| classify_frame(frame, thresholds).is_blank())
+            .map(|(index, _)| index)
+            .collect(),
+        tile_cursors: if frames.iter().any(|frame| frame.cursor.is_some()) {
+            frames.iter().map(|frame| frame.cursor).collect()
+        } else {
+            Vec::new()
+        },
+        context: None,
+        provenance: None,
     })
 }
 
@@ -284,11 +671,19 @@ pub enum CoreError {
     /// Frame buffer shape does not match declared geometry.
     #[error("invalid frame shape: expected {expected} bytes, got {actual}")]
     InvalidFrameShape {
-        /// Expected RGBA byte count.
+        /// Expected byte count.
         expected: usize,
-        /// Actual RGBA byte count.
+        /// Actual byte count.
         actual: usize,
     },
+    /// Row stride is shorter than one row of pixels.
+    #[error("invalid stride: {stride} bytes is shorter than one row ({min} bytes)")]
+    InvalidStride {
+        /// Declared stride in bytes.
+        stride: usize,
+        /// Minimum stride (`width * bytes_per_pixel`).
+        min: usize,
+    },
     /// Batch capacity must be strictly positive.
     #[error("batch capacity must be greater than zero")]
     InvalidBatchCapacity,
@@ -301,19 +696,15 @@ pub enum CoreError {
     /// Frame batch invariants were violated.
     #[error("batch invariant violation: {0}")]
     BatchInvariantViolation(String),
+    /// Payload hash chain is incomplete, reordered, or tampered with.
+    #[error("payload chain broken at index {index}: {reason}")]
+    ChainBroken {
+        /// Index of the first offending payload.
+        index: usize,
+        /// Human-readable mismatch description.
+        reason: String,
+    },
     /// JSON encoding/decoding error.
     #[error("payload codec failure: {0}")]
     Codec(#[from] serde_json::Error),
 }
-
-fn required_rgba_len(width: u32, height: u32) -> Result<usize, CoreError> {
-    let pixels = (width as usize)
-        .checked_mul(height as usize)
-        .ok_or_else(|| {
-            CoreError::BatchInvariantViolation("frame dimensions overflow".to_string())
-        })?;
-
-    pixels
-        .checked_mul(4)
-        .ok_or_else(|| CoreError::BatchInvariantViolation("rgba length overflow".to_string()))
-}
@@ -0,0 +1,228 @@
+//! # Module: pixel
+//!
+//! ## Purpose
+//! Describes pixel layouts produced by capture backends and converts between
+//! them without intermediate allocations.
+//!
+//! ## Responsibilities
+//! - Define [`PixelFormat`] and per-format byte sizes.
+//! - Validate `(width, height, stride, len)` buffer shapes.
+//! - Convert rows and whole buffers between formats, dropping row padding.
+//!
+//! ## Invariants
+//! - `stride >= width * bytes_per_pixel` for every valid buffer.
+//! - A valid buffer holds at least `stride * (height - 1) + row_bytes` and at
+//!   most `stride * height` bytes (the last row may omit its padding).
+//! - Converted output is always tightly packed (`stride == row_bytes`).
+//!
+//! ## Error model
+//! Shape problems return [`CoreError::InvalidFrameShape`] or
+//! [`CoreError::InvalidStride`].
+//!
+//! ## Security and privacy notes
+//! Conversions operate in memory only and never log pixel values.
+
+use serde::{Deserialize, Serialize};
+
+use crate::CoreError;
+
+/// Memory layout of one pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PixelFormat {
+    /// Red, green, blue, alpha; one byte each.
+    #[default]
+    Rgba8,
+    /// Blue, green, red, alpha; one byte each (native Windows DIB order).
+    Bgra8,
+    /// Red, green, blue; one byte each.
+    Rgb8,
+    /// Single luma byte.
+    Gray8,
+}
+
+impl PixelFormat {
+    /// Returns bytes used by one pixel.
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba8 | Self::Bgra8 => 4,
+            Self::Rgb8 => 3,
+            Self::Gray8 => 1,
+        }
+    }
+
+    /// Returns tightly packed row length for `width` pixels.
+    ///
+    /// # Errors
+    /// Returns [`CoreError::BatchInvariantViolation`] on overflow.
+    pub fn row_bytes(self, width: u32) -> Result<usize, CoreError> {
+        (width as usize)
+            .checked_mul(self.bytes_per_pixel())
+            .ok_or_else(|| CoreError::BatchInvariantViolation("row length overflow".to_string()))
+    }
+}
+
+/// Validates a pixel buffer shape.
+///
+/// # Errors
+/// - [`CoreError::InvalidStride`] when `stride` is shorter than one row.
+/// - [`CoreError::InvalidFrameShape`] when `len` is outside the valid range.
+pub fn validate_pixel_layout(
+    format: PixelFormat,
+    width: u32,
+    height: u32,
+    stride: usize,
+    len: usize,
+) -> Result<(), CoreError> {
+    let row_bytes = format.row_bytes(width)?;
+    if stride < row_bytes {
+        return Err(CoreError::InvalidStride {
+            stride,
+            min: row_bytes,
+        });
+    }
+    if height == 0 || row_bytes == 0 {
+        return if len == 0 {
+            Ok(())
+        } else {
+            Err(CoreError::InvalidFrameShape {
+                expected: 0,
+                actual: len,
+            })
+        };
+    }
+
+    let overflow =
+        || CoreError::BatchInvariantViolation("pixel buffer length overflow".to_string());
+    let padded = stride.checked_mul(height as usize).ok_or_else(overflow)?;
+    let minimal = padded - (stride - row_bytes);
+    if len < minimal || len > padded {
+        return Err(CoreError::InvalidFrameShape {
+            expected: if len < minimal { minimal } else { padded },
+            actual: len,
+        });
+    }
+    Ok(())
+}
+
+/// Converts one row of `width` pixels from `src_format` into `dst_format`.
+///
+/// # Panics
+/// Panics when `src` or `dst` is shorter than `width` pixels of its format;
+/// callers validate shapes first.
+pub fn convert_row(
+    src: &[u8],
+    src_format: PixelFormat,
+    dst: &mut [u8],
+    dst_format: PixelFormat,
+    width: usize,
+) {
+    let src = &src[..width * src_format.bytes_per_pixel()];
+    let dst = &mut dst[..width * dst_format.bytes_per_pixel()];
+
+    // Why:
+    // - Dedicated loops for the hot pairs (identity, channel swap, alpha
+    //   strip) let the compiler vectorize them; the rest go through a
+    //   generic decode/encode path.
+    match (src_format, dst_format) {
+        (from, to) if from == to => dst.copy_from_slice(src),
+        (PixelFormat::Rgba8, PixelFormat::Bgra8) | (PixelFormat::Bgra8, PixelFormat::Rgba8) => {
+            for (out, px) in dst.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+                out.copy_from_slice(&[px[2], px[1], px[0], px[3]]);
+            }
+        }
+        (PixelFormat::Rgba8, PixelFormat::Rgb8) => {
+            for (out, px) in dst.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+                out.copy_from_slice(&px[..3]);
+            }
+        }
+        (PixelFormat::Bgra8, PixelFormat::Rgb8) => {
+            for (out, px) in dst.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+                out.copy_from_slice(&[px[2], px[1], px[0]]);
+            }
+        }
+        _ => {
+            let src_bpp = src_format.bytes_per_pixel();
+            let dst_bpp = dst_format.bytes_per_pixel();
+            for (out, px) in dst.chunks_exact_mut(dst_bpp).zip(src.chunks_exact(src_bpp)) {
+                encode_pixel(decode_pixel(px, src_format), out, dst_format);
+            }
+        }
+    }
+}
+
+/// Converts a whole buffer into a tightly packed buffer of `dst_format`.
+///
+/// # Errors
+/// Returns shape errors from [`validate_pixel_layout`].
+pub fn convert_pixels(
+    src: &[u8],
+    width: u32,
+    height: u32,
+    src_stride: usize,
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+) -> Result<Vec<u8>, CoreError> {
+    let mut dst = Vec::new();
+    convert_pixels_into(
+        src, width, height, src_stride, src_format, dst_format, &mut dst,
+    )?;
+    Ok(dst)
+}
+
+/// Same as [`convert_pixels`] but writes into `dst` (e.g. a pooled buffer).
+///
+/// `dst` is cleared and resized to the packed output length.
+///
+/// # Errors
+/// Returns shape errors from [`validate_pixel_layout`].
+pub fn convert_pixels_into(
+    src: &[u8],
+    width: u32,
+    height: u32,
+    src_stride: usize,
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+    dst: &mut Vec<u8>,
+) -> Result<(), CoreError> {
+    validate_pixel_layout(src_format, width, height, src_stride, src.len())?;
+    let dst_row = dst_format.row_bytes(width)?;
+    dst.clear();
+    dst.resize(dst_row * height as usize, 0);
+    if dst_row == 0 {
+        return Ok(());
+    }
+
+    for (y, out) in dst.chunks_exact_mut(dst_row).enumerate() {
+        let offset = y * src_stride;
+        convert_row(&src[offset..], src_format, out, dst_format, width as usize);
+    }
+    Ok(())
+}
+
+/// Integer BT.601 luma of one RGB triple.
+pub(crate) fn luma(red: u8, green: u8, blue: u8) -> u8 {
+    // Why:
+    // - Integer weights (77/150/29 of 256) avoid float work per pixel.
+    ((77 * red as u32 + 150 * green as u32 + 29 * blue as u32) >> 8) as u8
+}
+
+/// Decodes one pixel into `[r, g, b, a]`.
+pub(crate) fn decode_pixel(px: &[u8], format: PixelFormat) -> [u8; 4] {
+    match format {
+        PixelFormat::Rgba8 => [px[0], px[1], px[2], px[3]],
+        PixelFormat::Bgra8 => [px[2], px[1], px[0], px[3]],
+        PixelFormat::Rgb8 => [px[0], px[1], px[2], 255],
+        PixelFormat::Gray8 => [px[0], px[0], px[0], 255],
+    }
+}
+
+fn encode_pixel(rgba: [u8; 4], out: &mut [u8], format: PixelFormat) {
+    let [red, green, blue, alpha] = rgba;
+    match format {
+        PixelFormat::Rgba8 => out.copy_from_slice(&rgba),
+        PixelFormat::Bgra8 => out.copy_from_slice(&[blue, green, red, alpha]),
+        PixelFormat::Rgb8 => out.copy_from_slice(&[red, green, blue]),
+        PixelFormat::Gray8 => out[0] = luma(red, green, blue),
+    }
+}
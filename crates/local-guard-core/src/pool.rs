@@ -0,0 +1,208 @@
+//! # Module: pool
+//!
+//! ## Purpose
+//! Recycles large pixel buffers so steady-state capture and mosaic
+//! composition do not allocate per frame (a 4K RGBA frame is ~33 MB).
+//!
+//! ## Responsibilities
+//! - Hand out empty buffers with at least the requested capacity.
+//! - Take buffers back, bounded by a maximum pooled count and byte total.
+//! - Count reuse versus fresh allocation for benchmarks and diagnostics.
+//!
+//! ## Invariants
+//! - The pool never holds more than `max_buffers` idle buffers, nor more
+//!   than `max_bytes` of idle capacity (a 4K mosaic alone is ~300 MB).
+//! - Acquired buffers are always empty (`len == 0`); callers fill them, so no
+//!   stale pixels from a previous frame can leak into a new one.
+//!
+//! ## Error model
+//! Pool operations are infallible. A poisoned internal lock degrades to
+//! plain allocation and dropping instead of panicking.
+//!
+//! ## Security and privacy notes
+//! Idle buffers keep previous pixel bytes in memory until reused or dropped;
+//! they never leave the process and are cleared before being handed out.
+
+use std::sync::{Arc, Mutex};
+
+use crate::SharedBytes;
+
+/// Default upper bound for idle buffers kept by a [`FramePool`].
+pub const DEFAULT_POOL_MAX_BUFFERS: usize = 32;
+
+/// Default upper bound for idle capacity kept by a [`FramePool`] (512 MiB).
+pub const DEFAULT_POOL_MAX_BYTES: usize = 512 * 1024 * 1024;
+
+/// Counters describing pool effectiveness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FramePoolStats {
+    /// Buffers handed out.
+    pub acquired: u64,
+    /// Acquisitions served from an idle buffer.
+    pub reused: u64,
+    /// Acquisitions that allocated a fresh buffer.
+    pub allocated: u64,
+    /// Buffers returned and kept idle.
+    pub released: u64,
+    /// Buffers returned but dropped because the pool was full by count or
+    /// bytes.
+    pub discarded: u64,
+}
+
+#[derive(Debug, Default)]
+struct PoolState {
+    idle: Vec<Vec<u8>>,
+    idle_bytes: usize,
+    stats: FramePoolStats,
+}
+
+/// Shared pool of reusable pixel buffers.
+///
+/// # Ownership
+/// Cloning a pool clones a handle; all clones share the same idle buffers.
+/// Frames created with [`crate::Frame::with_pool`] return their buffer here
+/// when dropped.
+#[derive(Clone)]
+pub struct FramePool {
+    max_buffers: usize,
+    max_bytes: usize,
+    state: Arc<Mutex<PoolState>>,
+}
+
+impl FramePool {
+    /// Creates a pool that keeps at most `max_buffers` idle buffers and
+    /// [`DEFAULT_POOL_MAX_BYTES`] of idle capacity.
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            max_buffers,
+            max_bytes: DEFAULT_POOL_MAX_BYTES,
+            state: Arc::new(Mutex::new(PoolState::default())),
+        }
+    }
+
+    /// Sets the idle-capacity bound in bytes.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Returns an empty buffer with capacity of at least `capacity` bytes.
+    ///
+    /// # Why
+    /// Best fit (smallest idle buffer that is large enough) keeps large
+    /// mosaic buffers available for mosaics instead of spending them on
+    /// single frames.
+    pub fn acquire(&self, capacity: usize) -> Vec<u8> {
+        let Ok(mut state) = self.state.lock() else {
+            return Vec::with_capacity(capacity);
+        };
+        state.stats.acquired = state.stats.acquired.saturating_add(1);
+
+        let best_fit = state
+            .idle
+            .iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.capacity() >= capacity)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(index, _)| index);
+        match best_fit {
+            Some(index) => {
+                state.stats.reused = state.stats.reused.saturating_add(1);
+                let mut buffer = state.idle.swap_remove(index);
+                state.idle_bytes = state.idle_bytes.saturating_sub(buffer.capacity());
+                buffer.clear();
+                buffer
+            }
+            None => {
+                state.stats.allocated = state.stats.allocated.saturating_add(1);
+                Vec::with_capacity(capacity)
+            }
+        }
+    }
+
+    /// Returns a zero-filled buffer of exactly `len` bytes.
+    pub fn acquire_zeroed(&self, len: usize) -> Vec<u8> {
+        let mut buffer = self.acquire(len);
+        buffer.resize(len, 0);
+        buffer
+    }
+
+    /// Gives a buffer back to the pool.
+    ///
+    /// Buffers without capacity, or beyond `max_buffers` or `max_bytes`,
+    /// are dropped.
+    pub fn release(&self, buffer: Vec<u8>) {
+        if buffer.capacity() == 0 {
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let fits = state
+            .idle_bytes
+            .checked_add(buffer.capacity())
+            .is_some_and(|bytes| bytes <= self.max_bytes);
+        if state.idle.len() < self.max_buffers && fits {
+            state.idle_bytes += buffer.capacity();
+            state.idle.push(buffer);
+            state.stats.released = state.stats.released.saturating_add(1);
+        } else {
+            state.stats.discarded = state.stats.discarded.saturating_add(1);
+        }
+    }
+
+    /// Gives a shared buffer back when this is its last handle.
+    ///
+    /// Buffers still referenced elsewhere are left alone; the last holder
+    /// releases them (or they are freed normally).
+    pub fn release_shared(&self, bytes: SharedBytes) {
+        if let Ok(buffer) = bytes.try_into_vec() {
+            self.release(buffer);
+        }
+    }
+
+    /// Returns the number of idle buffers.
+    pub fn available(&self) -> usize {
+        self.state.lock().map_or(0, |state| state.idle.len())
+    }
+
+    /// Returns the idle capacity in bytes.
+    pub fn idle_bytes(&self) -> usize {
+        self.state.lock().map_or(0, |state| state.idle_bytes)
+    }
+
+    /// Returns the configured idle-buffer bound.
+    pub fn max_buffers(&self) -> usize {
+        self.max_buffers
+    }
+
+    /// Returns the configured idle-capacity bound in bytes.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Returns a snapshot of pool counters.
+    pub fn stats(&self) -> FramePoolStats {
+        self.state
+            .lock()
+            .map_or_else(|_| FramePoolStats::default(), |state| state.stats)
+    }
+}
+
+impl Default for FramePool {
+    fn default() -> Self {
+        Self::new(DEFAULT_POOL_MAX_BUFFERS)
+    }
+}
+
+impl std::fmt::Debug for FramePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FramePool")
+            .field("max_buffers", &self.max_buffers)
+            .field("max_bytes", &self.max_bytes)
+            .field("available", &self.available())
+            .field("idle_bytes", &self.idle_bytes())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
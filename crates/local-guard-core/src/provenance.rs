@@ -0,0 +1,44 @@
+//! # Module: provenance
+//!
+//! ## Purpose
+//! Identifies the client build and environment that produced a payload so
+//! the server can correlate bad payloads with a release or platform.
+//!
+//! ## Responsibilities
+//! - Carry app version, build git hash, and target triple.
+//! - Carry OS name/version, capture backend, and encoder settings.
+//!
+//! ## Invariants
+//! - Values describe the client, never the captured content; two payloads
+//!   with equal content but different provenance are the same batch.
+//!
+//! ## Error model
+//! Plain data; unknown values are reported as `"unknown"` by producers.
+//!
+//! ## Security and privacy notes
+//! Provenance holds no user or host identity (hostname and user live in
+//! policy-gated [`crate::BatchContext`]).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Build and environment description of the producing client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientProvenance {
+    /// Application version (`VERSION` file).
+    pub app_version: String,
+    /// Git commit the client was built from.
+    pub git_hash: String,
+    /// Rust target triple of the client build.
+    pub target_triple: String,
+    /// Operating system family (for example `windows`).
+    pub os_name: String,
+    /// Operating system version as reported at runtime.
+    pub os_version: String,
+    /// Capture backend that produced the frames.
+    pub capture_backend: String,
+    /// Encoder parameters (format, quality, overlays) keyed by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub encoder_settings: BTreeMap<String, String>,
+}
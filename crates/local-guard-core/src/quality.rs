@@ -0,0 +1,162 @@
+//! # Module: quality
+//!
+//! ## Purpose
+//! Classifies captured frames that carry no usable content: all-black output
+//! from locked workstations, powered-off screens, or DRM-protected surfaces,
+//! and flat single-color frames.
+//!
+//! ## Responsibilities
+//! - Compute a cheap luma profile of an RGBA buffer.
+//! - Map the profile to a [`FrameQuality`] using [`QualityThresholds`].
+//!
+//! ## Invariants
+//! - Classification is deterministic and depends only on pixel bytes and
+//!   thresholds.
+//! - Precedence is `Black` > `Uniform` > `NearUniform` > `Normal`.
+//!
+//! ## Error model
+//! Classification is infallible; an empty buffer is reported as `Black`.
+//!
+//! ## Security and privacy notes
+//! Only aggregate luma statistics are computed; pixel values are never
+//! logged or retained.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Frame;
+use crate::pixel::{PixelFormat, decode_pixel, luma};
+
+/// Content classification of one captured frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameQuality {
+    /// Frame carries visible content.
+    Normal,
+    /// Every sampled pixel is at or below the black luma threshold.
+    Black,
+    /// Luma spread across the frame is within the uniform tolerance.
+    Uniform,
+    /// Almost all pixels sit close to the mean luma.
+    NearUniform,
+}
+
+impl FrameQuality {
+    /// Returns `true` when the frame is considered blank (not `Normal`).
+    pub fn is_blank(self) -> bool {
+        self != Self::Normal
+    }
+}
+
+/// Tunable thresholds for [`classify_frame`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityThresholds {
+    /// Highest luma (0..=255) still treated as black.
+    pub black_max_luma: u8,
+    /// Largest luma spread (max - min) treated as uniform.
+    pub uniform_max_spread: u8,
+    /// Luma distance from the mean counted as "close" for near-uniformity.
+    pub near_uniform_tolerance: u8,
+    /// Fraction (0..=1) of close pixels required for `NearUniform`.
+    pub near_uniform_fraction: f64,
+    /// Inspect every n-th pixel; `1` inspects all pixels. Frames too small
+    /// to yield [`MIN_QUALITY_SAMPLES`] samples are sampled more densely.
+    pub sample_stride: usize,
+}
+
+/// Fewest pixels a sampling stride may reduce a frame to.
+pub const MIN_QUALITY_SAMPLES: usize = 4096;
+
+/// Default [`QualityThresholds::sample_stride`].
+pub const DEFAULT_SAMPLE_STRIDE: usize = 8;
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            black_max_luma: 8,
+            uniform_max_spread: 2,
+            near_uniform_tolerance: 6,
+            near_uniform_fraction: 0.998,
+            // Why: every frame is classified, and retries re-classify; a
+            // blank screen is blank at any sampling density, while a
+            // 1-in-8 sample still sees any content larger than a cursor.
+            sample_stride: DEFAULT_SAMPLE_STRIDE,
+        }
+    }
+}
+
+/// Classifies one frame (any pixel format and stride) with the given
+/// thresholds.
+pub fn classify_frame(frame: &Frame, thresholds: &QualityThresholds) -> FrameQuality {
+    let bytes_per_pixel = frame.format.bytes_per_pixel();
+    let stride = effective_stride(thresholds, frame.width as usize * frame.height as usize);
+    classify_luma(
+        || {
+            (0..frame.height)
+                .filter_map(|y| frame.row(y))
+                .flat_map(move |row| row.chunks_exact(bytes_per_pixel))
+                .step_by(stride)
+                .map(|px| pixel_luma(px, frame.format))
+        },
+        thresholds,
+    )
+}
+
+/// Classifies a tightly packed RGBA buffer with the given thresholds.
+pub fn classify_rgba(rgba: &[u8], thresholds: &QualityThresholds) -> FrameQuality {
+    let stride = effective_stride(thresholds, rgba.len() / 4);
+    classify_luma(
+        || {
+            rgba.chunks_exact(4)
+                .step_by(stride)
+                .map(|px| pixel_luma(px, PixelFormat::Rgba8))
+        },
+        thresholds,
+    )
+}
+
+// Invariant: small frames (tests, thumbnails) keep enough samples that a
+// few content pixels are not skipped entirely.
+fn effective_stride(thresholds: &QualityThresholds, pixels: usize) -> usize {
+    thresholds
+        .sample_stride
+        .min(pixels / MIN_QUALITY_SAMPLES)
+        .max(1)
+}
+
+// Why: classification runs on every captured frame; streaming the samples
+// twice (profile, then closeness) avoids buffering a luma copy of the frame.
+fn classify_luma<I>(samples: impl Fn() -> I, thresholds: &QualityThresholds) -> FrameQuality
+where
+    I: Iterator<Item = u8>,
+{
+    let (mut count, mut sum) = (0_u64, 0_u64);
+    let (mut min, mut max) = (u8::MAX, u8::MIN);
+    for value in samples() {
+        count += 1;
+        sum += value as u64;
+        min = min.min(value);
+        max = max.max(value);
+    }
+    if count == 0 || max <= thresholds.black_max_luma {
+        return FrameQuality::Black;
+    }
+    if max - min <= thresholds.uniform_max_spread {
+        return FrameQuality::Uniform;
+    }
+
+    let mean = (sum / count) as i16;
+    let tolerance = thresholds.near_uniform_tolerance as i16;
+    let close = samples()
+        .filter(|value| (*value as i16 - mean).abs() <= tolerance)
+        .count();
+    if close as f64 >= thresholds.near_uniform_fraction * count as f64 {
+        return FrameQuality::NearUniform;
+    }
+
+    FrameQuality::Normal
+}
+
+fn pixel_luma(px: &[u8], format: PixelFormat) -> u8 {
+    let [red, green, blue, _] = decode_pixel(px, format);
+    luma(red, green, blue)
+}
@@ -0,0 +1,62 @@
+//! Tests frame buffer pooling and return-on-drop behavior.
+
+use local_guard_core::{Frame, FramePool};
+
+#[test]
+fn frame_pool_tests_dropped_frame_returns_buffer() {
+    let pool = FramePool::new(4);
+    let mut buffer = pool.acquire(16);
+    buffer.resize(16, 7);
+    let frame = Frame::new("display-1", 2, 2, 1, buffer)
+        .expect("frame should be valid")
+        .with_pool(&pool);
+    let plain = Frame::new("display-1", 2, 2, 1, vec![7; 16]).expect("frame should be valid");
+    assert_eq!(frame, plain, "pool handle must not affect equality");
+
+    assert_eq!(pool.available(), 0);
+    drop(frame);
+    drop(plain);
+    assert_eq!(pool.available(), 1);
+
+    let reused = pool.acquire(16);
+    assert!(reused.is_empty(), "reused buffers are handed out cleared");
+    assert!(reused.capacity() >= 16);
+    let stats = pool.stats();
+    assert_eq!((stats.allocated, stats.reused, stats.released), (1, 1, 1));
+}
+
+#[test]
+fn frame_pool_tests_best_fit_and_bounded_idle_set() {
+    let pool = FramePool::new(2);
+    pool.release(Vec::with_capacity(1_024));
+    pool.release(Vec::with_capacity(64));
+    pool.release(Vec::with_capacity(32));
+    assert_eq!(pool.available(), 2);
+    assert_eq!(pool.stats().discarded, 1);
+
+    // A small request must not consume the large buffer.
+    let small = pool.acquire(16);
+    assert!(small.capacity() < 1_024);
+    let large = pool.acquire(512);
+    assert!(large.capacity() >= 1_024);
+
+    let zeroed = pool.acquire_zeroed(8);
+    assert_eq!(zeroed, vec![0; 8]);
+}
+
+#[test]
+fn frame_pool_tests_idle_capacity_is_bounded_by_bytes() {
+    let pool = FramePool::new(8).with_max_bytes(1_024);
+    pool.release(Vec::with_capacity(600));
+    pool.release(Vec::with_capacity(600));
+    assert_eq!(pool.available(), 1);
+    assert_eq!(pool.idle_bytes(), 600);
+    assert_eq!(pool.stats().discarded, 1);
+
+    let reused = pool.acquire(600);
+    assert_eq!(pool.idle_bytes(), 0);
+    pool.release(reused);
+    pool.release(Vec::with_capacity(400));
+    assert_eq!(pool.available(), 2);
+    assert!(pool.idle_bytes() <= pool.max_bytes());
+}
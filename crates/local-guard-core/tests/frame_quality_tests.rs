@@ -0,0 +1,122 @@
+//! Tests blank and protected-content frame classification.
+
+use local_guard_core::{
+    DEFAULT_SAMPLE_STRIDE, Frame, FrameQuality, MIN_QUALITY_SAMPLES, QualityThresholds,
+    build_metadata, build_metadata_with_thresholds, classify_frame, classify_rgba,
+};
+
+fn solid(r: u8, g: u8, b: u8, pixels: usize) -> Vec<u8> {
+    [r, g, b, 255].repeat(pixels)
+}
+
+fn gradient(pixels: usize) -> Vec<u8> {
+    (0..pixels)
+        .flat_map(|index| {
+            let value = (index * 255 / pixels.max(1)) as u8;
+            [value, value, value, 255]
+        })
+        .collect()
+}
+
+#[test]
+fn frame_quality_tests_classify_black_uniform_and_normal() {
+    let thresholds = QualityThresholds::default();
+
+    assert_eq!(
+        classify_rgba(&solid(0, 0, 0, 64), &thresholds),
+        FrameQuality::Black
+    );
+    assert_eq!(
+        classify_rgba(&solid(4, 6, 3, 64), &thresholds),
+        FrameQuality::Black
+    );
+    assert_eq!(
+        classify_rgba(&solid(0, 120, 215, 64), &thresholds),
+        FrameQuality::Uniform
+    );
+    assert_eq!(
+        classify_rgba(&gradient(64), &thresholds),
+        FrameQuality::Normal
+    );
+    assert_eq!(classify_rgba(&[], &thresholds), FrameQuality::Black);
+}
+
+#[test]
+fn frame_quality_tests_detect_near_uniform_with_sparse_content() {
+    let thresholds = QualityThresholds::default();
+    let mut rgba = solid(30, 30, 30, 10_000);
+    // One bright pixel (e.g. a cursor on a lock screen) keeps it near-uniform.
+    rgba[0..4].copy_from_slice(&[255, 255, 255, 255]);
+    assert_eq!(classify_rgba(&rgba, &thresholds), FrameQuality::NearUniform);
+
+    // A 5% block of content is real content.
+    for pixel in rgba.chunks_exact_mut(4).take(500) {
+        pixel.copy_from_slice(&[255, 255, 255, 255]);
+    }
+    assert_eq!(classify_rgba(&rgba, &thresholds), FrameQuality::Normal);
+}
+
+#[test]
+fn frame_quality_tests_metadata_flags_blank_tiles() {
+    let frames: Vec<Frame> = (0..9_u64)
+        .map(|index| {
+            let rgba = if index % 3 == 0 {
+                solid(0, 0, 0, 16)
+            } else {
+                gradient(16)
+            };
+            Frame::new("display-1", 4, 4, 1_000 + index, rgba).expect("frame should be valid")
+        })
+        .collect();
+
+    let metadata = build_metadata(&frames, "session-abc").expect("metadata should build");
+    assert_eq!(metadata.blank_tiles, vec![0, 3, 6]);
+    assert!(classify_frame(&frames[0], &QualityThresholds::default()).is_blank());
+
+    let strict = QualityThresholds {
+        black_max_luma: 0,
+        uniform_max_spread: 0,
+        near_uniform_fraction: 1.0,
+        near_uniform_tolerance: 0,
+        sample_stride: 1,
+    };
+    let metadata =
+        build_metadata_with_thresholds(&frames, "session-abc", &strict).expect("should build");
+    assert_eq!(metadata.blank_tiles, vec![0, 3, 6]);
+
+    let json = serde_json::to_value(
+        build_metadata(&frames[1..3], "session-abc").expect("metadata should build"),
+    )
+    .expect("metadata should serialize");
+    assert!(json.get("blank_tiles").is_none());
+}
+
+#[test]
+fn frame_quality_tests_default_samples_large_frames() {
+    let thresholds = QualityThresholds::default();
+    assert_eq!(thresholds.sample_stride, DEFAULT_SAMPLE_STRIDE);
+
+    // A 256x256 black frame with a 16x16 window of content is sampled at
+    // the default stride and still classified as content.
+    let (width, height) = (256_u32, 256_u32);
+    assert!((width * height) as usize / MIN_QUALITY_SAMPLES >= DEFAULT_SAMPLE_STRIDE);
+    let mut rgba = solid(0, 0, 0, (width * height) as usize);
+    for y in 100..116 {
+        for x in 100..116 {
+            let offset = (y * width as usize + x) * 4;
+            rgba[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+        }
+    }
+    let frame = Frame::new("display-1", width, height, 1, rgba).expect("frame should be valid");
+    assert_eq!(classify_frame(&frame, &thresholds), FrameQuality::Normal);
+
+    let black = Frame::new(
+        "display-1",
+        width,
+        height,
+        2,
+        solid(0, 0, 0, (width * height) as usize),
+    )
+    .expect("frame should be valid");
+    assert_eq!(classify_frame(&black, &thresholds), FrameQuality::Black);
+}
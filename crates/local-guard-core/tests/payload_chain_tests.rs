@@ -0,0 +1,137 @@
+//! Tests payload hash-chain linking and verification.
+
+use local_guard_core::{
+    BatchMetadata, ChainState, CoreError, MosaicPayload, SCHEMA_VERSION_V1, chain_root_hash,
+    payload_chain_hash, verify_payload_chain, verify_payload_chain_from,
+};
+
+fn unchained_payload(session_id: &str, fill: u8) -> MosaicPayload {
+    MosaicPayload {
+        schema_version: SCHEMA_VERSION_V1.to_string(),
+        metadata: BatchMetadata {
+            start_timestamp_ms: 1,
+            end_timestamp_ms: 9,
+            screen_id: "display-a".to_string(),
+            source_width: 2,
+            source_height: 2,
+            session_id: session_id.to_string(),
+            frame_count: 9,
+            batch_id: None,
+            sequence_number: None,
+            prev_payload_hash: None,
+            device_id: None,
+            blank_tiles: Vec::new(),
+            tile_cursors: Vec::new(),
+            context: None,
+            provenance: None,
+        },
+        mosaic_width: 6,
+        mosaic_height: 6,
+        mosaic_rgba: vec![fill; 6 * 6 * 4].into(),
+    }
+}
+
+fn chain(session_id: &str, count: u8) -> Vec<MosaicPayload> {
+    let mut head = ChainState::root(session_id);
+    let mut payloads = Vec::new();
+    for fill in 0..count {
+        let mut payload = unchained_payload(session_id, fill);
+        payload.metadata.sequence_number = Some(head.sequence_number + 1);
+        payload.metadata.prev_payload_hash = Some(head.head_hash.clone());
+        head = head.advance(&payload).expect("payload should extend chain");
+        payloads.push(payload);
+    }
+    payloads
+}
+
+fn broken_index(result: Result<ChainState, CoreError>) -> usize {
+    match result {
+        Err(CoreError::ChainBroken { index, .. }) => index,
+        other => panic!("expected broken chain, got {other:?}"),
+    }
+}
+
+#[test]
+fn payload_chain_tests_complete_chain_verifies() {
+    let payloads = chain("session-a", 3);
+    let head = verify_payload_chain(&payloads).expect("chain should verify");
+
+    assert_eq!(head.session_id, "session-a");
+    assert_eq!(head.sequence_number, 3);
+    assert_eq!(
+        head.head_hash,
+        payload_chain_hash(&payloads[2]).expect("hash should compute")
+    );
+    assert_eq!(
+        payloads[0].metadata.prev_payload_hash.as_deref(),
+        Some(chain_root_hash("session-a").as_str())
+    );
+}
+
+#[test]
+fn payload_chain_tests_detects_dropped_payload() {
+    let mut payloads = chain("session-a", 3);
+    payloads.remove(1);
+    assert_eq!(broken_index(verify_payload_chain(&payloads)), 1);
+}
+
+#[test]
+fn payload_chain_tests_detects_reordered_payloads() {
+    let mut payloads = chain("session-a", 3);
+    payloads.swap(1, 2);
+    assert_eq!(broken_index(verify_payload_chain(&payloads)), 1);
+}
+
+#[test]
+fn payload_chain_tests_detects_tampered_content() {
+    let mut payloads = chain("session-a", 3);
+    payloads[1].mosaic_rgba = vec![255; 6 * 6 * 4].into();
+    assert_eq!(broken_index(verify_payload_chain(&payloads)), 2);
+
+    let mut payloads = chain("session-a", 3);
+    payloads[1].metadata.end_timestamp_ms += 1;
+    assert_eq!(broken_index(verify_payload_chain(&payloads)), 2);
+}
+
+#[test]
+fn payload_chain_tests_rejects_missing_or_foreign_links() {
+    let mut payloads = chain("session-a", 2);
+    payloads[1].metadata.prev_payload_hash = None;
+    assert_eq!(broken_index(verify_payload_chain(&payloads)), 1);
+
+    let mut payloads = chain("session-a", 2);
+    payloads[1].metadata.session_id = "session-b".to_string();
+    assert_eq!(broken_index(verify_payload_chain(&payloads)), 1);
+
+    assert_eq!(broken_index(verify_payload_chain(&[])), 0);
+}
+
+#[test]
+fn payload_chain_tests_rejects_chain_not_starting_at_root() {
+    let payloads = chain("session-a", 3);
+    assert_eq!(broken_index(verify_payload_chain(&payloads[1..])), 0);
+}
+
+#[test]
+fn payload_chain_tests_incremental_verification_matches_full() {
+    let payloads = chain("session-a", 4);
+    let midpoint = verify_payload_chain(&payloads[..2]).expect("prefix should verify");
+    let incremental =
+        verify_payload_chain_from(&midpoint, &payloads[2..]).expect("suffix should verify");
+    let full = verify_payload_chain(&payloads).expect("chain should verify");
+    assert_eq!(incremental, full);
+}
+
+#[test]
+fn payload_chain_tests_roots_are_per_session() {
+    assert_ne!(chain_root_hash("session-a"), chain_root_hash("session-b"));
+    assert_eq!(chain_root_hash("session-a").len(), 64);
+}
+
+#[test]
+fn payload_chain_tests_state_round_trips_json() {
+    let head = verify_payload_chain(&chain("session-a", 2)).expect("chain should verify");
+    let json = serde_json::to_string(&head).expect("state should serialize");
+    let decoded: ChainState = serde_json::from_str(&json).expect("state should deserialize");
+    assert_eq!(decoded, head);
+}
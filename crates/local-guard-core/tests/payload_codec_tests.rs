@@ -14,10 +14,18 @@ fn payload_codec_tests_round_trip_json() {
             source_height: 2,
             session_id: "session-abc".to_string(),
             frame_count: 9,
+            batch_id: None,
+            sequence_number: None,
+            prev_payload_hash: None,
+            device_id: None,
+            blank_tiles: Vec::new(),
+            tile_cursors: Vec::new(),
+            context: None,
+            provenance: None,
         },
         mosaic_width: 6,
         mosaic_height: 6,
-        mosaic_rgba: vec![9; 6 * 6 * 4],
+        mosaic_rgba: vec![9; 6 * 6 * 4].into(),
     };
 
     let encoded = payload.to_json_bytes().expect("encoding should succeed");
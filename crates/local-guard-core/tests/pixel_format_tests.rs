@@ -0,0 +1,169 @@
+//! Tests pixel formats, row stride validation, and format conversion.
+
+use local_guard_core::{
+    CoreError, Frame, FrameQuality, PixelFormat, QualityThresholds, classify_frame, convert_pixels,
+    validate_pixel_layout,
+};
+
+#[test]
+fn pixel_format_tests_bytes_per_pixel_matches_layout() {
+    assert_eq!(PixelFormat::Rgba8.bytes_per_pixel(), 4);
+    assert_eq!(PixelFormat::Bgra8.bytes_per_pixel(), 4);
+    assert_eq!(PixelFormat::Rgb8.bytes_per_pixel(), 3);
+    assert_eq!(PixelFormat::Gray8.bytes_per_pixel(), 1);
+}
+
+#[test]
+fn pixel_format_tests_layout_accepts_missing_padding_on_last_row() {
+    // Two rows of 2 RGBA pixels (8 bytes) with a 12-byte stride.
+    assert!(validate_pixel_layout(PixelFormat::Rgba8, 2, 2, 12, 24).is_ok());
+    assert!(validate_pixel_layout(PixelFormat::Rgba8, 2, 2, 12, 20).is_ok());
+    assert!(matches!(
+        validate_pixel_layout(PixelFormat::Rgba8, 2, 2, 12, 19),
+        Err(CoreError::InvalidFrameShape {
+            expected: 20,
+            actual: 19
+        })
+    ));
+    assert!(matches!(
+        validate_pixel_layout(PixelFormat::Rgba8, 2, 2, 12, 25),
+        Err(CoreError::InvalidFrameShape {
+            expected: 24,
+            actual: 25
+        })
+    ));
+}
+
+#[test]
+fn pixel_format_tests_short_stride_is_rejected() {
+    let result = Frame::with_format("display-a", 2, 1, 0, PixelFormat::Rgb8, 5, vec![0; 5]);
+    assert!(matches!(
+        result,
+        Err(CoreError::InvalidStride { stride: 5, min: 6 })
+    ));
+}
+
+#[test]
+fn pixel_format_tests_rgba_bgra_round_trip() {
+    let rgba = vec![10, 20, 30, 40, 50, 60, 70, 80];
+    let bgra = convert_pixels(&rgba, 2, 1, 8, PixelFormat::Rgba8, PixelFormat::Bgra8)
+        .expect("conversion should succeed");
+    assert_eq!(bgra, vec![30, 20, 10, 40, 70, 60, 50, 80]);
+
+    let back = convert_pixels(&bgra, 2, 1, 8, PixelFormat::Bgra8, PixelFormat::Rgba8)
+        .expect("conversion should succeed");
+    assert_eq!(back, rgba);
+}
+
+#[test]
+fn pixel_format_tests_conversion_drops_row_padding() {
+    // Two rows of one BGRA pixel each, padded to 8 bytes per row.
+    let padded = vec![1, 2, 3, 255, 9, 9, 9, 9, 4, 5, 6, 255];
+    let rgb = convert_pixels(&padded, 1, 2, 8, PixelFormat::Bgra8, PixelFormat::Rgb8)
+        .expect("conversion should succeed");
+    assert_eq!(rgb, vec![3, 2, 1, 6, 5, 4]);
+}
+
+#[test]
+fn pixel_format_tests_gray_expands_and_reduces() {
+    let rgba = convert_pixels(&[128], 1, 1, 1, PixelFormat::Gray8, PixelFormat::Rgba8)
+        .expect("conversion should succeed");
+    assert_eq!(rgba, vec![128, 128, 128, 255]);
+
+    let gray = convert_pixels(
+        &[255, 255, 255, 255, 0, 0, 0, 255],
+        2,
+        1,
+        8,
+        PixelFormat::Rgba8,
+        PixelFormat::Gray8,
+    )
+    .expect("conversion should succeed");
+    assert_eq!(gray, vec![255, 0]);
+}
+
+#[test]
+fn pixel_format_tests_frame_rows_skip_padding() {
+    let frame = Frame::with_format(
+        "display-a",
+        1,
+        2,
+        0,
+        PixelFormat::Rgb8,
+        4,
+        vec![1, 2, 3, 0, 4, 5, 6],
+    )
+    .expect("frame should be valid");
+
+    assert!(!frame.is_packed());
+    assert_eq!(frame.row(0), Some(&[1, 2, 3][..]));
+    assert_eq!(frame.row(1), Some(&[4, 5, 6][..]));
+    assert_eq!(frame.row(2), None);
+
+    let packed = frame
+        .to_format(PixelFormat::Rgba8)
+        .expect("conversion should succeed");
+    assert!(packed.is_packed());
+    assert_eq!(packed.pixels, vec![1, 2, 3, 255, 4, 5, 6, 255]);
+}
+
+#[test]
+fn pixel_format_tests_classify_ignores_padding_and_channel_order() {
+    // Black BGRA pixels with bright padding bytes that must not count.
+    let mut pixels = Vec::new();
+    for _ in 0..4 {
+        pixels.extend_from_slice(&[0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255]);
+    }
+    let frame = Frame::with_format("display-a", 2, 4, 0, PixelFormat::Bgra8, 12, pixels)
+        .expect("frame should be valid");
+
+    assert_eq!(
+        classify_frame(&frame, &QualityThresholds::default()),
+        FrameQuality::Black
+    );
+}
+
+#[test]
+fn pixel_format_tests_frame_json_defaults_to_rgba() {
+    let json = r#"{"screen_id":"display-a","width":1,"height":1,"captured_at_ms":0,"stride":4,"pixels":[1,2,3,4]}"#;
+    let frame: Frame = serde_json::from_str(json).expect("frame should deserialize");
+    assert_eq!(frame.format, PixelFormat::Rgba8);
+}
+
+#[test]
+fn pixel_format_tests_legacy_frame_json_loads_packed() {
+    // Frames serialized before strides existed carry `rgba` and no stride.
+    let json = r#"{"screen_id":"display-a","width":2,"height":1,"captured_at_ms":0,"rgba":[1,2,3,4,5,6,7,8]}"#;
+    let frame: Frame = serde_json::from_str(json).expect("legacy frame should deserialize");
+    assert_eq!(frame.stride, 8);
+    assert!(frame.is_packed());
+    assert_eq!(frame.pixels, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let round_trip: Frame =
+        serde_json::from_str(&serde_json::to_string(&frame).expect("frame should serialize"))
+            .expect("frame should deserialize");
+    assert_eq!(round_trip, frame);
+}
+
+#[test]
+fn pixel_format_tests_frame_json_rejects_short_buffer() {
+    let json = r#"{"screen_id":"display-a","width":1,"height":2,"captured_at_ms":0,"stride":8,"pixels":[1,2,3,4]}"#;
+    let error = serde_json::from_str::<Frame>(json).expect_err("buffer is too short");
+    assert!(error.to_string().contains("invalid frame shape"));
+
+    let json = r#"{"screen_id":"display-a","width":2,"height":1,"captured_at_ms":0,"stride":4,"pixels":[1,2,3,4]}"#;
+    assert!(
+        serde_json::from_str::<Frame>(json).is_err(),
+        "stride shorter than a row"
+    );
+}
+
+#[test]
+fn pixel_format_tests_row_is_checked_after_edits() {
+    let mut frame = Frame::new("display-a", 1, 2, 0, vec![1, 2, 3, 4, 5, 6, 7, 8])
+        .expect("frame should be valid");
+    frame.pixels = vec![1, 2, 3, 4].into();
+
+    assert_eq!(frame.row(0), Some(&[1, 2, 3, 4][..]));
+    assert_eq!(frame.row(1), None);
+}
@@ -0,0 +1,85 @@
+//! Tests shared, reference-counted frame and payload buffers.
+
+use local_guard_core::{BatchMetadata, Frame, FramePool, MosaicPayload, SharedBytes};
+
+fn payload() -> MosaicPayload {
+    MosaicPayload {
+        schema_version: "v1".to_string(),
+        metadata: BatchMetadata {
+            start_timestamp_ms: 1,
+            end_timestamp_ms: 9,
+            screen_id: "display-a".to_string(),
+            source_width: 1,
+            source_height: 1,
+            session_id: "session-abc".to_string(),
+            frame_count: 9,
+            batch_id: None,
+            sequence_number: None,
+            prev_payload_hash: None,
+            device_id: None,
+            blank_tiles: Vec::new(),
+            tile_cursors: Vec::new(),
+            context: None,
+            provenance: None,
+        },
+        mosaic_width: 3,
+        mosaic_height: 3,
+        mosaic_rgba: vec![7; 3 * 3 * 4].into(),
+    }
+}
+
+#[test]
+fn shared_bytes_tests_payload_clones_share_pixels() {
+    let original = payload();
+    let upload = original.clone();
+    let spool = original.clone();
+
+    assert!(upload.mosaic_rgba.ptr_eq(&original.mosaic_rgba));
+    assert!(spool.mosaic_rgba.ptr_eq(&original.mosaic_rgba));
+    assert_eq!(original.mosaic_rgba.ref_count(), 3);
+
+    let debug = format!("{:?}", original.mosaic_rgba);
+    assert!(debug.contains("len: 36"));
+    assert!(!debug.contains('7'), "debug output must not dump pixels");
+}
+
+#[test]
+fn shared_bytes_tests_serialize_like_v1_byte_array() {
+    let encoded: serde_json::Value =
+        serde_json::from_slice(&payload().to_json_bytes().expect("encoding should succeed"))
+            .expect("json should parse");
+    assert_eq!(encoded["mosaic_rgba"], serde_json::json!(vec![7_u8; 36]));
+
+    let fixture = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../contracts/fixtures/ingest-request.valid.json"
+    ))
+    .expect("fixture should be readable");
+    let decoded = MosaicPayload::from_json_bytes(&fixture).expect("fixture should decode");
+    assert_eq!(decoded.mosaic_rgba, vec![0, 0, 0, 255]);
+}
+
+#[test]
+fn shared_bytes_tests_last_frame_clone_returns_buffer_to_pool() {
+    let pool = FramePool::new(4);
+    let frame = Frame::new("display-1", 1, 1, 1, vec![1, 2, 3, 4])
+        .expect("frame should be valid")
+        .with_pool(&pool);
+    let preview = frame.clone();
+
+    drop(frame);
+    assert_eq!(pool.available(), 0, "buffer is still shared by the clone");
+    drop(preview);
+    assert_eq!(pool.available(), 1);
+
+    let bytes = SharedBytes::new(vec![1, 2]);
+    let other = bytes.clone();
+    let bytes = bytes
+        .try_into_vec()
+        .expect_err("shared buffer cannot be unwrapped");
+    drop(other);
+    assert_eq!(
+        bytes.try_into_vec().expect("unique buffer unwraps"),
+        vec![1, 2]
+    );
+}
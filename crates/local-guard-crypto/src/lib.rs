@@ -0,0 +1,368 @@
+#![warn(missing_docs)]
+//! # local-guard-crypto
+//!
+//! ## Purpose
+//! Gives each client installation an Ed25519 device identity and signs
+//! payloads with it, so a stolen bearer token alone cannot inject mosaics.
+//!
+//! ## Responsibilities
+//! - Generate, load, and persist the device keypair ([`DeviceKeyStore`]).
+//! - Produce detached payload signatures ([`PayloadSigner`], [`DeviceKey`]).
+//! - Format and parse the signature header shared with the server.
+//! - Verify signatures ([`verify_payload_signature`]) for servers and tests.
+//! - Prove key possession during device enrollment
+//!   ([`DeviceKey::sign_enrollment_proof`]).
+//! - Seal payload bodies to the analysis backend's X25519 key
+//!   ([`BodySealer`]) and open them for tests and tooling
+//!   ([`open_sealed_envelope`]).
+//!
+//! ## Data flow
+//! Canonical payload bytes -> [`payload_signing_message`] (domain-separated)
+//! -> Ed25519 signature -> [`format_signature_header`] -> upload envelope.
+//! When sealing is configured, the canonical bytes are then encrypted into a
+//! [`SealedEnvelope`]; the signature still covers the plaintext.
+//!
+//! ## Ownership and lifetimes
+//! [`DeviceKey`] owns its secret; the secret is zeroized on drop and never
+//! exposed through `Debug`. Public keys and signatures are plain values.
+//!
+//! ## Error model
+//! Malformed keys, signatures, and headers return [`CryptoError`]; a valid
+//! but wrong signature returns [`CryptoError::VerificationFailed`]. Sealed
+//! bodies that fail authentication return [`CryptoError::DecryptionFailed`].
+//!
+//! ## Security and privacy notes
+//! Signatures cover a domain-separated message, so the device key cannot be
+//! tricked into signing payload bytes for another protocol. Verification
+//! uses strict Ed25519 rules (no malleable or small-order keys).
+//!
+//! ## Example
+//! ```rust
+//! use local_guard_crypto::{DeviceKey, PayloadSigner, verify_payload_signature};
+//!
+//! let key = DeviceKey::from_seed([7; 32]);
+//! let signature = key.sign_payload(b"{\"schema_version\":\"v1\"}");
+//! verify_payload_signature(&key.public_key(), b"{\"schema_version\":\"v1\"}", &signature)
+//!     .expect("signature should verify");
+//! ```
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+mod protect;
+mod seal;
+mod store;
+
+pub use seal::{
+    BodySealer, SEAL_ALGORITHM, SEAL_DOMAIN, SEALED_CONTENT_TYPE, SEALED_ENVELOPE_VERSION_V1,
+    SealedEnvelope, ServerPublicKey, ServerSecretKey, open_sealed_envelope, open_sealed_json,
+};
+pub use store::{DEVICE_KEY_FILE_NAME, DeviceKeyStore};
+
+/// Algorithm name carried in signature headers.
+pub const SIGNATURE_ALGORITHM_ED25519: &str = "ed25519";
+
+/// HTTP header carrying the detached payload signature.
+pub const SIGNATURE_HEADER_NAME: &str = "X-Local-Guard-Signature";
+
+/// Domain separator prepended to canonical payload bytes before signing.
+pub const PAYLOAD_SIGNATURE_DOMAIN: &[u8] = b"local-guard/payload-signature/v1\0";
+
+/// Domain separator for enrollment proof-of-possession messages.
+pub const ENROLLMENT_PROOF_DOMAIN: &[u8] = b"local-guard/enrollment-proof/v1\0";
+
+/// Ed25519 device identity key.
+pub struct DeviceKey {
+    signing: SigningKey,
+}
+
+impl DeviceKey {
+    /// Generates a new key from OS randomness.
+    pub fn generate() -> Self {
+        let mut seed = [0_u8; 32];
+        StdRng::from_os_rng().fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+
+    /// Builds a key from a 32-byte secret seed (RFC 8032 private key).
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            signing: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Returns the public half of the key.
+    pub fn public_key(&self) -> DevicePublicKey {
+        DevicePublicKey(self.signing.verifying_key())
+    }
+
+    /// Signs an enrollment proof binding `enrollment_code` to this key.
+    pub fn sign_enrollment_proof(&self, enrollment_code: &str) -> PayloadSignature {
+        let message = enrollment_proof_message(enrollment_code, &self.public_key());
+        PayloadSignature(self.signing.sign(&message).to_bytes())
+    }
+
+    /// Returns the secret seed for persistence.
+    pub(crate) fn seed(&self) -> [u8; 32] {
+        self.signing.to_bytes()
+    }
+}
+
+impl std::fmt::Debug for DeviceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceKey")
+            .field("key_id", &self.public_key().key_id())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Ed25519 public key identifying a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DevicePublicKey(VerifyingKey);
+
+impl DevicePublicKey {
+    /// Parses a 32-byte compressed public key.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidKey`] for wrong length or invalid points.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| {
+            CryptoError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len()))
+        })?;
+        VerifyingKey::from_bytes(&bytes)
+            .map(Self)
+            .map_err(|error| CryptoError::InvalidKey(error.to_string()))
+    }
+
+    /// Parses a hex-encoded public key.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidKey`] for invalid hex or key bytes.
+    pub fn from_hex(value: &str) -> Result<Self, CryptoError> {
+        let bytes =
+            hex::decode(value).map_err(|error| CryptoError::InvalidKey(error.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the compressed key bytes.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// Returns the lowercase hex encoding of the key.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    /// Returns a short stable identifier: the first 8 bytes of the key's
+    /// SHA-256, hex-encoded.
+    pub fn key_id(&self) -> String {
+        let digest = Sha256::digest(self.to_bytes());
+        hex::encode(&digest[..8])
+    }
+}
+
+/// Detached Ed25519 signature (payloads and enrollment proofs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadSignature([u8; 64]);
+
+impl PayloadSignature {
+    /// Parses a 64-byte signature.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidSignature`] for wrong length.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        bytes.try_into().map(Self).map_err(|_| {
+            CryptoError::InvalidSignature(format!("expected 64 bytes, got {}", bytes.len()))
+        })
+    }
+
+    /// Parses a hex-encoded signature.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidSignature`] for invalid hex or length.
+    pub fn from_hex(value: &str) -> Result<Self, CryptoError> {
+        let bytes =
+            hex::decode(value).map_err(|error| CryptoError::InvalidSignature(error.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the raw signature bytes.
+    pub fn to_bytes(&self) -> [u8; 64] {
+        self.0
+    }
+
+    /// Returns the lowercase hex encoding of the signature.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+/// Signs canonical payload bytes on behalf of a device.
+///
+/// Implemented by [`DeviceKey`]; tests and hardware-backed keys may supply
+/// their own implementation.
+pub trait PayloadSigner: Send + Sync {
+    /// Returns the identifier of the signing key ([`DevicePublicKey::key_id`]).
+    fn key_id(&self) -> String;
+
+    /// Signs `canonical_payload` (the plaintext JSON payload body).
+    fn sign_payload(&self, canonical_payload: &[u8]) -> PayloadSignature;
+}
+
+impl PayloadSigner for DeviceKey {
+    fn key_id(&self) -> String {
+        self.public_key().key_id()
+    }
+
+    fn sign_payload(&self, canonical_payload: &[u8]) -> PayloadSignature {
+        let message = payload_signing_message(canonical_payload);
+        PayloadSignature(self.signing.sign(&message).to_bytes())
+    }
+}
+
+/// Returns the exact message signed for `canonical_payload`:
+/// [`PAYLOAD_SIGNATURE_DOMAIN`] followed by the payload bytes.
+pub fn payload_signing_message(canonical_payload: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(PAYLOAD_SIGNATURE_DOMAIN.len() + canonical_payload.len());
+    message.extend_from_slice(PAYLOAD_SIGNATURE_DOMAIN);
+    message.extend_from_slice(canonical_payload);
+    message
+}
+
+/// Verifies a detached payload signature.
+///
+/// # Errors
+/// Returns [`CryptoError::VerificationFailed`] when the signature does not
+/// match `canonical_payload` under `public_key`.
+pub fn verify_payload_signature(
+    public_key: &DevicePublicKey,
+    canonical_payload: &[u8],
+    signature: &PayloadSignature,
+) -> Result<(), CryptoError> {
+    let message = payload_signing_message(canonical_payload);
+    public_key
+        .0
+        .verify_strict(&message, &Signature::from_bytes(&signature.0))
+        .map_err(|_| CryptoError::VerificationFailed)
+}
+
+/// Returns the message signed as enrollment proof:
+/// [`ENROLLMENT_PROOF_DOMAIN`], the public key bytes, then the code.
+pub fn enrollment_proof_message(enrollment_code: &str, public_key: &DevicePublicKey) -> Vec<u8> {
+    let mut message =
+        Vec::with_capacity(ENROLLMENT_PROOF_DOMAIN.len() + 32 + enrollment_code.len());
+    message.extend_from_slice(ENROLLMENT_PROOF_DOMAIN);
+    message.extend_from_slice(&public_key.to_bytes());
+    message.extend_from_slice(enrollment_code.as_bytes());
+    message
+}
+
+/// Verifies that `signature` proves possession of `public_key` for
+/// `enrollment_code`.
+///
+/// # Errors
+/// Returns [`CryptoError::VerificationFailed`] when the proof is invalid.
+pub fn verify_enrollment_proof(
+    public_key: &DevicePublicKey,
+    enrollment_code: &str,
+    signature: &PayloadSignature,
+) -> Result<(), CryptoError> {
+    let message = enrollment_proof_message(enrollment_code, public_key);
+    public_key
+        .0
+        .verify_strict(&message, &Signature::from_bytes(&signature.0))
+        .map_err(|_| CryptoError::VerificationFailed)
+}
+
+/// Parsed value of the [`SIGNATURE_HEADER_NAME`] header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureHeader {
+    /// Identifier of the signing device key.
+    pub key_id: String,
+    /// Detached signature.
+    pub signature: PayloadSignature,
+}
+
+/// Formats the signature header value:
+/// `alg=ed25519;key_id=<hex>;sig=<hex>`.
+pub fn format_signature_header(key_id: &str, signature: &PayloadSignature) -> String {
+    format!(
+        "alg={SIGNATURE_ALGORITHM_ED25519};key_id={key_id};sig={}",
+        signature.to_hex()
+    )
+}
+
+/// Parses a value produced by [`format_signature_header`].
+///
+/// # Errors
+/// Returns [`CryptoError::InvalidSignature`] for missing or duplicate
+/// fields, unsupported algorithms, or malformed signatures.
+pub fn parse_signature_header(value: &str) -> Result<SignatureHeader, CryptoError> {
+    let mut algorithm = None;
+    let mut key_id = None;
+    let mut signature = None;
+    for part in value.split(';') {
+        let (name, field) = part
+            .trim()
+            .split_once('=')
+            .ok_or_else(|| CryptoError::InvalidSignature(format!("malformed field: {part}")))?;
+        let slot = match name {
+            "alg" => &mut algorithm,
+            "key_id" => &mut key_id,
+            "sig" => &mut signature,
+            other => {
+                return Err(CryptoError::InvalidSignature(format!(
+                    "unknown field: {other}"
+                )));
+            }
+        };
+        if slot.replace(field.to_string()).is_some() {
+            return Err(CryptoError::InvalidSignature(format!(
+                "duplicate field: {name}"
+            )));
+        }
+    }
+
+    let missing = |name: &str| CryptoError::InvalidSignature(format!("missing field: {name}"));
+    let algorithm = algorithm.ok_or_else(|| missing("alg"))?;
+    if algorithm != SIGNATURE_ALGORITHM_ED25519 {
+        return Err(CryptoError::InvalidSignature(format!(
+            "unsupported algorithm: {algorithm}"
+        )));
+    }
+    let key_id = key_id
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| missing("key_id"))?;
+    let signature = PayloadSignature::from_hex(&signature.ok_or_else(|| missing("sig"))?)?;
+    Ok(SignatureHeader { key_id, signature })
+}
+
+/// Device identity and signature errors.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// Key bytes are malformed.
+    #[error("invalid device key: {0}")]
+    InvalidKey(String),
+    /// Signature or signature header is malformed.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    /// Signature is well-formed but does not verify.
+    #[error("signature verification failed")]
+    VerificationFailed,
+    /// Key file could not be read, protected, or written.
+    #[error("device key storage failure: {0}")]
+    KeyStore(String),
+    /// Body could not be sealed to the server key.
+    #[error("encryption failed: {0}")]
+    EncryptionFailed(String),
+    /// Sealed envelope is malformed or uses an unsupported format.
+    #[error("invalid sealed envelope: {0}")]
+    InvalidEnvelope(String),
+    /// Sealed envelope does not decrypt under the given key.
+    #[error("decryption failed")]
+    DecryptionFailed,
+}
@@ -0,0 +1,129 @@
+//! # Module: protect
+//!
+//! ## Purpose
+//! Wraps the device key seed at rest with the platform's user-bound secret
+//! storage where one is available.
+//!
+//! ## Responsibilities
+//! - Windows: protect with DPAPI (current-user scope, no UI).
+//! - Other platforms: pass through; the key store restricts file mode.
+//!
+//! ## Invariants
+//! - [`unprotect`] accepts exactly the scheme names [`protect`] emits.
+//!
+//! ## Error model
+//! OS failures and unknown schemes return [`CryptoError::KeyStore`].
+//!
+//! ## Security and privacy notes
+//! DPAPI blobs only decrypt for the same Windows user on the same machine,
+//! so a copied key file cannot sign from another host.
+
+use crate::CryptoError;
+
+/// Scheme name for unwrapped seeds.
+pub(crate) const PROTECTION_NONE: &str = "none";
+/// Scheme name for DPAPI-wrapped seeds.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) const PROTECTION_DPAPI: &str = "dpapi";
+
+/// Wraps `secret`; returns the scheme name and the stored bytes.
+pub(crate) fn protect(secret: &[u8]) -> Result<(&'static str, Vec<u8>), CryptoError> {
+    #[cfg(windows)]
+    {
+        dpapi::protect(secret).map(|blob| (PROTECTION_DPAPI, blob))
+    }
+    #[cfg(not(windows))]
+    {
+        Ok((PROTECTION_NONE, secret.to_vec()))
+    }
+}
+
+/// Unwraps bytes produced by [`protect`] under `scheme`.
+pub(crate) fn unprotect(scheme: &str, stored: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    match scheme {
+        PROTECTION_NONE => Ok(stored.to_vec()),
+        #[cfg(windows)]
+        PROTECTION_DPAPI => dpapi::unprotect(stored),
+        other => Err(CryptoError::KeyStore(format!(
+            "unsupported key protection scheme on this platform: {other}"
+        ))),
+    }
+}
+
+#[cfg(windows)]
+mod dpapi {
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Cryptography::{
+        CRYPT_INTEGER_BLOB, CRYPTPROTECT_UI_FORBIDDEN, CryptProtectData, CryptUnprotectData,
+    };
+
+    use crate::CryptoError;
+
+    pub(super) fn protect(secret: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        transform(secret, true)
+    }
+
+    pub(super) fn unprotect(blob: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        transform(blob, false)
+    }
+
+    fn transform(input: &[u8], protect: bool) -> Result<Vec<u8>, CryptoError> {
+        let length = u32::try_from(input.len())
+            .map_err(|_| CryptoError::KeyStore("key blob too large".to_string()))?;
+        let input_blob = CRYPT_INTEGER_BLOB {
+            cbData: length,
+            pbData: input.as_ptr().cast_mut(),
+        };
+        let mut output = CRYPT_INTEGER_BLOB {
+            cbData: 0,
+            pbData: std::ptr::null_mut(),
+        };
+
+        // Safety:
+        // - `input_blob` points at `input`, which outlives the call; DPAPI
+        //   only reads it.
+        // - On success DPAPI allocates `output.pbData` with LocalAlloc; it is
+        //   copied and released with LocalFree below.
+        let ok = unsafe {
+            if protect {
+                CryptProtectData(
+                    &input_blob,
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    CRYPTPROTECT_UI_FORBIDDEN,
+                    &mut output,
+                )
+            } else {
+                CryptUnprotectData(
+                    &input_blob,
+                    std::ptr::null_mut(),
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    CRYPTPROTECT_UI_FORBIDDEN,
+                    &mut output,
+                )
+            }
+        };
+        if ok == 0 || output.pbData.is_null() {
+            return Err(CryptoError::KeyStore(format!(
+                "DPAPI {} failed: {}",
+                if protect { "protect" } else { "unprotect" },
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        // Safety:
+        // - DPAPI reported success, so `pbData` holds `cbData` bytes.
+        let bytes =
+            unsafe { std::slice::from_raw_parts(output.pbData, output.cbData as usize) }.to_vec();
+        // Safety:
+        // - `pbData` was allocated by DPAPI with LocalAlloc and is freed once.
+        unsafe {
+            LocalFree(output.pbData.cast());
+        }
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,370 @@
+//! # Module: seal
+//!
+//! ## Purpose
+//! Encrypts payload bodies end-to-end to the analysis backend, so staged
+//! artifacts and upload bodies stay confidential even where TLS terminates
+//! early or files are copied off the machine.
+//!
+//! ## Responsibilities
+//! - Hold the configured server public key ([`ServerPublicKey`]).
+//! - Seal plaintext into a [`SealedEnvelope`] with a fresh ephemeral key.
+//! - Open envelopes for tests and tooling ([`open_sealed_envelope`]).
+//!
+//! ## Invariants
+//! - Every envelope uses its own ephemeral X25519 key and random nonce.
+//! - The AEAD associated data binds version, algorithm, content type,
+//!   recipient key id, and ephemeral public key; editing any of them fails
+//!   decryption.
+//! - Non-contributory (low-order) key agreements are rejected on both sides.
+//!
+//! ## Error model
+//! Malformed keys return [`CryptoError::InvalidKey`]; malformed envelopes
+//! return [`CryptoError::InvalidEnvelope`]; authentication failures return
+//! [`CryptoError::DecryptionFailed`] without detail.
+//!
+//! ## Security and privacy notes
+//! The client only ever holds the server public key. [`ServerSecretKey`]
+//! exists for tests and offline tooling and never appears in `Debug` output.
+
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::CryptoError;
+
+/// Envelope format version written by [`BodySealer`].
+pub const SEALED_ENVELOPE_VERSION_V1: &str = "v1";
+
+/// Algorithm identifier for X25519 + HKDF-SHA256 + ChaCha20-Poly1305.
+pub const SEAL_ALGORITHM: &str = "x25519-hkdf-sha256-chacha20poly1305";
+
+/// Domain separator used as HKDF info and AAD prefix.
+pub const SEAL_DOMAIN: &[u8] = b"local-guard/seal/v1\0";
+
+/// Content type of a sealed upload body or staged artifact.
+pub const SEALED_CONTENT_TYPE: &str = "application/vnd.local-guard.sealed+json";
+
+const NONCE_LEN: usize = 12;
+
+/// X25519 public key of the analysis backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerPublicKey(PublicKey);
+
+impl ServerPublicKey {
+    /// Parses a 32-byte X25519 public key.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidKey`] for wrong length.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| {
+            CryptoError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len()))
+        })?;
+        Ok(Self(PublicKey::from(bytes)))
+    }
+
+    /// Parses a hex-encoded X25519 public key.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidKey`] for invalid hex or length.
+    pub fn from_hex(value: &str) -> Result<Self, CryptoError> {
+        let bytes =
+            hex::decode(value).map_err(|error| CryptoError::InvalidKey(error.to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the raw key bytes.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// Returns the lowercase hex encoding of the key.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    /// Returns the first 8 bytes of the key's SHA-256, hex-encoded.
+    pub fn key_id(&self) -> String {
+        let digest = Sha256::digest(self.to_bytes());
+        hex::encode(&digest[..8])
+    }
+}
+
+/// X25519 secret key of the analysis backend (tests and tooling only).
+#[derive(Clone)]
+pub struct ServerSecretKey(StaticSecret);
+
+impl ServerSecretKey {
+    /// Generates a new key from OS randomness.
+    pub fn generate() -> Self {
+        let mut bytes = [0_u8; 32];
+        StdRng::from_os_rng().fill_bytes(&mut bytes);
+        Self::from_bytes(bytes)
+    }
+
+    /// Builds a key from 32 secret bytes (clamped per RFC 7748).
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(StaticSecret::from(bytes))
+    }
+
+    /// Parses a hex-encoded secret key.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidKey`] for invalid hex or length.
+    pub fn from_hex(value: &str) -> Result<Self, CryptoError> {
+        let bytes =
+            hex::decode(value).map_err(|error| CryptoError::InvalidKey(error.to_string()))?;
+        let bytes: [u8; 32] = bytes.as_slice().try_into().map_err(|_| {
+            CryptoError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len()))
+        })?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Returns the matching public key.
+    pub fn public_key(&self) -> ServerPublicKey {
+        ServerPublicKey(PublicKey::from(&self.0))
+    }
+}
+
+impl std::fmt::Debug for ServerSecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerSecretKey")
+            .field("key_id", &self.public_key().key_id())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Encrypted body as defined by `contracts/sealed-envelope.schema.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedEnvelope {
+    /// Envelope format version.
+    pub version: String,
+    /// Key agreement, KDF, and AEAD identifier.
+    pub algorithm: String,
+    /// Key id of the server public key the body is sealed to.
+    pub recipient_key_id: String,
+    /// Hex-encoded ephemeral X25519 public key.
+    pub ephemeral_public_key: String,
+    /// Hex-encoded 12-byte AEAD nonce.
+    pub nonce: String,
+    /// Base64 (standard, padded) ciphertext including the Poly1305 tag.
+    pub ciphertext: String,
+    /// Media type of the plaintext (for example `application/json`).
+    pub content_type: String,
+}
+
+impl SealedEnvelope {
+    /// Serializes the envelope as compact JSON.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidEnvelope`] if serialization fails.
+    pub fn to_json_bytes(&self) -> Result<Vec<u8>, CryptoError> {
+        serde_json::to_vec(self).map_err(|error| CryptoError::InvalidEnvelope(error.to_string()))
+    }
+
+    /// Parses an envelope from JSON bytes.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::InvalidEnvelope`] for malformed JSON.
+    pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        serde_json::from_slice(bytes)
+            .map_err(|error| CryptoError::InvalidEnvelope(error.to_string()))
+    }
+}
+
+/// Seals plaintext bodies to one server public key.
+#[derive(Debug, Clone)]
+pub struct BodySealer {
+    recipient: ServerPublicKey,
+}
+
+impl BodySealer {
+    /// Creates a sealer for `recipient`.
+    pub fn new(recipient: ServerPublicKey) -> Self {
+        Self { recipient }
+    }
+
+    /// Returns the recipient key id recorded in every envelope.
+    pub fn recipient_key_id(&self) -> String {
+        self.recipient.key_id()
+    }
+
+    /// Seals `plaintext` with a fresh ephemeral key and random nonce.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::EncryptionFailed`] if key agreement is
+    /// non-contributory or the AEAD rejects the input.
+    pub fn seal(
+        &self,
+        plaintext: &[u8],
+        content_type: &str,
+    ) -> Result<SealedEnvelope, CryptoError> {
+        let mut rng = StdRng::from_os_rng();
+        let mut ephemeral = [0_u8; 32];
+        rng.fill_bytes(&mut ephemeral);
+        let mut nonce = [0_u8; NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+        self.seal_with(plaintext, content_type, ephemeral, nonce)
+    }
+
+    /// Seals with caller-supplied ephemeral secret and nonce.
+    ///
+    /// Exists so shared test vectors are reproducible; production code must
+    /// use [`BodySealer::seal`] and never reuse either input.
+    ///
+    /// # Errors
+    /// Same as [`BodySealer::seal`].
+    pub fn seal_with(
+        &self,
+        plaintext: &[u8],
+        content_type: &str,
+        ephemeral_secret: [u8; 32],
+        nonce: [u8; NONCE_LEN],
+    ) -> Result<SealedEnvelope, CryptoError> {
+        let ephemeral = StaticSecret::from(ephemeral_secret);
+        let ephemeral_public = PublicKey::from(&ephemeral);
+        let shared = ephemeral.diffie_hellman(&self.recipient.0);
+        // Why: a low-order recipient key yields an all-zero secret that any
+        // observer could compute.
+        if !shared.was_contributory() {
+            return Err(CryptoError::EncryptionFailed(
+                "non-contributory key agreement".to_string(),
+            ));
+        }
+
+        let mut envelope = SealedEnvelope {
+            version: SEALED_ENVELOPE_VERSION_V1.to_string(),
+            algorithm: SEAL_ALGORITHM.to_string(),
+            recipient_key_id: self.recipient.key_id(),
+            ephemeral_public_key: hex::encode(ephemeral_public.as_bytes()),
+            nonce: hex::encode(nonce),
+            ciphertext: String::new(),
+            content_type: content_type.to_string(),
+        };
+        let cipher = cipher_for(
+            shared.as_bytes(),
+            ephemeral_public.as_bytes(),
+            self.recipient.0.as_bytes(),
+        );
+        let aad = associated_data(&envelope);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| CryptoError::EncryptionFailed("aead encryption failed".to_string()))?;
+        envelope.ciphertext = base64::engine::general_purpose::STANDARD.encode(ciphertext);
+        Ok(envelope)
+    }
+}
+
+/// Decrypts `envelope` with the server secret key.
+///
+/// # Errors
+/// Returns [`CryptoError::InvalidEnvelope`] for unsupported versions or
+/// malformed fields, and [`CryptoError::DecryptionFailed`] when the key does
+/// not match or authentication fails.
+pub fn open_sealed_envelope(
+    secret: &ServerSecretKey,
+    envelope: &SealedEnvelope,
+) -> Result<Vec<u8>, CryptoError> {
+    if envelope.version != SEALED_ENVELOPE_VERSION_V1 {
+        return Err(CryptoError::InvalidEnvelope(format!(
+            "unsupported version {}",
+            envelope.version
+        )));
+    }
+    if envelope.algorithm != SEAL_ALGORITHM {
+        return Err(CryptoError::InvalidEnvelope(format!(
+            "unsupported algorithm {}",
+            envelope.algorithm
+        )));
+    }
+
+    let recipient = secret.public_key();
+    if envelope.recipient_key_id != recipient.key_id() {
+        return Err(CryptoError::DecryptionFailed);
+    }
+
+    let ephemeral_bytes: [u8; 32] =
+        decode_hex_field(&envelope.ephemeral_public_key, "ephemeral_public_key")?;
+    let nonce: [u8; NONCE_LEN] = decode_hex_field(&envelope.nonce, "nonce")?;
+    let ciphertext = base64::engine::general_purpose::STANDARD
+        .decode(&envelope.ciphertext)
+        .map_err(|error| CryptoError::InvalidEnvelope(format!("ciphertext: {error}")))?;
+
+    let ephemeral_public = PublicKey::from(ephemeral_bytes);
+    let shared = secret.0.diffie_hellman(&ephemeral_public);
+    if !shared.was_contributory() {
+        return Err(CryptoError::DecryptionFailed);
+    }
+
+    let cipher = cipher_for(shared.as_bytes(), &ephemeral_bytes, recipient.0.as_bytes());
+    let aad = associated_data(envelope);
+    cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: &aad,
+            },
+        )
+        .map_err(|_| CryptoError::DecryptionFailed)
+}
+
+/// Parses envelope JSON bytes and decrypts them with the server secret key.
+///
+/// # Errors
+/// Same as [`SealedEnvelope::from_json_bytes`] and [`open_sealed_envelope`].
+pub fn open_sealed_json(secret: &ServerSecretKey, bytes: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let envelope = SealedEnvelope::from_json_bytes(bytes)?;
+    open_sealed_envelope(secret, &envelope)
+}
+
+fn cipher_for(
+    shared: &[u8; 32],
+    ephemeral_public: &[u8; 32],
+    recipient: &[u8; 32],
+) -> ChaCha20Poly1305 {
+    let mut salt = [0_u8; 64];
+    salt[..32].copy_from_slice(ephemeral_public);
+    salt[32..].copy_from_slice(recipient);
+    let hkdf = Hkdf::<Sha256>::new(Some(&salt), shared);
+    let mut key = [0_u8; 32];
+    // Invariant: 32 bytes is far below the HKDF-SHA256 output limit.
+    hkdf.expand(SEAL_DOMAIN, &mut key)
+        .expect("32-byte HKDF output is always valid");
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+fn associated_data(envelope: &SealedEnvelope) -> Vec<u8> {
+    let mut aad = SEAL_DOMAIN.to_vec();
+    // Why: length prefixes keep field boundaries unambiguous.
+    for field in [
+        envelope.version.as_str(),
+        envelope.algorithm.as_str(),
+        envelope.content_type.as_str(),
+        envelope.recipient_key_id.as_str(),
+        envelope.ephemeral_public_key.as_str(),
+    ] {
+        aad.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        aad.extend_from_slice(field.as_bytes());
+    }
+    aad
+}
+
+fn decode_hex_field<const N: usize>(value: &str, name: &str) -> Result<[u8; N], CryptoError> {
+    let bytes = hex::decode(value)
+        .map_err(|error| CryptoError::InvalidEnvelope(format!("{name}: {error}")))?;
+    bytes.as_slice().try_into().map_err(|_| {
+        CryptoError::InvalidEnvelope(format!("{name}: expected {N} bytes, got {}", bytes.len()))
+    })
+}
@@ -0,0 +1,175 @@
+//! # Module: store
+//!
+//! ## Purpose
+//! Persists the device key so a client keeps one identity across restarts.
+//!
+//! ## Responsibilities
+//! - Create a key on first use and reuse it afterwards.
+//! - Store the seed wrapped by platform protection (DPAPI on Windows).
+//! - Detect corrupt or mismatched key files instead of silently rotating.
+//!
+//! ## Invariants
+//! - Saves write a sibling temp file and rename it over the target.
+//! - A loaded key always matches the public key recorded next to it.
+//!
+//! ## Error model
+//! I/O, decode, and protection failures return [`CryptoError::KeyStore`].
+//!
+//! ## Security and privacy notes
+//! On Unix the file is created with mode `0600`. The public key is stored
+//! in clear so tooling can read the key id without unwrapping the seed.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::protect::{protect, unprotect};
+use crate::{CryptoError, DeviceKey, SIGNATURE_ALGORITHM_ED25519};
+
+/// File name used for the device key inside the runtime directory.
+pub const DEVICE_KEY_FILE_NAME: &str = "device-key.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredDeviceKey {
+    algorithm: String,
+    protection: String,
+    seed: String,
+    public_key: String,
+}
+
+/// File-backed device key storage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceKeyStore {
+    path: PathBuf,
+}
+
+impl DeviceKeyStore {
+    /// Creates a store backed by the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a store for [`DEVICE_KEY_FILE_NAME`] inside `dir`.
+    pub fn in_dir(dir: impl AsRef<Path>) -> Self {
+        Self::new(dir.as_ref().join(DEVICE_KEY_FILE_NAME))
+    }
+
+    /// Returns the backing file path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the stored key; `Ok(None)` when no key was created yet.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::KeyStore`] when the file is unreadable,
+    /// corrupt, or its seed does not match its public key.
+    pub fn load(&self) -> Result<Option<DeviceKey>, CryptoError> {
+        let raw = match std::fs::read(&self.path) {
+            Ok(raw) => raw,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(self.io_error("read", error)),
+        };
+        let stored: StoredDeviceKey =
+            serde_json::from_slice(&raw).map_err(|error| self.decode_error(&error.to_string()))?;
+        if stored.algorithm != SIGNATURE_ALGORITHM_ED25519 {
+            return Err(self.decode_error(&format!("unsupported algorithm {}", stored.algorithm)));
+        }
+
+        let wrapped =
+            hex::decode(&stored.seed).map_err(|error| self.decode_error(&error.to_string()))?;
+        let seed: [u8; 32] = unprotect(&stored.protection, &wrapped)?
+            .try_into()
+            .map_err(|_| self.decode_error("seed is not 32 bytes"))?;
+        let key = DeviceKey::from_seed(seed);
+
+        // Invariant:
+        // - A mismatch means the file was edited or partially restored;
+        //   signing with a different key would look like impersonation.
+        if key.public_key().to_hex() != stored.public_key {
+            return Err(self.decode_error("seed does not match recorded public key"));
+        }
+        Ok(Some(key))
+    }
+
+    /// Loads the stored key or creates, saves, and returns a new one.
+    ///
+    /// # Errors
+    /// Same as [`Self::load`] and [`Self::save`].
+    pub fn load_or_create(&self) -> Result<DeviceKey, CryptoError> {
+        if let Some(key) = self.load()? {
+            return Ok(key);
+        }
+        let key = DeviceKey::generate();
+        self.save(&key)?;
+        Ok(key)
+    }
+
+    /// Atomically writes `key` to the store.
+    ///
+    /// # Errors
+    /// Returns [`CryptoError::KeyStore`] when protection or I/O fails.
+    pub fn save(&self, key: &DeviceKey) -> Result<(), CryptoError> {
+        let (protection, wrapped) = protect(&key.seed())?;
+        let stored = StoredDeviceKey {
+            algorithm: SIGNATURE_ALGORITHM_ED25519.to_string(),
+            protection: protection.to_string(),
+            seed: hex::encode(wrapped),
+            public_key: key.public_key().to_hex(),
+        };
+        let encoded = serde_json::to_vec_pretty(&stored)
+            .map_err(|error| CryptoError::KeyStore(format!("failed to encode key: {error}")))?;
+
+        if let Some(parent) = self
+            .path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)
+                .map_err(|error| self.io_error("create dir for", error))?;
+        }
+        let mut temp_name = self.path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+
+        let mut file =
+            create_private_file(&temp_path).map_err(|error| self.io_error("write", error))?;
+        file.write_all(&encoded)
+            .and_then(|()| file.sync_all())
+            .map_err(|error| self.io_error("write", error))?;
+        drop(file);
+        std::fs::rename(&temp_path, &self.path).map_err(|error| self.io_error("replace", error))
+    }
+
+    fn io_error(&self, action: &str, error: std::io::Error) -> CryptoError {
+        CryptoError::KeyStore(format!(
+            "failed to {action} {}: {error}",
+            self.path.display()
+        ))
+    }
+
+    fn decode_error(&self, reason: &str) -> CryptoError {
+        CryptoError::KeyStore(format!(
+            "corrupt key file {}: {reason}",
+            self.path.display()
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn create_private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    std::fs::File::create(path)
+}
@@ -8,6 +8,8 @@
 //! - Validate the frame count and geometry for one mosaic batch.
 //! - Map chronological frames into row-major tile coordinates.
 //! - Return upload-ready mosaic image bytes.
+//! - Optionally draw the output buffer from a [`FramePool`].
+//! - Optionally mark each tile's visible cursor with a [`CursorOverlay`].
 //!
 //! ## Data flow
 //! Completed frame batch -> [`compose_temporal_mosaic`] -> [`MosaicImage`]
@@ -15,16 +17,19 @@
 //!
 //! ## Ownership and lifetimes
 //! Mosaic output owns its byte buffer, enabling downstream upload retries
-//! without borrowing the source frame collection.
+//! without borrowing the source frame collection. When composed with a pool,
+//! callers return the buffer via [`FramePool::release_shared`] once the last
+//! consumer is done with it.
 //!
 //! ## Error model
 //! Non-9-frame inputs or geometry mismatches fail with [`MosaicError`].
 //!
 //! ## Security and privacy notes
-//! Mosaic composition mutates no content; it only rearranges existing frame
-//! pixels according to deterministic temporal ordering.
+//! Mosaic composition only rearranges existing frame pixels according to
+//! deterministic temporal ordering; the opt-in cursor overlay is the only
+//! content it adds.
 
-use local_guard_core::Frame;
+use local_guard_core::{Frame, FramePool, PixelFormat, SharedBytes, convert_row};
 use thiserror::Error;
 
 /// Required frame count for one 3x3 temporal mosaic.
@@ -37,8 +42,48 @@ pub struct MosaicImage {
     pub width: u32,
     /// Mosaic height in pixels (`tile_height * 3`).
     pub height: u32,
-    /// RGBA bytes in row-major order.
-    pub rgba: Vec<u8>,
+    /// Pixel layout of `pixels` (tightly packed).
+    pub format: PixelFormat,
+    /// Pixel bytes in row-major order, shareable without copying.
+    pub pixels: SharedBytes,
+}
+
+/// Options for [`compose_temporal_mosaic_with`].
+#[derive(Debug, Clone, Default)]
+pub struct MosaicOptions {
+    /// Pool for the output buffer; `None` allocates a fresh buffer.
+    pub pool: Option<FramePool>,
+    /// Pixel format of the composed mosaic; input frames are converted
+    /// per row, whatever their own format and stride.
+    pub output_format: PixelFormat,
+    /// Cursor marker drawn on tiles whose frame reports a visible cursor;
+    /// `None` leaves tiles untouched.
+    pub cursor_overlay: Option<CursorOverlay>,
+}
+
+/// Crosshair drawn at the cursor hotspot of each tile.
+///
+/// # Invariants
+/// - The marker is clipped to its own tile and never bleeds into neighbours.
+/// - Output depends only on frame cursor state and these settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorOverlay {
+    /// Arm length in pixels on each side of the hotspot.
+    pub radius: u32,
+    /// Marker color as RGBA, converted to the output format.
+    pub color: [u8; 4],
+}
+
+impl Default for CursorOverlay {
+    fn default() -> Self {
+        // Why:
+        // - Opaque magenta is rare in desktop content, so the marker stays
+        //   distinguishable from the UI underneath.
+        Self {
+            radius: 8,
+            color: [255, 0, 255, 255],
+        }
+    }
 }
 
 /// Composes a deterministic 3x3 temporal mosaic.
@@ -48,8 +93,20 @@ pub struct MosaicImage {
 ///
 /// # Errors
 /// Returns [`MosaicError::InvalidFrameCount`] when frame count is not exactly 9.
-/// Returns [`MosaicError::GeometryMismatch`] when any frame geometry differs.
+/// Returns [`MosaicError::GeometryMismatch`] when any frame geometry differs
+/// or a frame's buffer is too short for its geometry.
 pub fn compose_temporal_mosaic(frames: &[Frame]) -> Result<MosaicImage, MosaicError> {
+    compose_temporal_mosaic_with(frames, &MosaicOptions::default())
+}
+
+/// Composes a deterministic 3x3 temporal mosaic with explicit options.
+///
+/// # Errors
+/// Same as [`compose_temporal_mosaic`].
+pub fn compose_temporal_mosaic_with(
+    frames: &[Frame],
+    options: &MosaicOptions,
+) -> Result<MosaicImage, MosaicError> {
     if frames.len() != MOSAIC_FRAME_COUNT {
         return Err(MosaicError::InvalidFrameCount {
             expected: MOSAIC_FRAME_COUNT,
@@ -61,7 +118,12 @@ pub fn compose_temporal_mosaic(frames: &[Frame]) -> Result<MosaicImage, MosaicEr
     let tile_height = frames[0].height;
 
     for frame in frames {
-        if frame.width != tile_width || frame.height != tile_height {
+        // Invariant: a frame whose buffer lacks its last row (fields edited
+        // after validation) is rejected here, so every row below exists.
+        if frame.width != tile_width
+            || frame.height != tile_height
+            || (tile_height > 0 && frame.row(tile_height - 1).is_none())
+        {
             return Err(MosaicError::GeometryMismatch);
         }
     }
@@ -70,10 +132,18 @@ pub fn compose_temporal_mosaic(frames: &[Frame]) -> Result<MosaicImage, MosaicEr
     let mosaic_height = tile_height * 3;
     let mosaic_len = (mosaic_width as usize)
         .checked_mul(mosaic_height as usize)
-        .and_then(|pixels| pixels.checked_mul(4))
+        .and_then(|pixels| pixels.checked_mul(options.output_format.bytes_per_pixel()))
         .ok_or(MosaicError::Overflow)?;
 
-    let mut mosaic_rgba = vec![0_u8; mosaic_len];
+    let bytes_per_pixel = options.output_format.bytes_per_pixel();
+
+    // Invariant:
+    // - Every byte is overwritten by exactly one tile row below, so a pooled
+    //   buffer never exposes stale pixels.
+    let mut mosaic_pixels = match &options.pool {
+        Some(pool) => pool.acquire_zeroed(mosaic_len),
+        None => vec![0_u8; mosaic_len],
+    };
 
     for (frame_index, frame) in frames.iter().enumerate() {
         // Why:
@@ -84,25 +154,78 @@ pub fn compose_temporal_mosaic(frames: &[Frame]) -> Result<MosaicImage, MosaicEr
         let tile_row = frame_index / 3;
         let tile_col = frame_index % 3;
 
-        for y in 0..tile_height as usize {
-            let src_offset = y * tile_width as usize * 4;
+        for (y, row) in (0..tile_height).filter_map(|y| frame.row(y)).enumerate() {
             let dst_y = tile_row * tile_height as usize + y;
             let dst_x = tile_col * tile_width as usize;
-            let dst_offset = (dst_y * mosaic_width as usize + dst_x) * 4;
-            let row_len = tile_width as usize * 4;
+            let dst_offset = (dst_y * mosaic_width as usize + dst_x) * bytes_per_pixel;
 
-            mosaic_rgba[dst_offset..dst_offset + row_len]
-                .copy_from_slice(&frame.rgba[src_offset..src_offset + row_len]);
+            convert_row(
+                row,
+                frame.format,
+                &mut mosaic_pixels[dst_offset..],
+                options.output_format,
+                tile_width as usize,
+            );
+        }
+
+        if let (Some(overlay), Some(cursor)) = (&options.cursor_overlay, frame.cursor)
+            && cursor.is_visible_within(tile_width, tile_height)
+        {
+            let origin = (
+                tile_col * tile_width as usize,
+                tile_row * tile_height as usize,
+            );
+            draw_cursor_marker(
+                &mut mosaic_pixels,
+                mosaic_width as usize,
+                options.output_format,
+                origin,
+                (tile_width as usize, tile_height as usize),
+                (cursor.x as usize, cursor.y as usize),
+                overlay,
+            );
         }
     }
 
     Ok(MosaicImage {
         width: mosaic_width,
         height: mosaic_height,
-        rgba: mosaic_rgba,
+        format: options.output_format,
+        pixels: SharedBytes::new(mosaic_pixels),
     })
 }
 
+fn draw_cursor_marker(
+    mosaic: &mut [u8],
+    mosaic_width: usize,
+    format: PixelFormat,
+    tile_origin: (usize, usize),
+    tile_size: (usize, usize),
+    hotspot: (usize, usize),
+    overlay: &CursorOverlay,
+) {
+    let bytes_per_pixel = format.bytes_per_pixel();
+    let mut color = [0_u8; 4];
+    convert_row(&overlay.color, PixelFormat::Rgba8, &mut color, format, 1);
+    let color = &color[..bytes_per_pixel];
+
+    let radius = overlay.radius as usize;
+    let (hot_x, hot_y) = hotspot;
+    let x_range = hot_x.saturating_sub(radius)..=(hot_x + radius).min(tile_size.0 - 1);
+    let y_range = hot_y.saturating_sub(radius)..=(hot_y + radius).min(tile_size.1 - 1);
+
+    let mut paint = |x: usize, y: usize| {
+        let offset = ((tile_origin.1 + y) * mosaic_width + tile_origin.0 + x) * bytes_per_pixel;
+        mosaic[offset..offset + bytes_per_pixel].copy_from_slice(color);
+    };
+    for x in x_range {
+        paint(x, hot_y);
+    }
+    for y in y_range {
+        paint(hot_x, y);
+    }
+}
+
 /// Error type for mosaic assembly.
 #[derive(Debug, Error)]
 pub enum MosaicError {
@@ -126,7 +249,7 @@ pub enum MosaicError {
 mod tests {
     //! Unit tests for mosaic composition.
 
-    use local_guard_core::Frame;
+    use local_guard_core::{CursorState, Frame, PixelFormat};
 
     use super::*;
 
@@ -151,10 +274,93 @@ mod tests {
         assert_eq!(mosaic.height, 3);
 
         // First tile should remain in top-left pixel.
-        assert_eq!(mosaic.rgba[0], 0);
+        assert_eq!(mosaic.pixels[0], 0);
 
         // Last tile should end up bottom-right pixel.
         let bottom_right_offset = ((3 * 3) - 1) * 4;
-        assert_eq!(mosaic.rgba[bottom_right_offset], 8);
+        assert_eq!(mosaic.pixels[bottom_right_offset], 8);
+    }
+
+    #[test]
+    fn compose_converts_bgra_padded_frames_to_requested_format() {
+        let frames: Vec<Frame> = (0..MOSAIC_FRAME_COUNT)
+            .map(|index| {
+                // One BGRA pixel per row, padded to 8 bytes.
+                Frame::with_format(
+                    "display-1",
+                    1,
+                    1,
+                    index as u64,
+                    PixelFormat::Bgra8,
+                    8,
+                    vec![0, 0, index as u8, 255, 9, 9, 9, 9],
+                )
+                .expect("frame should be valid")
+            })
+            .collect();
+
+        let options = MosaicOptions {
+            output_format: PixelFormat::Rgb8,
+            ..MosaicOptions::default()
+        };
+        let mosaic =
+            compose_temporal_mosaic_with(&frames, &options).expect("mosaic should compose");
+
+        assert_eq!(mosaic.format, PixelFormat::Rgb8);
+        assert_eq!(mosaic.pixels.len(), 3 * 3 * 3);
+        assert_eq!(&mosaic.pixels[..3], &[0, 0, 0]);
+        assert_eq!(&mosaic.pixels[24..27], &[8, 0, 0]);
+    }
+
+    #[test]
+    fn compose_draws_cursor_overlay_clipped_to_tile() {
+        let frames: Vec<Frame> = (0..MOSAIC_FRAME_COUNT)
+            .map(|index| {
+                let frame = Frame::new("display-1", 3, 3, index as u64, vec![0; 3 * 3 * 4])
+                    .expect("frame should be valid");
+                // Only the first tile has a visible cursor, at its right edge.
+                frame.with_cursor(CursorState {
+                    x: 2,
+                    y: 1,
+                    visible: index == 0,
+                })
+            })
+            .collect();
+        let options = MosaicOptions {
+            output_format: PixelFormat::Gray8,
+            cursor_overlay: Some(CursorOverlay {
+                radius: 5,
+                color: [255, 255, 255, 255],
+            }),
+            ..MosaicOptions::default()
+        };
+
+        let mosaic =
+            compose_temporal_mosaic_with(&frames, &options).expect("mosaic should compose");
+        let marked: Vec<(usize, usize)> = mosaic
+            .pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value == 255)
+            .map(|(offset, _)| (offset % 9, offset / 9))
+            .collect();
+
+        assert_eq!(marked, vec![(2, 0), (0, 1), (1, 1), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn compose_rejects_frame_shorter_than_its_geometry() {
+        let mut frames: Vec<Frame> = (0..MOSAIC_FRAME_COUNT)
+            .map(|index| {
+                Frame::new("display-1", 1, 2, index as u64, vec![0; 8])
+                    .expect("frame should be valid")
+            })
+            .collect();
+        frames[4].pixels = vec![0; 4].into();
+
+        assert!(matches!(
+            compose_temporal_mosaic(&frames),
+            Err(MosaicError::GeometryMismatch)
+        ));
     }
 }
@@ -0,0 +1,190 @@
+//! # Module: allowlist
+//!
+//! ## Purpose
+//! Restricts which hosts and ports auth and ingest clients may be pointed
+//! at, so a misconfigured or hostile endpoint setting cannot redirect
+//! credentials or screen captures to an arbitrary server.
+//!
+//! ## Responsibilities
+//! - Parse allowlist entries: exact hosts, `*.suffix` domain patterns, and
+//!   optional explicit ports ([`EndpointAllowlist::parse`]).
+//! - Provide the compiled-in allowlist ([`EndpointAllowlist::builtin`]).
+//! - Check endpoint URLs against the allowlist
+//!   ([`EndpointAllowlist::check`]).
+//!
+//! ## Invariants
+//! - Endpoints carrying userinfo are always rejected.
+//! - IP literal endpoints are rejected unless that exact address is listed.
+//! - Only the default HTTPS port is allowed unless an entry names the port.
+//! - A `*.suffix` pattern matches subdomains only, never the bare suffix.
+//!
+//! ## Error model
+//! Rejected endpoints return [`TransportSecurityError::EndpointNotAllowed`];
+//! malformed entries return [`TransportSecurityError::InvalidPolicy`].
+//!
+//! ## Security and privacy notes
+//! Hosts are compared after URL normalization (lowercase, IDNA), so case or
+//! Unicode tricks cannot slip past a pattern. Rejection messages name the
+//! host but never the path, query, or credentials.
+
+use std::fmt;
+
+use url::{Host, Url};
+
+use crate::TransportSecurityError;
+
+/// Entries of the compiled-in endpoint allowlist.
+pub const BUILTIN_ENDPOINT_HOSTS: &[&str] = &["*.local-guard.test"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    Exact(Host<String>),
+    Suffix(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HostRule {
+    pattern: HostPattern,
+    port: Option<u16>,
+}
+
+impl HostRule {
+    fn parse(raw: &str) -> Result<Self, TransportSecurityError> {
+        let entry = raw.trim();
+        if entry.is_empty() || entry.contains(['/', '@', '?', '#']) {
+            return Err(invalid_entry(raw));
+        }
+
+        let (wildcard, authority) = match entry.strip_prefix("*.") {
+            Some(rest) => (true, rest),
+            None => (false, entry),
+        };
+        // Why: reuse the URL parser so entries normalize exactly like the
+        // endpoints they are compared against.
+        let parsed =
+            Url::parse(&format!("https://{authority}/")).map_err(|_| invalid_entry(raw))?;
+        let host = parsed.host().ok_or_else(|| invalid_entry(raw))?.to_owned();
+
+        let pattern = match (wildcard, host) {
+            (true, Host::Domain(domain)) if domain.contains('.') => HostPattern::Suffix(domain),
+            (true, _) => return Err(invalid_entry(raw)),
+            (false, host) => HostPattern::Exact(host),
+        };
+        Ok(Self {
+            pattern,
+            port: parsed.port(),
+        })
+    }
+
+    fn matches_host(&self, host: &Host<&str>) -> bool {
+        match (&self.pattern, host) {
+            (HostPattern::Exact(expected), host) => *expected == host.to_owned(),
+            (HostPattern::Suffix(suffix), Host::Domain(domain)) => domain
+                .strip_suffix(suffix.as_str())
+                .is_some_and(|label| label.len() > 1 && label.ends_with('.')),
+            (HostPattern::Suffix(_), _) => false,
+        }
+    }
+}
+
+impl fmt::Display for HostRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.pattern {
+            HostPattern::Exact(host) => write!(f, "{host}")?,
+            HostPattern::Suffix(suffix) => write!(f, "*.{suffix}")?,
+        }
+        match self.port {
+            Some(port) => write!(f, ":{port}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Hosts, domain suffixes, and ports that endpoints may point at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointAllowlist {
+    rules: Vec<HostRule>,
+}
+
+impl EndpointAllowlist {
+    /// Returns the compiled-in allowlist ([`BUILTIN_ENDPOINT_HOSTS`]).
+    pub fn builtin() -> Self {
+        Self::parse(&BUILTIN_ENDPOINT_HOSTS.join(","))
+            .expect("builtin endpoint allowlist must parse")
+    }
+
+    /// Parses a comma-separated list of `host`, `*.suffix`, `host:port`, or
+    /// `[ipv6]:port` entries.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::InvalidPolicy`] for a malformed
+    /// entry or a list without entries.
+    pub fn parse(raw: &str) -> Result<Self, TransportSecurityError> {
+        let rules = raw
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(HostRule::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if rules.is_empty() {
+            return Err(TransportSecurityError::InvalidPolicy(
+                "endpoint allowlist has no entries".to_string(),
+            ));
+        }
+        Ok(Self { rules })
+    }
+
+    /// Returns the entries in their normalized `host[:port]` form.
+    pub fn entries(&self) -> Vec<String> {
+        self.rules.iter().map(ToString::to_string).collect()
+    }
+
+    /// Checks that `endpoint` targets an allowed host and port.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::EndpointNotAllowed`] for malformed
+    /// URLs, userinfo, unlisted IP literals, unlisted hosts, and unlisted
+    /// ports.
+    pub fn check(&self, endpoint: &str) -> Result<(), TransportSecurityError> {
+        let url = Url::parse(endpoint).map_err(|error| {
+            TransportSecurityError::EndpointNotAllowed(format!("invalid url: {error}"))
+        })?;
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(TransportSecurityError::EndpointNotAllowed(
+                "endpoint must not carry userinfo".to_string(),
+            ));
+        }
+        let Some(host) = url.host() else {
+            return Err(TransportSecurityError::EndpointNotAllowed(
+                "endpoint has no host".to_string(),
+            ));
+        };
+
+        let host_rules: Vec<&HostRule> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches_host(&host))
+            .collect();
+        if host_rules.is_empty() {
+            let reason = match host {
+                Host::Domain(_) => "host is not in the endpoint allowlist",
+                Host::Ipv4(_) | Host::Ipv6(_) => "ip literal endpoints are not allowed",
+            };
+            return Err(TransportSecurityError::EndpointNotAllowed(format!(
+                "{host}: {reason}"
+            )));
+        }
+        // Invariant: `Url::port` is `None` for the scheme default, so an
+        // entry without a port admits exactly port 443.
+        if !host_rules.iter().any(|rule| rule.port == url.port()) {
+            let port = url.port_or_known_default().unwrap_or_default();
+            return Err(TransportSecurityError::EndpointNotAllowed(format!(
+                "{host}: port {port} is not allowed"
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn invalid_entry(raw: &str) -> TransportSecurityError {
+    TransportSecurityError::InvalidPolicy(format!("invalid endpoint allowlist entry: {raw:?}"))
+}
@@ -0,0 +1,199 @@
+//! # Module: der
+//!
+//! ## Purpose
+//! Minimal DER reader for the few X.509 fields the transport needs without
+//! a full certificate parser: the SubjectPublicKeyInfo (for pins) and the
+//! validity window (for client certificate expiry).
+//!
+//! ## Responsibilities
+//! - Split DER elements with bounded length decoding.
+//! - Walk the fixed prefix of `TBSCertificate`.
+//! - Convert `UTCTime` and `GeneralizedTime` values to Unix milliseconds.
+//! - Re-wrap bare SEQUENCE contents (trust anchor SPKIs) for hashing.
+//!
+//! ## Invariants
+//! - Every slice returned lies inside the input; truncated input is an
+//!   error, never a panic.
+//!
+//! ## Error model
+//! Returns a plain reason string; callers wrap it in the error variant that
+//! fits their context.
+//!
+//! ## Security and privacy notes
+//! Signature and chain checks stay with the WebPKI verifier; this module
+//! only locates fields.
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_EXPLICIT_VERSION: u8 = 0xa0;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+
+/// Validity window of a certificate in Unix milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Validity {
+    pub(crate) not_before_ms: u64,
+    pub(crate) not_after_ms: u64,
+}
+
+/// Returns the DER bytes of the SubjectPublicKeyInfo inside `certificate`.
+pub(crate) fn certificate_spki(certificate: &[u8]) -> Result<&[u8], String> {
+    let spki = tbs_field(certificate, 5)?;
+    if spki.first() != Some(&TAG_SEQUENCE) {
+        return Err("subjectPublicKeyInfo is not a sequence".to_string());
+    }
+    Ok(spki)
+}
+
+/// Returns the validity window of `certificate`.
+pub(crate) fn certificate_validity(certificate: &[u8]) -> Result<Validity, String> {
+    let (validity, _) = expect_element(tbs_field(certificate, 3)?, TAG_SEQUENCE, "validity")?;
+    let (not_before, rest) = split_element(validity)?;
+    let (not_after, _) = split_element(rest)?;
+    Ok(Validity {
+        not_before_ms: time_to_unix_ms(not_before)?,
+        not_after_ms: time_to_unix_ms(not_after)?,
+    })
+}
+
+/// Encodes `contents` as a DER SEQUENCE element.
+pub(crate) fn wrap_sequence(contents: &[u8]) -> Vec<u8> {
+    let length = contents.len();
+    let length_bytes = length.to_be_bytes();
+    let significant = &length_bytes[length_bytes.iter().take_while(|byte| **byte == 0).count()..];
+    let mut element = Vec::with_capacity(contents.len() + 2 + significant.len());
+    element.push(TAG_SEQUENCE);
+    if length < 0x80 {
+        element.push(length as u8);
+    } else {
+        element.push(0x80 | significant.len() as u8);
+        element.extend_from_slice(significant);
+    }
+    element.extend_from_slice(contents);
+    element
+}
+
+/// Returns the `index`-th field of `TBSCertificate`, counting from the
+/// serial number (0) and skipping the optional explicit version.
+fn tbs_field(certificate: &[u8], index: usize) -> Result<&[u8], String> {
+    let (certificate_body, _) = expect_element(certificate, TAG_SEQUENCE, "certificate")?;
+    let (mut tbs, _) = expect_element(certificate_body, TAG_SEQUENCE, "tbsCertificate")?;
+
+    // Invariant: RFC 5280 fixes the order version?, serial, signature,
+    // issuer, validity, subject, subjectPublicKeyInfo.
+    if tbs.first() == Some(&TAG_EXPLICIT_VERSION) {
+        tbs = split_element(tbs)?.1;
+    }
+    for _ in 0..index {
+        tbs = split_element(tbs)?.1;
+    }
+    Ok(split_element(tbs)?.0)
+}
+
+/// Converts a DER `UTCTime` or `GeneralizedTime` element in `Z` form to
+/// Unix milliseconds; instants before 1970 clamp to zero.
+fn time_to_unix_ms(element: &[u8]) -> Result<u64, String> {
+    let tag = *element.first().ok_or("truncated time")?;
+    let (header_len, _) = element_lengths(element)?;
+    let text = std::str::from_utf8(&element[header_len..]).map_err(|_| "time is not ascii")?;
+    let digits = text.strip_suffix('Z').ok_or("time must be in UTC")?;
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err("time has non-digit characters".to_string());
+    }
+
+    let (year, rest) = match (tag, digits.len()) {
+        (TAG_UTC_TIME, 12) => {
+            let short: i64 = digits[..2].parse().map_err(|_| "bad year")?;
+            // Why: RFC 5280 maps two-digit years 50..99 to 19xx.
+            (
+                if short >= 50 {
+                    1900 + short
+                } else {
+                    2000 + short
+                },
+                &digits[2..],
+            )
+        }
+        (TAG_GENERALIZED_TIME, 14) => (digits[..4].parse().map_err(|_| "bad year")?, &digits[4..]),
+        _ => return Err("unsupported time encoding".to_string()),
+    };
+    let field = |range: std::ops::Range<usize>| -> i64 { rest[range].parse().unwrap_or(-1) };
+    let (month, day, hour, minute, second) = (
+        field(0..2),
+        field(2..4),
+        field(4..6),
+        field(6..8),
+        field(8..10),
+    );
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || !(0..=23).contains(&hour)
+        || !(0..=59).contains(&minute)
+        || !(0..=60).contains(&second)
+    {
+        return Err("time field out of range".to_string());
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second;
+    Ok(u64::try_from(seconds).unwrap_or(0).saturating_mul(1_000))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Checks the tag of the first element and returns (contents, rest).
+fn expect_element<'a>(
+    input: &'a [u8],
+    tag: u8,
+    name: &str,
+) -> Result<(&'a [u8], &'a [u8]), String> {
+    if input.first() != Some(&tag) {
+        return Err(format!("{name} has unexpected tag"));
+    }
+    let (header_len, content_len) = element_lengths(input)?;
+    let (element, rest) = input.split_at(header_len + content_len);
+    Ok((&element[header_len..], rest))
+}
+
+/// Splits the first element off `input`, returning (element, rest).
+fn split_element(input: &[u8]) -> Result<(&[u8], &[u8]), String> {
+    let (header_len, content_len) = element_lengths(input)?;
+    Ok(input.split_at(header_len + content_len))
+}
+
+/// Returns (header length, content length) of the first DER element.
+///
+/// # Invariant
+/// On success `header_len + content_len` fits in `input`, so callers may
+/// add the two without overflow.
+fn element_lengths(input: &[u8]) -> Result<(usize, usize), String> {
+    let first_length = *input.get(1).ok_or("truncated element")?;
+    let (header_len, content_len) = if first_length < 0x80 {
+        (2, first_length as usize)
+    } else {
+        let count = (first_length & 0x7f) as usize;
+        // Why: certificates never need more than 4 length bytes; larger
+        // values only appear in malformed or hostile input.
+        if count == 0 || count > 4 {
+            return Err("unsupported length encoding".to_string());
+        }
+        let bytes = input.get(2..2 + count).ok_or("truncated length")?;
+        let length = bytes
+            .iter()
+            .fold(0_usize, |length, byte| (length << 8) | *byte as usize);
+        (2 + count, length)
+    };
+    // Why: the content length comes from up to four untrusted bytes and can
+    // overflow `usize` on 32-bit targets.
+    match header_len.checked_add(content_len) {
+        Some(total) if total <= input.len() => Ok((header_len, content_len)),
+        _ => Err("truncated element".to_string()),
+    }
+}
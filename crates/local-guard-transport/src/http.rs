@@ -0,0 +1,482 @@
+//! # Module: http
+//!
+//! ## Purpose
+//! Executes single HTTPS/1.1 requests under a [`TransportSecurity`] policy
+//! for the auth and ingest transports.
+//!
+//! ## Responsibilities
+//! - Resolve and connect with bounded timeouts, optionally through an HTTP
+//!   proxy CONNECT tunnel.
+//! - Complete the TLS handshake before any request byte is written.
+//! - Encode requests and decode `Content-Length` or chunked responses.
+//! - Classify failures into [`TransportError`] variants.
+//!
+//! ## Invariants
+//! - Only `https` URLs are sent; there is no plaintext fallback.
+//! - One request per connection (`Connection: close`).
+//!
+//! ## Error model
+//! TLS trust failures return [`TransportError::Security`]; timeouts return
+//! [`TransportError::Timeout`]; proxy refusals return
+//! [`TransportError::ProxyAuthentication`] or
+//! [`TransportError::ProxyRefused`]; everything else is connect, I/O,
+//! request, or response shape errors.
+//!
+//! ## Security and privacy notes
+//! Header names and values containing CR or LF are rejected to prevent
+//! request splitting. Request bodies and proxy credentials never appear in
+//! errors.
+
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::pki_types::ServerName;
+use rustls::{AlertDescription, CertificateError, ClientConfig, ClientConnection, StreamOwned};
+use url::Url;
+
+use crate::{ProxyConfig, TransportError, TransportSecurity, TransportSecurityError};
+
+/// Default connect, read, and write timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest CONNECT response head accepted from a proxy.
+const MAX_PROXY_RESPONSE_HEAD: usize = 16 * 1024;
+
+/// One HTTPS request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// Request method (for example `POST`).
+    pub method: String,
+    /// Absolute `https` URL.
+    pub url: String,
+    /// Extra headers; `Host`, `Connection`, and `Content-Length` are set by
+    /// the connector.
+    pub headers: Vec<(String, String)>,
+    /// Request body.
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Creates a `POST` request with `body`.
+    pub fn post(url: impl Into<String>, body: Vec<u8>) -> Self {
+        Self {
+            method: "POST".to_string(),
+            url: url.into(),
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Appends a header.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Decoded HTTPS response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// Status code.
+    pub status: u16,
+    /// Response headers in wire order.
+    pub headers: Vec<(String, String)>,
+    /// Response body with transfer encoding removed.
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Creates a response with `status` and no headers or body.
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Returns the first header named `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Blocking HTTPS client bound to one transport security policy.
+#[derive(Debug, Clone)]
+pub struct HttpsConnector {
+    config: Arc<ClientConfig>,
+    timeout: Duration,
+    resolved: BTreeMap<String, SocketAddr>,
+    proxy: Option<ProxyConfig>,
+}
+
+impl HttpsConnector {
+    /// Creates a connector enforcing `policy`.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError`] when the policy cannot be turned
+    /// into a TLS configuration.
+    pub fn new(policy: &TransportSecurity) -> Result<Self, TransportSecurityError> {
+        Ok(Self {
+            config: policy.client_config()?,
+            timeout: DEFAULT_TIMEOUT,
+            resolved: BTreeMap::new(),
+            proxy: None,
+        })
+    }
+
+    /// Sets the connect, read, and write timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Routes `host` to `addr` without DNS.
+    ///
+    /// SNI and certificate name checks still use `host`, so this only
+    /// changes where bytes go, never what is trusted.
+    pub fn with_resolved_addr(mut self, host: impl Into<String>, addr: SocketAddr) -> Self {
+        self.resolved.insert(host.into().to_ascii_lowercase(), addr);
+        self
+    }
+
+    /// Tunnels requests through `proxy` with HTTP CONNECT, except for hosts
+    /// on its bypass list.
+    ///
+    /// The TLS handshake still runs with the origin inside the tunnel, so
+    /// pins, the CA bundle, and the client certificate apply unchanged.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Returns the configured proxy, if any.
+    pub fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    /// Sends `request` and reads the full response.
+    ///
+    /// # Errors
+    /// See [`TransportError`].
+    pub fn send(&self, request: &HttpRequest) -> Result<HttpResponse, TransportError> {
+        let url = Url::parse(&request.url)
+            .map_err(|error| TransportError::InvalidRequest(format!("invalid url: {error}")))?;
+        if url.scheme() != "https" {
+            return Err(TransportError::InvalidRequest(
+                "only https urls are supported".to_string(),
+            ));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| TransportError::InvalidRequest("url has no host".to_string()))?
+            .to_string();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let head = encode_request_head(&url, &host, request)?;
+
+        let mut tcp = match &self.proxy {
+            Some(proxy) if proxy.applies_to(&host) => self.connect_via_proxy(proxy, &host, port)?,
+            _ => self.connect_tcp(&host, port)?,
+        };
+        let server_name = ServerName::try_from(host.clone())
+            .map_err(|error| TransportError::InvalidRequest(format!("invalid host: {error}")))?;
+        let mut connection = ClientConnection::new(Arc::clone(&self.config), server_name)
+            .map_err(|error| tls_error(&error))?;
+        // Why: finishing the handshake first guarantees no request byte,
+        // including the bearer token, reaches an untrusted peer.
+        while connection.is_handshaking() {
+            connection.complete_io(&mut tcp).map_err(io_error)?;
+        }
+
+        let mut stream = StreamOwned::new(connection, tcp);
+        let written = stream
+            .write_all(&head)
+            .and_then(|()| stream.write_all(&request.body))
+            .and_then(|()| stream.flush());
+        if let Err(error) = written {
+            // Failure mode: a server that rejects the client certificate
+            // sends an alert and closes, so the write can fail with a broken
+            // pipe before the alert has been read.
+            return Err(pending_tls_alert(&mut stream).unwrap_or_else(|| io_error(error)));
+        }
+        parse_response(&read_until_close(&mut stream)?)
+    }
+
+    /// Opens a CONNECT tunnel to `host:port` through `proxy`.
+    fn connect_via_proxy(
+        &self,
+        proxy: &ProxyConfig,
+        host: &str,
+        port: u16,
+    ) -> Result<TcpStream, TransportError> {
+        let mut tcp =
+            self.connect_tcp(&proxy.host(), proxy.port())
+                .map_err(|error| match error {
+                    TransportError::Connect(reason) => {
+                        TransportError::Connect(format!("proxy {}: {reason}", proxy.authority()))
+                    }
+                    other => other,
+                })?;
+
+        // Invariant: `host` comes from a parsed URL, so it cannot carry CR
+        // or LF; IPv6 literals are already bracketed.
+        let mut head = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+        if let Some(authorization) = proxy.authorization() {
+            head.push_str(&format!("Proxy-Authorization: {authorization}\r\n"));
+        }
+        head.push_str("\r\n");
+        tcp.write_all(head.as_bytes())
+            .and_then(|()| tcp.flush())
+            .map_err(io_error)?;
+
+        let status = read_proxy_status(&mut tcp)?;
+        match status {
+            200..=299 => Ok(tcp),
+            407 => Err(TransportError::ProxyAuthentication),
+            status => Err(TransportError::ProxyRefused(status)),
+        }
+    }
+
+    fn connect_tcp(&self, host: &str, port: u16) -> Result<TcpStream, TransportError> {
+        let addrs: Vec<SocketAddr> = match self.resolved.get(&host.to_ascii_lowercase()) {
+            Some(addr) => vec![*addr],
+            None => (host, port)
+                .to_socket_addrs()
+                .map_err(|error| TransportError::Connect(format!("resolve {host}: {error}")))?
+                .collect(),
+        };
+
+        let mut last_error = TransportError::Connect(format!("no addresses for {host}"));
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream
+                        .set_read_timeout(Some(self.timeout))
+                        .and_then(|()| stream.set_write_timeout(Some(self.timeout)))
+                        .map_err(io_error)?;
+                    return Ok(stream);
+                }
+                Err(error) if error.kind() == ErrorKind::TimedOut => {
+                    last_error = TransportError::Timeout;
+                }
+                Err(error) => {
+                    last_error = TransportError::Connect(format!("{addr}: {error}"));
+                }
+            }
+        }
+        Err(last_error)
+    }
+}
+
+fn encode_request_head(
+    url: &Url,
+    host: &str,
+    request: &HttpRequest,
+) -> Result<Vec<u8>, TransportError> {
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+    let host_header = match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    };
+
+    let mut head = format!(
+        "{} {target} HTTP/1.1\r\nHost: {host_header}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        request.method,
+        request.body.len()
+    );
+    for (name, value) in &request.headers {
+        let forbidden = |text: &str| text.contains(['\r', '\n']);
+        if name.is_empty() || name.contains(':') || forbidden(name) || forbidden(value) {
+            return Err(TransportError::InvalidRequest(format!(
+                "header {name:?} contains forbidden characters"
+            )));
+        }
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    Ok(head.into_bytes())
+}
+
+/// Reads the proxy's CONNECT response head and returns its status.
+///
+/// Why: reads one byte at a time so no TLS byte after the head is consumed.
+fn read_proxy_status(stream: &mut impl Read) -> Result<u16, TransportError> {
+    let mut head = Vec::new();
+    let mut byte = [0_u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_PROXY_RESPONSE_HEAD {
+            return Err(invalid_response("proxy response head too large"));
+        }
+        match stream.read(&mut byte) {
+            Ok(0) => return Err(invalid_response("proxy closed during connect")),
+            Ok(_) => head.push(byte[0]),
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(io_error(error)),
+        }
+    }
+    std::str::from_utf8(&head)
+        .ok()
+        .and_then(|head| head.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| invalid_response("malformed proxy status line"))
+}
+
+fn read_until_close(stream: &mut impl Read) -> Result<Vec<u8>, TransportError> {
+    let mut raw = Vec::new();
+    let mut buffer = [0_u8; 8 * 1024];
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) => return Ok(raw),
+            Ok(read) => raw.extend_from_slice(&buffer[..read]),
+            // Failure mode: some servers close without `close_notify`; the
+            // framing checks in `parse_response` catch real truncation.
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof && !raw.is_empty() => {
+                return Ok(raw);
+            }
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(io_error(error)),
+        }
+    }
+}
+
+fn parse_response(raw: &[u8]) -> Result<HttpResponse, TransportError> {
+    let split = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| invalid_response("missing header terminator"))?;
+    let head =
+        std::str::from_utf8(&raw[..split]).map_err(|_| invalid_response("non-utf8 headers"))?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| invalid_response("malformed status line"))?;
+    let headers = lines
+        .map(|line| {
+            line.split_once(':')
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| invalid_response("malformed header line"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut response = HttpResponse {
+        status,
+        headers,
+        body: Vec::new(),
+    };
+    let body = &raw[split + 4..];
+    response.body = if response
+        .header("transfer-encoding")
+        .is_some_and(|value| value.to_ascii_lowercase().contains("chunked"))
+    {
+        decode_chunked(body)?
+    } else if let Some(length) = response.header("content-length") {
+        let length = length
+            .parse::<usize>()
+            .map_err(|_| invalid_response("invalid content-length"))?;
+        body.get(..length)
+            .ok_or_else(|| invalid_response("truncated body"))?
+            .to_vec()
+    } else {
+        body.to_vec()
+    };
+    Ok(response)
+}
+
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>, TransportError> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(|| invalid_response("truncated chunk size"))?;
+        let size_line = std::str::from_utf8(&body[..line_end])
+            .map_err(|_| invalid_response("non-utf8 chunk size"))?;
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| invalid_response("invalid chunk size"))?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = body
+            .get(..size)
+            .ok_or_else(|| invalid_response("truncated chunk"))?;
+        decoded.extend_from_slice(chunk);
+        body = body
+            .get(size..)
+            .and_then(|rest| rest.strip_prefix(b"\r\n"))
+            .ok_or_else(|| invalid_response("missing chunk terminator"))?;
+    }
+}
+
+fn invalid_response(reason: &str) -> TransportError {
+    TransportError::InvalidResponse(reason.to_string())
+}
+
+fn io_error(error: std::io::Error) -> TransportError {
+    if let Some(tls) = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+    {
+        return tls_error(tls);
+    }
+    match error.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => TransportError::Timeout,
+        _ => TransportError::Io(error.to_string()),
+    }
+}
+
+/// Reads once to surface a TLS alert already sent by the peer.
+fn pending_tls_alert(stream: &mut impl Read) -> Option<TransportError> {
+    let mut byte = [0_u8; 1];
+    match stream.read(&mut byte) {
+        Err(error)
+            if error
+                .get_ref()
+                .is_some_and(|inner| inner.is::<rustls::Error>()) =>
+        {
+            Some(io_error(error))
+        }
+        _ => None,
+    }
+}
+
+fn tls_error(error: &rustls::Error) -> TransportError {
+    let security = match error {
+        rustls::Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure) => {
+            TransportSecurityError::PinMismatch
+        }
+        rustls::Error::InvalidCertificate(reason) => {
+            TransportSecurityError::UntrustedCertificate(format!("{reason:?}"))
+        }
+        // Why: these alerts come from the server after it inspected (or
+        // missed) our client certificate.
+        rustls::Error::AlertReceived(
+            alert @ (AlertDescription::CertificateRequired
+            | AlertDescription::BadCertificate
+            | AlertDescription::CertificateExpired
+            | AlertDescription::CertificateRevoked
+            | AlertDescription::CertificateUnknown
+            | AlertDescription::UnknownCA
+            | AlertDescription::UnsupportedCertificate),
+        ) => TransportSecurityError::ClientCertificateRejected(format!("{alert:?}")),
+        rustls::Error::PeerIncompatible(_)
+        | rustls::Error::AlertReceived(AlertDescription::ProtocolVersion) => {
+            TransportSecurityError::ProtocolVersion
+        }
+        other => TransportSecurityError::Handshake(other.to_string()),
+    };
+    TransportError::Security(security)
+}
@@ -0,0 +1,237 @@
+//! # Module: identity
+//!
+//! ## Purpose
+//! Loads the client certificate and private key presented for mutual TLS,
+//! binding auth and ingest connections to the machine rather than only to a
+//! bearer token.
+//!
+//! ## Responsibilities
+//! - Load an identity from PEM (certificate chain plus PKCS#8, SEC1, or
+//!   PKCS#1 key) or from a password-protected PKCS#12 archive.
+//! - Reject keys the TLS provider cannot use or that do not match the leaf
+//!   certificate.
+//! - Report the leaf certificate's validity window and expiry status.
+//!
+//! ## Invariants
+//! - The first certificate in the chain is the leaf that matches the key.
+//!
+//! ## Error model
+//! Every loading failure returns [`TransportSecurityError::ClientIdentity`].
+//!
+//! ## Security and privacy notes
+//! - `Debug` output never includes key material.
+//! - Errors name files but never echo file contents or the PKCS#12
+//!   password.
+
+use std::fmt;
+use std::path::Path;
+
+use p12_keystore::KeyStore;
+use rustls::sign::CertifiedKey;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+use crate::TransportSecurityError;
+use crate::der::certificate_validity;
+
+/// Expiry status of a client certificate at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientCertificateStatus {
+    /// The certificate's `notBefore` is still in the future.
+    NotYetValid,
+    /// The certificate is valid beyond the warning window.
+    Valid,
+    /// The certificate expires within the warning window.
+    ExpiringSoon {
+        /// Milliseconds until `notAfter`.
+        remaining_ms: u64,
+    },
+    /// The certificate's `notAfter` has passed.
+    Expired,
+}
+
+/// Client certificate chain and private key for mutual TLS.
+pub struct ClientIdentity {
+    chain: Vec<CertificateDer<'static>>,
+    key: PrivateKeyDer<'static>,
+    not_before_ms: u64,
+    not_after_ms: u64,
+}
+
+impl ClientIdentity {
+    /// Builds an identity from a DER chain (leaf first) and private key.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::ClientIdentity`] for an empty
+    /// chain, an unreadable leaf validity, an unsupported key type, or a key
+    /// that does not match the leaf certificate.
+    pub fn new(
+        chain: Vec<CertificateDer<'static>>,
+        key: PrivateKeyDer<'static>,
+    ) -> Result<Self, TransportSecurityError> {
+        let leaf = chain
+            .first()
+            .ok_or_else(|| identity_error("certificate chain is empty"))?;
+        let validity = certificate_validity(leaf)
+            .map_err(|reason| identity_error(&format!("leaf certificate: {reason}")))?;
+        // Why: fail when the identity is configured, not on the first
+        // handshake, if the key is unusable or belongs to another cert.
+        CertifiedKey::from_der(
+            chain.clone(),
+            key.clone_key(),
+            &rustls::crypto::ring::default_provider(),
+        )
+        .map_err(|error| identity_error(&error.to_string()))?;
+
+        Ok(Self {
+            chain,
+            key,
+            not_before_ms: validity.not_before_ms,
+            not_after_ms: validity.not_after_ms,
+        })
+    }
+
+    /// Loads an identity from PEM certificate and key bytes. `cert_pem` may
+    /// hold the whole chain; `key_pem` may be the same buffer.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::ClientIdentity`] when no
+    /// certificate or key is found, or for the reasons in
+    /// [`ClientIdentity::new`].
+    pub fn from_pem(cert_pem: &[u8], key_pem: &[u8]) -> Result<Self, TransportSecurityError> {
+        let chain = CertificateDer::pem_slice_iter(cert_pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| identity_error(&format!("certificate pem: {error}")))?;
+        let key = PrivateKeyDer::from_pem_slice(key_pem)
+            .map_err(|error| identity_error(&format!("private key pem: {error}")))?;
+        Self::new(chain, key)
+    }
+
+    /// Reads a PEM identity from `cert_path` and, when given, `key_path`
+    /// (otherwise the key is read from `cert_path` too).
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::ClientIdentity`] when a file
+    /// cannot be read or parsed.
+    pub fn from_pem_files(
+        cert_path: impl AsRef<Path>,
+        key_path: Option<&Path>,
+    ) -> Result<Self, TransportSecurityError> {
+        let cert_path = cert_path.as_ref();
+        let cert_pem = read_file(cert_path)?;
+        match key_path {
+            Some(key_path) => Self::from_pem(&cert_pem, &read_file(key_path)?),
+            None => Self::from_pem(&cert_pem, &cert_pem),
+        }
+    }
+
+    /// Loads the first key and its chain from a PKCS#12 archive.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::ClientIdentity`] for a wrong
+    /// password, a malformed archive, an archive without a private key, or
+    /// the reasons in [`ClientIdentity::new`].
+    pub fn from_pkcs12(der: &[u8], password: &str) -> Result<Self, TransportSecurityError> {
+        let store = KeyStore::from_pkcs12(der, password, Default::default())
+            .map_err(|error| identity_error(&format!("pkcs12: {error}")))?;
+        let (_, key_chain) = store.private_key_chain().ok_or_else(|| {
+            identity_error("pkcs12 archive has no private key with a certificate")
+        })?;
+        let chain = key_chain
+            .certs()
+            .iter()
+            .map(|certificate| CertificateDer::from(certificate.as_der().to_vec()))
+            .collect();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_chain.key().as_der().to_vec()));
+        Self::new(chain, key)
+    }
+
+    /// Reads a PKCS#12 identity from `path`.
+    ///
+    /// # Errors
+    /// Same as [`ClientIdentity::from_pkcs12`], plus read failures.
+    pub fn from_pkcs12_file(
+        path: impl AsRef<Path>,
+        password: &str,
+    ) -> Result<Self, TransportSecurityError> {
+        Self::from_pkcs12(&read_file(path.as_ref())?, password)
+    }
+
+    /// Returns the chain, leaf first.
+    pub fn chain(&self) -> &[CertificateDer<'static>] {
+        &self.chain
+    }
+
+    /// Returns the leaf `notBefore` in Unix milliseconds.
+    pub fn not_before_ms(&self) -> u64 {
+        self.not_before_ms
+    }
+
+    /// Returns the leaf `notAfter` in Unix milliseconds.
+    pub fn not_after_ms(&self) -> u64 {
+        self.not_after_ms
+    }
+
+    /// Classifies the leaf validity at `now_ms`; certificates expiring
+    /// within `warn_within_ms` are [`ClientCertificateStatus::ExpiringSoon`].
+    pub fn status(&self, now_ms: u64, warn_within_ms: u64) -> ClientCertificateStatus {
+        if now_ms < self.not_before_ms {
+            return ClientCertificateStatus::NotYetValid;
+        }
+        // Invariant: `notAfter` is inclusive (RFC 5280 section 4.1.2.5).
+        if now_ms > self.not_after_ms {
+            return ClientCertificateStatus::Expired;
+        }
+        let remaining_ms = self.not_after_ms - now_ms;
+        if remaining_ms <= warn_within_ms {
+            ClientCertificateStatus::ExpiringSoon { remaining_ms }
+        } else {
+            ClientCertificateStatus::Valid
+        }
+    }
+
+    pub(crate) fn key(&self) -> PrivateKeyDer<'static> {
+        self.key.clone_key()
+    }
+}
+
+impl Clone for ClientIdentity {
+    fn clone(&self) -> Self {
+        Self {
+            chain: self.chain.clone(),
+            key: self.key.clone_key(),
+            not_before_ms: self.not_before_ms,
+            not_after_ms: self.not_after_ms,
+        }
+    }
+}
+
+impl PartialEq for ClientIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        // Why: `new` proves the key matches the leaf, so equal chains mean
+        // equal key pairs; private key bytes are never compared.
+        self.chain == other.chain
+    }
+}
+
+impl Eq for ClientIdentity {}
+
+impl fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientIdentity")
+            .field("chain_len", &self.chain.len())
+            .field("not_before_ms", &self.not_before_ms)
+            .field("not_after_ms", &self.not_after_ms)
+            .field("key", &"<redacted>")
+            .finish()
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, TransportSecurityError> {
+    std::fs::read(path)
+        .map_err(|error| identity_error(&format!("failed to read {}: {error}", path.display())))
+}
+
+fn identity_error(reason: &str) -> TransportSecurityError {
+    TransportSecurityError::ClientIdentity(reason.to_string())
+}
@@ -0,0 +1,140 @@
+#![warn(missing_docs)]
+//! # local-guard-transport
+//!
+//! ## Purpose
+//! Owns the TLS transport security policy shared by the auth and ingest
+//! connections, so both trust servers the same way.
+//!
+//! ## Responsibilities
+//! - Describe trust ([`TransportSecurity`]): SPKI pins with backup pins, an
+//!   optional custom CA bundle, and a minimum TLS version.
+//! - Verify server chains with WebPKI plus pin checks.
+//! - Execute HTTPS requests under that policy ([`HttpsConnector`]).
+//! - Present an optional client certificate for mutual TLS
+//!   ([`ClientIdentity`]) and report its expiry status.
+//! - Restrict endpoints to allowed hosts and ports ([`EndpointAllowlist`]).
+//! - Tunnel through an optional HTTP proxy with CONNECT ([`ProxyConfig`]).
+//! - Report trust failures as a distinct class ([`TransportSecurityError`]).
+//!
+//! ## Data flow
+//! [`TransportSecurity`] -> rustls `ClientConfig` -> [`HttpsConnector`] ->
+//! auth and upload transports.
+//!
+//! ## Ownership and lifetimes
+//! Policies are plain values; a connector shares its TLS configuration
+//! through an `Arc` and is cheap to clone across worker threads.
+//!
+//! ## Error model
+//! Policy, pin, certificate, and protocol failures return
+//! [`TransportSecurityError`]; network and framing failures return
+//! [`TransportError`], which wraps the security class unchanged.
+//!
+//! ## Security and privacy notes
+//! - Pins are SHA-256 hashes of the SubjectPublicKeyInfo, so certificate
+//!   renewal with the same key keeps working.
+//! - Pins add to chain validation; they never replace it.
+//! - The TLS handshake completes before any request byte is written.
+//! - Proxied connections run the TLS handshake end to end with the origin,
+//!   so the proxy sees only the target host and port.
+//! - Endpoints outside the compiled-in allowlist are refused when clients
+//!   are built, before any connection exists.
+//!
+//! ## Example
+//! ```rust
+//! use local_guard_transport::{HttpsConnector, SpkiPin, TlsVersion, TransportSecurity};
+//!
+//! let primary = SpkiPin::from_spki_der(b"primary key");
+//! let backup = SpkiPin::from_spki_der(b"backup key");
+//! let policy = TransportSecurity::new()
+//!     .with_pins(vec![primary], vec![backup])
+//!     .expect("policy should be coherent")
+//!     .with_min_tls_version(TlsVersion::Tls13);
+//! let connector = HttpsConnector::new(&policy).expect("policy should build");
+//! # let _ = connector;
+//! ```
+
+use thiserror::Error;
+
+mod allowlist;
+mod der;
+mod http;
+mod identity;
+mod pin;
+mod policy;
+mod proxy;
+mod verifier;
+
+pub use allowlist::{BUILTIN_ENDPOINT_HOSTS, EndpointAllowlist};
+pub use http::{DEFAULT_TIMEOUT, HttpRequest, HttpResponse, HttpsConnector};
+pub use identity::{ClientCertificateStatus, ClientIdentity};
+pub use pin::SpkiPin;
+pub use policy::{TlsVersion, TransportSecurity};
+pub use proxy::ProxyConfig;
+
+/// Transport security failures: the server or policy cannot be trusted.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TransportSecurityError {
+    /// Pin string or certificate used for pinning is malformed.
+    #[error("invalid pin: {0}")]
+    InvalidPin(String),
+    /// Policy settings contradict each other.
+    #[error("invalid transport security policy: {0}")]
+    InvalidPolicy(String),
+    /// CA bundle cannot be read or used as trust anchors.
+    #[error("invalid ca bundle: {0}")]
+    CaBundle(String),
+    /// Server chain is valid but no certificate matches a pin.
+    #[error("server certificate does not match any pin")]
+    PinMismatch,
+    /// Server chain failed validation (issuer, name, or validity).
+    #[error("untrusted server certificate: {0}")]
+    UntrustedCertificate(String),
+    /// Server does not support the minimum TLS version.
+    #[error("server does not support the minimum tls version")]
+    ProtocolVersion,
+    /// Endpoint host, port, or form is outside the endpoint allowlist.
+    #[error("endpoint not allowed: {0}")]
+    EndpointNotAllowed(String),
+    /// Client certificate or key cannot be loaded or used.
+    #[error("invalid client identity: {0}")]
+    ClientIdentity(String),
+    /// Server refused the client certificate (missing, expired, or
+    /// untrusted).
+    #[error("server rejected the client certificate: {0}")]
+    ClientCertificateRejected(String),
+    /// Other TLS handshake failure.
+    #[error("tls handshake failed: {0}")]
+    Handshake(String),
+}
+
+/// HTTPS request failures.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TransportError {
+    /// Transport security policy rejected the connection.
+    #[error("transport security: {0}")]
+    Security(#[from] TransportSecurityError),
+    /// Connect, read, or write timed out.
+    #[error("request timed out")]
+    Timeout,
+    /// Host could not be resolved or connected.
+    #[error("connect failed: {0}")]
+    Connect(String),
+    /// Connection failed after it was established.
+    #[error("i/o failure: {0}")]
+    Io(String),
+    /// Request is malformed (URL, scheme, or headers).
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    /// Response framing is malformed.
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+    /// Proxy URL or bypass list is malformed.
+    #[error("invalid proxy configuration: {0}")]
+    InvalidProxy(String),
+    /// Proxy rejected the configured credentials (HTTP 407).
+    #[error("proxy authentication required")]
+    ProxyAuthentication,
+    /// Proxy answered CONNECT with a non-success status.
+    #[error("proxy refused tunnel with status {0}")]
+    ProxyRefused(u16),
+}
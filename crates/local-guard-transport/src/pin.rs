@@ -0,0 +1,86 @@
+//! # Module: pin
+//!
+//! ## Purpose
+//! Represents SPKI pins and extracts the SubjectPublicKeyInfo from X.509
+//! certificates so pins survive certificate renewal with the same key.
+//!
+//! ## Responsibilities
+//! - Parse and format pins in the `sha256/<base64>` form used by HPKP and
+//!   curl's `--pinnedpubkey`.
+//! - Hash the SPKI element of a DER certificate (located by [`crate::der`]).
+//!
+//! ## Invariants
+//! - A pin is always the SHA-256 of the complete DER-encoded SPKI.
+//!
+//! ## Error model
+//! Malformed pins and certificates return
+//! [`TransportSecurityError::InvalidPin`].
+//!
+//! ## Security and privacy notes
+//! Pins are computed from certificate bytes only; signature and validity
+//! checks stay with the WebPKI verifier.
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+use crate::TransportSecurityError;
+use crate::der::certificate_spki;
+
+const PIN_PREFIX: &str = "sha256/";
+
+/// SHA-256 pin over a certificate's SubjectPublicKeyInfo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SpkiPin([u8; 32]);
+
+impl SpkiPin {
+    /// Parses a pin in `sha256/<base64>` form.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::InvalidPin`] for a missing prefix,
+    /// invalid base64, or a digest that is not 32 bytes.
+    pub fn parse(value: &str) -> Result<Self, TransportSecurityError> {
+        let encoded = value.trim().strip_prefix(PIN_PREFIX).ok_or_else(|| {
+            TransportSecurityError::InvalidPin(format!("pin must start with {PIN_PREFIX}"))
+        })?;
+        let digest = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|error| TransportSecurityError::InvalidPin(error.to_string()))?;
+        let digest: [u8; 32] = digest.as_slice().try_into().map_err(|_| {
+            TransportSecurityError::InvalidPin(format!(
+                "expected 32-byte digest, got {}",
+                digest.len()
+            ))
+        })?;
+        Ok(Self(digest))
+    }
+
+    /// Hashes a DER-encoded SubjectPublicKeyInfo.
+    pub fn from_spki_der(spki: &[u8]) -> Self {
+        Self(Sha256::digest(spki).into())
+    }
+
+    /// Computes the pin of a DER-encoded X.509 certificate's public key.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::InvalidPin`] when the certificate
+    /// structure cannot be walked.
+    pub fn from_certificate_der(certificate: &[u8]) -> Result<Self, TransportSecurityError> {
+        certificate_spki(certificate)
+            .map(Self::from_spki_der)
+            .map_err(invalid)
+    }
+}
+
+impl std::fmt::Display for SpkiPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{PIN_PREFIX}{}",
+            base64::engine::general_purpose::STANDARD.encode(self.0)
+        )
+    }
+}
+
+fn invalid(reason: String) -> TransportSecurityError {
+    TransportSecurityError::InvalidPin(format!("certificate: {reason}"))
+}
@@ -0,0 +1,233 @@
+//! # Module: policy
+//!
+//! ## Purpose
+//! Describes how auth and ingest connections decide to trust a server, and
+//! turns that description into a rustls client configuration.
+//!
+//! ## Responsibilities
+//! - Hold primary and backup SPKI pins, the trust roots, the minimum TLS
+//!   version, and the optional mutual TLS client identity
+//!   ([`TransportSecurity`]).
+//! - Reject incoherent policies before any connection is attempted.
+//! - Build the shared [`rustls::ClientConfig`].
+//!
+//! ## Invariants
+//! - A pinned policy always carries at least one backup pin that differs
+//!   from every primary pin, so a key rotation cannot brick the client.
+//! - A custom CA bundle replaces the public WebPKI roots instead of
+//!   extending them.
+//!
+//! ## Error model
+//! Policy and bundle problems return [`TransportSecurityError::InvalidPolicy`]
+//! or [`TransportSecurityError::CaBundle`].
+//!
+//! ## Security and privacy notes
+//! Pins are checked in addition to, never instead of, chain validation.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::client::WebPkiServerVerifier;
+use rustls::{ClientConfig, RootCertStore};
+use rustls_pki_types::CertificateDer;
+use rustls_pki_types::pem::PemObject;
+
+use crate::verifier::PinningVerifier;
+use crate::{ClientIdentity, SpkiPin, TransportSecurityError};
+
+/// Lowest TLS protocol version a connection may negotiate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum TlsVersion {
+    /// TLS 1.2 or newer.
+    #[default]
+    Tls12,
+    /// TLS 1.3 only.
+    Tls13,
+}
+
+impl TlsVersion {
+    /// Parses `1.2` or `1.3` (an optional `tls` prefix is accepted).
+    pub fn parse(raw: &str) -> Option<Self> {
+        let normalized = raw.trim().to_ascii_lowercase();
+        match normalized.trim_start_matches("tls").trim_start_matches('v') {
+            "1.2" => Some(Self::Tls12),
+            "1.3" => Some(Self::Tls13),
+            _ => None,
+        }
+    }
+}
+
+/// Transport security policy shared by auth and ingest connections.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TransportSecurity {
+    pins: Vec<SpkiPin>,
+    backup_pins: Vec<SpkiPin>,
+    ca_bundle: Option<Vec<CertificateDer<'static>>>,
+    min_tls_version: TlsVersion,
+    client_identity: Option<ClientIdentity>,
+}
+
+impl TransportSecurity {
+    /// Creates the default policy: public WebPKI roots, no pins, TLS 1.2+.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires the server chain to contain a key matching `pins` or
+    /// `backup_pins`.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::InvalidPolicy`] when primary pins
+    /// are given without a distinct backup pin, or backup pins without
+    /// primary pins.
+    pub fn with_pins(
+        mut self,
+        pins: Vec<SpkiPin>,
+        backup_pins: Vec<SpkiPin>,
+    ) -> Result<Self, TransportSecurityError> {
+        if pins.is_empty() && !backup_pins.is_empty() {
+            return Err(TransportSecurityError::InvalidPolicy(
+                "backup pins require at least one primary pin".to_string(),
+            ));
+        }
+        if !pins.is_empty() && !backup_pins.iter().any(|backup| !pins.contains(backup)) {
+            return Err(TransportSecurityError::InvalidPolicy(
+                "pinned policies need a backup pin distinct from the primary pins".to_string(),
+            ));
+        }
+        self.pins = pins;
+        self.backup_pins = backup_pins;
+        Ok(self)
+    }
+
+    /// Trusts only the CA certificates in a PEM bundle.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::CaBundle`] for malformed PEM or a
+    /// bundle without certificates.
+    pub fn with_ca_bundle_pem(mut self, pem: &[u8]) -> Result<Self, TransportSecurityError> {
+        let certificates = CertificateDer::pem_slice_iter(pem)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| TransportSecurityError::CaBundle(error.to_string()))?;
+        if certificates.is_empty() {
+            return Err(TransportSecurityError::CaBundle(
+                "bundle contains no certificates".to_string(),
+            ));
+        }
+        self.ca_bundle = Some(certificates);
+        Ok(self)
+    }
+
+    /// Reads a PEM CA bundle from `path` (see
+    /// [`TransportSecurity::with_ca_bundle_pem`]).
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::CaBundle`] when the file cannot be
+    /// read or parsed.
+    pub fn with_ca_bundle_file(
+        self,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, TransportSecurityError> {
+        let path = path.as_ref();
+        let pem = std::fs::read(path).map_err(|error| {
+            TransportSecurityError::CaBundle(format!("failed to read {}: {error}", path.display()))
+        })?;
+        self.with_ca_bundle_pem(&pem)
+    }
+
+    /// Sets the minimum negotiated TLS version.
+    pub fn with_min_tls_version(mut self, version: TlsVersion) -> Self {
+        self.min_tls_version = version;
+        self
+    }
+
+    /// Presents `identity` when the server requests a client certificate.
+    pub fn with_client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.client_identity = Some(identity);
+        self
+    }
+
+    /// Returns the primary pins.
+    pub fn pins(&self) -> &[SpkiPin] {
+        &self.pins
+    }
+
+    /// Returns the backup pins.
+    pub fn backup_pins(&self) -> &[SpkiPin] {
+        &self.backup_pins
+    }
+
+    /// Returns `true` when connections are pinned.
+    pub fn is_pinned(&self) -> bool {
+        !self.pins.is_empty()
+    }
+
+    /// Returns `true` when a custom CA bundle replaces the WebPKI roots.
+    pub fn uses_custom_ca(&self) -> bool {
+        self.ca_bundle.is_some()
+    }
+
+    /// Returns the minimum negotiated TLS version.
+    pub fn min_tls_version(&self) -> TlsVersion {
+        self.min_tls_version
+    }
+
+    /// Returns the mutual TLS client identity, if configured.
+    pub fn client_identity(&self) -> Option<&ClientIdentity> {
+        self.client_identity.as_ref()
+    }
+
+    /// Builds the rustls client configuration enforcing this policy.
+    ///
+    /// # Errors
+    /// Returns [`TransportSecurityError::CaBundle`] when a bundle
+    /// certificate is not a usable trust anchor,
+    /// [`TransportSecurityError::InvalidPolicy`] when the provider cannot
+    /// satisfy the TLS version constraint, and
+    /// [`TransportSecurityError::ClientIdentity`] when the client key
+    /// cannot be used.
+    pub fn client_config(&self) -> Result<Arc<ClientConfig>, TransportSecurityError> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let mut roots = RootCertStore::empty();
+        match &self.ca_bundle {
+            Some(certificates) => {
+                for certificate in certificates {
+                    roots
+                        .add(certificate.clone())
+                        .map_err(|error| TransportSecurityError::CaBundle(error.to_string()))?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        let roots = Arc::new(roots);
+        let chain_verifier =
+            WebPkiServerVerifier::builder_with_provider(roots.clone(), provider.clone())
+                .build()
+                .map_err(|error| TransportSecurityError::CaBundle(error.to_string()))?;
+        let accepted_pins = self.pins.iter().chain(&self.backup_pins).copied().collect();
+        let verifier = PinningVerifier::new(
+            chain_verifier,
+            roots,
+            provider.signature_verification_algorithms,
+            accepted_pins,
+        );
+
+        let versions: &[&'static rustls::SupportedProtocolVersion] = match self.min_tls_version {
+            TlsVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12],
+            TlsVersion::Tls13 => &[&rustls::version::TLS13],
+        };
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(versions)
+            .map_err(|error| TransportSecurityError::InvalidPolicy(error.to_string()))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier));
+        let config = match &self.client_identity {
+            Some(identity) => builder
+                .with_client_auth_cert(identity.chain().to_vec(), identity.key())
+                .map_err(|error| TransportSecurityError::ClientIdentity(error.to_string()))?,
+            None => builder.with_no_client_auth(),
+        };
+        Ok(Arc::new(config))
+    }
+}
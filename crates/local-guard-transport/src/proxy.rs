@@ -0,0 +1,261 @@
+//! # Module: proxy
+//!
+//! ## Purpose
+//! Describes the HTTP proxy that auth and ingest connections tunnel
+//! through on networks without direct outbound access.
+//!
+//! ## Responsibilities
+//! - Parse `http://[user:password@]host[:port]` proxy URLs
+//!   ([`ProxyConfig::parse`]).
+//! - Parse `NO_PROXY`-style bypass lists ([`ProxyConfig::with_no_proxy`]).
+//! - Decide per target host whether to tunnel ([`ProxyConfig::applies_to`]).
+//! - Build the `Proxy-Authorization` header for basic auth.
+//!
+//! ## Invariants
+//! - Only plain `http://` proxies are supported; TLS to the origin runs
+//!   inside the CONNECT tunnel, so the proxy never sees request bytes.
+//! - A bypass entry matches its host and every subdomain of it; `*`
+//!   bypasses every host.
+//!
+//! ## Error model
+//! Malformed proxy URLs or bypass entries return
+//! [`TransportError::InvalidProxy`].
+//!
+//! ## Security and privacy notes
+//! - `Debug` and `Display` replace the password with `***`; error messages
+//!   never include credentials.
+//! - Basic credentials reach the proxy in cleartext, as with every HTTP
+//!   proxy; server trust (pins, CA, client certificate) is unchanged
+//!   because the handshake runs end to end with the origin.
+
+use std::fmt;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use url::{Host, Url};
+
+use crate::TransportError;
+
+#[derive(Clone, PartialEq, Eq)]
+struct ProxyCredentials {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BypassRule {
+    All,
+    Host(Host<String>),
+    Domain(String),
+}
+
+impl BypassRule {
+    fn parse(raw: &str) -> Result<Self, TransportError> {
+        let entry = raw.trim();
+        if entry == "*" {
+            return Ok(Self::All);
+        }
+        // Why: `.corp.example` and `corp.example` mean the same thing in
+        // curl-style bypass lists.
+        let entry = entry
+            .strip_prefix("*.")
+            .or_else(|| entry.strip_prefix('.'))
+            .unwrap_or(entry);
+        // Why: ports in bypass entries are accepted and ignored, like curl.
+        let parsed = Url::parse(&format!("http://{entry}/"))
+            .ok()
+            .filter(|url| url.path() == "/" && url.username().is_empty())
+            .ok_or_else(|| invalid_proxy(&format!("invalid no-proxy entry {raw:?}")))?;
+        match parsed.host() {
+            Some(Host::Domain(domain)) => Ok(Self::Domain(domain.to_string())),
+            Some(host) => Ok(Self::Host(host.to_owned())),
+            None => Err(invalid_proxy(&format!("invalid no-proxy entry {raw:?}"))),
+        }
+    }
+
+    fn matches(&self, host: &Host<&str>) -> bool {
+        match (self, host) {
+            (Self::All, _) => true,
+            (Self::Host(expected), host) => *expected == host.to_owned(),
+            (Self::Domain(domain), Host::Domain(target)) => {
+                target == domain
+                    || target
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|label| label.ends_with('.'))
+            }
+            (Self::Domain(_), _) => false,
+        }
+    }
+}
+
+/// HTTP proxy used for CONNECT tunnelling, with optional basic auth and a
+/// bypass list.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    host: Host<String>,
+    port: u16,
+    credentials: Option<ProxyCredentials>,
+    bypass: Vec<BypassRule>,
+}
+
+impl ProxyConfig {
+    /// Parses an `http://[user:password@]host[:port]` proxy URL. A bare
+    /// `host[:port]` is read as `http://`, and an omitted port means 80.
+    /// Userinfo is percent-decoded into basic auth credentials.
+    ///
+    /// # Errors
+    /// Returns [`TransportError::InvalidProxy`] for other schemes, a path,
+    /// query, or fragment, a missing host, or undecodable credentials.
+    pub fn parse(raw: &str) -> Result<Self, TransportError> {
+        let raw = raw.trim();
+        let candidate = if raw.contains("://") {
+            raw.to_string()
+        } else {
+            format!("http://{raw}")
+        };
+        // Failure mode: echoing `raw` could leak the password, so parse
+        // errors only describe the problem.
+        let url = Url::parse(&candidate).map_err(|error| invalid_proxy(&error.to_string()))?;
+        if url.scheme() != "http" {
+            return Err(invalid_proxy("only http:// proxies are supported"));
+        }
+        if url.path() != "/" || url.query().is_some() || url.fragment().is_some() {
+            return Err(invalid_proxy("proxy url must not have a path or query"));
+        }
+        let host = url
+            .host()
+            .ok_or_else(|| invalid_proxy("proxy url has no host"))?
+            .to_owned();
+
+        let credentials = if url.username().is_empty() && url.password().is_none() {
+            None
+        } else {
+            let decode = |value: &str| {
+                percent_decode(value)
+                    .ok_or_else(|| invalid_proxy("proxy credentials are not valid utf-8"))
+            };
+            Some(ProxyCredentials {
+                username: decode(url.username())?,
+                password: decode(url.password().unwrap_or_default())?,
+            })
+        };
+        Ok(Self {
+            host,
+            port: url.port_or_known_default().unwrap_or(80),
+            credentials,
+            bypass: Vec::new(),
+        })
+    }
+
+    /// Replaces basic auth credentials, for deployments that keep them out
+    /// of the proxy URL.
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some(ProxyCredentials {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Sets the bypass list from a comma-separated `NO_PROXY` value: hosts,
+    /// domains (with or without a leading `.`), IP literals, or `*`.
+    ///
+    /// # Errors
+    /// Returns [`TransportError::InvalidProxy`] for a malformed entry.
+    pub fn with_no_proxy(mut self, raw: &str) -> Result<Self, TransportError> {
+        self.bypass = raw
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(BypassRule::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self)
+    }
+
+    /// Returns the proxy host.
+    pub fn host(&self) -> String {
+        self.host.to_string()
+    }
+
+    /// Returns the proxy port.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns whether basic auth credentials are configured.
+    pub fn has_credentials(&self) -> bool {
+        self.credentials.is_some()
+    }
+
+    /// Returns whether requests to `host` go through the proxy, i.e. no
+    /// bypass entry matches it.
+    pub fn applies_to(&self, host: &str) -> bool {
+        let Ok(host) = Host::parse(&host.to_ascii_lowercase()) else {
+            return true;
+        };
+        let host = match &host {
+            Host::Domain(domain) => Host::Domain(domain.as_str()),
+            Host::Ipv4(addr) => Host::Ipv4(*addr),
+            Host::Ipv6(addr) => Host::Ipv6(*addr),
+        };
+        !self.bypass.iter().any(|rule| rule.matches(&host))
+    }
+
+    /// Returns the `Proxy-Authorization` header value, if credentials are
+    /// configured.
+    pub(crate) fn authorization(&self) -> Option<String> {
+        self.credentials.as_ref().map(|credentials| {
+            let token =
+                STANDARD.encode(format!("{}:{}", credentials.username, credentials.password));
+            format!("Basic {token}")
+        })
+    }
+
+    /// Returns the `host:port` authority to connect to.
+    pub(crate) fn authority(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+impl fmt::Display for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://")?;
+        if let Some(credentials) = &self.credentials {
+            write!(f, "{}:***@", credentials.username)?;
+        }
+        write!(f, "{}", self.authority())
+    }
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("url", &self.to_string())
+            .field("bypass", &self.bypass)
+            .finish()
+    }
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn invalid_proxy(reason: &str) -> TransportError {
+    TransportError::InvalidProxy(reason.to_string())
+}
@@ -0,0 +1,160 @@
+//! # Module: verifier
+//!
+//! ## Purpose
+//! Adds SPKI pin checks on top of standard WebPKI chain validation.
+//!
+//! ## Responsibilities
+//! - Delegate chain, name, and signature checks to rustls' WebPKI verifier.
+//! - Accept the chain only if a certificate on a validated path matches a
+//!   pin.
+//!
+//! ## Invariants
+//! - Pins never relax validation: an unpinned policy behaves exactly like
+//!   the WebPKI verifier.
+//! - Pins are matched against the path WebPKI built from the leaf to a
+//!   trust anchor, never against every certificate the server sent; an
+//!   unused extra certificate in the handshake cannot satisfy a pin.
+//!
+//! ## Error model
+//! A pin mismatch is reported as
+//! `CertificateError::ApplicationVerificationFailure`, which the connector
+//! maps to [`crate::TransportSecurityError::PinMismatch`].
+//!
+//! ## Security and privacy notes
+//! A pin may name the leaf, an intermediate, or the trust anchor of the
+//! validated path. When several valid paths exist, the chain is accepted if
+//! any of them carries a pinned key.
+
+use std::sync::Arc;
+
+use rustls::client::WebPkiServerVerifier;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::{CertificateDer, ServerName, TrustAnchor, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, Error, RootCertStore, SignatureScheme};
+use webpki::{EndEntityCert, KeyUsage, VerifiedPath};
+
+use crate::SpkiPin;
+use crate::der::wrap_sequence;
+
+/// Chain verifier that additionally enforces an SPKI pin set.
+#[derive(Debug)]
+pub(crate) struct PinningVerifier {
+    chain: Arc<WebPkiServerVerifier>,
+    roots: Arc<RootCertStore>,
+    algorithms: WebPkiSupportedAlgorithms,
+    pins: Vec<SpkiPin>,
+}
+
+impl PinningVerifier {
+    pub(crate) fn new(
+        chain: Arc<WebPkiServerVerifier>,
+        roots: Arc<RootCertStore>,
+        algorithms: WebPkiSupportedAlgorithms,
+        pins: Vec<SpkiPin>,
+    ) -> Self {
+        Self {
+            chain,
+            roots,
+            algorithms,
+            pins,
+        }
+    }
+
+    /// Rebuilds the chain with WebPKI and returns whether some validated
+    /// path contains a pinned key.
+    fn verified_path_matches_pin(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> bool {
+        let Ok(certificate) = EndEntityCert::try_from(end_entity) else {
+            return false;
+        };
+        // Why: path building backtracks when the callback fails, so a
+        // pinned alternate path is still found after an unpinned one.
+        let require_pin = |path: &VerifiedPath<'_>| {
+            if self.path_matches_pin(path) {
+                Ok(())
+            } else {
+                Err(webpki::Error::UnknownIssuer)
+            }
+        };
+        certificate
+            .verify_for_usage(
+                self.algorithms.all,
+                &self.roots.roots,
+                intermediates,
+                now,
+                KeyUsage::server_auth(),
+                None,
+                Some(&require_pin),
+            )
+            .is_ok()
+    }
+
+    fn path_matches_pin(&self, path: &VerifiedPath<'_>) -> bool {
+        std::iter::once(path.end_entity().subject_public_key_info())
+            .chain(
+                path.intermediate_certificates()
+                    .map(|certificate| certificate.subject_public_key_info()),
+            )
+            .map(|spki| SpkiPin::from_spki_der(spki.as_ref()))
+            .chain(std::iter::once(anchor_pin(path.anchor())))
+            .any(|pin| self.pins.contains(&pin))
+    }
+}
+
+/// Pins a trust anchor, whose SPKI is stored without its outer SEQUENCE.
+fn anchor_pin(anchor: &TrustAnchor<'_>) -> SpkiPin {
+    SpkiPin::from_spki_der(&wrap_sequence(anchor.subject_public_key_info.as_ref()))
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        let verified = self.chain.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )?;
+        if self.pins.is_empty() || self.verified_path_matches_pin(end_entity, intermediates, now) {
+            Ok(verified)
+        } else {
+            Err(Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.chain.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        self.chain.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.chain.supported_verify_schemes()
+    }
+}
@@ -0,0 +1,266 @@
+//! Tests mutual TLS client identities: PEM and PKCS#12 loading, expiry
+//! status, and handshakes against a local server that requires client
+//! certificates.
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::time::Duration;
+
+use local_guard_transport::{
+    ClientCertificateStatus, ClientIdentity, HttpRequest, HttpsConnector, TransportError,
+    TransportSecurity, TransportSecurityError,
+};
+use p12_keystore::{Certificate, KeyStore, KeyStoreEntry, PrivateKey, PrivateKeyChain};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedIssuer, DnType, IsCa, KeyPair, date_time_ymd,
+};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+
+const HOST: &str = "ingest.local-guard.test";
+const DAY_MS: u64 = 86_400_000;
+// 2030-01-01T00:00:00Z and 2031-01-01T00:00:00Z.
+const NOT_BEFORE_MS: u64 = 1_893_456_000_000;
+const NOT_AFTER_MS: u64 = 1_924_992_000_000;
+
+struct Pki {
+    ca: CertifiedIssuer<'static, KeyPair>,
+}
+
+struct IssuedCert {
+    cert_pem: String,
+    key_pem: String,
+    cert_der: Vec<u8>,
+    key_der: Vec<u8>,
+}
+
+impl Pki {
+    fn new(name: &str) -> Self {
+        let mut params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Self {
+            ca: CertifiedIssuer::self_signed(params, KeyPair::generate().expect("ca key"))
+                .expect("ca cert"),
+        }
+    }
+
+    fn issue(&self, names: Vec<String>, validity: Option<(i32, i32)>) -> IssuedCert {
+        let key = KeyPair::generate().expect("key");
+        let mut params = CertificateParams::new(names).expect("params");
+        if let Some((from_year, to_year)) = validity {
+            params.not_before = date_time_ymd(from_year, 1, 1);
+            params.not_after = date_time_ymd(to_year, 1, 1);
+        }
+        let cert = params.signed_by(&key, &self.ca).expect("cert");
+        IssuedCert {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+            cert_der: cert.der().to_vec(),
+            key_der: key.serialize_der(),
+        }
+    }
+
+    fn client(&self) -> IssuedCert {
+        self.issue(vec!["device-1".to_string()], None)
+    }
+
+    fn policy(&self) -> TransportSecurity {
+        TransportSecurity::new()
+            .with_ca_bundle_pem(self.ca.pem().as_bytes())
+            .expect("ca bundle")
+    }
+}
+
+/// Serves one request over TLS, requiring client certificates issued by
+/// `client_ca`. Returns the bound address.
+fn serve_requiring_client_auth(server_pki: &Pki, client_ca: &Pki) -> SocketAddr {
+    let leaf = server_pki.issue(vec![HOST.to_string()], None);
+    let mut roots = RootCertStore::empty();
+    roots.add(client_ca.ca.der().clone()).expect("client root");
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let client_verifier =
+        WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build()
+            .expect("client verifier");
+    let config = Arc::new(
+        ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .expect("versions")
+            .with_client_cert_verifier(client_verifier)
+            .with_single_cert(
+                vec![leaf.cert_der.into()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf.key_der)),
+            )
+            .expect("server cert"),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    std::thread::spawn(move || {
+        let Ok((tcp, _)) = listener.accept() else {
+            return;
+        };
+        let mut tls = StreamOwned::new(ServerConnection::new(config).expect("conn"), tcp);
+        let mut raw = Vec::new();
+        let mut buffer = [0_u8; 4096];
+        while !raw.ends_with(b"\r\n\r\n{}") {
+            match tls.read(&mut buffer) {
+                Ok(0) => return,
+                Ok(read) => raw.extend_from_slice(&buffer[..read]),
+                Err(_) => {
+                    // Why: closing with unread client bytes makes the kernel
+                    // reset the connection, which can discard the alert
+                    // before the client reads it.
+                    let _ = tls.conn.write_tls(&mut tls.sock);
+                    let _ = tls.sock.shutdown(Shutdown::Write);
+                    let _ = tls.sock.set_read_timeout(Some(Duration::from_secs(2)));
+                    while matches!(tls.sock.read(&mut buffer), Ok(read) if read > 0) {}
+                    return;
+                }
+            }
+        }
+        let _ = tls.write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n");
+        tls.conn.send_close_notify();
+        let _ = tls.flush();
+    });
+    addr
+}
+
+fn send(policy: &TransportSecurity, addr: SocketAddr) -> Result<u16, TransportError> {
+    HttpsConnector::new(policy)?
+        .with_timeout(Duration::from_secs(5))
+        .with_resolved_addr(HOST, addr)
+        .send(&HttpRequest::post(
+            format!("https://{HOST}/r1/ingest"),
+            b"{}".to_vec(),
+        ))
+        .map(|response| response.status)
+}
+
+#[test]
+fn client_identity_tests_loads_pem_and_reports_expiry_status() {
+    let pki = Pki::new("device ca");
+    let issued = pki.issue(vec!["device-1".to_string()], Some((2030, 2031)));
+    let bundle = format!("{}{}", issued.cert_pem, issued.key_pem);
+
+    let identity = ClientIdentity::from_pem(bundle.as_bytes(), bundle.as_bytes())
+        .expect("combined pem should load");
+    assert_eq!(
+        ClientIdentity::from_pem(issued.cert_pem.as_bytes(), issued.key_pem.as_bytes()),
+        Ok(identity.clone())
+    );
+    assert_eq!(identity.chain().len(), 1);
+    assert_eq!(identity.not_before_ms(), NOT_BEFORE_MS);
+    assert_eq!(identity.not_after_ms(), NOT_AFTER_MS);
+    assert!(!format!("{identity:?}").contains(&issued.key_pem));
+
+    let warn = 30 * DAY_MS;
+    assert_eq!(
+        identity.status(NOT_BEFORE_MS - 1, warn),
+        ClientCertificateStatus::NotYetValid
+    );
+    assert_eq!(
+        identity.status(NOT_BEFORE_MS, warn),
+        ClientCertificateStatus::Valid
+    );
+    assert_eq!(
+        identity.status(NOT_AFTER_MS - 10 * DAY_MS, warn),
+        ClientCertificateStatus::ExpiringSoon {
+            remaining_ms: 10 * DAY_MS
+        }
+    );
+    assert_eq!(
+        identity.status(NOT_AFTER_MS + 1, warn),
+        ClientCertificateStatus::Expired
+    );
+}
+
+#[test]
+fn client_identity_tests_loads_pkcs12_and_rejects_wrong_password() {
+    let pki = Pki::new("device ca");
+    let issued = pki.client();
+    let mut store = KeyStore::new();
+    store.add_entry(
+        "device",
+        KeyStoreEntry::PrivateKeyChain(PrivateKeyChain::new(
+            b"device-key".to_vec(),
+            PrivateKey::from_der(&issued.key_der).expect("p12 key"),
+            [
+                Certificate::from_der(&issued.cert_der).expect("p12 leaf"),
+                Certificate::from_der(pki.ca.der()).expect("p12 root"),
+            ],
+        )),
+    );
+    let archive = store.writer("hunter2").write().expect("p12 archive");
+
+    let identity = ClientIdentity::from_pkcs12(&archive, "hunter2").expect("pkcs12 should load");
+    assert_eq!(identity.chain()[0].as_ref(), issued.cert_der.as_slice());
+
+    let error = ClientIdentity::from_pkcs12(&archive, "wrong").expect_err("wrong password");
+    assert!(matches!(error, TransportSecurityError::ClientIdentity(_)));
+    assert!(!error.to_string().contains("hunter2"));
+}
+
+#[test]
+fn client_identity_tests_rejects_missing_and_mismatched_keys() {
+    let pki = Pki::new("device ca");
+    let first = pki.client();
+    let second = pki.client();
+
+    for (cert, key) in [
+        (first.cert_pem.as_str(), second.key_pem.as_str()),
+        (first.cert_pem.as_str(), ""),
+        ("", first.key_pem.as_str()),
+    ] {
+        assert!(matches!(
+            ClientIdentity::from_pem(cert.as_bytes(), key.as_bytes()),
+            Err(TransportSecurityError::ClientIdentity(_))
+        ));
+    }
+    assert!(matches!(
+        ClientIdentity::from_pem_files("/nonexistent/local-guard-client.pem", None),
+        Err(TransportSecurityError::ClientIdentity(_))
+    ));
+}
+
+#[test]
+fn client_identity_tests_mutual_tls_round_trips_with_trusted_identity() {
+    let pki = Pki::new("device ca");
+    let issued = pki.client();
+    let identity = ClientIdentity::from_pem(issued.cert_pem.as_bytes(), issued.key_pem.as_bytes())
+        .expect("identity");
+    let addr = serve_requiring_client_auth(&pki, &pki);
+
+    assert_eq!(
+        send(&pki.policy().with_client_identity(identity), addr),
+        Ok(202)
+    );
+}
+
+#[test]
+fn client_identity_tests_server_rejects_missing_or_untrusted_identity() {
+    let pki = Pki::new("device ca");
+    let addr = serve_requiring_client_auth(&pki, &pki);
+    assert!(matches!(
+        send(&pki.policy(), addr),
+        Err(TransportError::Security(
+            TransportSecurityError::ClientCertificateRejected(_)
+        ))
+    ));
+
+    let other_ca = Pki::new("other ca");
+    let stranger = other_ca.client();
+    let identity =
+        ClientIdentity::from_pem(stranger.cert_pem.as_bytes(), stranger.key_pem.as_bytes())
+            .expect("identity");
+    let addr = serve_requiring_client_auth(&pki, &pki);
+    assert!(matches!(
+        send(&pki.policy().with_client_identity(identity), addr),
+        Err(TransportError::Security(
+            TransportSecurityError::ClientCertificateRejected(_)
+        ))
+    ));
+}
@@ -0,0 +1,412 @@
+//! Tests transport security policy against local TLS servers presenting
+//! pinned and unpinned certificates.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, channel};
+use std::time::Duration;
+
+use local_guard_transport::{
+    HttpRequest, HttpsConnector, SpkiPin, TlsVersion, TransportError, TransportSecurity,
+    TransportSecurityError,
+};
+use rcgen::{
+    BasicConstraints, CertificateParams, CertifiedIssuer, DnType, IsCa, KeyPair, PublicKeyData,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+const HOST: &str = "ingest.local-guard.test";
+
+struct TestPki {
+    ca: CertifiedIssuer<'static, KeyPair>,
+    ca_pin: SpkiPin,
+    leaf_der: CertificateDer<'static>,
+    leaf_key: PrivatePkcs8KeyDer<'static>,
+    leaf_pin: SpkiPin,
+}
+
+impl TestPki {
+    fn new() -> Self {
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).expect("ca params");
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().expect("ca key");
+        let ca_pin = SpkiPin::from_spki_der(&ca_key.subject_public_key_info());
+        let ca = CertifiedIssuer::self_signed(ca_params, ca_key).expect("ca cert");
+
+        let leaf_key = KeyPair::generate().expect("leaf key");
+        let leaf = CertificateParams::new(vec![HOST.to_string()])
+            .expect("leaf params")
+            .signed_by(&leaf_key, &ca)
+            .expect("leaf cert");
+        Self {
+            ca_pin,
+            leaf_pin: SpkiPin::from_spki_der(&leaf_key.subject_public_key_info()),
+            leaf_der: leaf.der().clone(),
+            leaf_key: PrivatePkcs8KeyDer::from(leaf_key.serialize_der()),
+            ca,
+        }
+    }
+
+    fn ca_policy(&self) -> TransportSecurity {
+        TransportSecurity::new()
+            .with_ca_bundle_pem(self.ca.pem().as_bytes())
+            .expect("ca bundle should parse")
+    }
+
+    fn serve(&self, versions: &[&'static rustls::SupportedProtocolVersion]) -> TestServer {
+        serve_chain(
+            vec![self.leaf_der.clone(), self.ca.der().clone()],
+            &self.leaf_key,
+            versions,
+        )
+    }
+
+    /// Issues a pinned intermediate under the CA and returns it with its pin.
+    fn intermediate(&self) -> (CertifiedIssuer<'static, KeyPair>, SpkiPin) {
+        let mut params = CertificateParams::new(Vec::<String>::new()).expect("params");
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "Pinned Intermediate");
+        let key = KeyPair::generate().expect("intermediate key");
+        let pin = SpkiPin::from_spki_der(&key.subject_public_key_info());
+        let intermediate = CertifiedIssuer::signed_by(params, key, &self.ca).expect("intermediate");
+        (intermediate, pin)
+    }
+}
+
+/// Issues a leaf for [`HOST`] signed by `issuer`.
+fn leaf_signed_by(
+    issuer: &CertifiedIssuer<'static, KeyPair>,
+) -> (CertificateDer<'static>, PrivatePkcs8KeyDer<'static>) {
+    let key = KeyPair::generate().expect("leaf key");
+    let leaf = CertificateParams::new(vec![HOST.to_string()])
+        .expect("leaf params")
+        .signed_by(&key, issuer)
+        .expect("leaf cert");
+    (
+        leaf.der().clone(),
+        PrivatePkcs8KeyDer::from(key.serialize_der()),
+    )
+}
+
+fn serve_chain(
+    chain: Vec<CertificateDer<'static>>,
+    key: &PrivatePkcs8KeyDer<'static>,
+    versions: &[&'static rustls::SupportedProtocolVersion],
+) -> TestServer {
+    let config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_protocol_versions(versions)
+            .expect("server versions")
+            .with_no_client_auth()
+            .with_single_cert(chain, PrivateKeyDer::Pkcs8(key.clone_key()))
+            .expect("server cert");
+    TestServer::spawn(Arc::new(config))
+}
+
+struct TestServer {
+    addr: SocketAddr,
+    requests: Receiver<Vec<u8>>,
+}
+
+impl TestServer {
+    fn spawn(config: Arc<ServerConfig>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let (sender, requests) = channel();
+        std::thread::spawn(move || {
+            let Ok((tcp, _)) = listener.accept() else {
+                return;
+            };
+            let connection = ServerConnection::new(config).expect("server connection");
+            let mut tls = StreamOwned::new(connection, tcp);
+            let Some(request) = read_request(&mut tls) else {
+                return;
+            };
+            let _ = sender.send(request);
+            let _ = tls.write_all(
+                b"HTTP/1.1 202 Accepted\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nack\r\n4\r\nnowl\r\n0\r\n\r\n",
+            );
+            tls.conn.send_close_notify();
+            let _ = tls.flush();
+        });
+        Self { addr, requests }
+    }
+
+    fn connector(&self, policy: &TransportSecurity) -> HttpsConnector {
+        HttpsConnector::new(policy)
+            .expect("connector should build")
+            .with_timeout(Duration::from_secs(5))
+            .with_resolved_addr(HOST, self.addr)
+    }
+}
+
+fn read_request(stream: &mut impl Read) -> Option<Vec<u8>> {
+    let mut raw = Vec::new();
+    let mut buffer = [0_u8; 4096];
+    loop {
+        if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&raw[..end]).to_ascii_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if raw.len() >= end + 4 + length {
+                return Some(raw);
+            }
+        }
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return None,
+            Ok(read) => raw.extend_from_slice(&buffer[..read]),
+        }
+    }
+}
+
+fn ingest_request() -> HttpRequest {
+    HttpRequest::post(format!("https://{HOST}/r1/ingest?v=1"), b"{}".to_vec())
+        .with_header("Authorization", "Bearer token")
+}
+
+fn unrelated_pin(label: &[u8]) -> SpkiPin {
+    SpkiPin::from_spki_der(label)
+}
+
+#[test]
+fn tls_policy_tests_pin_round_trips_and_rejects_malformed_values() {
+    let pin = unrelated_pin(b"key");
+    assert_eq!(SpkiPin::parse(&pin.to_string()), Ok(pin));
+    assert!(pin.to_string().starts_with("sha256/"));
+
+    for bad in ["", "sha1/AAAA", "sha256/not base64!", "sha256/AAAA"] {
+        assert!(
+            matches!(
+                SpkiPin::parse(bad),
+                Err(TransportSecurityError::InvalidPin(_))
+            ),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn tls_policy_tests_certificate_pin_matches_key_spki() {
+    let pki = TestPki::new();
+    assert_eq!(
+        SpkiPin::from_certificate_der(&pki.leaf_der),
+        Ok(pki.leaf_pin)
+    );
+    assert_eq!(SpkiPin::from_certificate_der(pki.ca.der()), Ok(pki.ca_pin));
+    assert!(SpkiPin::from_certificate_der(&pki.leaf_der[..40]).is_err());
+
+    // Failure mode: hostile length fields are errors, not panics.
+    for hostile in [
+        &[0x30, 0x84, 0xff, 0xff, 0xff, 0xff, 0x30][..],
+        &[0x30, 0x84, 0xff, 0xff, 0xff][..],
+        &[0x30, 0x85, 0x01, 0x00, 0x00, 0x00, 0x00][..],
+    ] {
+        assert!(SpkiPin::from_certificate_der(hostile).is_err());
+    }
+}
+
+#[test]
+fn tls_policy_tests_rejects_incoherent_pin_sets() {
+    let primary = unrelated_pin(b"primary");
+    let backup = unrelated_pin(b"backup");
+
+    assert!(
+        TransportSecurity::new()
+            .with_pins(vec![primary], vec![backup])
+            .is_ok()
+    );
+    assert!(TransportSecurity::new().with_pins(vec![], vec![]).is_ok());
+    for (pins, backups) in [
+        (vec![primary], vec![]),
+        (vec![primary], vec![primary]),
+        (vec![], vec![backup]),
+    ] {
+        assert!(matches!(
+            TransportSecurity::new().with_pins(pins, backups),
+            Err(TransportSecurityError::InvalidPolicy(_))
+        ));
+    }
+}
+
+#[test]
+fn tls_policy_tests_parses_tls_versions_and_ca_bundles() {
+    assert_eq!(TlsVersion::parse("1.2"), Some(TlsVersion::Tls12));
+    assert_eq!(TlsVersion::parse("TLSv1.3"), Some(TlsVersion::Tls13));
+    assert_eq!(TlsVersion::parse("1.1"), None);
+    assert_eq!(
+        TransportSecurity::new().min_tls_version(),
+        TlsVersion::Tls12
+    );
+
+    assert!(matches!(
+        TransportSecurity::new().with_ca_bundle_pem(b"not pem"),
+        Err(TransportSecurityError::CaBundle(_))
+    ));
+    assert!(TestPki::new().ca_policy().uses_custom_ca());
+}
+
+#[test]
+fn tls_policy_tests_custom_ca_request_round_trips() {
+    let pki = TestPki::new();
+    let server = pki.serve(rustls::DEFAULT_VERSIONS);
+
+    let response = server
+        .connector(&pki.ca_policy())
+        .send(&ingest_request())
+        .expect("request should succeed");
+    assert_eq!(response.status, 202);
+    assert_eq!(response.header("content-type"), Some("text/plain"));
+    assert_eq!(response.body, b"acknowl");
+
+    let request =
+        String::from_utf8(server.requests.recv().expect("request received")).expect("utf8 request");
+    assert!(request.starts_with("POST /r1/ingest?v=1 HTTP/1.1\r\n"));
+    assert!(request.contains(&format!("Host: {HOST}\r\n")));
+    assert!(request.contains("Authorization: Bearer token\r\n"));
+    assert!(request.ends_with("\r\n\r\n{}"));
+}
+
+#[test]
+fn tls_policy_tests_pinned_leaf_intermediate_and_backup_are_accepted() {
+    let pki = TestPki::new();
+    let other = unrelated_pin(b"other");
+    for (pins, backups) in [
+        (vec![pki.leaf_pin], vec![other]),
+        (vec![pki.ca_pin], vec![other]),
+        (vec![other], vec![pki.leaf_pin]),
+    ] {
+        let server = pki.serve(rustls::DEFAULT_VERSIONS);
+        let policy = pki.ca_policy().with_pins(pins, backups).expect("policy");
+        let response = server
+            .connector(&policy)
+            .send(&ingest_request())
+            .expect("pinned request should succeed");
+        assert_eq!(response.status, 202);
+    }
+}
+
+#[test]
+fn tls_policy_tests_unpinned_certificate_is_rejected_before_request_bytes() {
+    let pki = TestPki::new();
+    let server = pki.serve(rustls::DEFAULT_VERSIONS);
+    let policy = pki
+        .ca_policy()
+        .with_pins(vec![unrelated_pin(b"a")], vec![unrelated_pin(b"b")])
+        .expect("policy");
+
+    assert_eq!(
+        server.connector(&policy).send(&ingest_request()),
+        Err(TransportError::Security(
+            TransportSecurityError::PinMismatch
+        ))
+    );
+    assert!(
+        server
+            .requests
+            .recv_timeout(Duration::from_secs(2))
+            .is_err()
+    );
+}
+
+#[test]
+fn tls_policy_tests_pinned_intermediate_must_be_on_the_validated_path() {
+    let pki = TestPki::new();
+    let (intermediate, intermediate_pin) = pki.intermediate();
+    let policy = pki
+        .ca_policy()
+        .with_pins(vec![intermediate_pin], vec![unrelated_pin(b"backup")])
+        .expect("policy");
+
+    let (leaf, key) = leaf_signed_by(&intermediate);
+    let server = serve_chain(
+        vec![leaf, intermediate.der().clone()],
+        &key,
+        rustls::DEFAULT_VERSIONS,
+    );
+    let response = server
+        .connector(&policy)
+        .send(&ingest_request())
+        .expect("leaf issued through the pinned intermediate is accepted");
+    assert_eq!(response.status, 202);
+
+    // Failure mode: a mis-issued leaf chaining straight to the trusted CA,
+    // with an unused copy of the pinned intermediate appended.
+    let (rogue, rogue_key) = leaf_signed_by(&pki.ca);
+    let server = serve_chain(
+        vec![rogue, intermediate.der().clone()],
+        &rogue_key,
+        rustls::DEFAULT_VERSIONS,
+    );
+    assert_eq!(
+        server.connector(&policy).send(&ingest_request()),
+        Err(TransportError::Security(
+            TransportSecurityError::PinMismatch
+        ))
+    );
+    assert!(
+        server
+            .requests
+            .recv_timeout(Duration::from_secs(2))
+            .is_err()
+    );
+}
+
+#[test]
+fn tls_policy_tests_private_ca_is_untrusted_without_bundle() {
+    let pki = TestPki::new();
+    let server = pki.serve(rustls::DEFAULT_VERSIONS);
+    let policy = TransportSecurity::new()
+        .with_pins(vec![pki.leaf_pin], vec![unrelated_pin(b"backup")])
+        .expect("policy");
+
+    assert!(matches!(
+        server.connector(&policy).send(&ingest_request()),
+        Err(TransportError::Security(
+            TransportSecurityError::UntrustedCertificate(_)
+        ))
+    ));
+}
+
+#[test]
+fn tls_policy_tests_enforces_minimum_tls_version() {
+    let pki = TestPki::new();
+
+    let server = pki.serve(&[&rustls::version::TLS12]);
+    let strict = pki.ca_policy().with_min_tls_version(TlsVersion::Tls13);
+    assert_eq!(
+        server.connector(&strict).send(&ingest_request()),
+        Err(TransportError::Security(
+            TransportSecurityError::ProtocolVersion
+        ))
+    );
+
+    let server = pki.serve(&[&rustls::version::TLS12]);
+    let response = server
+        .connector(&pki.ca_policy())
+        .send(&ingest_request())
+        .expect("tls 1.2 should be accepted by default");
+    assert_eq!(response.status, 202);
+}
+
+#[test]
+fn tls_policy_tests_rejects_plaintext_urls_and_header_injection() {
+    let connector = HttpsConnector::new(&TransportSecurity::new()).expect("connector");
+    assert!(matches!(
+        connector.send(&HttpRequest::post(
+            "http://ingest.local-guard.test/r1/ingest",
+            vec![]
+        )),
+        Err(TransportError::InvalidRequest(_))
+    ));
+    assert!(matches!(
+        connector.send(&ingest_request().with_header("X-Bad", "a\r\nInjected: 1")),
+        Err(TransportError::InvalidRequest(_))
+    ));
+}
@@ -5,9 +5,12 @@
 //! Defines the UI-facing runtime state model for `local-guard`.
 //!
 //! ## Responsibilities
-//! - Represent login/auth, consent, display selection, and pipeline statuses.
+//! - Represent login/auth, device enrollment, client certificate, consent,
+//!   display selection, capture mode, and pipeline statuses.
 //! - Project analysis risk signals into display-safe status text.
 //! - Expose guard checks for whether capture can start.
+//! - Derive user-facing warnings (client certificate expiry, upload data
+//!   budget).
 //!
 //! ## Data flow
 //! App orchestration events mutate [`UiState`], which drives rendered status in
@@ -37,6 +40,86 @@ pub enum UiAuthState {
     ReauthRequired,
 }
 
+/// UI projection of device enrollment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiDeviceState {
+    /// Device has no server-issued identity.
+    Unenrolled,
+    /// Device is enrolled and active.
+    Enrolled,
+    /// Device was revoked by an administrator.
+    Revoked,
+}
+
+/// UI projection of the mutual TLS client certificate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiClientCertificateState {
+    /// No client certificate is configured.
+    NotConfigured,
+    /// Certificate is valid beyond the warning window.
+    Valid,
+    /// Certificate expires soon.
+    ExpiringSoon {
+        /// Whole days left before expiry (rounded down).
+        days_left: u64,
+    },
+    /// Certificate has expired; the server will refuse uploads.
+    Expired,
+    /// Certificate is not valid yet (clock skew or early deployment).
+    NotYetValid,
+}
+
+/// Budget period named in [`UiDataBudgetState::Exhausted`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiBudgetPeriod {
+    /// UTC calendar day.
+    Daily,
+    /// UTC calendar month.
+    Monthly,
+}
+
+/// What the app does while a data budget is exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiBudgetAction {
+    /// Uploads continue at reduced image quality.
+    LowerQuality,
+    /// Uploads continue at a reduced capture rate.
+    ReduceFps,
+    /// Uploads are paused; batches stay queued.
+    QueueOnly,
+}
+
+/// UI projection of the upload data budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiDataBudgetState {
+    /// No daily or monthly limit is configured.
+    Unlimited,
+    /// Every limit has room left.
+    Within {
+        /// Highest share of any limit already used (0-99).
+        used_percent: u8,
+    },
+    /// A limit is used up until its period resets.
+    Exhausted {
+        /// Exhausted period.
+        period: UiBudgetPeriod,
+        /// Policy in effect.
+        action: UiBudgetAction,
+    },
+}
+
+/// Used share of a data budget at which the UI starts warning.
+pub const DATA_BUDGET_WARNING_PERCENT: u8 = 80;
+
+/// UI projection of which displays capture should acquire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiCaptureMode {
+    /// Only the display chosen in the display selector is captured.
+    SelectedDisplay,
+    /// Every display is captured on each tick; display selection is ignored.
+    AllDisplays,
+}
+
 /// Generic stage status used for capture/network/upload flow.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StageStatus {
@@ -57,10 +140,20 @@ pub struct UiState {
     pub version: String,
     /// Current auth status.
     pub auth: UiAuthState,
+    /// Device enrollment status.
+    pub device: UiDeviceState,
+    /// Mutual TLS client certificate status.
+    pub client_certificate: UiClientCertificateState,
+    /// Upload data budget status.
+    pub data_budget: UiDataBudgetState,
+    /// Upload rate limit in bytes per second, when pacing is configured.
+    pub upload_rate_limit_bytes_per_sec: Option<u64>,
     /// Whether user explicitly granted capture consent.
     pub consent_granted: bool,
     /// Selected display id.
     pub selected_display: Option<String>,
+    /// Whether capture targets the selected display or all displays.
+    pub capture_mode: UiCaptureMode,
     /// Capture pipeline stage status.
     pub capture: StageStatus,
     /// Network stage status.
@@ -77,8 +170,13 @@ impl UiState {
         Self {
             version: version.into(),
             auth: UiAuthState::Unauthenticated,
+            device: UiDeviceState::Unenrolled,
+            client_certificate: UiClientCertificateState::NotConfigured,
+            data_budget: UiDataBudgetState::Unlimited,
+            upload_rate_limit_bytes_per_sec: None,
             consent_granted: false,
             selected_display: None,
+            capture_mode: UiCaptureMode::SelectedDisplay,
             capture: StageStatus::Idle,
             network: StageStatus::Idle,
             upload: StageStatus::Idle,
@@ -91,16 +189,77 @@ impl UiState {
         self.selected_display = Some(display_id.into());
     }
 
+    /// Sets capture mode.
+    pub fn set_capture_mode(&mut self, capture_mode: UiCaptureMode) {
+        self.capture_mode = capture_mode;
+    }
+
+    /// Returns `true` when the capture mode has a usable display target.
+    ///
+    /// All-displays mode does not depend on the display selector.
+    pub fn has_capture_target(&self) -> bool {
+        match self.capture_mode {
+            UiCaptureMode::SelectedDisplay => self.selected_display.is_some(),
+            UiCaptureMode::AllDisplays => true,
+        }
+    }
+
     /// Sets consent flag.
     pub fn set_consent(&mut self, consent_granted: bool) {
         self.consent_granted = consent_granted;
     }
 
     /// Returns `true` when user may start capture.
+    ///
+    /// Unenrolled and revoked devices never capture.
     pub fn can_start_capture(&self) -> bool {
         self.auth == UiAuthState::Authenticated
+            && self.device == UiDeviceState::Enrolled
             && self.consent_granted
-            && self.selected_display.is_some()
+            && self.has_capture_target()
+    }
+
+    /// Returns the warning to show for the client certificate, if any.
+    pub fn client_certificate_warning(&self) -> Option<String> {
+        match self.client_certificate {
+            UiClientCertificateState::NotConfigured | UiClientCertificateState::Valid => None,
+            UiClientCertificateState::ExpiringSoon { days_left: 0 } => {
+                Some("Client certificate expires today".to_string())
+            }
+            UiClientCertificateState::ExpiringSoon { days_left } => {
+                Some(format!("Client certificate expires in {days_left} day(s)"))
+            }
+            UiClientCertificateState::Expired => {
+                Some("Client certificate has expired; uploads will be refused".to_string())
+            }
+            UiClientCertificateState::NotYetValid => {
+                Some("Client certificate is not valid yet; check the system clock".to_string())
+            }
+        }
+    }
+
+    /// Returns the warning to show for the upload data budget, if any.
+    pub fn data_budget_warning(&self) -> Option<String> {
+        match self.data_budget {
+            UiDataBudgetState::Unlimited => None,
+            UiDataBudgetState::Within { used_percent } => (used_percent
+                >= DATA_BUDGET_WARNING_PERCENT)
+                .then(|| format!("Upload data budget {used_percent}% used")),
+            UiDataBudgetState::Exhausted { period, action } => {
+                let period = match period {
+                    UiBudgetPeriod::Daily => "Daily",
+                    UiBudgetPeriod::Monthly => "Monthly",
+                };
+                let consequence = match action {
+                    UiBudgetAction::LowerQuality => "uploading at reduced quality",
+                    UiBudgetAction::ReduceFps => "capturing at a reduced rate",
+                    UiBudgetAction::QueueOnly => "uploads queued until it resets",
+                };
+                Some(format!(
+                    "{period} upload data budget exhausted; {consequence}"
+                ))
+            }
+        }
     }
 
     /// Updates analysis status from risk signals.
@@ -147,10 +306,74 @@ mod tests {
         let mut state = UiState::new("v0.1.0");
         assert!(!state.can_start_capture());
 
+        state.auth = UiAuthState::Authenticated;
+        state.device = UiDeviceState::Enrolled;
+        state.set_consent(true);
+        state.select_display("display-1");
+
+        assert!(state.can_start_capture());
+    }
+
+    #[test]
+    fn capture_gate_requires_enrolled_device() {
+        let mut state = UiState::new("v0.1.0");
         state.auth = UiAuthState::Authenticated;
         state.set_consent(true);
         state.select_display("display-1");
+        assert!(!state.can_start_capture());
+
+        state.device = UiDeviceState::Revoked;
+        assert!(!state.can_start_capture());
 
+        state.device = UiDeviceState::Enrolled;
         assert!(state.can_start_capture());
     }
+
+    #[test]
+    fn all_displays_mode_does_not_require_selection() {
+        let mut state = UiState::new("v0.1.0");
+        state.auth = UiAuthState::Authenticated;
+        state.device = UiDeviceState::Enrolled;
+        state.set_consent(true);
+        assert!(!state.can_start_capture());
+
+        state.set_capture_mode(UiCaptureMode::AllDisplays);
+        assert!(state.can_start_capture());
+    }
+
+    #[test]
+    fn client_certificate_warning_tracks_expiry() {
+        let mut state = UiState::new("v0.1.0");
+        assert_eq!(state.client_certificate_warning(), None);
+
+        state.client_certificate = UiClientCertificateState::ExpiringSoon { days_left: 3 };
+        assert_eq!(
+            state.client_certificate_warning().as_deref(),
+            Some("Client certificate expires in 3 day(s)")
+        );
+        state.client_certificate = UiClientCertificateState::Expired;
+        assert!(state.client_certificate_warning().is_some());
+    }
+
+    #[test]
+    fn data_budget_warning_starts_near_limit() {
+        let mut state = UiState::new("v0.1.0");
+        assert_eq!(state.data_budget_warning(), None);
+
+        state.data_budget = UiDataBudgetState::Within { used_percent: 79 };
+        assert_eq!(state.data_budget_warning(), None);
+        state.data_budget = UiDataBudgetState::Within { used_percent: 80 };
+        assert_eq!(
+            state.data_budget_warning().as_deref(),
+            Some("Upload data budget 80% used")
+        );
+        state.data_budget = UiDataBudgetState::Exhausted {
+            period: UiBudgetPeriod::Monthly,
+            action: UiBudgetAction::QueueOnly,
+        };
+        assert_eq!(
+            state.data_budget_warning().as_deref(),
+            Some("Monthly upload data budget exhausted; uploads queued until it resets")
+        );
+    }
 }
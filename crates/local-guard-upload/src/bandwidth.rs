@@ -0,0 +1,593 @@
+//! # Module: bandwidth
+//!
+//! ## Purpose
+//! Keeps uploads from saturating metered or tethered links: paces bytes on
+//! the wire and enforces daily and monthly data budgets.
+//!
+//! ## Responsibilities
+//! - Pace sends with a shared token bucket ([`RateLimiter`]).
+//! - Describe byte budgets and what happens when one runs out
+//!   ([`DataBudget`], [`BudgetExhaustionPolicy`]).
+//! - Count bytes per UTC day and month in a serializable form
+//!   ([`BudgetUsage`]) so the app can persist counters across restarts.
+//! - Share budget state between clones of the upload client
+//!   ([`BudgetMeter`]).
+//!
+//! ## Invariants
+//! - Counters reset when the UTC day or month changes, never otherwise.
+//! - Every byte handed to a transport is counted, including resends and
+//!   chunks whose acknowledgement was lost.
+//! - A budget is checked before a send, so one in-flight body (or chunk)
+//!   may overshoot it; nothing is sent after it is exhausted under
+//!   [`BudgetExhaustionPolicy::QueueOnly`], or after degraded uploads used
+//!   a further [`DEGRADED_ALLOWANCE_PERCENT`] of it under the other
+//!   policies.
+//!
+//! ## Error model
+//! Sends refused by the budget return [`UploadError::BudgetExhausted`],
+//! which is permanent: retrying cannot help before the period resets.
+//!
+//! ## Security and privacy notes
+//! Counters hold byte totals and period labels only.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CancellationToken, UploadClock, UploadError};
+
+/// Share of a limit, in percent, that degraded uploads may use on top of it
+/// under [`BudgetExhaustionPolicy::LowerQuality`] and
+/// [`BudgetExhaustionPolicy::ReduceFps`] before sends stop.
+pub const DEGRADED_ALLOWANCE_PERCENT: u64 = 25;
+
+/// Longest a cancellable pacing wait sleeps before re-checking its token.
+pub const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Token-bucket pacer shared by every send of an upload client.
+///
+/// Sends reserve their byte count up front and sleep until the bucket has
+/// covered it, so a body larger than the burst is delayed in proportion to
+/// its size rather than refused.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    burst_bytes: u64,
+    clock: Arc<dyn UploadClock>,
+    // Invariant: shared across clones so concurrent senders draw from one
+    // bucket for the single physical link.
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Duration,
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("RateLimiter")
+            .field("bytes_per_second", &self.bytes_per_second)
+            .field("burst_bytes", &self.burst_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RateLimiter {
+    /// Limits sends to `bytes_per_second` (minimum 1) with a burst of one
+    /// second's worth of bytes.
+    pub fn new(bytes_per_second: u64, clock: Arc<dyn UploadClock>) -> Self {
+        let bytes_per_second = bytes_per_second.max(1);
+        let refilled_at = clock.now();
+        Self {
+            bytes_per_second,
+            burst_bytes: bytes_per_second,
+            clock,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: bytes_per_second as f64,
+                refilled_at,
+            })),
+        }
+    }
+
+    /// Sets the bucket capacity (minimum 1) and fills the bucket.
+    pub fn with_burst_bytes(mut self, burst_bytes: u64) -> Self {
+        self.burst_bytes = burst_bytes.max(1);
+        self.bucket = Arc::new(Mutex::new(Bucket {
+            tokens: self.burst_bytes as f64,
+            refilled_at: self.clock.now(),
+        }));
+        self
+    }
+
+    /// Returns the sustained rate.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    /// Returns the bucket capacity.
+    pub fn burst_bytes(&self) -> u64 {
+        self.burst_bytes
+    }
+
+    /// Reserves `bytes` and sleeps until the bucket covers them; returns
+    /// the time slept.
+    pub fn acquire(&self, bytes: u64) -> Duration {
+        let wait = self.reserve(bytes);
+        if !wait.is_zero() {
+            self.clock.sleep(wait);
+        }
+        wait
+    }
+
+    /// Like [`RateLimiter::acquire`], but re-checks `cancellation` at least
+    /// every [`CANCEL_POLL_INTERVAL`]; a cancelled wait hands its
+    /// reservation back to the bucket.
+    ///
+    /// # Errors
+    /// Returns [`UploadError::Cancelled`] once `cancellation` is tripped
+    /// during the wait.
+    pub fn acquire_cancellable(
+        &self,
+        bytes: u64,
+        cancellation: &CancellationToken,
+    ) -> Result<Duration, UploadError> {
+        let wait = self.reserve(bytes);
+        let mut slept = Duration::ZERO;
+        while slept < wait {
+            if cancellation.is_cancelled() {
+                self.refund(bytes);
+                return Err(UploadError::Cancelled);
+            }
+            let slice = (wait - slept).min(CANCEL_POLL_INTERVAL);
+            self.clock.sleep(slice);
+            slept += slice;
+        }
+        Ok(wait)
+    }
+
+    /// Takes `bytes` from the bucket and returns how long until it has
+    /// covered them.
+    fn reserve(&self, bytes: u64) -> Duration {
+        let mut bucket = self.lock();
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(bucket.refilled_at);
+        let rate = self.bytes_per_second as f64;
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(self.burst_bytes as f64);
+        bucket.refilled_at = now;
+        // Why: the reservation is taken before sleeping, so concurrent
+        // senders queue behind each other instead of all waking at once.
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+
+    fn refund(&self, bytes: u64) {
+        let mut bucket = self.lock();
+        bucket.tokens = (bucket.tokens + bytes as f64).min(self.burst_bytes as f64);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.bucket
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Calendar period of a data budget (UTC).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    /// UTC calendar day.
+    Daily,
+    /// UTC calendar month.
+    Monthly,
+}
+
+impl fmt::Display for BudgetPeriod {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::Daily => "daily",
+            Self::Monthly => "monthly",
+        })
+    }
+}
+
+/// What the client does once a data budget is exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetExhaustionPolicy {
+    /// Keep uploading at reduced image quality, within
+    /// [`DEGRADED_ALLOWANCE_PERCENT`] over the limit.
+    LowerQuality,
+    /// Keep uploading fewer batches, within [`DEGRADED_ALLOWANCE_PERCENT`]
+    /// over the limit.
+    ReduceFps,
+    /// Stop sending; batches stay queued until the period resets.
+    #[default]
+    QueueOnly,
+}
+
+impl BudgetExhaustionPolicy {
+    /// Returns the configuration token.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::LowerQuality => "lower-quality",
+            Self::ReduceFps => "reduce-fps",
+            Self::QueueOnly => "queue-only",
+        }
+    }
+
+    /// Parses a configuration token (case-insensitive).
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "lower-quality" => Some(Self::LowerQuality),
+            "reduce-fps" => Some(Self::ReduceFps),
+            "queue-only" => Some(Self::QueueOnly),
+            _ => None,
+        }
+    }
+}
+
+/// Byte limits per UTC day and month, and the exhaustion policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DataBudget {
+    daily_limit_bytes: Option<u64>,
+    monthly_limit_bytes: Option<u64>,
+    policy: BudgetExhaustionPolicy,
+}
+
+impl DataBudget {
+    /// Creates a budget without limits that applies `policy` once a limit
+    /// is added and exhausted.
+    pub fn new(policy: BudgetExhaustionPolicy) -> Self {
+        Self {
+            daily_limit_bytes: None,
+            monthly_limit_bytes: None,
+            policy,
+        }
+    }
+
+    /// Limits bytes sent per UTC day.
+    pub fn with_daily_limit_bytes(mut self, limit_bytes: u64) -> Self {
+        self.daily_limit_bytes = Some(limit_bytes);
+        self
+    }
+
+    /// Limits bytes sent per UTC month.
+    pub fn with_monthly_limit_bytes(mut self, limit_bytes: u64) -> Self {
+        self.monthly_limit_bytes = Some(limit_bytes);
+        self
+    }
+
+    /// Returns the daily limit.
+    pub fn daily_limit_bytes(&self) -> Option<u64> {
+        self.daily_limit_bytes
+    }
+
+    /// Returns the monthly limit.
+    pub fn monthly_limit_bytes(&self) -> Option<u64> {
+        self.monthly_limit_bytes
+    }
+
+    /// Returns the exhaustion policy.
+    pub fn policy(&self) -> BudgetExhaustionPolicy {
+        self.policy
+    }
+
+    /// Returns the budget with every limit raised by `percent`.
+    fn with_allowance_percent(self, percent: u64) -> Self {
+        let raise = |limit: u64| limit.saturating_add(limit.saturating_mul(percent) / 100);
+        Self {
+            daily_limit_bytes: self.daily_limit_bytes.map(raise),
+            monthly_limit_bytes: self.monthly_limit_bytes.map(raise),
+            ..self
+        }
+    }
+
+    /// Evaluates `usage` at `unix_ms`; counters from an earlier period
+    /// count as zero.
+    pub fn status(&self, usage: &BudgetUsage, unix_ms: u64) -> BudgetStatus {
+        let mut current = usage.clone();
+        current.roll_over(unix_ms);
+        // Why: the monthly limit is reported first because it outlasts any
+        // daily reset.
+        let periods = [
+            (
+                BudgetPeriod::Monthly,
+                self.monthly_limit_bytes,
+                current.month_bytes,
+            ),
+            (
+                BudgetPeriod::Daily,
+                self.daily_limit_bytes,
+                current.day_bytes,
+            ),
+        ];
+        let mut used_percent = 0_u8;
+        for (period, limit, used) in periods {
+            let Some(limit) = limit else {
+                continue;
+            };
+            if used >= limit {
+                return BudgetStatus::Exhausted(period);
+            }
+            let percent = (u128::from(used) * 100 / u128::from(limit)) as u8;
+            used_percent = used_percent.max(percent);
+        }
+        BudgetStatus::Within { used_percent }
+    }
+}
+
+/// Budget evaluation result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetStatus {
+    /// Every configured limit has room left.
+    Within {
+        /// Highest share of any configured limit already used (0-99).
+        used_percent: u8,
+    },
+    /// A limit is used up for the rest of its period.
+    Exhausted(BudgetPeriod),
+}
+
+/// Bytes sent in the current UTC day and month.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    /// Day the daily counter belongs to (`YYYY-MM-DD`).
+    pub day: String,
+    /// Bytes sent during `day`.
+    pub day_bytes: u64,
+    /// Month the monthly counter belongs to (`YYYY-MM`).
+    pub month: String,
+    /// Bytes sent during `month`.
+    pub month_bytes: u64,
+}
+
+impl BudgetUsage {
+    /// Resets counters whose period ended before `unix_ms`.
+    pub fn roll_over(&mut self, unix_ms: u64) {
+        let (year, month, day) = civil_date(unix_ms);
+        let day_label = format!("{year:04}-{month:02}-{day:02}");
+        let month_label = format!("{year:04}-{month:02}");
+        if self.day != day_label {
+            self.day = day_label;
+            self.day_bytes = 0;
+        }
+        if self.month != month_label {
+            self.month = month_label;
+            self.month_bytes = 0;
+        }
+    }
+
+    /// Adds `bytes` to both counters at `unix_ms`.
+    pub fn record(&mut self, bytes: u64, unix_ms: u64) {
+        self.roll_over(unix_ms);
+        self.day_bytes = self.day_bytes.saturating_add(bytes);
+        self.month_bytes = self.month_bytes.saturating_add(bytes);
+    }
+}
+
+/// Budget state shared by clones of an upload client.
+#[derive(Clone)]
+pub struct BudgetMeter {
+    budget: DataBudget,
+    clock: Arc<dyn UploadClock>,
+    usage: Arc<Mutex<BudgetUsage>>,
+}
+
+impl fmt::Debug for BudgetMeter {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("BudgetMeter")
+            .field("budget", &self.budget)
+            .field("usage", &self.usage())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BudgetMeter {
+    /// Starts metering `budget` from `usage` (typically loaded from disk).
+    pub fn new(budget: DataBudget, usage: BudgetUsage, clock: Arc<dyn UploadClock>) -> Self {
+        Self {
+            budget,
+            clock,
+            usage: Arc::new(Mutex::new(usage)),
+        }
+    }
+
+    /// Returns the configured budget.
+    pub fn budget(&self) -> DataBudget {
+        self.budget
+    }
+
+    /// Returns a snapshot of the counters, rolled over to now, for
+    /// persistence.
+    pub fn usage(&self) -> BudgetUsage {
+        let mut usage = self.lock().clone();
+        usage.roll_over(self.clock.unix_time_ms());
+        usage
+    }
+
+    /// Returns the current budget status.
+    pub fn status(&self) -> BudgetStatus {
+        self.budget.status(&self.lock(), self.clock.unix_time_ms())
+    }
+
+    /// Refuses a send once a limit is exhausted under
+    /// [`BudgetExhaustionPolicy::QueueOnly`], or once it is exceeded by
+    /// [`DEGRADED_ALLOWANCE_PERCENT`] under the degrading policies.
+    ///
+    /// # Errors
+    /// Returns [`UploadError::BudgetExhausted`] with the exhausted period.
+    pub fn check_send(&self) -> Result<(), UploadError> {
+        // Why: degrading policies shrink what capture produces, but batches
+        // queued before exhaustion are full size; the allowance bounds how
+        // far they can carry usage past the limit.
+        let enforced = match self.budget.policy {
+            BudgetExhaustionPolicy::QueueOnly => self.budget,
+            BudgetExhaustionPolicy::LowerQuality | BudgetExhaustionPolicy::ReduceFps => self
+                .budget
+                .with_allowance_percent(DEGRADED_ALLOWANCE_PERCENT),
+        };
+        let unix_ms = self.clock.unix_time_ms();
+        match enforced.status(&self.lock(), unix_ms) {
+            BudgetStatus::Exhausted(period) => Err(UploadError::BudgetExhausted(period)),
+            BudgetStatus::Within { .. } => Ok(()),
+        }
+    }
+
+    /// Counts `bytes` handed to the transport.
+    pub fn record(&self, bytes: u64) {
+        let unix_ms = self.clock.unix_time_ms();
+        self.lock().record(bytes, unix_ms);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BudgetUsage> {
+        self.usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Converts Unix milliseconds to a UTC `(year, month, day)`.
+fn civil_date(unix_ms: u64) -> (i64, u32, u32) {
+    // Why: days-to-civil conversion (H. Hinnant) keeps the crate free of a
+    // calendar dependency for two period labels.
+    let days = (unix_ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for token-bucket pacing and budget periods.
+
+    use super::*;
+    use crate::VirtualUploadClock;
+
+    #[test]
+    fn bucket_allows_burst_then_paces_to_rate() {
+        let clock = Arc::new(VirtualUploadClock::new());
+        let limiter = RateLimiter::new(1_000, clock.clone());
+
+        assert_eq!(limiter.acquire(1_000), Duration::ZERO);
+        assert_eq!(limiter.acquire(500), Duration::from_millis(500));
+        assert_eq!(limiter.acquire(500), Duration::from_millis(500));
+        clock.advance(Duration::from_secs(10));
+        // Invariant: idle time refills at most one burst.
+        assert_eq!(limiter.acquire(1_000), Duration::ZERO);
+        assert_eq!(limiter.acquire(2_000), Duration::from_secs(2));
+        assert_eq!(clock.slept(), Duration::from_secs(3));
+    }
+
+    /// Virtual clock that trips `token` on its first sleep.
+    struct CancellingClock {
+        inner: VirtualUploadClock,
+        token: CancellationToken,
+    }
+
+    impl UploadClock for CancellingClock {
+        fn now(&self) -> Duration {
+            self.inner.now()
+        }
+
+        fn unix_time_ms(&self) -> u64 {
+            self.inner.unix_time_ms()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.inner.sleep(duration);
+            self.token.cancel();
+        }
+    }
+
+    #[test]
+    fn cancelled_wait_returns_its_reservation() {
+        let token = CancellationToken::new();
+        let clock = Arc::new(CancellingClock {
+            inner: VirtualUploadClock::new(),
+            token: token.clone(),
+        });
+        let limiter = RateLimiter::new(1_000, clock.clone());
+
+        assert_eq!(limiter.acquire(1_000), Duration::ZERO);
+        assert_eq!(
+            limiter.acquire_cancellable(2_000, &token),
+            Err(UploadError::Cancelled)
+        );
+        // Invariant: the wait stops after one poll slice, not two seconds.
+        assert_eq!(clock.inner.slept(), CANCEL_POLL_INTERVAL);
+        assert_eq!(
+            limiter.acquire_cancellable(50, &CancellationToken::new()),
+            Ok(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn civil_date_handles_leap_years_and_month_ends() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        // 2024-02-29T23:59:59.999Z and the following millisecond.
+        assert_eq!(civil_date(1_709_251_199_999), (2024, 2, 29));
+        assert_eq!(civil_date(1_709_251_200_000), (2024, 3, 1));
+        // 2026-12-31T12:00:00Z.
+        assert_eq!(civil_date(1_798_718_400_000), (2026, 12, 31));
+    }
+
+    #[test]
+    fn usage_resets_per_period() {
+        // 2026-10-18T12:00:00Z.
+        let noon = 1_792_324_800_000;
+        let day_ms = 86_400_000;
+        let budget = DataBudget::new(BudgetExhaustionPolicy::QueueOnly)
+            .with_daily_limit_bytes(100)
+            .with_monthly_limit_bytes(250);
+        let mut usage = BudgetUsage::default();
+
+        usage.record(60, noon);
+        assert_eq!(
+            budget.status(&usage, noon),
+            BudgetStatus::Within { used_percent: 60 }
+        );
+        usage.record(40, noon);
+        assert_eq!(
+            budget.status(&usage, noon),
+            BudgetStatus::Exhausted(BudgetPeriod::Daily)
+        );
+        assert_eq!(
+            budget.status(&usage, noon + day_ms),
+            BudgetStatus::Within { used_percent: 40 }
+        );
+
+        usage.record(100, noon + day_ms);
+        usage.record(50, noon + 2 * day_ms);
+        assert_eq!(usage.day, "2026-10-20");
+        assert_eq!(usage.month_bytes, 250);
+        assert_eq!(
+            budget.status(&usage, noon + 2 * day_ms),
+            BudgetStatus::Exhausted(BudgetPeriod::Monthly)
+        );
+        // 2026-11-01 starts a new month.
+        assert_eq!(
+            budget.status(&usage, noon + 14 * day_ms),
+            BudgetStatus::Within { used_percent: 0 }
+        );
+    }
+}
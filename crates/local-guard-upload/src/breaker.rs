@@ -0,0 +1,435 @@
+//! # Module: breaker
+//!
+//! ## Purpose
+//! Shares one view of ingest reachability across every upload, so an outage
+//! stops sends after a few failures instead of each batch running its own
+//! retry sequence against a dead endpoint.
+//!
+//! ## Responsibilities
+//! - Track consecutive retriable failures and open the circuit at the
+//!   configured threshold.
+//! - Short-circuit sends with [`UploadError::CircuitOpen`] while open.
+//! - After the cool-down, admit a single half-open probe and close again
+//!   once enough probes succeed.
+//!
+//! ## Invariants
+//! - Clones share state; one breaker guards every client built from it.
+//! - Only [`FailureClass::Retriable`] errors count as failures. A permanent
+//!   error (401, 4xx, checksum mismatch) proves the server is reachable and
+//!   counts as success.
+//! - At most one probe is in flight while half-open, and only the probe's
+//!   outcome moves a half-open circuit; a send admitted before the circuit
+//!   opened that finishes late is ignored.
+//!
+//! ## Error model
+//! [`CircuitBreaker::admit`] returns [`UploadError::CircuitOpen`] with the
+//! remaining cool-down; callers keep the payload queued.
+//!
+//! ## Security and privacy notes
+//! Holds counters and timestamps only.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::clock::UploadClock;
+use crate::{FailureClass, UploadError, classify_upload_error};
+
+/// Default consecutive failures that open the circuit.
+pub const DEFAULT_BREAKER_FAILURE_THRESHOLD: u32 = 5;
+
+/// Default time the circuit stays open before a probe is allowed.
+pub const DEFAULT_BREAKER_COOL_DOWN: Duration = Duration::from_secs(30);
+
+/// Thresholds and cool-down for a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    failure_threshold: u32,
+    cool_down: Duration,
+    success_threshold: u32,
+}
+
+impl CircuitBreakerConfig {
+    /// Creates the default configuration: open after
+    /// [`DEFAULT_BREAKER_FAILURE_THRESHOLD`] failures, probe after
+    /// [`DEFAULT_BREAKER_COOL_DOWN`], close after one successful probe.
+    pub fn new() -> Self {
+        Self {
+            failure_threshold: DEFAULT_BREAKER_FAILURE_THRESHOLD,
+            cool_down: DEFAULT_BREAKER_COOL_DOWN,
+            success_threshold: 1,
+        }
+    }
+
+    /// Sets consecutive failures that open the circuit (minimum 1).
+    pub fn with_failure_threshold(mut self, failures: u32) -> Self {
+        self.failure_threshold = failures.max(1);
+        self
+    }
+
+    /// Sets how long the circuit stays open before probing.
+    pub fn with_cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+
+    /// Sets successful half-open probes required to close (minimum 1).
+    pub fn with_success_threshold(mut self, successes: u32) -> Self {
+        self.success_threshold = successes.max(1);
+        self
+    }
+
+    /// Returns consecutive failures that open the circuit.
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    /// Returns the open-state cool-down.
+    pub fn cool_down(&self) -> Duration {
+        self.cool_down
+    }
+
+    /// Returns successful probes required to close.
+    pub fn success_threshold(&self) -> u32 {
+        self.success_threshold
+    }
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Circuit breaker state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Sends flow normally.
+    Closed,
+    /// Sends are short-circuited until the cool-down ends.
+    Open,
+    /// Cool-down ended; probes decide whether to close or re-open.
+    HalfOpen,
+}
+
+/// Point-in-time view of a [`CircuitBreaker`] for status projection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitSnapshot {
+    /// Current state.
+    pub state: CircuitState,
+    /// Consecutive retriable failures since the last success.
+    pub consecutive_failures: u32,
+    /// Whether any send outcome has been recorded yet.
+    pub observed: bool,
+}
+
+/// Ticket for one admitted send; hand it back to [`CircuitBreaker::record`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use = "pass the admission to CircuitBreaker::record"]
+pub struct Admission {
+    probe: bool,
+}
+
+impl Admission {
+    /// Returns whether this send is the half-open probe.
+    pub fn is_probe(&self) -> bool {
+        self.probe
+    }
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    probe_successes: u32,
+    probe_in_flight: bool,
+    opened_at: Duration,
+    observed: bool,
+}
+
+/// Shared closed/open/half-open circuit breaker for upload sends.
+///
+/// # Ownership
+/// Cheap to clone; clones share one circuit, so hand the same breaker to
+/// every [`crate::UploadClient`] that talks to one ingest service.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    clock: Arc<dyn UploadClock>,
+    circuit: Arc<Mutex<Circuit>>,
+}
+
+impl std::fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("config", &self.config)
+            .field("snapshot", &self.snapshot())
+            .finish()
+    }
+}
+
+impl CircuitBreaker {
+    /// Creates a closed breaker.
+    pub fn new(config: CircuitBreakerConfig, clock: Arc<dyn UploadClock>) -> Self {
+        Self {
+            config,
+            clock,
+            circuit: Arc::new(Mutex::new(Circuit {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                probe_successes: 0,
+                probe_in_flight: false,
+                opened_at: Duration::ZERO,
+                observed: false,
+            })),
+        }
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> CircuitBreakerConfig {
+        self.config
+    }
+
+    /// Returns the current state and failure count.
+    pub fn snapshot(&self) -> CircuitSnapshot {
+        let circuit = self.lock();
+        CircuitSnapshot {
+            state: circuit.state,
+            consecutive_failures: circuit.consecutive_failures,
+            observed: circuit.observed,
+        }
+    }
+
+    /// Returns the current state.
+    pub fn state(&self) -> CircuitState {
+        self.lock().state
+    }
+
+    /// Returns how long sends are still short-circuited; zero once the
+    /// cool-down has ended or while the circuit is not open.
+    pub fn retry_after(&self) -> Duration {
+        let now = self.clock.now();
+        let circuit = self.lock();
+        match circuit.state {
+            CircuitState::Open => self
+                .config
+                .cool_down
+                .saturating_sub(now.saturating_sub(circuit.opened_at)),
+            CircuitState::Closed | CircuitState::HalfOpen => Duration::ZERO,
+        }
+    }
+
+    /// Admits one send, or refuses it while the circuit is open.
+    ///
+    /// # Errors
+    /// Returns [`UploadError::CircuitOpen`] during the cool-down and while a
+    /// half-open probe is already in flight.
+    pub fn admit(&self) -> Result<Admission, UploadError> {
+        let now = self.clock.now();
+        let mut circuit = self.lock();
+        match circuit.state {
+            CircuitState::Closed => Ok(Admission { probe: false }),
+            CircuitState::Open => {
+                let elapsed = now.saturating_sub(circuit.opened_at);
+                if elapsed < self.config.cool_down {
+                    let remaining = self.config.cool_down - elapsed;
+                    return Err(UploadError::CircuitOpen {
+                        retry_after_ms: remaining.as_millis() as u64,
+                    });
+                }
+                circuit.state = CircuitState::HalfOpen;
+                circuit.probe_successes = 0;
+                circuit.probe_in_flight = true;
+                Ok(Admission { probe: true })
+            }
+            CircuitState::HalfOpen if circuit.probe_in_flight => {
+                Err(UploadError::CircuitOpen { retry_after_ms: 0 })
+            }
+            CircuitState::HalfOpen => {
+                circuit.probe_in_flight = true;
+                Ok(Admission { probe: true })
+            }
+        }
+    }
+
+    /// Records the outcome of the send `admission` was issued for.
+    pub fn record(&self, admission: Admission, outcome: Result<(), &UploadError>) {
+        let failed = match outcome {
+            Ok(()) => false,
+            // Invariant: a short-circuited or cancelled send never reached
+            // the server; a probe only hands its slot to the next send.
+            Err(UploadError::CircuitOpen { .. } | UploadError::Cancelled) => {
+                let mut circuit = self.lock();
+                if admission.probe && circuit.state == CircuitState::HalfOpen {
+                    circuit.probe_in_flight = false;
+                }
+                return;
+            }
+            Err(error) => classify_upload_error(error) == FailureClass::Retriable,
+        };
+        let now = self.clock.now();
+        let mut circuit = self.lock();
+        circuit.observed = true;
+        // Why: a send admitted before the circuit opened may finish while
+        // half-open; only the probe decides whether the circuit closes.
+        if circuit.state == CircuitState::HalfOpen && !admission.probe {
+            return;
+        }
+        match (circuit.state, failed) {
+            (CircuitState::Closed, false) => circuit.consecutive_failures = 0,
+            (CircuitState::Closed, true) => {
+                circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+                if circuit.consecutive_failures >= self.config.failure_threshold {
+                    circuit.state = CircuitState::Open;
+                    circuit.opened_at = now;
+                }
+            }
+            (CircuitState::HalfOpen, false) => {
+                circuit.probe_in_flight = false;
+                circuit.probe_successes = circuit.probe_successes.saturating_add(1);
+                if circuit.probe_successes >= self.config.success_threshold {
+                    circuit.state = CircuitState::Closed;
+                    circuit.consecutive_failures = 0;
+                }
+            }
+            (CircuitState::HalfOpen, true) => {
+                circuit.probe_in_flight = false;
+                circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+                circuit.state = CircuitState::Open;
+                circuit.opened_at = now;
+            }
+            // Why: a send admitted before the circuit opened may finish
+            // late; the cool-down already in progress stays authoritative.
+            (CircuitState::Open, _) => {}
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Circuit> {
+        self.circuit
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for circuit breaker transitions on a virtual clock.
+
+    use super::*;
+    use crate::clock::VirtualUploadClock;
+
+    fn breaker(clock: &Arc<VirtualUploadClock>) -> CircuitBreaker {
+        CircuitBreaker::new(
+            CircuitBreakerConfig::new()
+                .with_failure_threshold(3)
+                .with_cool_down(Duration::from_secs(10))
+                .with_success_threshold(2),
+            clock.clone(),
+        )
+    }
+
+    fn fail(breaker: &CircuitBreaker) {
+        let admission = breaker.admit().expect("admitted");
+        breaker.record(admission, Err(&UploadError::Server(503)));
+    }
+
+    fn succeed(breaker: &CircuitBreaker) {
+        let admission = breaker.admit().expect("admitted");
+        breaker.record(admission, Ok(()));
+    }
+
+    #[test]
+    fn opens_after_consecutive_retriable_failures() {
+        let clock = Arc::new(VirtualUploadClock::new());
+        let breaker = breaker(&clock);
+        fail(&breaker);
+        fail(&breaker);
+        succeed(&breaker);
+        assert_eq!(breaker.snapshot().consecutive_failures, 0);
+
+        // Invariant: permanent errors prove reachability.
+        let admission = breaker.admit().expect("closed");
+        breaker.record(admission, Err(&UploadError::Client(400)));
+        for _ in 0..3 {
+            fail(&breaker);
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(breaker.retry_after(), Duration::from_secs(6));
+        assert_eq!(
+            breaker.admit(),
+            Err(UploadError::CircuitOpen {
+                retry_after_ms: 6_000
+            })
+        );
+    }
+
+    #[test]
+    fn half_open_admits_one_probe_and_closes_after_successes() {
+        let clock = Arc::new(VirtualUploadClock::new());
+        let breaker = breaker(&clock);
+        for _ in 0..3 {
+            fail(&breaker);
+        }
+        clock.advance(Duration::from_secs(10));
+
+        let probe = breaker.admit().expect("probe");
+        assert!(probe.is_probe());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(
+            breaker.admit(),
+            Err(UploadError::CircuitOpen { retry_after_ms: 0 })
+        );
+        breaker.record(probe, Ok(()));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        succeed(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.snapshot().consecutive_failures, 0);
+    }
+
+    #[test]
+    fn failed_probe_restarts_cool_down() {
+        let clock = Arc::new(VirtualUploadClock::new());
+        let breaker = breaker(&clock);
+        for _ in 0..3 {
+            fail(&breaker);
+        }
+        clock.advance(Duration::from_secs(15));
+        fail(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        clock.advance(Duration::from_secs(9));
+        assert!(breaker.admit().is_err());
+        clock.advance(Duration::from_secs(1));
+        let _probe = breaker.admit().expect("probe after a full cool-down");
+
+        // Invariant: clones share the circuit.
+        let clone = breaker.clone();
+        assert_eq!(clone.state(), CircuitState::HalfOpen);
+    }
+
+    #[test]
+    fn late_result_admitted_before_opening_is_not_the_probe() {
+        let clock = Arc::new(VirtualUploadClock::new());
+        let breaker = breaker(&clock);
+        let late_success = breaker.admit().expect("closed");
+        let late_failure = breaker.admit().expect("closed");
+        assert!(!late_success.is_probe());
+        for _ in 0..3 {
+            fail(&breaker);
+        }
+        clock.advance(Duration::from_secs(10));
+        let probe = breaker.admit().expect("probe");
+
+        breaker.record(late_success, Ok(()));
+        breaker.record(late_failure, Err(&UploadError::Timeout));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(
+            breaker.admit(),
+            Err(UploadError::CircuitOpen { retry_after_ms: 0 }),
+            "the probe is still in flight"
+        );
+
+        breaker.record(probe, Ok(()));
+        succeed(&breaker);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,659 @@
+//! # Module: chunked
+//!
+//! ## Purpose
+//! Uploads large bodies as a resumable sequence of fixed-size chunks, so a
+//! timeout on a slow link costs one chunk instead of the whole mosaic.
+//!
+//! ## Responsibilities
+//! - Describe the protocol: session create, chunks with offsets and
+//!   SHA-256 checksums, finalize ([`ChunkedUploadTransport`]).
+//! - Drive a session and resume from the server's acknowledged offset
+//!   after retriable failures.
+//! - Provide an in-process stand-in server with fault injection
+//!   ([`LocalChunkedIngest`]).
+//!
+//! ## Invariants
+//! - Chunks are sent in order; each starts at the offset the server last
+//!   acknowledged, never at a client-side guess.
+//! - A server acknowledgement must advance the offset and stay within the
+//!   body, otherwise the upload stops with [`UploadError::ChunkProtocol`].
+//! - Session create returning `None` means the server does not support
+//!   chunking; nothing has been uploaded yet.
+//! - Once every byte is acknowledged, a `404`/`410` for the session means
+//!   it was finalized (or expired) without the client seeing the answer;
+//!   the upload is handed back for a single-shot resend under the same
+//!   idempotency key instead of failing.
+//!
+//! ## Error model
+//! Transport failures use the regular [`UploadError`] variants and are
+//! classified by [`crate::classify_upload_error`]. Protocol violations
+//! (checksum mismatch, offset regression, unknown session) return
+//! [`UploadError::ChunkProtocol`] and are permanent.
+//!
+//! ## Security and privacy notes
+//! Every request carries the envelope's authorization header; the whole
+//! body checksum lets the server reject a reassembled body that differs
+//! from what the client signed and sealed.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use local_guard_analysis_contract::{ANALYSIS_SCHEMA_VERSION_V1, IngestAck, IngestStatus};
+use local_guard_transport::HttpResponse;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ContentEncoding, RetryPolicy, SendGate, UploadEnvelope, UploadError, UploadTransport, guarded,
+    stops_retrying,
+};
+
+/// Default chunk size (256 KiB).
+pub const DEFAULT_CHUNK_SIZE_BYTES: usize = 256 * 1024;
+
+/// Bodies smaller than this are sent single-shot by default (1 MiB).
+pub const DEFAULT_CHUNKING_THRESHOLD_BYTES: usize = 1024 * 1024;
+
+/// Client-side chunking settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingPolicy {
+    chunk_size_bytes: usize,
+    min_body_bytes: usize,
+}
+
+impl Default for ChunkingPolicy {
+    fn default() -> Self {
+        Self {
+            chunk_size_bytes: DEFAULT_CHUNK_SIZE_BYTES,
+            min_body_bytes: DEFAULT_CHUNKING_THRESHOLD_BYTES,
+        }
+    }
+}
+
+impl ChunkingPolicy {
+    /// Returns the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the requested chunk size; zero is treated as one byte.
+    pub fn with_chunk_size_bytes(mut self, chunk_size_bytes: usize) -> Self {
+        self.chunk_size_bytes = chunk_size_bytes.max(1);
+        self
+    }
+
+    /// Sets the body size below which uploads stay single-shot.
+    pub fn with_min_body_bytes(mut self, min_body_bytes: usize) -> Self {
+        self.min_body_bytes = min_body_bytes;
+        self
+    }
+
+    /// Returns the requested chunk size.
+    pub fn chunk_size_bytes(&self) -> usize {
+        self.chunk_size_bytes
+    }
+
+    /// Returns the chunking threshold.
+    pub fn min_body_bytes(&self) -> usize {
+        self.min_body_bytes
+    }
+}
+
+/// Envelope metadata sent with every chunked-protocol call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkedUploadRequest {
+    /// Ingest endpoint the session belongs to.
+    pub endpoint: String,
+    /// Authorization header value (`Bearer <token>`).
+    pub authorization_header: String,
+    /// Idempotency key of the payload; a server may resume an existing
+    /// session for the same key.
+    pub idempotency_key: String,
+    /// Device signature header, when signing.
+    pub signature_header: Option<String>,
+    /// Content type of the reassembled body.
+    pub content_type: String,
+    /// Content encoding of the reassembled body.
+    pub content_encoding: Option<ContentEncoding>,
+    /// Total body length in bytes.
+    pub total_bytes: u64,
+    /// Hex SHA-256 of the whole body.
+    pub body_sha256: String,
+    /// Chunk size the client asks for.
+    pub chunk_size_bytes: u64,
+}
+
+impl ChunkedUploadRequest {
+    /// Describes `envelope` for a chunked upload with `chunk_size_bytes`.
+    pub fn from_envelope(envelope: &UploadEnvelope, chunk_size_bytes: usize) -> Self {
+        Self {
+            endpoint: envelope.endpoint.clone(),
+            authorization_header: envelope.authorization_header.clone(),
+            idempotency_key: envelope.idempotency_key.clone(),
+            signature_header: envelope.signature_header.clone(),
+            content_type: envelope.content_type.clone(),
+            content_encoding: envelope.content_encoding,
+            total_bytes: envelope.body.len() as u64,
+            body_sha256: sha256_hex(&envelope.body),
+            chunk_size_bytes: chunk_size_bytes as u64,
+        }
+    }
+}
+
+/// Server-side upload session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSession {
+    /// Opaque session id.
+    pub session_id: String,
+    /// Chunk size the server accepts (may be smaller than requested).
+    pub chunk_size_bytes: u64,
+    /// Bytes the server already holds (non-zero when it resumed a session
+    /// for the same idempotency key).
+    pub offset: u64,
+}
+
+/// One chunk of the body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadChunk<'a> {
+    /// Byte offset of `data` within the body.
+    pub offset: u64,
+    /// Chunk bytes.
+    pub data: &'a [u8],
+    /// Hex SHA-256 of `data`.
+    pub sha256: String,
+}
+
+/// Transport for the chunked upload protocol.
+pub trait ChunkedUploadTransport: Send + Sync {
+    /// Opens (or resumes) a session; `Ok(None)` when the server does not
+    /// support chunked uploads.
+    fn create_session(
+        &self,
+        upload: &ChunkedUploadRequest,
+    ) -> Result<Option<ChunkSession>, UploadError>;
+
+    /// Stores `chunk` and returns the server's acknowledged offset.
+    fn put_chunk(
+        &self,
+        upload: &ChunkedUploadRequest,
+        session_id: &str,
+        chunk: &UploadChunk<'_>,
+    ) -> Result<u64, UploadError>;
+
+    /// Returns the offset the server has durably received.
+    fn query_offset(
+        &self,
+        upload: &ChunkedUploadRequest,
+        session_id: &str,
+    ) -> Result<u64, UploadError>;
+
+    /// Completes the session once every byte is acknowledged; the response
+    /// carries the ingest acknowledgement.
+    fn finalize(
+        &self,
+        upload: &ChunkedUploadRequest,
+        session_id: &str,
+    ) -> Result<HttpResponse, UploadError>;
+}
+
+/// Progress of a finished chunked upload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ChunkedOutcome {
+    pub(crate) chunks: u32,
+    pub(crate) resumes: u32,
+    pub(crate) response: HttpResponse,
+}
+
+/// How a chunked upload ended when it did not fail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ChunkedRun {
+    /// The session was finalized.
+    Finalized(ChunkedOutcome),
+    /// The server does not support chunking; nothing was uploaded.
+    Unsupported,
+    /// Every byte was acknowledged, then the session disappeared before a
+    /// finalize answer arrived: the server most likely committed it.
+    SessionGone { chunks: u32, resumes: u32 },
+}
+
+/// Runs one chunked upload of `envelope`, resuming after retriable
+/// failures while `retries` stays within `retry.max_retries`.
+pub(crate) fn run_chunked_upload(
+    transport: &dyn ChunkedUploadTransport,
+    policy: &ChunkingPolicy,
+    retry: &RetryPolicy,
+    envelope: &UploadEnvelope,
+    gate: &dyn SendGate,
+    attempts: &mut u32,
+    retries: &mut u32,
+) -> Result<ChunkedRun, UploadError> {
+    let upload = ChunkedUploadRequest::from_envelope(envelope, policy.chunk_size_bytes);
+    let body = envelope.body.as_slice();
+    let mut session: Option<ChunkSession> = None;
+    let mut chunks = 0_u32;
+    let mut resumes = 0_u32;
+    let mut body_sent = false;
+
+    loop {
+        *attempts = attempts.saturating_add(1);
+        let resuming = resumes > 0;
+        let result = (|| {
+            let session = match &mut session {
+                Some(session) => {
+                    if resuming {
+                        // Why: an ack can be lost after the server stored the
+                        // chunk, so only the server knows the true offset.
+                        session.offset = guarded(gate, 0, || {
+                            transport.query_offset(&upload, &session.session_id)
+                        })?;
+                    }
+                    session
+                }
+                None => match guarded(gate, 0, || transport.create_session(&upload))? {
+                    Some(created) => session.insert(created),
+                    None => return Ok(None),
+                },
+            };
+            send_remaining(transport, &upload, body, session, gate, &mut chunks)?;
+            body_sent = true;
+            guarded(gate, 0, || transport.finalize(&upload, &session.session_id)).map(Some)
+        })();
+
+        match result {
+            Ok(Some(response)) => {
+                return Ok(ChunkedRun::Finalized(ChunkedOutcome {
+                    chunks,
+                    resumes,
+                    response,
+                }));
+            }
+            Ok(None) => return Ok(ChunkedRun::Unsupported),
+            // Failure mode: a finalize answer lost after commit leaves no
+            // session to resume; the caller resends single-shot with the
+            // same idempotency key and accepts a duplicate acknowledgement.
+            Err(error) if body_sent && session_gone(&error) => {
+                return Ok(ChunkedRun::SessionGone { chunks, resumes });
+            }
+            Err(error) => {
+                if stops_retrying(&error, *retries, retry) {
+                    return Err(error);
+                }
+                // In production this is where async sleep/backoff occurs.
+                let _next_delay = retry.backoff_delay_ms(*retries);
+                *retries = retries.saturating_add(1);
+                resumes = resumes.saturating_add(1);
+            }
+        }
+    }
+}
+
+/// Whether `error` says the server no longer knows the session.
+fn session_gone(error: &UploadError) -> bool {
+    matches!(error, UploadError::Client(404 | 410))
+}
+
+fn send_remaining(
+    transport: &dyn ChunkedUploadTransport,
+    upload: &ChunkedUploadRequest,
+    body: &[u8],
+    session: &mut ChunkSession,
+    gate: &dyn SendGate,
+    chunks: &mut u32,
+) -> Result<(), UploadError> {
+    let total = body.len() as u64;
+    let chunk_size = session.chunk_size_bytes.max(1);
+    if session.offset > total {
+        return Err(protocol_error(&format!(
+            "server offset {} exceeds body length {total}",
+            session.offset
+        )));
+    }
+    while session.offset < total {
+        let start = session.offset;
+        let end = start.saturating_add(chunk_size).min(total);
+        let data = &body[start as usize..end as usize];
+        let chunk = UploadChunk {
+            offset: start,
+            data,
+            sha256: sha256_hex(data),
+        };
+        let acknowledged = guarded(gate, data.len() as u64, || {
+            transport.put_chunk(upload, &session.session_id, &chunk)
+        })?;
+        if acknowledged <= start || acknowledged > end {
+            return Err(protocol_error(&format!(
+                "acknowledged offset {acknowledged} outside chunk {start}..{end}"
+            )));
+        }
+        session.offset = acknowledged;
+        *chunks = chunks.saturating_add(1);
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn protocol_error(reason: &str) -> UploadError {
+    UploadError::ChunkProtocol(reason.to_string())
+}
+
+/// Fault injected into the next [`LocalChunkedIngest`] chunk or finalize
+/// request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkFault {
+    /// Fail with `error` before the chunk is stored.
+    BeforeStore(UploadError),
+    /// Store the chunk, then fail with `error` (the acknowledgement is
+    /// lost).
+    AfterStore(UploadError),
+    /// Commit the finalized upload, then fail with `error` (the finalize
+    /// answer is lost).
+    AfterFinalize(UploadError),
+}
+
+impl ChunkFault {
+    fn applies_to_finalize(&self) -> bool {
+        matches!(self, Self::AfterFinalize(_))
+    }
+}
+
+/// Upload completed by [`LocalChunkedIngest`], chunked or single-shot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedUpload {
+    /// Idempotency key of the upload.
+    pub idempotency_key: String,
+    /// Content type of the body.
+    pub content_type: String,
+    /// Content encoding of the body.
+    pub content_encoding: Option<ContentEncoding>,
+    /// Device signature header.
+    pub signature_header: Option<String>,
+    /// Reassembled body.
+    pub body: Vec<u8>,
+    /// Whether the body arrived through the chunked protocol.
+    pub chunked: bool,
+}
+
+#[derive(Debug, Default)]
+struct LocalChunkedState {
+    supports_chunking: bool,
+    max_chunk_size_bytes: Option<u64>,
+    next_session: u64,
+    sessions: BTreeMap<String, (ChunkedUploadRequest, Vec<u8>)>,
+    finalized: BTreeMap<String, ChunkedUploadRequest>,
+    forget_finalized: bool,
+    faults: Vec<ChunkFault>,
+    chunk_offsets: Vec<u64>,
+    received: Vec<ReceivedUpload>,
+    request_ids: BTreeMap<String, String>,
+    ack_body: Option<Vec<u8>>,
+}
+
+/// In-process stand-in for an ingest server that speaks the chunked
+/// protocol and the single-shot [`UploadTransport`].
+///
+/// Verifies offsets and checksums, resumes sessions by idempotency key,
+/// records every chunk offset it stores, and injects queued faults. Each
+/// completed upload is answered with an [`IngestAck`]; a repeated
+/// idempotency key is acknowledged as a duplicate of its first request.
+/// Finalized sessions stay queryable at their full length and answer a
+/// repeated finalize as a duplicate, unless
+/// [`Self::forget_finalized_sessions`] is set.
+/// Used by tests; never talks to the network.
+#[derive(Debug)]
+pub struct LocalChunkedIngest {
+    state: Mutex<LocalChunkedState>,
+}
+
+impl Default for LocalChunkedIngest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalChunkedIngest {
+    /// Creates a server that supports chunked uploads.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(LocalChunkedState {
+                supports_chunking: true,
+                ..LocalChunkedState::default()
+            }),
+        }
+    }
+
+    /// Creates a server that only accepts single-shot uploads.
+    pub fn single_shot_only() -> Self {
+        Self {
+            state: Mutex::new(LocalChunkedState::default()),
+        }
+    }
+
+    /// Caps the chunk size the server grants.
+    pub fn with_max_chunk_size_bytes(self, max_chunk_size_bytes: u64) -> Self {
+        self.lock().max_chunk_size_bytes = Some(max_chunk_size_bytes.max(1));
+        self
+    }
+
+    /// Drops sessions once finalized, so later calls for them fail with
+    /// `404` like a server that does not keep finalized sessions.
+    pub fn forget_finalized_sessions(self) -> Self {
+        self.lock().forget_finalized = true;
+        self
+    }
+
+    /// Queues `fault` for an upcoming chunk request, or finalize request
+    /// for [`ChunkFault::AfterFinalize`] (first in, first out).
+    pub fn inject_fault(&self, fault: ChunkFault) {
+        self.lock().faults.push(fault);
+    }
+
+    /// Answers later completed uploads with `body` instead of the generated
+    /// acknowledgement.
+    pub fn set_ack_body(&self, body: impl Into<Vec<u8>>) {
+        self.lock().ack_body = Some(body.into());
+    }
+
+    /// Returns the offsets of every chunk stored, in arrival order.
+    pub fn chunk_offsets(&self) -> Vec<u64> {
+        self.lock().chunk_offsets.clone()
+    }
+
+    /// Returns completed uploads in completion order.
+    pub fn received(&self) -> Vec<ReceivedUpload> {
+        self.lock().received.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LocalChunkedState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl LocalChunkedState {
+    /// Pops the first queued fault if it targets finalize (`true`) or a
+    /// chunk (`false`).
+    fn take_fault(&mut self, finalize: bool) -> Option<ChunkFault> {
+        let first = self.faults.first()?;
+        (first.applies_to_finalize() == finalize).then(|| self.faults.remove(0))
+    }
+
+    /// Returns the session an earlier finalize committed, or the error a
+    /// server that dropped it would answer with.
+    fn finalized_session(&self, session_id: &str) -> Result<&ChunkedUploadRequest, UploadError> {
+        match self.finalized.get(session_id) {
+            Some(_) if self.forget_finalized => Err(UploadError::Client(404)),
+            Some(upload) => Ok(upload),
+            None => Err(protocol_error(&format!("unknown session {session_id}"))),
+        }
+    }
+
+    /// Records `upload` and builds its acknowledgement response.
+    fn complete(&mut self, upload: ReceivedUpload) -> Result<HttpResponse, UploadError> {
+        let response = self.acknowledge(&upload.idempotency_key)?;
+        self.received.push(upload);
+        Ok(response)
+    }
+
+    /// Builds the acknowledgement for `idempotency_key`, as a duplicate when
+    /// the key was seen before.
+    fn acknowledge(&mut self, idempotency_key: &str) -> Result<HttpResponse, UploadError> {
+        let known = self.request_ids.get(idempotency_key).cloned();
+        let status = match known {
+            Some(_) => IngestStatus::Duplicate,
+            None => IngestStatus::Accepted,
+        };
+        let request_id = known.unwrap_or_else(|| format!("req-{:04}", self.request_ids.len() + 1));
+        self.request_ids
+            .insert(idempotency_key.to_string(), request_id.clone());
+        let ack = IngestAck {
+            schema_version: ANALYSIS_SCHEMA_VERSION_V1.to_string(),
+            request_id,
+            status,
+            batch_id: None,
+            idempotency_key: Some(idempotency_key.to_string()),
+            analysis: None,
+        };
+        let body = match &self.ack_body {
+            Some(body) => body.clone(),
+            None => serde_json::to_vec(&ack)
+                .map_err(|error| UploadError::Serialize(error.to_string()))?,
+        };
+        Ok(HttpResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body,
+        })
+    }
+}
+
+impl ChunkedUploadTransport for LocalChunkedIngest {
+    fn create_session(
+        &self,
+        upload: &ChunkedUploadRequest,
+    ) -> Result<Option<ChunkSession>, UploadError> {
+        let mut state = self.lock();
+        if !state.supports_chunking {
+            return Ok(None);
+        }
+        let chunk_size_bytes = match state.max_chunk_size_bytes {
+            Some(max) => upload.chunk_size_bytes.min(max),
+            None => upload.chunk_size_bytes,
+        };
+        // Invariant: the same idempotency key and body resume the open
+        // session; a re-encoded body starts a new one.
+        if let Some((session_id, (_, received))) = state.sessions.iter().find(|(_, (open, _))| {
+            open.idempotency_key == upload.idempotency_key && open.body_sha256 == upload.body_sha256
+        }) {
+            return Ok(Some(ChunkSession {
+                session_id: session_id.clone(),
+                chunk_size_bytes,
+                offset: received.len() as u64,
+            }));
+        }
+        state.next_session = state.next_session.saturating_add(1);
+        let session_id = format!("session-{:04}", state.next_session);
+        state
+            .sessions
+            .insert(session_id.clone(), (upload.clone(), Vec::new()));
+        Ok(Some(ChunkSession {
+            session_id,
+            chunk_size_bytes,
+            offset: 0,
+        }))
+    }
+
+    fn put_chunk(
+        &self,
+        _upload: &ChunkedUploadRequest,
+        session_id: &str,
+        chunk: &UploadChunk<'_>,
+    ) -> Result<u64, UploadError> {
+        let mut state = self.lock();
+        let fault = state.take_fault(false);
+        if let Some(ChunkFault::BeforeStore(error)) = fault {
+            return Err(error);
+        }
+        let (_, received) = state
+            .sessions
+            .get_mut(session_id)
+            .ok_or_else(|| protocol_error(&format!("unknown session {session_id}")))?;
+        if chunk.offset != received.len() as u64 {
+            return Err(protocol_error(&format!(
+                "chunk offset {} does not match received {}",
+                chunk.offset,
+                received.len()
+            )));
+        }
+        if sha256_hex(chunk.data) != chunk.sha256 {
+            return Err(protocol_error(&format!(
+                "checksum mismatch at offset {}",
+                chunk.offset
+            )));
+        }
+        received.extend_from_slice(chunk.data);
+        let acknowledged = received.len() as u64;
+        state.chunk_offsets.push(chunk.offset);
+        match fault {
+            Some(ChunkFault::AfterStore(error)) => Err(error),
+            _ => Ok(acknowledged),
+        }
+    }
+
+    fn query_offset(
+        &self,
+        _upload: &ChunkedUploadRequest,
+        session_id: &str,
+    ) -> Result<u64, UploadError> {
+        let state = self.lock();
+        match state.sessions.get(session_id) {
+            Some((_, received)) => Ok(received.len() as u64),
+            None => state
+                .finalized_session(session_id)
+                .map(|upload| upload.total_bytes),
+        }
+    }
+
+    fn finalize(
+        &self,
+        _upload: &ChunkedUploadRequest,
+        session_id: &str,
+    ) -> Result<HttpResponse, UploadError> {
+        let mut state = self.lock();
+        let Some((open, body)) = state.sessions.remove(session_id) else {
+            let idempotency_key = state.finalized_session(session_id)?.idempotency_key.clone();
+            return state.acknowledge(&idempotency_key);
+        };
+        if body.len() as u64 != open.total_bytes || sha256_hex(&body) != open.body_sha256 {
+            return Err(protocol_error("reassembled body does not match checksum"));
+        }
+        state.finalized.insert(session_id.to_string(), open.clone());
+        let response = state.complete(ReceivedUpload {
+            idempotency_key: open.idempotency_key,
+            content_type: open.content_type,
+            content_encoding: open.content_encoding,
+            signature_header: open.signature_header,
+            body,
+            chunked: true,
+        })?;
+        match state.take_fault(true) {
+            Some(ChunkFault::AfterFinalize(error)) => Err(error),
+            _ => Ok(response),
+        }
+    }
+}
+
+impl UploadTransport for LocalChunkedIngest {
+    fn send(&self, envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        self.lock().complete(ReceivedUpload {
+            idempotency_key: envelope.idempotency_key.clone(),
+            content_type: envelope.content_type.clone(),
+            content_encoding: envelope.content_encoding,
+            signature_header: envelope.signature_header.clone(),
+            body: envelope.body.clone(),
+            chunked: false,
+        })
+    }
+}
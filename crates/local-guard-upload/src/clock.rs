@@ -0,0 +1,143 @@
+//! # Module: clock
+//!
+//! ## Purpose
+//! Abstracts time for upload pacing and budgets so tests can run against a
+//! [`VirtualUploadClock`] instead of sleeping.
+//!
+//! ## Responsibilities
+//! - Provide monotonic time for token refill and wall time (Unix ms) for
+//!   calendar budget periods.
+//! - Sleep on behalf of the rate limiter.
+//!
+//! ## Invariants
+//! - `now` never goes backwards.
+//! - [`VirtualUploadClock::sleep`] advances both monotonic and wall time by
+//!   the slept duration, so pacing is observable without real delays.
+//!
+//! ## Error model
+//! Infallible.
+//!
+//! ## Security and privacy notes
+//! Handles timestamps only.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of time for the upload path.
+pub trait UploadClock: Send + Sync {
+    /// Returns monotonic time since an arbitrary, fixed origin.
+    fn now(&self) -> Duration;
+
+    /// Returns wall-clock time as milliseconds since the Unix epoch.
+    fn unix_time_ms(&self) -> u64;
+
+    /// Blocks the calling thread for `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// Production clock backed by [`Instant`], [`SystemTime`], and
+/// [`std::thread::sleep`].
+#[derive(Debug, Clone, Copy)]
+pub struct SystemUploadClock {
+    origin: Instant,
+}
+
+impl SystemUploadClock {
+    /// Creates a clock whose monotonic origin is the moment of construction.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemUploadClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UploadClock for SystemUploadClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn unix_time_ms(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+#[derive(Debug, Default)]
+struct VirtualTime {
+    now: Duration,
+    unix_origin_ms: u64,
+    slept: Duration,
+}
+
+/// Manually advanced clock for deterministic upload tests.
+///
+/// # Ownership
+/// Share it as `Arc<VirtualUploadClock>`: hand one clone to the upload path
+/// and keep another in the test to call [`VirtualUploadClock::advance`].
+#[derive(Debug, Default)]
+pub struct VirtualUploadClock {
+    time: Mutex<VirtualTime>,
+}
+
+impl VirtualUploadClock {
+    /// Creates a virtual clock at monotonic zero and the Unix epoch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a virtual clock whose wall time starts at `unix_ms`.
+    pub fn starting_at_unix_ms(unix_ms: u64) -> Self {
+        Self {
+            time: Mutex::new(VirtualTime {
+                unix_origin_ms: unix_ms,
+                ..VirtualTime::default()
+            }),
+        }
+    }
+
+    /// Moves the clock forward by `delta`.
+    pub fn advance(&self, delta: Duration) {
+        let mut time = self.lock();
+        time.now = time.now.saturating_add(delta);
+    }
+
+    /// Returns the total time spent in [`UploadClock::sleep`].
+    pub fn slept(&self) -> Duration {
+        self.lock().slept
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VirtualTime> {
+        self.time
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl UploadClock for VirtualUploadClock {
+    fn now(&self) -> Duration {
+        self.lock().now
+    }
+
+    fn unix_time_ms(&self) -> u64 {
+        let time = self.lock();
+        time.unix_origin_ms
+            .saturating_add(time.now.as_millis() as u64)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut time = self.lock();
+        time.now = time.now.saturating_add(duration);
+        time.slept = time.slept.saturating_add(duration);
+    }
+}
@@ -0,0 +1,161 @@
+//! # Module: compression
+//!
+//! ## Purpose
+//! Compresses upload bodies with an HTTP `Content-Encoding` the ingest
+//! server accepts, so large mosaics use less bandwidth without breaking
+//! servers that only accept identity bodies.
+//!
+//! ## Responsibilities
+//! - Describe supported encodings ([`ContentEncoding`]) and the client
+//!   policy ([`CompressionPolicy`]): preferred encoding and size threshold.
+//! - Compress bodies, skipping small bodies and bodies that do not shrink.
+//! - Parse a server's `Accept-Encoding` advertisement
+//!   ([`parse_accept_encoding`]) for negotiation.
+//!
+//! ## Invariants
+//! - Compression never changes the idempotency key or the signed bytes;
+//!   both are computed on the uncompressed canonical payload.
+//! - A compressed body is always strictly smaller than the original.
+//!
+//! ## Error model
+//! Encoder failures return [`UploadError::Compress`].
+//!
+//! ## Security and privacy notes
+//! Sealed bodies are sent uncompressed. Compressing plaintext ahead of
+//! encryption would let ciphertext length reveal how repetitive the screen
+//! content is, and compressing the sealed envelope afterwards only wins
+//! back its base64 overhead, so the client skips compression whenever a
+//! sealer is configured.
+
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use crate::UploadError;
+
+/// Bodies smaller than this are sent uncompressed by default.
+pub const DEFAULT_COMPRESSION_THRESHOLD_BYTES: usize = 1024;
+
+/// Supported `Content-Encoding` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    /// `gzip` (RFC 1952); accepted by virtually every server.
+    Gzip,
+    /// `zstd` (RFC 8878); smaller and faster, but newer.
+    Zstd,
+}
+
+impl ContentEncoding {
+    /// Returns the header token.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Parses a header token (case-insensitive; `x-gzip` is an alias).
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Compresses `body`.
+    ///
+    /// # Errors
+    /// Returns [`UploadError::Compress`] when the encoder fails.
+    pub fn compress(self, body: &[u8]) -> Result<Vec<u8>, UploadError> {
+        let compressed = match self {
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body).and_then(|()| encoder.finish())
+            }
+            Self::Zstd => zstd::encode_all(body, zstd::DEFAULT_COMPRESSION_LEVEL),
+        };
+        compressed.map_err(|error| UploadError::Compress(format!("{}: {error}", self.as_str())))
+    }
+}
+
+/// Client-side body compression settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionPolicy {
+    encoding: ContentEncoding,
+    min_size_bytes: usize,
+}
+
+impl CompressionPolicy {
+    /// Prefers `encoding` for bodies of at least
+    /// [`DEFAULT_COMPRESSION_THRESHOLD_BYTES`].
+    pub fn new(encoding: ContentEncoding) -> Self {
+        Self {
+            encoding,
+            min_size_bytes: DEFAULT_COMPRESSION_THRESHOLD_BYTES,
+        }
+    }
+
+    /// Sets the size below which bodies are sent uncompressed.
+    pub fn with_min_size_bytes(mut self, min_size_bytes: usize) -> Self {
+        self.min_size_bytes = min_size_bytes;
+        self
+    }
+
+    /// Returns the preferred encoding.
+    pub fn encoding(&self) -> ContentEncoding {
+        self.encoding
+    }
+
+    /// Returns the compression threshold in bytes.
+    pub fn min_size_bytes(&self) -> usize {
+        self.min_size_bytes
+    }
+
+    /// Compresses `body` with `encoding` when it meets the threshold and
+    /// actually shrinks; otherwise returns it unchanged with no encoding.
+    ///
+    /// # Errors
+    /// Returns [`UploadError::Compress`] when the encoder fails.
+    pub fn apply(
+        &self,
+        encoding: ContentEncoding,
+        body: Vec<u8>,
+    ) -> Result<(Option<ContentEncoding>, Vec<u8>), UploadError> {
+        if body.len() < self.min_size_bytes {
+            return Ok((None, body));
+        }
+        let compressed = encoding.compress(&body)?;
+        // Why: incompressible bodies (already-compressed JPEG, ciphertext)
+        // would only grow and cost the server a pointless decode.
+        if compressed.len() >= body.len() {
+            return Ok((None, body));
+        }
+        Ok((Some(encoding), compressed))
+    }
+}
+
+/// Parses an `Accept-Encoding` value into the supported encodings it
+/// allows, in the server's order. Entries with `q=0`, `identity`, `*`, and
+/// unknown codings are skipped.
+pub fn parse_accept_encoding(raw: &str) -> Vec<ContentEncoding> {
+    let mut accepted = Vec::new();
+    for entry in raw.split(',') {
+        let mut parts = entry.split(';');
+        let Some(encoding) = parts.next().and_then(ContentEncoding::parse) else {
+            continue;
+        };
+        let refused = parts.any(|parameter| {
+            parameter
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|quality| quality.trim().parse::<f32>().ok())
+                .is_some_and(|quality| quality <= 0.0)
+        });
+        if !refused && !accepted.contains(&encoding) {
+            accepted.push(encoding);
+        }
+    }
+    accepted
+}
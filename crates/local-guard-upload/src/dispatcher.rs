@@ -0,0 +1,516 @@
+//! # Module: dispatcher
+//!
+//! ## Purpose
+//! Runs uploads on a pool of worker threads so a slow batch does not hold
+//! up the ones behind it, while keeping batches of one capture session in
+//! the order the server's payload chain expects.
+//!
+//! ## Responsibilities
+//! - Run up to `workers` [`UploadClient::upload_payload`] calls at once.
+//! - Bound queued plus running payloads to `max_in_flight`; submitters
+//!   block (or [`UploadDispatcher::try_submit`] declines) when it is full.
+//! - Under [`UploadOrdering::PerSession`], start a payload only after the
+//!   previous payload of its session has completed, and hold the session
+//!   after a failure until the caller retries or releases it.
+//! - Emit one [`DispatchEvent`] per submitted payload.
+//! - Cancel queued and in-flight work on logout or kill switch.
+//!
+//! ## Invariants
+//! - Every submitted payload yields exactly one event.
+//! - Under per-session ordering, events of one session arrive in
+//!   submission order, and no payload of a held session is sent ahead of
+//!   the failed one.
+//! - Cancellation takes effect at the next transport call; a request
+//!   already on the wire completes and reports its real outcome.
+//!
+//! ## Error model
+//! Upload failures are delivered as [`DispatchOutcome::Failed`]; cancelled
+//! work as [`DispatchOutcome::Cancelled`]. Under per-session ordering a
+//! failed payload is kept and blocks its session until
+//! [`UploadDispatcher::retry_held`] sends it again or
+//! [`UploadDispatcher::release_session`] drops it.
+//!
+//! ## Security and privacy notes
+//! Bearer tokens are held only for the lifetime of their job and never
+//! appear in events.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+use local_guard_core::MosaicPayload;
+
+use crate::{UploadClient, UploadError, UploadReport};
+
+/// Default number of concurrent upload workers.
+pub const DEFAULT_UPLOAD_WORKERS: usize = 2;
+
+/// Default bound on queued plus running payloads.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+
+/// Shared flag that stops an upload at its next transport call.
+///
+/// # Ownership
+/// Clones share one flag. Once cancelled it stays cancelled; the
+/// dispatcher issues a fresh token for work submitted afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates an untripped token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trips the token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the token has been tripped.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Ordering the server requires between payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UploadOrdering {
+    /// Payloads sharing a `session_id` are sent one at a time, in
+    /// submission order.
+    #[default]
+    PerSession,
+    /// Any payload may start as soon as a worker is free.
+    Unordered,
+}
+
+/// Worker count, in-flight window, and ordering for an
+/// [`UploadDispatcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatcherConfig {
+    workers: usize,
+    max_in_flight: usize,
+    ordering: UploadOrdering,
+}
+
+impl DispatcherConfig {
+    /// Creates the default configuration: [`DEFAULT_UPLOAD_WORKERS`]
+    /// workers, a [`DEFAULT_MAX_IN_FLIGHT`] window, per-session ordering.
+    pub fn new() -> Self {
+        Self {
+            workers: DEFAULT_UPLOAD_WORKERS,
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            ordering: UploadOrdering::PerSession,
+        }
+    }
+
+    /// Sets the worker count (minimum 1).
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Sets the in-flight window (minimum 1).
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Sets the ordering guarantee.
+    pub fn with_ordering(mut self, ordering: UploadOrdering) -> Self {
+        self.ordering = ordering;
+        self
+    }
+
+    /// Returns the worker count.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Returns the in-flight window.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// Returns the ordering guarantee.
+    pub fn ordering(&self) -> UploadOrdering {
+        self.ordering
+    }
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Identifies one submitted payload; tickets increase in submission order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DispatchTicket(u64);
+
+impl DispatchTicket {
+    /// Returns the numeric ticket id.
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+}
+
+/// How a dispatched payload ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DispatchOutcome {
+    /// The upload completed.
+    Uploaded(Box<UploadReport>),
+    /// The upload failed after its retries.
+    Failed(UploadError),
+    /// The upload was cancelled before completing.
+    Cancelled,
+}
+
+/// Completion event for one submitted payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DispatchEvent {
+    /// Ticket returned at submission.
+    pub ticket: DispatchTicket,
+    /// Capture session of the payload.
+    pub session_id: String,
+    /// Idempotency key of the payload, for ledger correlation.
+    pub idempotency_key: String,
+    /// Final outcome.
+    pub outcome: DispatchOutcome,
+}
+
+struct Job {
+    ticket: DispatchTicket,
+    session_id: String,
+    idempotency_key: String,
+    payload: MosaicPayload,
+    token: String,
+    cancellation: CancellationToken,
+}
+
+struct Queue {
+    pending: VecDeque<Job>,
+    busy_sessions: BTreeSet<String>,
+    held: BTreeMap<String, Job>,
+    running: usize,
+    next_ticket: u64,
+    cancellation: CancellationToken,
+    shutdown: bool,
+}
+
+struct Shared {
+    config: DispatcherConfig,
+    queue: Mutex<Queue>,
+    // Why: workers wait for runnable jobs, submitters for window space;
+    // separate condvars keep either side from waking the other needlessly.
+    work: Condvar,
+    space: Condvar,
+    events: Sender<DispatchEvent>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Concurrent upload dispatcher over one [`UploadClient`].
+///
+/// # Ownership
+/// Owns its worker threads. Dropping the dispatcher cancels outstanding
+/// work and joins the workers; call [`UploadDispatcher::wait_idle`] first
+/// to let queued uploads finish.
+pub struct UploadDispatcher {
+    shared: Arc<Shared>,
+    client: UploadClient,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for UploadDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UploadDispatcher")
+            .field("config", &self.shared.config)
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+impl UploadDispatcher {
+    /// Starts `config.workers()` workers uploading through `client` and
+    /// returns the dispatcher with its completion event stream.
+    pub fn new(client: UploadClient, config: DispatcherConfig) -> (Self, Receiver<DispatchEvent>) {
+        let (events, receiver) = channel();
+        let shared = Arc::new(Shared {
+            config,
+            queue: Mutex::new(Queue {
+                pending: VecDeque::new(),
+                busy_sessions: BTreeSet::new(),
+                held: BTreeMap::new(),
+                running: 0,
+                next_ticket: 0,
+                cancellation: CancellationToken::new(),
+                shutdown: false,
+            }),
+            work: Condvar::new(),
+            space: Condvar::new(),
+            events,
+        });
+        let workers = (0..config.workers)
+            .map(|_| {
+                let shared = shared.clone();
+                let client = client.clone();
+                std::thread::spawn(move || run_worker(&shared, &client))
+            })
+            .collect();
+        (
+            Self {
+                shared,
+                client,
+                workers,
+            },
+            receiver,
+        )
+    }
+
+    /// Returns the configuration.
+    pub fn config(&self) -> DispatcherConfig {
+        self.shared.config
+    }
+
+    /// Queues `payload`, blocking while the in-flight window is full.
+    pub fn submit(&self, payload: MosaicPayload, token: &str) -> DispatchTicket {
+        let mut queue = self.shared.lock();
+        while in_flight(&queue) >= self.shared.config.max_in_flight {
+            queue = self
+                .shared
+                .space
+                .wait(queue)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        self.enqueue(&mut queue, payload, token)
+    }
+
+    /// Queues `payload` if the in-flight window has room; returns `None`
+    /// without queuing otherwise.
+    pub fn try_submit(&self, payload: MosaicPayload, token: &str) -> Option<DispatchTicket> {
+        let mut queue = self.shared.lock();
+        if in_flight(&queue) >= self.shared.config.max_in_flight {
+            return None;
+        }
+        Some(self.enqueue(&mut queue, payload, token))
+    }
+
+    /// Returns sessions held by a failed payload, in session order.
+    pub fn held_sessions(&self) -> Vec<String> {
+        self.shared.lock().held.keys().cloned().collect()
+    }
+
+    /// Sends the failed payload holding `session_id` again, ahead of the
+    /// session's queued payloads; returns its new ticket, or `None` when
+    /// the session is not held.
+    ///
+    /// The retry is queued even when the in-flight window is full, since
+    /// the payloads behind it cannot drain first.
+    pub fn retry_held(&self, session_id: &str, token: &str) -> Option<DispatchTicket> {
+        let mut queue = self.shared.lock();
+        let mut job = queue.held.remove(session_id)?;
+        job.ticket = DispatchTicket(queue.next_ticket);
+        queue.next_ticket = queue.next_ticket.saturating_add(1);
+        job.token = token.to_string();
+        job.cancellation = queue.cancellation.clone();
+        let ticket = job.ticket;
+        queue.pending.push_front(job);
+        self.shared.work.notify_one();
+        Some(ticket)
+    }
+
+    /// Drops the failed payload holding `session_id` so the session's
+    /// queued payloads proceed without it; returns whether it was held.
+    pub fn release_session(&self, session_id: &str) -> bool {
+        let released = self.shared.lock().held.remove(session_id).is_some();
+        if released {
+            self.shared.work.notify_all();
+        }
+        released
+    }
+
+    /// Returns queued plus running payloads.
+    ///
+    /// Payloads queued behind a held session stay counted until the
+    /// session is retried or released.
+    pub fn in_flight(&self) -> usize {
+        in_flight(&self.shared.lock())
+    }
+
+    /// Blocks until every submitted payload has completed. Payloads queued
+    /// behind a held session keep it waiting until the session is retried
+    /// or released.
+    pub fn wait_idle(&self) {
+        let mut queue = self.shared.lock();
+        while in_flight(&queue) > 0 {
+            queue = self
+                .shared
+                .space
+                .wait(queue)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Cancels all queued and in-flight uploads, for logout or the kill
+    /// switch. Returns how many payloads were outstanding.
+    ///
+    /// Queued payloads report [`DispatchOutcome::Cancelled`] immediately;
+    /// running ones stop at their next transport call. Payloads submitted
+    /// afterwards run normally.
+    pub fn cancel_all(&self) -> usize {
+        let mut queue = self.shared.lock();
+        queue.cancellation.cancel();
+        queue.cancellation = CancellationToken::new();
+        // Why: held payloads already reported their failure; cancelling
+        // drops them with the rest of the session's work.
+        queue.held.clear();
+        let outstanding = in_flight(&queue);
+        for job in std::mem::take(&mut queue.pending) {
+            emit(&self.shared, &job, DispatchOutcome::Cancelled);
+        }
+        self.shared.space.notify_all();
+        outstanding
+    }
+
+    fn enqueue(&self, queue: &mut Queue, payload: MosaicPayload, token: &str) -> DispatchTicket {
+        let ticket = DispatchTicket(queue.next_ticket);
+        queue.next_ticket = queue.next_ticket.saturating_add(1);
+        queue.pending.push_back(Job {
+            ticket,
+            session_id: payload.metadata.session_id.clone(),
+            idempotency_key: self.client.idempotency_key(&payload),
+            payload,
+            token: token.to_string(),
+            cancellation: queue.cancellation.clone(),
+        });
+        self.shared.work.notify_one();
+        ticket
+    }
+}
+
+impl Drop for UploadDispatcher {
+    fn drop(&mut self) {
+        self.cancel_all();
+        self.shared.lock().shutdown = true;
+        self.shared.work.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn in_flight(queue: &Queue) -> usize {
+    queue.pending.len() + queue.running
+}
+
+fn emit(shared: &Shared, job: &Job, outcome: DispatchOutcome) {
+    // Failure mode: the receiver may be gone; events are then dropped, but
+    // the window still drains so submitters never block forever.
+    let _ = shared.events.send(DispatchEvent {
+        ticket: job.ticket,
+        session_id: job.session_id.clone(),
+        idempotency_key: job.idempotency_key.clone(),
+        outcome,
+    });
+}
+
+fn next_job(shared: &Shared) -> Option<Job> {
+    let mut queue = shared.lock();
+    loop {
+        let runnable = queue.pending.iter().position(|job| {
+            shared.config.ordering == UploadOrdering::Unordered
+                || !(queue.busy_sessions.contains(&job.session_id)
+                    || queue.held.contains_key(&job.session_id))
+        });
+        if let Some(index) = runnable {
+            let job = queue.pending.remove(index)?;
+            if shared.config.ordering == UploadOrdering::PerSession {
+                queue.busy_sessions.insert(job.session_id.clone());
+            }
+            queue.running += 1;
+            return Some(job);
+        }
+        if queue.shutdown {
+            return None;
+        }
+        queue = shared
+            .work
+            .wait(queue)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+}
+
+fn run_worker(shared: &Shared, client: &UploadClient) {
+    while let Some(job) = next_job(shared) {
+        let outcome = if job.cancellation.is_cancelled() {
+            DispatchOutcome::Cancelled
+        } else {
+            match client
+                .clone()
+                .with_cancellation(job.cancellation.clone())
+                .upload_payload(&job.payload, &job.token)
+            {
+                Ok(report) => DispatchOutcome::Uploaded(Box::new(report)),
+                Err(UploadError::Cancelled) => DispatchOutcome::Cancelled,
+                Err(error) => DispatchOutcome::Failed(error),
+            }
+        };
+
+        let failed = matches!(outcome, DispatchOutcome::Failed(_));
+        let mut queue = shared.lock();
+        // Invariant: the event is sent before the session is released, so
+        // the next payload of the session cannot report first.
+        emit(shared, &job, outcome);
+        queue.busy_sessions.remove(&job.session_id);
+        queue.running -= 1;
+        // Why: the server chains a session's payloads in order, so later
+        // payloads wait for the failed one instead of skipping past it. A
+        // job whose cancellation tripped meanwhile is dropped instead.
+        if shared.config.ordering == UploadOrdering::PerSession
+            && failed
+            && !job.cancellation.is_cancelled()
+        {
+            queue.held.insert(job.session_id.clone(), job);
+        }
+        drop(queue);
+        shared.work.notify_all();
+        shared.space.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for dispatcher configuration and cancellation tokens.
+
+    use super::*;
+
+    #[test]
+    fn config_clamps_to_one_worker_and_slot() {
+        let config = DispatcherConfig::new()
+            .with_workers(0)
+            .with_max_in_flight(0);
+        assert_eq!(config.workers(), 1);
+        assert_eq!(config.max_in_flight(), 1);
+        assert_eq!(config.ordering(), UploadOrdering::PerSession);
+    }
+
+    #[test]
+    fn cancellation_is_shared_by_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+}
@@ -0,0 +1,283 @@
+//! # Module: https
+//!
+//! ## Purpose
+//! Sends upload envelopes over HTTPS under the shared transport security
+//! policy.
+//!
+//! ## Responsibilities
+//! - Map [`UploadEnvelope`] fields onto request headers and body.
+//! - Speak the chunked protocol ([`ChunkedUploadTransport`]):
+//!   `POST {endpoint}/sessions`, `PATCH`/`GET {endpoint}/sessions/{id}`,
+//!   `POST {endpoint}/sessions/{id}/finalize`, with `Upload-*` headers.
+//! - Map HTTP status codes and transport failures onto [`UploadError`].
+//!
+//! ## Invariants
+//! - Trust failures always surface as [`UploadError::TransportSecurity`] so
+//!   the retry loop stops immediately.
+//! - Proxy credential and configuration failures surface as
+//!   [`UploadError::Proxy`]; proxy gateway errors (5xx) stay retriable.
+//! - Session ids from the server are restricted to `[A-Za-z0-9_-]` before
+//!   they are placed in a URL path.
+//!
+//! ## Error model
+//! See [`HttpsUploadTransport::send`].
+//!
+//! ## Security and privacy notes
+//! The authorization header is only written after the TLS handshake has
+//! verified the server (enforced by [`HttpsConnector`]).
+
+use local_guard_crypto::SIGNATURE_HEADER_NAME;
+use local_guard_transport::{HttpRequest, HttpResponse, HttpsConnector, TransportError};
+
+use crate::{
+    ChunkSession, ChunkedUploadRequest, ChunkedUploadTransport, UploadChunk, UploadEnvelope,
+    UploadError, UploadTransport,
+};
+
+/// Header carrying [`UploadEnvelope::idempotency_key`].
+pub const IDEMPOTENCY_KEY_HEADER_NAME: &str = "Idempotency-Key";
+
+const UPLOAD_OFFSET_HEADER: &str = "Upload-Offset";
+const UPLOAD_SESSION_HEADER: &str = "Upload-Session";
+const UPLOAD_CHUNK_SIZE_HEADER: &str = "Upload-Chunk-Size";
+
+/// [`UploadTransport`] backed by an [`HttpsConnector`].
+#[derive(Debug, Clone)]
+pub struct HttpsUploadTransport {
+    connector: HttpsConnector,
+}
+
+impl HttpsUploadTransport {
+    /// Creates a transport sending through `connector`.
+    pub fn new(connector: HttpsConnector) -> Self {
+        Self { connector }
+    }
+}
+
+impl UploadTransport for HttpsUploadTransport {
+    /// Posts one envelope and returns the 2xx response with its body.
+    ///
+    /// # Errors
+    /// - `401`/`403` => [`UploadError::Unauthorized`].
+    /// - `415` => [`UploadError::UnsupportedEncoding`] with the server's
+    ///   `Accept-Encoding`.
+    /// - `429` and `5xx` => [`UploadError::Server`] (retriable).
+    /// - Other non-2xx => [`UploadError::Client`].
+    /// - Trust failures => [`UploadError::TransportSecurity`].
+    fn send(&self, envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError> {
+        let mut request = HttpRequest::post(envelope.endpoint.clone(), envelope.body.clone())
+            .with_header("Authorization", envelope.authorization_header.clone())
+            .with_header("Content-Type", envelope.content_type.clone())
+            .with_header(
+                IDEMPOTENCY_KEY_HEADER_NAME,
+                envelope.idempotency_key.clone(),
+            );
+        if let Some(signature) = &envelope.signature_header {
+            request = request.with_header(SIGNATURE_HEADER_NAME, signature.clone());
+        }
+        if let Some(encoding) = envelope.content_encoding {
+            request = request.with_header("Content-Encoding", encoding.as_str());
+        }
+
+        let response = self.connector.send(&request)?;
+        match response.status {
+            200..=299 => Ok(response),
+            _ => Err(status_error(&response)),
+        }
+    }
+}
+
+impl ChunkedUploadTransport for HttpsUploadTransport {
+    /// Posts the session request to `{endpoint}/sessions`.
+    ///
+    /// # Errors
+    /// - `404`/`405`/`501` => `Ok(None)` (chunking unsupported).
+    /// - `201` without a valid `Upload-Session` =>
+    ///   [`UploadError::ChunkProtocol`].
+    /// - Other statuses as for [`HttpsUploadTransport::send`].
+    fn create_session(
+        &self,
+        upload: &ChunkedUploadRequest,
+    ) -> Result<Option<ChunkSession>, UploadError> {
+        let mut request = HttpRequest::post(sessions_url(upload), Vec::new())
+            .with_header("Upload-Length", upload.total_bytes.to_string())
+            .with_header("Upload-Checksum", format!("sha256={}", upload.body_sha256))
+            .with_header(
+                UPLOAD_CHUNK_SIZE_HEADER,
+                upload.chunk_size_bytes.to_string(),
+            )
+            .with_header("Upload-Content-Type", upload.content_type.clone());
+        if let Some(encoding) = upload.content_encoding {
+            request = request.with_header("Upload-Content-Encoding", encoding.as_str());
+        }
+        if let Some(signature) = &upload.signature_header {
+            request = request.with_header(SIGNATURE_HEADER_NAME, signature.clone());
+        }
+
+        let response = self.send_session_request(upload, request)?;
+        match response.status {
+            200..=299 => {
+                let session_id = response
+                    .header(UPLOAD_SESSION_HEADER)
+                    .filter(|id| is_valid_session_id(id))
+                    .ok_or_else(|| {
+                        UploadError::ChunkProtocol("missing or invalid session id".to_string())
+                    })?
+                    .to_string();
+                let chunk_size_bytes = match response.header(UPLOAD_CHUNK_SIZE_HEADER) {
+                    Some(_) => header_u64(&response, UPLOAD_CHUNK_SIZE_HEADER)?,
+                    None => upload.chunk_size_bytes,
+                };
+                let offset = match response.header(UPLOAD_OFFSET_HEADER) {
+                    Some(_) => header_u64(&response, UPLOAD_OFFSET_HEADER)?,
+                    None => 0,
+                };
+                Ok(Some(ChunkSession {
+                    session_id,
+                    chunk_size_bytes,
+                    offset,
+                }))
+            }
+            // Why: a server without the sessions resource answers as for any
+            // unknown route; the client then falls back to single-shot.
+            404 | 405 | 501 => Ok(None),
+            _ => Err(status_error(&response)),
+        }
+    }
+
+    fn put_chunk(
+        &self,
+        upload: &ChunkedUploadRequest,
+        session_id: &str,
+        chunk: &UploadChunk<'_>,
+    ) -> Result<u64, UploadError> {
+        let mut request = HttpRequest::post(session_url(upload, session_id)?, chunk.data.to_vec())
+            .with_header(UPLOAD_OFFSET_HEADER, chunk.offset.to_string())
+            .with_header("Upload-Checksum", format!("sha256={}", chunk.sha256))
+            .with_header("Content-Type", "application/octet-stream");
+        request.method = "PATCH".to_string();
+        let response = self.send_session_request(upload, request)?;
+        match response.status {
+            200..=299 => header_u64(&response, UPLOAD_OFFSET_HEADER),
+            _ => Err(status_error(&response)),
+        }
+    }
+
+    fn query_offset(
+        &self,
+        upload: &ChunkedUploadRequest,
+        session_id: &str,
+    ) -> Result<u64, UploadError> {
+        // Why: GET rather than HEAD, because the response parser reads the
+        // body its Content-Length announces.
+        let mut request = HttpRequest::post(session_url(upload, session_id)?, Vec::new());
+        request.method = "GET".to_string();
+        let response = self.send_session_request(upload, request)?;
+        match response.status {
+            200..=299 => header_u64(&response, UPLOAD_OFFSET_HEADER),
+            _ => Err(status_error(&response)),
+        }
+    }
+
+    fn finalize(
+        &self,
+        upload: &ChunkedUploadRequest,
+        session_id: &str,
+    ) -> Result<HttpResponse, UploadError> {
+        let url = format!("{}/finalize", session_url(upload, session_id)?);
+        let response = self.send_session_request(upload, HttpRequest::post(url, Vec::new()))?;
+        match response.status {
+            200..=299 => Ok(response),
+            _ => Err(status_error(&response)),
+        }
+    }
+}
+
+impl HttpsUploadTransport {
+    fn send_session_request(
+        &self,
+        upload: &ChunkedUploadRequest,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, UploadError> {
+        let request = request
+            .with_header("Authorization", upload.authorization_header.clone())
+            .with_header(IDEMPOTENCY_KEY_HEADER_NAME, upload.idempotency_key.clone());
+        Ok(self.connector.send(&request)?)
+    }
+}
+
+fn sessions_url(upload: &ChunkedUploadRequest) -> String {
+    format!("{}/sessions", upload.endpoint.trim_end_matches('/'))
+}
+
+fn session_url(upload: &ChunkedUploadRequest, session_id: &str) -> Result<String, UploadError> {
+    if !is_valid_session_id(session_id) {
+        return Err(UploadError::ChunkProtocol("invalid session id".to_string()));
+    }
+    Ok(format!("{}/{session_id}", sessions_url(upload)))
+}
+
+fn is_valid_session_id(session_id: &str) -> bool {
+    !session_id.is_empty()
+        && session_id.len() <= 128
+        && session_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+}
+
+fn header_u64(response: &HttpResponse, name: &str) -> Result<u64, UploadError> {
+    response
+        .header(name)
+        .and_then(|value| value.trim().parse().ok())
+        .ok_or_else(|| UploadError::ChunkProtocol(format!("missing or invalid {name} header")))
+}
+
+/// Maps a non-2xx response onto [`UploadError`].
+///
+/// - `401`/`403` => [`UploadError::Unauthorized`].
+/// - `415` => [`UploadError::UnsupportedEncoding`] with the server's
+///   `Accept-Encoding`.
+/// - `429` and `5xx` => [`UploadError::Server`] (retriable).
+/// - Other statuses => [`UploadError::Client`].
+fn status_error(response: &HttpResponse) -> UploadError {
+    match response.status {
+        401 | 403 => UploadError::Unauthorized,
+        // Why: RFC 7694 servers list what they accept on a 415, which
+        // drives the client's encoding fallback.
+        415 => UploadError::UnsupportedEncoding(
+            response
+                .header("accept-encoding")
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        // Why: rate limiting is transient, so it shares the retriable
+        // server class.
+        429 | 500..=599 => UploadError::Server(response.status),
+        status => UploadError::Client(status),
+    }
+}
+
+impl From<TransportError> for UploadError {
+    fn from(error: TransportError) -> Self {
+        match error {
+            TransportError::Security(error) => UploadError::TransportSecurity(error.to_string()),
+            TransportError::Timeout => UploadError::Timeout,
+            TransportError::InvalidRequest(reason) => UploadError::InvalidEndpoint(reason),
+            TransportError::Connect(reason)
+            | TransportError::Io(reason)
+            | TransportError::InvalidResponse(reason) => UploadError::Transport(reason),
+            // Why: a proxy gateway error means the origin was unreachable
+            // from the proxy, which is as transient as a direct failure.
+            TransportError::ProxyRefused(status @ 500..=599) => {
+                UploadError::Transport(format!("proxy returned status {status}"))
+            }
+            TransportError::ProxyRefused(status) => {
+                UploadError::Proxy(format!("proxy refused tunnel with status {status}"))
+            }
+            TransportError::ProxyAuthentication => {
+                UploadError::Proxy("proxy authentication required".to_string())
+            }
+            TransportError::InvalidProxy(reason) => UploadError::Proxy(reason),
+        }
+    }
+}
@@ -5,8 +5,23 @@
 //! Implements protected ingest upload semantics with idempotency and retries.
 //!
 //! ## Responsibilities
-//! - Enforce HTTPS endpoint policy.
+//! - Enforce HTTPS endpoint policy and the endpoint host allowlist.
+//! - Send envelopes under the shared transport security policy
+//!   ([`HttpsUploadTransport`]).
 //! - Build deterministic idempotency keys per payload.
+//! - Attach a detached device signature header when a signer is configured.
+//! - Seal bodies end-to-end to the analysis backend when a sealer is
+//!   configured.
+//! - Compress bodies with a negotiated `Content-Encoding` when a
+//!   [`CompressionPolicy`] is configured.
+//! - Upload large bodies in resumable chunks when a [`ChunkingPolicy`] is
+//!   configured and the server supports it.
+//! - Pace sends with a shared [`RateLimiter`] and meter them against a
+//!   daily/monthly [`DataBudget`].
+//! - Short-circuit sends through a shared [`CircuitBreaker`] during ingest
+//!   outages.
+//! - Run concurrent uploads with per-session ordering and cancellation
+//!   through an [`UploadDispatcher`].
 //! - Retry transient failures using capped exponential backoff with jitter.
 //! - Classify failures for UI and telemetry projection.
 //!
@@ -24,15 +39,66 @@
 //!
 //! ## Security and privacy notes
 //! Requires HTTPS endpoint and non-empty bearer tokens.
+//! Endpoints outside the [`EndpointAllowlist`] (unlisted hosts or ports, IP
+//! literals, userinfo) are refused when the client is built.
+//! Certificate, pin, and TLS version failures are classified
+//! [`UploadError::TransportSecurity`] and never retried.
 //! Token values are never embedded in errors.
+//! With a [`PayloadSigner`] configured, every body carries a signature by
+//! the device key, so a stolen token alone cannot inject payloads.
+//! With a [`BodySealer`] configured, the body is encrypted to the server key
+//! after signing; the signature still covers the plaintext JSON.
+//! Compression runs last, on the final body, and never affects the
+//! idempotency key or signature.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use local_guard_analysis_contract::{IngestAck, parse_ingest_ack};
 use local_guard_core::MosaicPayload;
+use local_guard_crypto::{BodySealer, PayloadSigner, SEALED_CONTENT_TYPE, format_signature_header};
+use local_guard_transport::EndpointAllowlist;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 use url::Url;
 
+mod bandwidth;
+mod breaker;
+mod chunked;
+mod clock;
+mod compression;
+mod dispatcher;
+mod https;
+
+pub use bandwidth::{
+    BudgetExhaustionPolicy, BudgetMeter, BudgetPeriod, BudgetStatus, BudgetUsage,
+    CANCEL_POLL_INTERVAL, DEGRADED_ALLOWANCE_PERCENT, DataBudget, RateLimiter,
+};
+
+pub use breaker::{
+    Admission, CircuitBreaker, CircuitBreakerConfig, CircuitSnapshot, CircuitState,
+    DEFAULT_BREAKER_COOL_DOWN, DEFAULT_BREAKER_FAILURE_THRESHOLD,
+};
+
+pub use chunked::{
+    ChunkFault, ChunkSession, ChunkedUploadRequest, ChunkedUploadTransport, ChunkingPolicy,
+    DEFAULT_CHUNK_SIZE_BYTES, DEFAULT_CHUNKING_THRESHOLD_BYTES, LocalChunkedIngest, ReceivedUpload,
+    UploadChunk,
+};
+
+pub use clock::{SystemUploadClock, UploadClock, VirtualUploadClock};
+pub use compression::{
+    CompressionPolicy, ContentEncoding, DEFAULT_COMPRESSION_THRESHOLD_BYTES, parse_accept_encoding,
+};
+pub use dispatcher::{
+    CancellationToken, DEFAULT_MAX_IN_FLIGHT, DEFAULT_UPLOAD_WORKERS, DispatchEvent,
+    DispatchOutcome, DispatchTicket, DispatcherConfig, UploadDispatcher, UploadOrdering,
+};
+pub use https::{HttpsUploadTransport, IDEMPOTENCY_KEY_HEADER_NAME};
+pub use local_guard_transport::HttpResponse;
+
+/// Content type of a plaintext JSON payload body.
+pub const PAYLOAD_CONTENT_TYPE: &str = "application/json";
+
 /// Upload failure class used by retry policy.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FailureClass {
@@ -91,23 +157,40 @@ pub struct UploadEnvelope {
     pub authorization_header: String,
     /// Idempotency key associated with payload.
     pub idempotency_key: String,
-    /// JSON payload body.
+    /// Value for the `X-Local-Guard-Signature` header
+    /// ([`local_guard_crypto::SIGNATURE_HEADER_NAME`]), when signing.
+    pub signature_header: Option<String>,
+    /// Value for the `Content-Type` header: [`PAYLOAD_CONTENT_TYPE`] or
+    /// [`SEALED_CONTENT_TYPE`].
+    pub content_type: String,
+    /// Value for the `Content-Encoding` header, when `body` is compressed.
+    pub content_encoding: Option<ContentEncoding>,
+    /// JSON payload body, or the sealed envelope JSON when sealing;
+    /// compressed when `content_encoding` is set.
     pub body: Vec<u8>,
 }
 
 /// Upload result details for observability.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct UploadReport {
     /// Number of attempts performed.
     pub attempts: u32,
     /// Final outcome class.
     pub final_class: Option<FailureClass>,
+    /// Chunks acknowledged by the server; `0` for single-shot uploads.
+    pub chunks: u32,
+    /// Times a chunked upload resumed from the server's offset.
+    pub resumes: u32,
+    /// Parsed ingest acknowledgement; `None` when the server answered
+    /// without a JSON body.
+    pub ack: Option<IngestAck>,
 }
 
 /// Upload transport abstraction implemented by concrete HTTP client.
 pub trait UploadTransport: Send + Sync {
-    /// Sends one upload attempt.
-    fn send(&self, envelope: &UploadEnvelope) -> Result<(), UploadError>;
+    /// Sends one upload attempt and returns the 2xx response, whose body
+    /// carries the ingest acknowledgement.
+    fn send(&self, envelope: &UploadEnvelope) -> Result<HttpResponse, UploadError>;
 }
 
 /// Protected ingest upload client.
@@ -116,29 +199,190 @@ pub struct UploadClient {
     endpoint: String,
     policy: RetryPolicy,
     transport: Arc<dyn UploadTransport>,
+    signer: Option<Arc<dyn PayloadSigner>>,
+    sealer: Option<BodySealer>,
+    compression: Option<CompressionPolicy>,
+    // Invariant: shared across clones so a downgrade learned by one upload
+    // applies to every later upload to the same server.
+    negotiated_encoding: Arc<Mutex<Option<ContentEncoding>>>,
+    chunking: Option<(Arc<dyn ChunkedUploadTransport>, ChunkingPolicy)>,
+    // Invariant: shared across clones; once a server declines a session,
+    // later uploads go single-shot without asking again.
+    chunking_unsupported: Arc<Mutex<bool>>,
+    rate_limiter: Option<RateLimiter>,
+    budget: Option<BudgetMeter>,
+    breaker: Option<CircuitBreaker>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl UploadClient {
-    /// Creates an upload client with endpoint validation.
+    /// Creates an upload client whose endpoint must pass the compiled-in
+    /// allowlist ([`EndpointAllowlist::builtin`]).
     ///
     /// # Errors
     /// Returns [`UploadError::InvalidEndpoint`] for malformed URLs.
     /// Returns [`UploadError::NonHttpsEndpoint`] for non-HTTPS URLs.
+    /// Returns [`UploadError::EndpointNotAllowed`] for hosts or ports
+    /// outside the allowlist, IP literals, and userinfo.
     pub fn new(
         endpoint: impl Into<String>,
         policy: RetryPolicy,
         transport: Arc<dyn UploadTransport>,
+    ) -> Result<Self, UploadError> {
+        Self::new_with_allowlist(endpoint, policy, transport, &EndpointAllowlist::builtin())
+    }
+
+    /// Creates an upload client whose endpoint must pass `allowlist`.
+    ///
+    /// # Errors
+    /// Same as [`UploadClient::new`].
+    pub fn new_with_allowlist(
+        endpoint: impl Into<String>,
+        policy: RetryPolicy,
+        transport: Arc<dyn UploadTransport>,
+        allowlist: &EndpointAllowlist,
     ) -> Result<Self, UploadError> {
         let endpoint = endpoint.into();
         validate_upload_endpoint(&endpoint)?;
+        allowlist
+            .check(&endpoint)
+            .map_err(|error| UploadError::EndpointNotAllowed(error.to_string()))?;
 
         Ok(Self {
             endpoint,
             policy,
             transport,
+            signer: None,
+            sealer: None,
+            compression: None,
+            negotiated_encoding: Arc::new(Mutex::new(None)),
+            chunking: None,
+            chunking_unsupported: Arc::new(Mutex::new(false)),
+            rate_limiter: None,
+            budget: None,
+            breaker: None,
+            cancellation: None,
         })
     }
 
+    /// Signs every envelope body with `signer` (typically the device key).
+    pub fn with_signer(mut self, signer: Arc<dyn PayloadSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Returns the key id of the configured signer, if any.
+    pub fn signer_key_id(&self) -> Option<String> {
+        self.signer.as_ref().map(|signer| signer.key_id())
+    }
+
+    /// Seals every envelope body to the analysis backend's public key.
+    pub fn with_sealer(mut self, sealer: BodySealer) -> Self {
+        self.sealer = Some(sealer);
+        self
+    }
+
+    /// Returns the recipient key id of the configured sealer, if any.
+    pub fn sealer_key_id(&self) -> Option<String> {
+        self.sealer.as_ref().map(BodySealer::recipient_key_id)
+    }
+
+    /// Compresses bodies per `policy`, starting with its preferred encoding
+    /// until the server asks for another one.
+    pub fn with_compression(mut self, policy: CompressionPolicy) -> Self {
+        self.compression = Some(policy);
+        self.negotiated_encoding = Arc::new(Mutex::new(Some(policy.encoding())));
+        self
+    }
+
+    /// Returns the encoding the next upload will use for bodies above the
+    /// threshold, or `None` when compression is off or was negotiated away.
+    pub fn content_encoding(&self) -> Option<ContentEncoding> {
+        *self
+            .negotiated_encoding
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn set_content_encoding(&self, encoding: Option<ContentEncoding>) {
+        *self
+            .negotiated_encoding
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = encoding;
+    }
+
+    /// Sends bodies of at least [`ChunkingPolicy::min_body_bytes`] through
+    /// `transport` in resumable chunks. Falls back to single-shot uploads
+    /// when the server does not support chunking.
+    pub fn with_chunking(
+        mut self,
+        transport: Arc<dyn ChunkedUploadTransport>,
+        policy: ChunkingPolicy,
+    ) -> Self {
+        self.chunking = Some((transport, policy));
+        self.chunking_unsupported = Arc::new(Mutex::new(false));
+        self
+    }
+
+    /// Returns whether the server declined a chunked session, so uploads
+    /// now go single-shot.
+    pub fn chunking_unsupported(&self) -> bool {
+        *self
+            .chunking_unsupported
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn set_chunking_unsupported(&self) {
+        *self
+            .chunking_unsupported
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = true;
+    }
+
+    /// Paces every body and chunk through `limiter`.
+    pub fn with_rate_limit(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Returns the configured rate limiter, if any.
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Counts every body and chunk against `meter`, and refuses sends once
+    /// it is exhausted under [`BudgetExhaustionPolicy::QueueOnly`].
+    pub fn with_data_budget(mut self, meter: BudgetMeter) -> Self {
+        self.budget = Some(meter);
+        self
+    }
+
+    /// Returns the budget meter, if any; its usage is what the app persists.
+    pub fn data_budget(&self) -> Option<&BudgetMeter> {
+        self.budget.as_ref()
+    }
+
+    /// Guards every transport call with `breaker`. Share one breaker across
+    /// clients so an outage seen by one upload pauses all of them.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Returns the circuit breaker, if any; the app projects it onto the
+    /// network stage status.
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.breaker.as_ref()
+    }
+
+    /// Stops uploads with [`UploadError::Cancelled`] at the next transport
+    /// call once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Builds deterministic idempotency key for a payload.
     pub fn idempotency_key(&self, payload: &MosaicPayload) -> String {
         idempotency_key_for_payload(payload)
@@ -146,8 +390,21 @@ impl UploadClient {
 
     /// Creates upload envelope for one payload/token pair.
     ///
+    /// # Signing
+    /// The signature covers the canonical payload bytes
+    /// ([`MosaicPayload::to_json_bytes`]). Without a sealer those bytes are
+    /// the body; with one, the body is the sealed envelope of those bytes.
+    ///
+    /// # Compression
+    /// With a [`CompressionPolicy`], the body is compressed with the
+    /// negotiated encoding when it reaches the threshold and shrinks. The
+    /// idempotency key is always derived from the uncompressed payload.
+    /// Sealed bodies are never compressed (see the `compression` module).
+    ///
     /// # Errors
     /// Returns [`UploadError::MissingToken`] when token is blank.
+    /// Returns [`UploadError::Encrypt`] when sealing fails.
+    /// Returns [`UploadError::Compress`] when compression fails.
     pub fn build_envelope(
         &self,
         payload: &MosaicPayload,
@@ -157,50 +414,170 @@ impl UploadClient {
             return Err(UploadError::MissingToken);
         }
 
-        let body = payload
+        let canonical = payload
             .to_json_bytes()
             .map_err(|error| UploadError::Serialize(error.to_string()))?;
 
+        let signature_header = self.signer.as_ref().map(|signer| {
+            format_signature_header(&signer.key_id(), &signer.sign_payload(&canonical))
+        });
+
+        // Why: sign-then-seal lets the backend verify device origin on the
+        // exact bytes it analyzes after decryption.
+        let (content_type, body) = match &self.sealer {
+            Some(sealer) => {
+                let sealed = sealer
+                    .seal(&canonical, PAYLOAD_CONTENT_TYPE)
+                    .and_then(|envelope| envelope.to_json_bytes())
+                    .map_err(|error| UploadError::Encrypt(error.to_string()))?;
+                (SEALED_CONTENT_TYPE.to_string(), sealed)
+            }
+            None => (PAYLOAD_CONTENT_TYPE.to_string(), canonical),
+        };
+        let (content_encoding, body) = match (self.compression, self.content_encoding()) {
+            (Some(policy), Some(encoding)) if self.sealer.is_none() => {
+                policy.apply(encoding, body)?
+            }
+            _ => (None, body),
+        };
+
         Ok(UploadEnvelope {
             endpoint: self.endpoint.clone(),
             authorization_header: format!("Bearer {token}"),
             idempotency_key: self.idempotency_key(payload),
+            signature_header,
+            content_type,
+            content_encoding,
             body,
         })
     }
 
     /// Uploads payload with retry policy and failure classification.
     ///
+    /// # Negotiation
+    /// When the server rejects a compressed body with
+    /// [`UploadError::UnsupportedEncoding`], the client switches to another
+    /// encoding from the server's `Accept-Encoding` list, or to identity,
+    /// remembers the choice, and resends. These resends do not use up the
+    /// retry budget.
+    ///
+    /// # Chunking
+    /// With [`UploadClient::with_chunking`], large bodies are sent in
+    /// chunks. A retriable failure mid-upload resumes from the server's
+    /// acknowledged offset instead of restarting; each resume uses one
+    /// retry from the same budget as single-shot resends.
+    ///
+    /// # Acknowledgement
+    /// The server's response body is parsed into [`UploadReport::ack`]; its
+    /// `request_id` links the batch to the analysis that follows.
+    ///
     /// # Errors
     /// Returns final upload error when retries are exhausted or failure is
     /// classified permanent.
+    /// Returns [`UploadError::IngestAck`] when the acknowledgement is
+    /// malformed or echoes a different `batch_id`.
     pub fn upload_payload(
         &self,
         payload: &MosaicPayload,
         token: &str,
     ) -> Result<UploadReport, UploadError> {
-        let envelope = self.build_envelope(payload, token)?;
+        let mut envelope = self.build_envelope(payload, token)?;
 
         let mut attempts = 0_u32;
+        let mut retries = 0_u32;
+        let mut rejected = Vec::new();
+        loop {
+            match self.deliver(&envelope, &mut attempts, &mut retries) {
+                Err(UploadError::UnsupportedEncoding(accepted))
+                    if envelope.content_encoding.is_some() =>
+                {
+                    rejected.extend(envelope.content_encoding);
+                    // Invariant: each rejected encoding is excluded, so this
+                    // branch runs at most once per supported encoding.
+                    let fallback = parse_accept_encoding(&accepted)
+                        .into_iter()
+                        .find(|encoding| !rejected.contains(encoding));
+                    self.set_content_encoding(fallback);
+                    envelope = self.build_envelope(payload, token)?;
+                }
+                Ok(report) => {
+                    let echoed = report.ack.as_ref().and_then(|ack| ack.batch_id.as_ref());
+                    if let (Some(echoed), Some(uploaded)) = (echoed, &payload.metadata.batch_id)
+                        && echoed != uploaded
+                    {
+                        return Err(UploadError::IngestAck(format!(
+                            "acknowledged batch_id {echoed} does not match uploaded batch {uploaded}"
+                        )));
+                    }
+                    return Ok(report);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn deliver(
+        &self,
+        envelope: &UploadEnvelope,
+        attempts: &mut u32,
+        retries: &mut u32,
+    ) -> Result<UploadReport, UploadError> {
+        let mut progress = (0, 0);
+        if let Some((transport, policy)) = &self.chunking
+            && envelope.body.len() >= policy.min_body_bytes()
+            && !self.chunking_unsupported()
+        {
+            match chunked::run_chunked_upload(
+                transport.as_ref(),
+                policy,
+                &self.policy,
+                envelope,
+                self,
+                attempts,
+                retries,
+            )? {
+                chunked::ChunkedRun::Finalized(outcome) => {
+                    return Ok(UploadReport {
+                        attempts: *attempts,
+                        final_class: None,
+                        chunks: outcome.chunks,
+                        resumes: outcome.resumes,
+                        ack: parse_ack(&outcome.response, envelope)?,
+                    });
+                }
+                chunked::ChunkedRun::Unsupported => self.set_chunking_unsupported(),
+                // Why: the server holds the whole body; a single-shot resend
+                // under the same idempotency key returns its acknowledgement
+                // (as a duplicate) without marking chunking unsupported.
+                chunked::ChunkedRun::SessionGone { chunks, resumes } => {
+                    progress = (chunks, resumes)
+                }
+            }
+        }
+
         loop {
-            attempts = attempts.saturating_add(1);
+            *attempts = attempts.saturating_add(1);
 
-            match self.transport.send(&envelope) {
-                Ok(()) => {
+            match guarded(self, envelope.body.len() as u64, || {
+                self.transport.send(envelope)
+            }) {
+                Ok(response) => {
                     return Ok(UploadReport {
-                        attempts,
+                        attempts: *attempts,
                         final_class: None,
+                        chunks: progress.0,
+                        resumes: progress.1,
+                        ack: parse_ack(&response, envelope)?,
                     });
                 }
                 Err(error) => {
-                    let failure_class = classify_upload_error(&error);
-                    let exhausted = attempts > self.policy.max_retries;
-                    if failure_class == FailureClass::Permanent || exhausted {
+                    if stops_retrying(&error, *retries, &self.policy) {
                         return Err(error);
                     }
 
                     // In production this is where async sleep/backoff occurs.
-                    let _next_delay = self.policy.backoff_delay_ms(attempts - 1);
+                    let _next_delay = self.policy.backoff_delay_ms(*retries);
+                    *retries = retries.saturating_add(1);
                 }
             }
         }
@@ -217,6 +594,128 @@ impl UploadClient {
     }
 }
 
+/// Admission and outcome hooks wrapped around every transport call.
+pub(crate) trait SendGate {
+    /// Admits a call about to send `bytes` of body data.
+    fn admit(&self, bytes: u64) -> Result<GateAdmission, UploadError>;
+
+    /// Records the outcome of the call `admission` was issued for.
+    fn observe(&self, admission: GateAdmission, outcome: Result<(), &UploadError>);
+}
+
+/// What [`SendGate::admit`] granted, handed back to [`SendGate::observe`].
+pub(crate) struct GateAdmission {
+    breaker: Option<Admission>,
+}
+
+impl SendGate for UploadClient {
+    fn admit(&self, bytes: u64) -> Result<GateAdmission, UploadError> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(UploadError::Cancelled);
+        }
+        if let Some(meter) = &self.budget {
+            meter.check_send()?;
+        }
+        // Invariant: every call the breaker admits (including a half-open
+        // probe) reaches `observe`, here if it is cancelled while paced.
+        let breaker = self
+            .breaker
+            .as_ref()
+            .map(CircuitBreaker::admit)
+            .transpose()?;
+        let paced = match (&self.rate_limiter, &self.cancellation) {
+            (Some(limiter), Some(cancellation)) => {
+                limiter.acquire_cancellable(bytes, cancellation).map(drop)
+            }
+            (Some(limiter), None) => {
+                limiter.acquire(bytes);
+                Ok(())
+            }
+            (None, _) => Ok(()),
+        };
+        // Why: the pacing wait can outlast a logout or kill switch; the
+        // cancelled body is neither sent nor counted against the budget.
+        let cancelled = self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled);
+        if paced.is_err() || cancelled {
+            self.observe(GateAdmission { breaker }, Err(&UploadError::Cancelled));
+            return Err(UploadError::Cancelled);
+        }
+        if let Some(meter) = &self.budget {
+            meter.record(bytes);
+        }
+        Ok(GateAdmission { breaker })
+    }
+
+    fn observe(&self, admission: GateAdmission, outcome: Result<(), &UploadError>) {
+        if let (Some(breaker), Some(breaker_admission)) = (&self.breaker, admission.breaker) {
+            breaker.record(breaker_admission, outcome);
+        }
+    }
+}
+
+/// Runs `call` through `gate`: admit, call, then observe the outcome.
+pub(crate) fn guarded<T>(
+    gate: &dyn SendGate,
+    bytes: u64,
+    call: impl FnOnce() -> Result<T, UploadError>,
+) -> Result<T, UploadError> {
+    let admission = gate.admit(bytes)?;
+    let result = call();
+    gate.observe(admission, result.as_ref().map(|_| ()));
+    result
+}
+
+/// Parses the acknowledgement in a 2xx upload response.
+///
+/// Responses without an `application/json` body yield `None`.
+///
+/// # Errors
+/// Returns [`UploadError::IngestAck`] when a JSON body violates the ingest
+/// response contract or echoes another upload's idempotency key.
+fn parse_ack(
+    response: &HttpResponse,
+    envelope: &UploadEnvelope,
+) -> Result<Option<IngestAck>, UploadError> {
+    // Why: servers predating the acknowledgement contract answer with an
+    // empty or non-JSON body; their uploads still succeeded.
+    let is_json = response.header("Content-Type").is_some_and(|value| {
+        value
+            .split(';')
+            .next()
+            .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+    });
+    if !is_json || response.body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    let raw = std::str::from_utf8(&response.body)
+        .map_err(|_| UploadError::IngestAck("body is not UTF-8".to_string()))?;
+    let ack = parse_ingest_ack(raw).map_err(|error| UploadError::IngestAck(error.to_string()))?;
+    if let Some(echoed) = &ack.idempotency_key
+        && *echoed != envelope.idempotency_key
+    {
+        return Err(UploadError::IngestAck(format!(
+            "acknowledged idempotency key {echoed} does not match upload"
+        )));
+    }
+    Ok(Some(ack))
+}
+
+/// Returns whether a failed attempt ends the retry loop.
+pub(crate) fn stops_retrying(error: &UploadError, retries: u32, policy: &RetryPolicy) -> bool {
+    // Why: retrying against an open circuit would only burn the retry
+    // budget; the payload stays queued until the cool-down ends.
+    matches!(error, UploadError::CircuitOpen { .. })
+        || classify_upload_error(error) == FailureClass::Permanent
+        || retries >= policy.max_retries
+}
+
 /// Validates ingest endpoint policy.
 ///
 /// # Errors
@@ -236,19 +735,36 @@ pub fn validate_upload_endpoint(endpoint: &str) -> Result<(), UploadError> {
 /// Classifies upload failures into retry/non-retry classes.
 pub fn classify_upload_error(error: &UploadError) -> FailureClass {
     match error {
-        UploadError::Timeout | UploadError::Server(_) | UploadError::Transport(_) => {
-            FailureClass::Retriable
-        }
+        UploadError::Timeout
+        | UploadError::Server(_)
+        | UploadError::Transport(_)
+        | UploadError::CircuitOpen { .. } => FailureClass::Retriable,
         UploadError::InvalidEndpoint(_)
         | UploadError::NonHttpsEndpoint
+        | UploadError::EndpointNotAllowed(_)
         | UploadError::MissingToken
         | UploadError::Unauthorized
         | UploadError::Client(_)
-        | UploadError::Serialize(_) => FailureClass::Permanent,
+        | UploadError::Serialize(_)
+        | UploadError::Encrypt(_)
+        | UploadError::Compress(_)
+        | UploadError::UnsupportedEncoding(_)
+        | UploadError::ChunkProtocol(_)
+        | UploadError::BudgetExhausted(_)
+        | UploadError::Cancelled
+        | UploadError::IngestAck(_)
+        | UploadError::TransportSecurity(_)
+        | UploadError::Proxy(_) => FailureClass::Permanent,
     }
 }
 
 /// Generates deterministic SHA-256 idempotency key.
+///
+/// # Key material
+/// Only batch identity and content are hashed (schema version, session,
+/// screen, time window, mosaic geometry, pixels). Annotations such as
+/// provenance, host context, and quality flags are excluded, so a batch
+/// re-sent by an upgraded client still deduplicates server-side.
 pub fn idempotency_key_for_payload(payload: &MosaicPayload) -> String {
     let mut hasher = Sha256::new();
     hasher.update(payload.schema_version.as_bytes());
@@ -271,6 +787,9 @@ pub enum UploadError {
     /// Endpoint must be HTTPS.
     #[error("upload endpoint must use https")]
     NonHttpsEndpoint,
+    /// Endpoint host, port, or form is outside the endpoint allowlist.
+    #[error("upload endpoint not allowed: {0}")]
+    EndpointNotAllowed(String),
     /// Bearer token is required for upload.
     #[error("upload token is missing")]
     MissingToken,
@@ -289,9 +808,52 @@ pub enum UploadError {
     /// Generic transport failure.
     #[error("upload transport failure: {0}")]
     Transport(String),
+    /// Server failed the transport security policy (certificate, pin, or
+    /// TLS version).
+    #[error("upload transport security failure: {0}")]
+    TransportSecurity(String),
+    /// Proxy configuration is invalid, or the proxy refused credentials or
+    /// the tunnel target.
+    #[error("upload proxy failure: {0}")]
+    Proxy(String),
     /// Payload serialization failed.
     #[error("payload serialization failure: {0}")]
     Serialize(String),
+    /// Payload body could not be sealed to the server key.
+    #[error("payload encryption failure: {0}")]
+    Encrypt(String),
+    /// Payload body could not be compressed.
+    #[error("payload compression failure: {0}")]
+    Compress(String),
+    /// Server rejected the body's `Content-Encoding` (HTTP 415); carries
+    /// its `Accept-Encoding` header, possibly empty.
+    #[error("server rejected content encoding; accepts: {0:?}")]
+    UnsupportedEncoding(String),
+    /// Chunked upload violated the protocol (checksum mismatch, offset
+    /// outside the body, unknown session).
+    #[error("chunked upload protocol failure: {0}")]
+    ChunkProtocol(String),
+    /// A data budget is exhausted under
+    /// [`BudgetExhaustionPolicy::QueueOnly`]; keep the payload queued until
+    /// the period resets.
+    #[error("upload {0} data budget exhausted")]
+    BudgetExhausted(BudgetPeriod),
+    /// The shared [`CircuitBreaker`] is open after repeated failures; keep
+    /// the payload queued for at least `retry_after_ms`.
+    #[error("upload circuit open; retry after {retry_after_ms} ms")]
+    CircuitOpen {
+        /// Remaining cool-down in milliseconds (0 while a probe is in
+        /// flight).
+        retry_after_ms: u64,
+    },
+    /// The upload's [`CancellationToken`] was cancelled (logout or kill
+    /// switch).
+    #[error("upload cancelled")]
+    Cancelled,
+    /// The server accepted the upload but its acknowledgement violated
+    /// `contracts/ingest-response.schema.json` or named another upload.
+    #[error("ingest acknowledgement invalid: {0}")]
+    IngestAck(String),
 }
 
 #[cfg(test)]
@@ -315,4 +877,30 @@ mod tests {
             FailureClass::Permanent
         );
     }
+
+    #[test]
+    fn accept_encoding_skips_refused_and_unknown_codings() {
+        assert_eq!(
+            parse_accept_encoding("br, zstd;q=0, X-GZIP;q=0.5, identity, gzip"),
+            vec![ContentEncoding::Gzip]
+        );
+        assert_eq!(
+            parse_accept_encoding("zstd, gzip"),
+            vec![ContentEncoding::Zstd, ContentEncoding::Gzip]
+        );
+        assert!(parse_accept_encoding("").is_empty());
+    }
+
+    #[test]
+    fn incompressible_body_is_sent_unencoded() {
+        // Why: hash output is high-entropy, like ciphertext or JPEG data.
+        let body: Vec<u8> = (0..128_u32)
+            .flat_map(|index| Sha256::digest(index.to_le_bytes()))
+            .collect();
+        let policy = CompressionPolicy::new(ContentEncoding::Gzip).with_min_size_bytes(0);
+        assert_eq!(
+            policy.apply(ContentEncoding::Gzip, body.clone()),
+            Ok((None, body))
+        );
+    }
 }